@@ -6,6 +6,7 @@ fn main() {
         web: { all(target_arch = "wasm32", feature = "web") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
         with_testing: { any(test, feature = "test") },
+        with_timer: { all(feature = "timer", any(web, not(target_arch = "wasm32"))) },
 
         // the old version of `getrandom` we pin here is available on all targets, but
         // using it will panic if no suitable source of entropy is found
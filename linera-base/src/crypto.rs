@@ -41,6 +41,19 @@ type HasherOutput = generic_array::GenericArray<u8, HasherOutputSize>;
 #[cfg_attr(with_testing, derive(Default))]
 pub struct CryptoHash(HasherOutput);
 
+/// A hash function for content-addressed data, such as blobs.
+#[derive(
+    Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[cfg_attr(with_testing, derive(test_strategy::Arbitrary))]
+pub enum HashAlgorithm {
+    /// SHA3-256, as computed by [`CryptoHash::new`].
+    #[default]
+    Sha3_256,
+    /// Keccak-256, the variant of SHA3-256 used by Ethereum.
+    Keccak256,
+}
+
 /// A signature value.
 #[derive(Eq, PartialEq, Copy, Clone)]
 pub struct Signature(pub dalek::Signature);
@@ -67,6 +80,8 @@ pub enum CryptoError {
     IncorrectPublicKeySize(usize),
     #[error("Could not parse integer")]
     ParseIntError(#[from] ParseIntError),
+    #[error("Unknown hash algorithm: {0}")]
+    UnknownHashAlgorithm(String),
 }
 
 impl PublicKey {
@@ -436,6 +451,90 @@ impl CryptoHash {
     }
 }
 
+impl HashAlgorithm {
+    /// Computes the hash of `value` with this algorithm.
+    pub fn hash<T: ?Sized + BcsHashable>(&self, value: &T) -> CryptoHash {
+        use sha3::digest::Digest;
+
+        match self {
+            HashAlgorithm::Sha3_256 => CryptoHash::new(value),
+            HashAlgorithm::Keccak256 => {
+                let mut hasher = sha3::Keccak256::default();
+                value.write(&mut hasher);
+                CryptoHash(hasher.finalize())
+            }
+        }
+    }
+
+    /// Returns a hasher computing a hash with this algorithm from data written in several
+    /// pieces, e.g. as they are streamed.
+    pub fn incremental_hasher(&self) -> IncrementalHasher {
+        match self {
+            HashAlgorithm::Sha3_256 => IncrementalHasher::Sha3_256(sha3::Sha3_256::default()),
+            HashAlgorithm::Keccak256 => IncrementalHasher::Keccak256(sha3::Keccak256::default()),
+        }
+    }
+}
+
+/// A hash being computed from data written in several pieces, with
+/// [`HashAlgorithm::incremental_hasher`].
+#[derive(Clone)]
+pub enum IncrementalHasher {
+    /// A SHA3-256 hash.
+    Sha3_256(sha3::Sha3_256),
+    /// A Keccak-256 hash.
+    Keccak256(sha3::Keccak256),
+}
+
+impl IncrementalHasher {
+    /// Returns the hash of the data written so far.
+    pub fn finalize(self) -> CryptoHash {
+        use sha3::digest::Digest;
+
+        match self {
+            IncrementalHasher::Sha3_256(hasher) => CryptoHash(hasher.finalize()),
+            IncrementalHasher::Keccak256(hasher) => CryptoHash(hasher.finalize()),
+        }
+    }
+}
+
+impl std::io::Write for IncrementalHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use sha3::digest::Digest;
+
+        match self {
+            IncrementalHasher::Sha3_256(hasher) => hasher.update(buf),
+            IncrementalHasher::Keccak256(hasher) => hasher.update(buf),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashAlgorithm::Sha3_256 => write!(f, "sha3-256"),
+            HashAlgorithm::Keccak256 => write!(f, "keccak256"),
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha3-256" => Ok(HashAlgorithm::Sha3_256),
+            "keccak256" => Ok(HashAlgorithm::Keccak256),
+            _ => Err(CryptoError::UnknownHashAlgorithm(s.to_string())),
+        }
+    }
+}
+
 impl Signature {
     /// Computes a signature.
     pub fn new<T>(value: &T, secret: &KeyPair) -> Self
@@ -4,7 +4,7 @@
 
 //! Core data-types used in the Linera protocol.
 
-use std::fmt;
+use std::{borrow::Cow, fmt, io::Write as _};
 
 use anyhow::Context as _;
 use async_graphql::{InputObject, SimpleObject};
@@ -14,7 +14,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
 use crate::{
-    crypto::{BcsHashable, CryptoHash},
+    crypto::{BcsHashable, CryptoHash, HasTypeName, HashAlgorithm, IncrementalHasher},
     doc_scalar,
     identifiers::{ApplicationId, BlobId, Destination, GenericApplicationId},
     time::{Duration, SystemTime},
@@ -777,7 +777,7 @@ impl std::str::FromStr for OracleResponse {
             ));
         }
         if let Some(string) = s.strip_prefix("Blob:") {
-            return Ok(OracleResponse::Blob(BlobId(CryptoHash::from_str(string)?)));
+            return Ok(OracleResponse::Blob(BlobId::from_str(string)?));
         }
         Err(anyhow::anyhow!("Invalid enum! Enum: {}", s))
     }
@@ -843,6 +843,11 @@ impl HashedBlob {
         self.id
     }
 
+    /// Returns whether the blob ID is actually the hash of the `Blob`.
+    pub fn has_valid_id(&self) -> bool {
+        self.id.is_id_of(&self.blob)
+    }
+
     /// Creates a [`HashedBlob`] from a string for testing purposes.
     #[cfg(with_testing)]
     pub fn test_blob(content: &str) -> Self {
@@ -861,14 +866,224 @@ impl HashedBlob {
     pub fn into_inner(self) -> Blob {
         self.blob
     }
+
+    /// Splits the content of the blob into chunks of at most `chunk_size` bytes, for
+    /// transports that limit the size of their messages.
+    ///
+    /// An empty blob is a single empty chunk.
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero, or if there would be more than `u32::MAX` chunks.
+    pub fn to_chunks(&self, chunk_size: usize) -> Vec<BlobChunk> {
+        assert!(chunk_size > 0, "the chunk size must be positive");
+        let count = self.blob.bytes.len().div_ceil(chunk_size).max(1);
+        let count = u32::try_from(count).expect("too many chunks");
+        let mut chunks = self
+            .blob
+            .bytes
+            .chunks(chunk_size)
+            .zip(0..)
+            .map(|(bytes, index)| BlobChunk {
+                blob_id: self.id,
+                index,
+                count,
+                bytes: bytes.to_vec(),
+            })
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            chunks.push(BlobChunk {
+                blob_id: self.id,
+                index: 0,
+                count,
+                bytes: Vec::new(),
+            });
+        }
+        chunks
+    }
+
+    /// Reassembles a blob from all its chunks, in order, and checks that its content matches
+    /// its ID.
+    pub fn from_chunks(
+        chunks: impl IntoIterator<Item = BlobChunk>,
+    ) -> Result<HashedBlob, BlobChunkError> {
+        let mut chunks = chunks.into_iter();
+        let first = chunks.next().ok_or(BlobChunkError::NoChunks)?;
+        let (blob_id, count) = (first.blob_id, first.count);
+        let mut bytes = first.bytes;
+        let mut next_index = 1;
+        if first.index != 0 {
+            return Err(BlobChunkError::UnexpectedChunk {
+                expected: 0,
+                found: first.index,
+            });
+        }
+        for chunk in chunks {
+            if chunk.blob_id != blob_id || chunk.count != count {
+                return Err(BlobChunkError::MismatchedChunk { index: chunk.index });
+            }
+            if chunk.index != next_index {
+                return Err(BlobChunkError::UnexpectedChunk {
+                    expected: next_index,
+                    found: chunk.index,
+                });
+            }
+            bytes.extend(chunk.bytes);
+            next_index += 1;
+        }
+        if next_index != count {
+            return Err(BlobChunkError::MissingChunks {
+                received: next_index,
+                count,
+            });
+        }
+        let blob = Blob { bytes };
+        if !blob_id.is_id_of(&blob) {
+            return Err(BlobChunkError::InvalidContent(blob_id));
+        }
+        Ok(blob.with_hash_unchecked(blob_id))
+    }
+}
+
+/// A piece of the content of a blob, as produced by [`HashedBlob::to_chunks`].
+///
+/// The chunks of a blob all have its ID and the total number of chunks, and are numbered
+/// consecutively from zero.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct BlobChunk {
+    /// The ID of the whole blob.
+    pub blob_id: BlobId,
+    /// The position of this chunk in the blob.
+    pub index: u32,
+    /// The number of chunks of the blob.
+    pub count: u32,
+    /// The part of the content in this chunk.
+    #[serde(with = "serde_bytes")]
+    pub bytes: Vec<u8>,
+}
+
+/// An error when reassembling a blob from its chunks.
+#[derive(Debug, Error)]
+pub enum BlobChunkError {
+    /// No chunks were provided.
+    #[error("No chunks were provided")]
+    NoChunks,
+    /// A chunk doesn't have the same blob ID or number of chunks as the first one.
+    #[error("Chunk {index} belongs to another blob")]
+    MismatchedChunk {
+        /// The index of the chunk.
+        index: u32,
+    },
+    /// The chunks are not in order.
+    #[error("Expected chunk {expected}, found chunk {found}")]
+    UnexpectedChunk {
+        /// The index of the expected chunk.
+        expected: u32,
+        /// The index of the chunk that was found instead.
+        found: u32,
+    },
+    /// Some of the last chunks are missing.
+    #[error("Received {received} chunks out of {count}")]
+    MissingChunks {
+        /// The number of chunks received.
+        received: u32,
+        /// The number of chunks of the blob.
+        count: u32,
+    },
+    /// The content of the chunks doesn't match the blob ID.
+    #[error("The reassembled content doesn't match blob {0}")]
+    InvalidContent(BlobId),
+}
+
+/// Computes the ID of a blob from its content written in several pieces, e.g. to check a
+/// blob that is streamed without buffering it.
+#[derive(Clone)]
+pub struct BlobIdHasher {
+    algorithm: HashAlgorithm,
+    len: usize,
+    written: usize,
+    hasher: IncrementalHasher,
+}
+
+impl BlobIdHasher {
+    /// Creates a hasher for the content of a blob of `len` bytes, with the given algorithm.
+    pub fn new(len: usize, algorithm: HashAlgorithm) -> Self {
+        let mut hasher = algorithm.incremental_hasher();
+        // This is what `Hashable::write` writes for a `Blob`, up to its content: the type
+        // name, then the BCS length of the content.
+        write!(hasher, "{}::", <Blob as HasTypeName>::type_name()).expect("Hasher should not fail");
+        let mut len_left = len;
+        loop {
+            let byte = (len_left & 0x7f) as u8;
+            len_left >>= 7;
+            if len_left == 0 {
+                hasher.write_all(&[byte]).expect("Hasher should not fail");
+                break;
+            }
+            hasher
+                .write_all(&[byte | 0x80])
+                .expect("Hasher should not fail");
+        }
+        BlobIdHasher {
+            algorithm,
+            len,
+            written: 0,
+            hasher,
+        }
+    }
+
+    /// Adds the next piece of the content.
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.written += bytes.len();
+        self.hasher
+            .write_all(bytes)
+            .expect("Hasher should not fail");
+    }
+
+    /// Returns the ID of the blob, or `None` if the pieces don't add up to the length given
+    /// to [`BlobIdHasher::new`].
+    pub fn finalize(self) -> Option<BlobId> {
+        (self.written == self.len).then(|| BlobId {
+            hash: self.hasher.finalize(),
+            algorithm: self.algorithm,
+        })
+    }
 }
 
+/// The human-readable format of a [`HashedBlob`]: its blob, and its ID so that the ID is
+/// rebuilt with the right algorithm. Blobs serialized without their ID are given the ID
+/// with the default algorithm.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "HashedBlob")]
+struct HumanReadableHashedBlob<'a> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<BlobId>,
+    #[serde(with = "serde_bytes", borrow)]
+    bytes: Cow<'a, [u8]>,
+}
+
+/// In the binary format, a [`HashedBlob`] is only its blob, like [`BlobId`]s that only
+/// have a binary format with the default algorithm.
 impl Serialize for HashedBlob {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        self.blob.serialize(serializer)
+        if serializer.is_human_readable() {
+            HumanReadableHashedBlob {
+                id: Some(self.id),
+                bytes: Cow::Borrowed(&self.blob.bytes),
+            }
+            .serialize(serializer)
+        } else if self.id.algorithm == HashAlgorithm::default() {
+            self.blob.serialize(serializer)
+        } else {
+            Err(serde::ser::Error::custom(format!(
+                "blob {} has no binary format: only blobs hashed with {} have one",
+                self.id,
+                HashAlgorithm::default()
+            )))
+        }
     }
 }
 
@@ -877,7 +1092,22 @@ impl<'a> Deserialize<'a> for HashedBlob {
     where
         D: Deserializer<'a>,
     {
-        Ok(Blob::deserialize(deserializer)?.into_hashed())
+        if deserializer.is_human_readable() {
+            let HumanReadableHashedBlob { id, bytes } =
+                HumanReadableHashedBlob::deserialize(deserializer)?;
+            let blob = Blob {
+                bytes: bytes.into_owned(),
+            };
+            match id {
+                Some(id) if id.is_id_of(&blob) => Ok(blob.with_hash_unchecked(id)),
+                Some(id) => Err(serde::de::Error::custom(format!(
+                    "the blob doesn't match its ID {id}"
+                ))),
+                None => Ok(blob.into_hashed()),
+            }
+        } else {
+            Ok(Blob::deserialize(deserializer)?.into_hashed())
+        }
     }
 }
 
@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     bcs_scalar,
-    crypto::{BcsHashable, CryptoError, CryptoHash, PublicKey},
+    crypto::{BcsHashable, CryptoError, CryptoHash, HashAlgorithm, PublicKey},
     data_types::{Blob, BlockHeight},
     doc_scalar,
 };
@@ -142,21 +142,114 @@ impl ChainDescription {
 #[cfg_attr(with_testing, derive(Default))]
 pub struct ChainId(pub CryptoHash);
 
-/// A content-addressed blob ID i.e. the hash of the Blob.
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
+/// A content-addressed blob ID i.e. the hash of the Blob, with the algorithm used to
+/// compute it.
+///
+/// Only blob IDs with the default algorithm can be stored or sent in the binary format,
+/// which is unchanged from when they had no algorithm. The others can be verified, and
+/// exchanged in the human-readable and gRPC formats.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash, Debug)]
 #[cfg_attr(with_testing, derive(test_strategy::Arbitrary, Default))]
-pub struct BlobId(pub CryptoHash);
+pub struct BlobId {
+    /// The hash of the blob.
+    pub hash: CryptoHash,
+    /// The algorithm used to compute the hash.
+    #[cfg_attr(
+        with_testing,
+        strategy(proptest::strategy::Just(HashAlgorithm::default()))
+    )]
+    pub algorithm: HashAlgorithm,
+}
 
 impl BlobId {
-    /// Creates a new `BlobId` from a `Blob`
+    /// Creates a new `BlobId` from a `Blob`, with the default hash algorithm.
     pub fn new(blob: &Blob) -> Self {
-        BlobId(CryptoHash::new(blob))
+        BlobId::with_algorithm(blob, HashAlgorithm::default())
+    }
+
+    /// Creates a new `BlobId` from a `Blob`, with the given hash algorithm.
+    pub fn with_algorithm(blob: &Blob, algorithm: HashAlgorithm) -> Self {
+        BlobId {
+            hash: algorithm.hash(blob),
+            algorithm,
+        }
+    }
+
+    /// Returns whether this is the ID of `blob`, hashing it with the algorithm of the ID.
+    pub fn is_id_of(&self, blob: &Blob) -> bool {
+        BlobId::with_algorithm(blob, self.algorithm) == *self
     }
 }
 
+/// Blob IDs with the default algorithm are displayed as their hash, and the others are
+/// prefixed with the algorithm, e.g. `keccak256:<hash>`.
 impl Display for BlobId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(&self.0, f)
+        if self.algorithm != HashAlgorithm::default() {
+            write!(f, "{}:", self.algorithm)?;
+        }
+        Display::fmt(&self.hash, f)
+    }
+}
+
+impl FromStr for BlobId {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, hash) = match s.split_once(':') {
+            Some((algorithm, hash)) => (algorithm.parse()?, hash),
+            None => (HashAlgorithm::default(), s),
+        };
+        Ok(BlobId {
+            hash: hash.parse()?,
+            algorithm,
+        })
+    }
+}
+
+/// The binary format of a [`BlobId`], which is that of its hash only, as before hash
+/// algorithms were introduced. It is used in storage keys, in the messages between nodes
+/// and in the hashes of certificates, so it must not change.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "BlobId")]
+struct SerializableBlobId(CryptoHash);
+
+/// Blob IDs are serialized as their string in human-readable formats. The binary format
+/// has no room for the algorithm, so only blob IDs with the default algorithm can be
+/// serialized with it, and deserialized blob IDs have the default algorithm.
+impl Serialize for BlobId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else if self.algorithm == HashAlgorithm::default() {
+            SerializableBlobId(self.hash).serialize(serializer)
+        } else {
+            Err(serde::ser::Error::custom(format!(
+                "blob ID {self} has no binary format: only blob IDs hashed with {} have one",
+                HashAlgorithm::default()
+            )))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlobId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            let SerializableBlobId(hash) = SerializableBlobId::deserialize(deserializer)?;
+            Ok(BlobId {
+                hash,
+                algorithm: HashAlgorithm::default(),
+            })
+        }
     }
 }
 
@@ -21,7 +21,11 @@ pub mod identifiers;
 pub mod ownership;
 #[cfg(with_metrics)]
 pub mod prometheus_util;
+pub mod redaction;
 pub mod sync;
+pub mod task;
+pub mod time;
+pub mod tracing_config;
 #[cfg(test)]
 mod unit_tests;
 
@@ -29,15 +33,6 @@ pub use graphql::BcsHexParseError;
 #[doc(hidden)]
 pub use {async_graphql, bcs, hex};
 
-cfg_if::cfg_if! {
-    if #[cfg(web)] {
-        #[cfg(web)]
-        pub use web_time as time;
-    } else {
-        pub use std::time;
-    }
-}
-
 /// A macro for asserting that a condition is true, returning an error if it is not.
 ///
 /// # Examples
@@ -0,0 +1,63 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Redaction of identifiers in logs, for deployments whose logs are shipped off the machine.
+//!
+//! Redaction is controlled by a global flag, so that it applies uniformly to every log
+//! statement that wraps its identifiers with [`redact`].
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The environment variable that enables redaction when set to `true` or `1`.
+pub const REDACT_LOGS_ENV_VAR: &str = "LINERA_REDACT_LOGS";
+
+/// The number of characters of a redacted identifier that are kept in logs.
+pub const REDACTED_PREFIX_LEN: usize = 8;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the redaction of identifiers in logs.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether identifiers are redacted in logs.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Enables redaction if the [`REDACT_LOGS_ENV_VAR`] environment variable is set to `true`
+/// or `1`.
+///
+/// This is meant to be called when initializing logging.
+pub fn init_from_env() {
+    if let Ok(value) = std::env::var(REDACT_LOGS_ENV_VAR) {
+        set_enabled(matches!(value.as_str(), "true" | "1"));
+    }
+}
+
+/// Wraps a value so that only a prefix of it is displayed while redaction is enabled.
+pub fn redact<T: fmt::Display>(value: T) -> Redacted<T> {
+    Redacted(value)
+}
+
+/// A value that is displayed in full, or as a prefix of [`REDACTED_PREFIX_LEN`] characters
+/// if redaction is enabled.
+#[derive(Clone, Copy, Debug)]
+pub struct Redacted<T>(T);
+
+impl<T: fmt::Display> fmt::Display for Redacted<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if !is_enabled() {
+            return self.0.fmt(formatter);
+        }
+        let value = self.0.to_string();
+        match value.char_indices().nth(REDACTED_PREFIX_LEN) {
+            Some((end, _)) => write!(formatter, "{}…", &value[..end]),
+            None => formatter.write_str(&value),
+        }
+    }
+}
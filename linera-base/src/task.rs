@@ -0,0 +1,362 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Abstractions over tasks that can be used natively or on the Web.
+
+use std::{any::Any, future::Future, pin::pin};
+
+use futures::{
+    future::{self, Either},
+    FutureExt as _, Stream, StreamExt as _,
+};
+use thiserror::Error;
+
+/// Runs a CPU-bound closure off the asynchronous executor and returns a future of its
+/// result.
+///
+/// This is meant for occasional heavy computations (e.g. hashing a large blob). Natively,
+/// the closure runs on Tokio's blocking thread pool, and a panic in the closure is resumed
+/// when the returned future is awaited. On Wasm, where there is no blocking thread pool,
+/// the closure runs on the current thread when the returned future is first polled.
+pub fn spawn_blocking<F, R>(f: F) -> impl Future<Output = R> + Send
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    BlockingBuilder::new().spawn(f)
+}
+
+/// The scheduling intent of blocking work, used by [`BlockingBuilder::priority`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlockingPriority {
+    /// Work that someone is waiting for, e.g. to answer a request. Natively, it runs on a
+    /// dedicated OS thread, so that it never queues behind other blocking work.
+    Latency,
+    /// Ordinary work. Natively, it runs on Tokio's shared blocking thread pool.
+    #[default]
+    Normal,
+    /// Work that can wait, e.g. maintenance. Natively, it runs on a shared pool with one
+    /// worker per CPU, so that it doesn't take more than its share of the machine.
+    Background,
+}
+
+/// The workers shared by the blocking work with [`BlockingPriority::Background`].
+#[cfg(not(target_arch = "wasm32"))]
+static BACKGROUND_WORKERS: crate::sync::Lazy<WorkerPool> = crate::sync::Lazy::new(|| {
+    WorkerPool::new(std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get))
+});
+
+/// A builder to configure where blocking work runs.
+#[derive(Clone, Debug, Default)]
+pub struct BlockingBuilder {
+    dedicated_thread: bool,
+    priority: BlockingPriority,
+}
+
+impl BlockingBuilder {
+    /// Creates a builder that runs work on Tokio's shared blocking thread pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the work on a dedicated OS thread instead of the shared blocking thread pool,
+    /// so that it neither waits for nor uses up the runtime's blocking budget.
+    ///
+    /// This has no effect on Wasm, and takes precedence over the [priority](Self::priority).
+    pub fn dedicated_thread(mut self) -> Self {
+        self.dedicated_thread = true;
+        self
+    }
+
+    /// Sets the priority of the work, [`BlockingPriority::Normal`] by default.
+    ///
+    /// This has no effect on Wasm, where the work runs on the current thread rather than in
+    /// a worker whose options could be set.
+    pub fn priority(mut self, priority: BlockingPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Runs the closure `f` as configured and returns a future of its result.
+    ///
+    /// See [`spawn_blocking`] for how panics are handled.
+    pub fn spawn<F, R>(&self, f: F) -> impl Future<Output = R> + Send
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_arch = "wasm32"))] {
+                let dedicated_thread =
+                    self.dedicated_thread || self.priority == BlockingPriority::Latency;
+                if !dedicated_thread && self.priority == BlockingPriority::Background {
+                    return BACKGROUND_WORKERS.execute(f).left_future();
+                }
+                let handle = if dedicated_thread {
+                    let (sender, receiver) = tokio::sync::oneshot::channel();
+                    std::thread::spawn(move || {
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+                        // The receiver is gone if the caller is no longer interested.
+                        let _ = sender.send(result);
+                    });
+                    BlockingHandle::Thread(receiver)
+                } else {
+                    BlockingHandle::Pool(tokio::task::spawn_blocking(f))
+                };
+                async move {
+                    match handle {
+                        BlockingHandle::Pool(handle) => handle
+                            .await
+                            .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic())),
+                        BlockingHandle::Thread(receiver) => receiver
+                            .await
+                            .expect("the dedicated thread should always send a result")
+                            .unwrap_or_else(|payload| std::panic::resume_unwind(payload)),
+                    }
+                }
+                .right_future()
+            } else {
+                async move { f() }
+            }
+        }
+    }
+}
+
+/// A handle to blocking work started by a [`BlockingBuilder`].
+#[cfg(not(target_arch = "wasm32"))]
+enum BlockingHandle<R> {
+    Pool(tokio::task::JoinHandle<R>),
+    Thread(tokio::sync::oneshot::Receiver<std::thread::Result<R>>),
+}
+
+/// A pool of workers running CPU-bound jobs in parallel, e.g. to verify many signatures,
+/// without starting a worker per job.
+///
+/// Natively, the jobs run on Tokio's blocking thread pool, at most `size` of them at a time.
+/// On the Web (with the `web` feature), the pool starts `size` Web Workers that take the
+/// jobs from a shared queue, since starting a worker per job is expensive in a browser.
+/// Elsewhere on Wasm, e.g. in applications, there are no workers, and each job runs on the
+/// current thread when the future returned by [`WorkerPool::execute`] is first polled, as
+/// with [`spawn_blocking`].
+#[derive(Clone, Debug)]
+pub struct WorkerPool {
+    #[cfg(not(target_arch = "wasm32"))]
+    permits: std::sync::Arc<tokio::sync::Semaphore>,
+    #[cfg(all(target_arch = "wasm32", feature = "web"))]
+    jobs: std::sync::mpsc::Sender<WebJob>,
+}
+
+/// A job queued for the Web Workers of a [`WorkerPool`].
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+type WebJob = Box<dyn FnOnce() + Send>;
+
+impl WorkerPool {
+    /// Creates a pool of `size` workers.
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "a worker pool needs at least one worker");
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_arch = "wasm32"))] {
+                Self {
+                    permits: std::sync::Arc::new(tokio::sync::Semaphore::new(size)),
+                }
+            } else if #[cfg(feature = "web")] {
+                let (jobs, receiver) = std::sync::mpsc::channel::<WebJob>();
+                let receiver = std::sync::Arc::new(std::sync::Mutex::new(receiver));
+                for _ in 0..size {
+                    let receiver = receiver.clone();
+                    // The workers stop once every clone of the pool, and so of the sender,
+                    // is dropped. Jobs run after the lock is released, so that the other
+                    // workers can take the next ones in the meantime.
+                    wasm_thread::spawn(move || loop {
+                        let job = receiver
+                            .lock()
+                            .expect("a worker never panics while holding the queue")
+                            .recv();
+                        let Ok(job) = job else {
+                            break;
+                        };
+                        job();
+                    });
+                }
+                Self { jobs }
+            } else {
+                Self {}
+            }
+        }
+    }
+
+    /// Runs `job` once a worker is free, and returns a future of its result.
+    ///
+    /// The job waits for a worker when the returned future is first polled. Dropping the
+    /// future before then cancels the job, but a job that started runs to completion. See
+    /// [`spawn_blocking`] for how panics are handled.
+    pub fn execute<F, R>(&self, job: F) -> impl Future<Output = R> + Send
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_arch = "wasm32"))] {
+                let permits = self.permits.clone();
+                async move {
+                    let permit = permits
+                        .acquire_owned()
+                        .await
+                        .expect("the semaphore of a worker pool is never closed");
+                    // The worker stays busy until the job completes, even if the caller is
+                    // no longer waiting for it. This uses Tokio directly rather than a
+                    // `BlockingBuilder`, whose background work runs on a `WorkerPool`.
+                    tokio::task::spawn_blocking(move || {
+                        let _permit = permit;
+                        job()
+                    })
+                    .await
+                    .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))
+                }
+            } else if #[cfg(feature = "web")] {
+                let jobs = self.jobs.clone();
+                async move {
+                    let (sender, receiver) = futures::channel::oneshot::channel();
+                    jobs.send(Box::new(move || {
+                        // Skip the job if the caller stopped waiting while it was queued.
+                        if sender.is_canceled() {
+                            return;
+                        }
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                        // The receiver is gone if the caller is no longer interested.
+                        let _ = sender.send(result);
+                    }))
+                    .expect("the workers of a pool run as long as the pool exists");
+                    receiver
+                        .await
+                        .expect("a worker always sends the result of a job")
+                        .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+                }
+            } else {
+                spawn_blocking(job)
+            }
+        }
+    }
+}
+
+/// Blocking work that is restarted if it panics, up to a limit.
+///
+/// The worker is created by a factory closure, so that a fresh one replaces a worker that
+/// panicked. Each worker borrows the same input (typically the receiving end of a
+/// channel), so inputs that were queued when a worker panicked are handled by the next one.
+#[derive(Clone, Debug)]
+pub struct SupervisedBlocking {
+    builder: BlockingBuilder,
+    max_restarts: usize,
+}
+
+/// The error returned when a supervised worker panicked more times than allowed.
+#[derive(Debug, Error)]
+#[error("Blocking worker panicked after being restarted {restarts} times")]
+pub struct RestartLimitExceeded {
+    /// The number of times the worker was restarted.
+    pub restarts: usize,
+    /// The payload of the last panic.
+    pub payload: Box<dyn Any + Send>,
+}
+
+impl SupervisedBlocking {
+    /// Creates a supervisor that restarts the worker at most `max_restarts` times.
+    pub fn new(max_restarts: usize) -> Self {
+        Self {
+            builder: BlockingBuilder::new(),
+            max_restarts,
+        }
+    }
+
+    /// Runs the workers as configured by `builder`.
+    pub fn with_builder(mut self, builder: BlockingBuilder) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Runs workers created by `factory` on `input` until one of them returns, and returns a
+    /// future of its result.
+    ///
+    /// The future resolves to an error, with the payload of the last panic, once the restart
+    /// limit is exhausted.
+    pub fn spawn<I, F, W, R>(
+        &self,
+        mut input: I,
+        mut factory: F,
+    ) -> impl Future<Output = Result<R, RestartLimitExceeded>> + Send
+    where
+        I: Send + 'static,
+        F: FnMut() -> W + Send + 'static,
+        W: FnOnce(&mut I) -> R,
+        R: Send + 'static,
+    {
+        let max_restarts = self.max_restarts;
+        self.builder.spawn(move || {
+            let mut restarts = 0;
+            loop {
+                let worker = factory();
+                let result =
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| worker(&mut input)));
+                match result {
+                    Ok(output) => return Ok(output),
+                    Err(payload) if restarts == max_restarts => {
+                        return Err(RestartLimitExceeded { restarts, payload })
+                    }
+                    Err(_) => restarts += 1,
+                }
+            }
+        })
+    }
+}
+
+/// Runs `work` until it completes or until the `signal` future resolves, e.g. on a global
+/// shutdown. Returns the output of `work`, or `None` if the signal came first.
+///
+/// If both are ready at once, the signal takes precedence. The `work` is dropped as soon as
+/// the signal resolves, so it must be safe to cancel at its await points.
+pub async fn with_shutdown<W>(signal: impl Future, work: W) -> Option<W::Output>
+where
+    W: Future,
+{
+    match future::select(pin!(signal), pin!(work)).await {
+        Either::Left(_) => None,
+        Either::Right((output, _)) => Some(output),
+    }
+}
+
+/// Helpers for worker loops that consume a stream of inputs.
+pub trait InputReceiverExt: Stream + Unpin {
+    /// Waits for the next input, returning `None` once the stream is closed.
+    fn recv_one(&mut self) -> futures::stream::Next<'_, Self> {
+        self.next()
+    }
+
+    /// Waits for at least one input, then takes the inputs that are already queued, up to
+    /// `max` in total, without waiting for more.
+    ///
+    /// Returns an empty batch once the stream is closed, or if `max` is zero.
+    fn recv_batch(&mut self, max: usize) -> impl Future<Output = Vec<Self::Item>> + '_ {
+        async move {
+            let mut batch = Vec::new();
+            if max == 0 {
+                return batch;
+            }
+            let Some(first) = self.next().await else {
+                return batch;
+            };
+            batch.push(first);
+            while batch.len() < max {
+                match self.next().now_or_never() {
+                    Some(Some(input)) => batch.push(input),
+                    Some(None) | None => break,
+                }
+            }
+            batch
+        }
+    }
+}
+
+impl<S: Stream + Unpin> InputReceiverExt for S {}
@@ -0,0 +1,54 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Abstractions over time that can be used natively or on the Web.
+//!
+//! The types and helpers re-exported from [`types`] don't need a runtime. The [`timer`]
+//! module needs Tokio natively, or `wasmtimer` on the Web, and is only available with the
+//! `timer` feature, which is enabled by default. Targets that only need the types can
+//! disable the default features to leave out these dependencies.
+
+pub mod types;
+
+pub use self::types::*;
+
+/// Timers that can be awaited natively or on the Web.
+#[cfg(with_timer)]
+pub mod timer {
+    use std::{future::Future, pin::pin};
+
+    use futures::future::{self, Either};
+    pub use tokio_util::sync::CancellationToken;
+
+    use super::{Duration, Instant};
+
+    cfg_if::cfg_if! {
+        if #[cfg(web)] {
+            pub use wasmtimer::tokio::{error::Elapsed, sleep, timeout};
+        } else {
+            pub use tokio::time::{error::Elapsed, sleep, timeout};
+        }
+    }
+
+    /// Like [`timeout`], but also returns how much of the `duration` was left when the
+    /// future completed, e.g. to bound the next operation under the same deadline.
+    pub async fn timeout_with_remaining<F: Future>(
+        duration: Duration,
+        future: F,
+    ) -> Result<(F::Output, Duration), Elapsed> {
+        let start = Instant::now();
+        let output = timeout(duration, future).await?;
+        Ok((output, duration.saturating_sub(start.elapsed())))
+    }
+
+    /// Sleeps for `duration`, unless `token` is cancelled first, e.g. to wait between
+    /// retries and still shut down promptly. Returns `true` if the whole `duration` elapsed,
+    /// or `false` if the token was cancelled, including before the call.
+    pub async fn sleep_cancellable(duration: Duration, token: &CancellationToken) -> bool {
+        // The token is polled first, so that it wins if both are ready.
+        match future::select(pin!(token.cancelled()), pin!(sleep(duration))).await {
+            Either::Left(((), _)) => false,
+            Either::Right(((), _)) => true,
+        }
+    }
+}
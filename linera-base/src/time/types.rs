@@ -0,0 +1,61 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Time types and duration arithmetic that don't depend on any timer machinery.
+
+cfg_if::cfg_if! {
+    if #[cfg(web)] {
+        pub use web_time::*;
+    } else {
+        pub use std::time::*;
+    }
+}
+
+/// A monotonic clock for measuring how long an operation takes.
+#[derive(Clone, Copy, Debug)]
+pub struct Stopwatch {
+    start: Instant,
+    last_lap: Instant,
+}
+
+impl Stopwatch {
+    /// Starts a new stopwatch at the current instant.
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Stopwatch {
+            start: now,
+            last_lap: now,
+        }
+    }
+
+    /// Returns the time elapsed since the stopwatch was started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns the time elapsed since the previous lap, or since the stopwatch was started if
+    /// this is the first lap, and starts a new lap.
+    pub fn lap(&mut self) -> Duration {
+        let now = Instant::now();
+        let lap = now.duration_since(self.last_lap);
+        self.last_lap = now;
+        lap
+    }
+}
+
+/// Returns the number of microseconds between the Unix epoch and `time`.
+///
+/// This behaves the same natively and on the Web. Times before the epoch are clamped to
+/// zero, and times too far in the future to fit in a `u64` are clamped to `u64::MAX`.
+pub fn to_micros_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |duration| {
+        u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+    })
+}
+
+/// Returns the [`SystemTime`] that is `micros` microseconds after the Unix epoch.
+///
+/// This is the inverse of [`to_micros_since_epoch`].
+pub fn from_micros_since_epoch(micros: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_micros(micros)
+}
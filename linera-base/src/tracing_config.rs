@@ -0,0 +1,186 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The configuration of tracing, shared by the initializers of the native and web targets.
+//!
+//! The layers that record traces differ between targets, so this module only describes
+//! what should be recorded. Each initializer translates a [`TracingConfig`] for its own
+//! layers, so that a binary configures tracing once and behaves the same on every target.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::redaction;
+
+/// The environment variable with the filter directives, e.g. `info,linera_core=debug`.
+pub const FILTER_ENV_VAR: &str = "RUST_LOG";
+
+/// The environment variable listing the span events to record, separated by commas, e.g.
+/// `new,close`.
+pub const SPAN_EVENTS_ENV_VAR: &str = "LINERA_LOG_SPAN_EVENTS";
+
+/// The environment variable that enables JSON output when set to `true` or `1`.
+pub const JSON_ENV_VAR: &str = "LINERA_LOG_JSON";
+
+/// The events in the lifecycle of spans that are recorded as log events.
+///
+/// This is the equivalent of `FmtSpan` in `tracing-subscriber`, for targets that use
+/// other layers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SpanEvents {
+    /// Whether a span is recorded when it is created.
+    pub new: bool,
+    /// Whether a span is recorded whenever it is entered.
+    pub enter: bool,
+    /// Whether a span is recorded whenever it is exited.
+    pub exit: bool,
+    /// Whether a span is recorded when it is closed, with its busy and idle times.
+    pub close: bool,
+}
+
+impl SpanEvents {
+    /// No span events are recorded.
+    pub const NONE: Self = SpanEvents {
+        new: false,
+        enter: false,
+        exit: false,
+        close: false,
+    };
+
+    /// Spans are recorded when they are entered and exited.
+    pub const ACTIVE: Self = SpanEvents {
+        new: false,
+        enter: true,
+        exit: true,
+        close: false,
+    };
+
+    /// All span events are recorded.
+    pub const FULL: Self = SpanEvents {
+        new: true,
+        enter: true,
+        exit: true,
+        close: true,
+    };
+}
+
+/// An error parsing [`SpanEvents`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[error("unknown span event {0:?}; expected `new`, `enter`, `exit`, `close`, `active`, `full` or `none`")]
+pub struct ParseSpanEventsError(String);
+
+/// Parses a list of span events separated by commas, e.g. `new,close`.
+impl FromStr for SpanEvents {
+    type Err = ParseSpanEventsError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut events = SpanEvents::NONE;
+        for event in string.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match event.to_ascii_lowercase().as_str() {
+                "new" => events.new = true,
+                "enter" => events.enter = true,
+                "exit" => events.exit = true,
+                "close" => events.close = true,
+                "active" => {
+                    events.enter = true;
+                    events.exit = true;
+                }
+                "full" => events = SpanEvents::FULL,
+                "none" => {}
+                _ => return Err(ParseSpanEventsError(event.to_owned())),
+            }
+        }
+        Ok(events)
+    }
+}
+
+/// What tracing records, and how.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TracingConfig {
+    /// The filter directives, in the syntax of `RUST_LOG`, e.g. `info,linera_core=debug`.
+    pub filter: String,
+    /// The events in the lifecycle of spans that are recorded.
+    pub span_events: SpanEvents,
+    /// Whether events are output as JSON objects rather than as text.
+    pub json: bool,
+    /// Whether identifiers are redacted in logs. See [`redaction`].
+    pub redact: bool,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        TracingConfig {
+            filter: "info".to_owned(),
+            span_events: SpanEvents::NONE,
+            json: false,
+            redact: false,
+        }
+    }
+}
+
+impl TracingConfig {
+    /// Returns the default configuration, overridden by the environment variables
+    /// [`FILTER_ENV_VAR`], [`SPAN_EVENTS_ENV_VAR`], [`JSON_ENV_VAR`] and
+    /// [`redaction::REDACT_LOGS_ENV_VAR`].
+    ///
+    /// Tracing is not initialized yet when this is called, so invalid span events are
+    /// ignored rather than reported.
+    pub fn from_env() -> Self {
+        let mut config = TracingConfig::default();
+        if let Ok(filter) = std::env::var(FILTER_ENV_VAR) {
+            config.filter = filter;
+        }
+        if let Some(span_events) = std::env::var(SPAN_EVENTS_ENV_VAR)
+            .ok()
+            .and_then(|value| value.parse().ok())
+        {
+            config.span_events = span_events;
+        }
+        if let Some(json) = env_flag(JSON_ENV_VAR) {
+            config.json = json;
+        }
+        if let Some(redact) = env_flag(redaction::REDACT_LOGS_ENV_VAR) {
+            config.redact = redact;
+        }
+        config
+    }
+
+    /// Sets the filter directives.
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = filter.into();
+        self
+    }
+
+    /// Sets the span events that are recorded.
+    pub fn with_span_events(mut self, span_events: SpanEvents) -> Self {
+        self.span_events = span_events;
+        self
+    }
+
+    /// Sets whether events are output as JSON.
+    pub fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Sets whether identifiers are redacted in logs.
+    pub fn with_redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Enables or disables the redaction of identifiers according to this configuration.
+    ///
+    /// This is meant to be called by the tracing initializers.
+    pub fn apply_redaction(&self) {
+        redaction::set_enabled(self.redact);
+    }
+}
+
+/// Returns whether the environment variable `name` is set to `true` or `1`, or `None` if
+/// it is not set.
+fn env_flag(name: &str) -> Option<bool> {
+    let value = std::env::var(name).ok()?;
+    Some(matches!(value.as_str(), "true" | "1"))
+}
@@ -170,3 +170,487 @@ fn chain_ownership_test_case() -> ChainOwnership {
         },
     }
 }
+
+/// Tests that `spawn_blocking` runs the closure and returns its result.
+#[test]
+fn test_spawn_blocking() {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let result = runtime.block_on(async { crate::task::spawn_blocking(|| 6 * 7).await });
+    assert_eq!(result, 42);
+}
+
+/// Tests that a panic in a `spawn_blocking` closure is resumed with its original payload.
+#[test]
+fn test_spawn_blocking_propagates_panic() {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        runtime.block_on(async {
+            crate::task::spawn_blocking(|| -> u32 { panic!("worker failed") }).await
+        })
+    }));
+    let payload = result.expect_err("The panic should be propagated");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"worker failed"));
+}
+
+/// Tests that blocking work on a dedicated thread returns its result and propagates panics.
+#[test]
+fn test_blocking_on_dedicated_thread() {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let builder = crate::task::BlockingBuilder::new().dedicated_thread();
+    let result = runtime.block_on(async { builder.spawn(|| 6 * 7).await });
+    assert_eq!(result, 42);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        runtime.block_on(async { builder.spawn(|| -> u32 { panic!("worker failed") }).await })
+    }));
+    let payload = result.expect_err("The panic should be propagated");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"worker failed"));
+}
+
+/// Tests that blocking work returns its result and propagates panics at every priority.
+#[test]
+fn test_blocking_priorities() {
+    use crate::task::{BlockingBuilder, BlockingPriority};
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    for priority in [
+        BlockingPriority::Latency,
+        BlockingPriority::Normal,
+        BlockingPriority::Background,
+    ] {
+        let builder = BlockingBuilder::new().priority(priority);
+        let result = runtime.block_on(async { builder.spawn(|| 6 * 7).await });
+        assert_eq!(result, 42);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            runtime.block_on(async { builder.spawn(|| -> u32 { panic!("worker failed") }).await })
+        }));
+        let payload = result.expect_err("The panic should be propagated");
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"worker failed"));
+    }
+}
+
+/// Tests that a worker pool runs all the jobs, never more at a time than it has workers.
+#[test]
+fn test_worker_pool() {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use crate::task::WorkerPool;
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let pool = WorkerPool::new(2);
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let jobs = (0..8).map(|input| {
+        let running = running.clone();
+        let max_running = max_running.clone();
+        pool.execute(move || {
+            let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(count, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+            input * 2
+        })
+    });
+    let results = runtime.block_on(futures::future::join_all(jobs));
+    assert_eq!(results, (0..8).map(|input| input * 2).collect::<Vec<_>>());
+    assert!(max_running.load(Ordering::SeqCst) <= 2);
+}
+
+/// Tests that a supervised worker is restarted after a panic, without losing queued inputs.
+#[test]
+fn test_supervised_blocking_restarts_worker() {
+    use std::sync::mpsc;
+
+    use crate::task::SupervisedBlocking;
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let (sender, receiver) = mpsc::channel();
+    for input in [1, 2, 0, 3, 4] {
+        sender.send(input).unwrap();
+    }
+    drop(sender);
+    let mut workers = 0;
+    let supervisor = SupervisedBlocking::new(1);
+    let factory = move || {
+        workers += 1;
+        move |receiver: &mut mpsc::Receiver<u32>| {
+            let mut sum = 0;
+            for input in receiver.iter() {
+                assert_ne!(input, 0, "worker failed");
+                sum += input;
+            }
+            (workers, sum)
+        }
+    };
+    let result = runtime.block_on(async { supervisor.spawn(receiver, factory).await });
+    // The second worker only sees the inputs that follow the one that caused the panic.
+    assert_eq!(result.unwrap(), (2, 7));
+}
+
+/// Tests that a supervised worker fails once it has panicked more times than allowed.
+#[test]
+fn test_supervised_blocking_restart_limit() {
+    use crate::task::SupervisedBlocking;
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let supervisor = SupervisedBlocking::new(2);
+    let factory = || |_: &mut ()| -> u32 { panic!("worker failed") };
+    let result = runtime.block_on(async { supervisor.spawn((), factory).await });
+    let error = result.expect_err("The worker should keep failing");
+    assert_eq!(error.restarts, 2);
+    assert_eq!(error.payload.downcast_ref::<&str>(), Some(&"worker failed"));
+}
+
+/// Tests that `recv_batch` drains queued inputs up to the limit and stops when closed.
+#[test]
+fn test_recv_batch() {
+    use futures::{channel::mpsc, executor::block_on};
+
+    use crate::task::InputReceiverExt as _;
+
+    let (sender, mut receiver) = mpsc::unbounded();
+    for input in 1..=5 {
+        sender.unbounded_send(input).unwrap();
+    }
+    assert_eq!(block_on(receiver.recv_one()), Some(1));
+    assert_eq!(block_on(receiver.recv_batch(0)), Vec::<u32>::new());
+    assert_eq!(block_on(receiver.recv_batch(3)), vec![2, 3, 4]);
+    assert_eq!(block_on(receiver.recv_batch(3)), vec![5]);
+    drop(sender);
+    assert_eq!(block_on(receiver.recv_batch(3)), Vec::<u32>::new());
+    assert_eq!(block_on(receiver.recv_one()), None);
+}
+
+/// Tests that `with_shutdown` returns the output of the work unless the signal comes first.
+#[test]
+fn test_with_shutdown() {
+    use futures::{
+        channel::oneshot,
+        executor::block_on,
+        future::{self, FutureExt as _},
+    };
+
+    use crate::task::with_shutdown;
+
+    assert_eq!(
+        block_on(with_shutdown(future::pending::<()>(), future::ready(1))),
+        Some(1)
+    );
+    assert_eq!(
+        block_on(with_shutdown(future::ready(()), future::pending::<u32>())),
+        None
+    );
+    assert_eq!(
+        block_on(with_shutdown(future::ready(()), future::ready(1))),
+        None
+    );
+
+    let (sender, receiver) = oneshot::channel::<()>();
+    let mut work = Box::pin(with_shutdown(receiver, future::pending::<u32>()));
+    assert_eq!((&mut work).now_or_never(), None);
+    sender.send(()).unwrap();
+    assert_eq!(block_on(work), None);
+}
+
+/// Tests that redacted values are truncated only while redaction is enabled.
+#[test]
+fn test_redaction() {
+    use crate::redaction::{redact, set_enabled};
+
+    let hash = CryptoHash::test_hash("redacted");
+    let full = hash.to_string();
+    assert_eq!(redact(hash).to_string(), full);
+
+    set_enabled(true);
+    assert_eq!(redact(hash).to_string(), format!("{}…", &full[..8]));
+    assert_eq!(redact("short").to_string(), "short");
+    set_enabled(false);
+    assert_eq!(redact(hash).to_string(), full);
+}
+
+/// Tests that a `Stopwatch` measures both the total elapsed time and the time between laps.
+#[test]
+fn test_stopwatch() {
+    use crate::time::{Duration, Stopwatch};
+
+    let mut stopwatch = Stopwatch::start();
+    std::thread::sleep(Duration::from_millis(10));
+    let first_lap = stopwatch.lap();
+    assert!(first_lap >= Duration::from_millis(10));
+    std::thread::sleep(Duration::from_millis(10));
+    let second_lap = stopwatch.lap();
+    assert!(second_lap >= Duration::from_millis(10));
+    assert!(stopwatch.elapsed() >= first_lap + second_lap);
+}
+
+/// Tests that converting a `SystemTime` to microseconds since the epoch and back is lossless.
+#[test]
+fn test_micros_since_epoch_roundtrip() {
+    use crate::time::{
+        from_micros_since_epoch, to_micros_since_epoch, Duration, SystemTime, UNIX_EPOCH,
+    };
+
+    for micros in [0, 1, 1_700_000_000_123_456] {
+        let time = from_micros_since_epoch(micros);
+        assert_eq!(time, UNIX_EPOCH + Duration::from_micros(micros));
+        assert_eq!(to_micros_since_epoch(time), micros);
+    }
+    let now = from_micros_since_epoch(to_micros_since_epoch(SystemTime::now()));
+    assert_eq!(from_micros_since_epoch(to_micros_since_epoch(now)), now);
+    assert_eq!(
+        to_micros_since_epoch(UNIX_EPOCH - Duration::from_secs(1)),
+        0
+    );
+}
+
+/// Tests that a blob larger than the chunk size can be split into chunks and reassembled.
+#[test]
+fn test_blob_chunks_roundtrip() {
+    use crate::data_types::{Blob, HashedBlob};
+
+    let blob = Blob {
+        bytes: (0..=255).cycle().take(1000).collect(),
+    }
+    .into_hashed();
+    let chunks = blob.to_chunks(300);
+    assert_eq!(chunks.len(), 4);
+    assert!(chunks
+        .iter()
+        .all(|chunk| chunk.blob_id == blob.id() && chunk.count == 4));
+    assert_eq!(chunks[3].bytes.len(), 100);
+    assert_eq!(HashedBlob::from_chunks(chunks).unwrap(), blob);
+
+    let empty_blob = Blob { bytes: Vec::new() }.into_hashed();
+    let chunks = empty_blob.to_chunks(300);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(HashedBlob::from_chunks(chunks).unwrap(), empty_blob);
+}
+
+/// Tests that blob IDs are verified with the hash algorithm they indicate.
+#[test]
+fn test_blob_id_hash_algorithms() {
+    use crate::{crypto::HashAlgorithm, data_types::Blob, identifiers::BlobId};
+
+    let blob = Blob {
+        bytes: b"blob".to_vec(),
+    };
+    let sha3_id = BlobId::new(&blob);
+    let keccak_id = BlobId::with_algorithm(&blob, HashAlgorithm::Keccak256);
+    assert_eq!(sha3_id.algorithm, HashAlgorithm::Sha3_256);
+    assert_ne!(sha3_id.hash, keccak_id.hash);
+    assert!(sha3_id.is_id_of(&blob) && keccak_id.is_id_of(&blob));
+    let mislabeled_id = BlobId {
+        hash: keccak_id.hash,
+        algorithm: HashAlgorithm::Sha3_256,
+    };
+    assert!(!mislabeled_id.is_id_of(&blob));
+    assert!(blob.clone().with_hash_unchecked(keccak_id).has_valid_id());
+
+    assert_eq!(sha3_id.to_string(), sha3_id.hash.to_string());
+    assert_eq!(
+        keccak_id.to_string(),
+        format!("keccak256:{}", keccak_id.hash)
+    );
+    for blob_id in [sha3_id, keccak_id] {
+        assert_eq!(blob_id.to_string().parse::<BlobId>().unwrap(), blob_id);
+        let json = serde_json::to_string(&blob_id).unwrap();
+        assert_eq!(serde_json::from_str::<BlobId>(&json).unwrap(), blob_id);
+    }
+}
+
+/// Tests that the binary format of blob IDs and blobs is unchanged by hash algorithms, and
+/// that the human-readable format of blobs keeps the algorithm of their ID.
+#[test]
+fn test_blob_formats_with_hash_algorithms() {
+    use crate::{
+        crypto::HashAlgorithm,
+        data_types::{Blob, HashedBlob},
+        identifiers::BlobId,
+    };
+
+    let blob = Blob {
+        bytes: b"blob".to_vec(),
+    };
+    let sha3_blob = blob.clone().into_hashed();
+    let keccak_id = BlobId::with_algorithm(&blob, HashAlgorithm::Keccak256);
+    let keccak_blob = blob.clone().with_hash_unchecked(keccak_id);
+
+    let bytes = bcs::to_bytes(&sha3_blob.id()).unwrap();
+    assert_eq!(bytes, bcs::to_bytes(&sha3_blob.id().hash).unwrap());
+    assert_eq!(bcs::from_bytes::<BlobId>(&bytes).unwrap(), sha3_blob.id());
+    let bytes = bcs::to_bytes(&sha3_blob).unwrap();
+    assert_eq!(bytes, bcs::to_bytes(&blob).unwrap());
+    assert_eq!(bcs::from_bytes::<HashedBlob>(&bytes).unwrap(), sha3_blob);
+    assert!(bcs::to_bytes(&keccak_id).is_err());
+    assert!(bcs::to_bytes(&keccak_blob).is_err());
+
+    for hashed_blob in [sha3_blob, keccak_blob] {
+        let json = serde_json::to_string(&hashed_blob).unwrap();
+        let deserialized = serde_json::from_str::<HashedBlob>(&json).unwrap();
+        assert_eq!(deserialized.id(), hashed_blob.id());
+    }
+    let json = serde_json::to_string(&blob).unwrap();
+    let hashed_blob = serde_json::from_str::<HashedBlob>(&json).unwrap();
+    assert_eq!(hashed_blob.id(), BlobId::new(&blob));
+    let mislabeled = serde_json::json!({
+        "id": keccak_id,
+        "bytes": b"other blob".to_vec(),
+    });
+    assert!(serde_json::from_value::<HashedBlob>(mislabeled).is_err());
+}
+
+/// Tests that blob IDs computed from pieces of the content match the IDs of whole blobs.
+#[test]
+fn test_blob_id_hasher() {
+    use crate::{
+        crypto::HashAlgorithm,
+        data_types::{Blob, BlobIdHasher},
+        identifiers::BlobId,
+    };
+
+    // The length takes two bytes in BCS.
+    let blob = Blob {
+        bytes: (0..1000).map(|i| i as u8).collect(),
+    };
+    for algorithm in [HashAlgorithm::Sha3_256, HashAlgorithm::Keccak256] {
+        let mut hasher = BlobIdHasher::new(blob.bytes.len(), algorithm);
+        for chunk in blob.bytes.chunks(300) {
+            hasher.update(chunk);
+        }
+        assert_eq!(
+            hasher.finalize(),
+            Some(BlobId::with_algorithm(&blob, algorithm))
+        );
+    }
+
+    let empty_blob = Blob { bytes: Vec::new() };
+    let hasher = BlobIdHasher::new(0, HashAlgorithm::default());
+    assert_eq!(hasher.finalize(), Some(BlobId::new(&empty_blob)));
+
+    let mut hasher = BlobIdHasher::new(blob.bytes.len(), HashAlgorithm::default());
+    hasher.update(&blob.bytes[..999]);
+    assert_eq!(hasher.finalize(), None);
+}
+
+/// Tests that reassembling a blob fails if chunks are missing, out of order or corrupted.
+#[test]
+fn test_blob_chunks_errors() {
+    use crate::data_types::{Blob, BlobChunkError, HashedBlob};
+
+    let blob = Blob {
+        bytes: vec![1; 1000],
+    }
+    .into_hashed();
+    let chunks = blob.to_chunks(300);
+
+    assert!(matches!(
+        HashedBlob::from_chunks(Vec::new()),
+        Err(BlobChunkError::NoChunks)
+    ));
+    assert!(matches!(
+        HashedBlob::from_chunks(chunks[..3].to_vec()),
+        Err(BlobChunkError::MissingChunks {
+            received: 3,
+            count: 4
+        })
+    ));
+    let mut swapped_chunks = chunks.clone();
+    swapped_chunks.swap(1, 2);
+    assert!(matches!(
+        HashedBlob::from_chunks(swapped_chunks),
+        Err(BlobChunkError::UnexpectedChunk {
+            expected: 1,
+            found: 2
+        })
+    ));
+    let mut corrupted_chunks = chunks;
+    corrupted_chunks[2].bytes[0] = 2;
+    assert!(matches!(
+        HashedBlob::from_chunks(corrupted_chunks),
+        Err(BlobChunkError::InvalidContent(blob_id)) if blob_id == blob.id()
+    ));
+}
+
+/// Tests that `timeout_with_remaining` returns the time left, or fails after the duration.
+#[cfg(with_timer)]
+#[test]
+fn test_timeout_with_remaining() {
+    use crate::time::{timer, Duration};
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let budget = Duration::from_secs(60);
+    let (output, remaining) = runtime
+        .block_on(timer::timeout_with_remaining(budget, async { 42 }))
+        .unwrap();
+    assert_eq!(output, 42);
+    assert!(remaining <= budget && remaining > Duration::from_secs(30));
+
+    let result = runtime.block_on(async {
+        timer::timeout_with_remaining(
+            Duration::from_millis(10),
+            timer::sleep(Duration::from_secs(60)),
+        )
+        .await
+    });
+    assert!(result.is_err());
+}
+
+/// Tests that `sleep_cancellable` sleeps the whole duration unless the token is cancelled.
+#[cfg(with_timer)]
+#[test]
+fn test_sleep_cancellable() {
+    use crate::time::{
+        timer::{self, CancellationToken},
+        Duration, Instant,
+    };
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let token = CancellationToken::new();
+    assert!(runtime.block_on(timer::sleep_cancellable(Duration::from_millis(10), &token)));
+
+    let start = Instant::now();
+    let sleeping_token = token.clone();
+    let cancelled = runtime.block_on(async move {
+        let sleep = timer::sleep_cancellable(Duration::from_secs(60), &sleeping_token);
+        let cancel = async {
+            timer::sleep(Duration::from_millis(10)).await;
+            token.cancel();
+        };
+        futures::join!(sleep, cancel).0
+    });
+    assert!(!cancelled);
+    assert!(start.elapsed() < Duration::from_secs(30));
+
+    let cancelled_token = CancellationToken::new();
+    cancelled_token.cancel();
+    assert!(!runtime.block_on(timer::sleep_cancellable(Duration::ZERO, &cancelled_token)));
+}
+
+/// Tests the parsing of the span events recorded by tracing.
+#[test]
+fn test_parse_span_events() {
+    use crate::tracing_config::SpanEvents;
+
+    assert_eq!("".parse(), Ok(SpanEvents::NONE));
+    assert_eq!("none".parse(), Ok(SpanEvents::NONE));
+    assert_eq!("Active".parse(), Ok(SpanEvents::ACTIVE));
+    assert_eq!("enter, exit".parse(), Ok(SpanEvents::ACTIVE));
+    assert_eq!("full".parse(), Ok(SpanEvents::FULL));
+    assert_eq!(
+        "new,close".parse(),
+        Ok(SpanEvents {
+            new: true,
+            close: true,
+            ..SpanEvents::NONE
+        })
+    );
+    assert!("new,closed".parse::<SpanEvents>().is_err());
+}
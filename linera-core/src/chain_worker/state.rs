@@ -58,6 +58,7 @@ where
     recent_hashed_certificate_values: Arc<ValueCache<CryptoHash, HashedCertificateValue>>,
     recent_hashed_blobs: Arc<ValueCache<BlobId, HashedBlob>>,
     knows_chain_is_active: bool,
+    knows_chain_is_indexed: bool,
 }
 
 impl<StorageClient> ChainWorkerState<StorageClient>
@@ -83,6 +84,7 @@ where
             recent_hashed_certificate_values: certificate_value_cache,
             recent_hashed_blobs: blob_cache,
             knows_chain_is_active: false,
+            knows_chain_is_indexed: false,
         })
     }
 
@@ -699,7 +701,7 @@ where
     /// Prepares a [`ChainInfoResponse`] for a [`ChainInfoQuery`].
     pub async fn prepare_chain_info_response(
         &mut self,
-        query: ChainInfoQuery,
+        mut query: ChainInfoQuery,
     ) -> Result<ChainInfoResponse, WorkerError> {
         let chain = &self.0.chain;
         let mut info = ChainInfo::from(chain);
@@ -750,6 +752,34 @@ where
                     .min(chain.confirmed_log.count()),
             };
             let keys = chain.confirmed_log.read(start..end).await?;
+            if query.request_sent_certificate_dependencies {
+                let certificates = self.0.storage.read_certificates(keys.clone()).await?;
+                let mut blob_ids = BTreeSet::new();
+                let mut value_hashes = BTreeSet::new();
+                for certificate in &certificates {
+                    if let Some(block) = certificate.value().block() {
+                        blob_ids.extend(block.blob_ids());
+                        value_hashes.extend(
+                            block
+                                .bytecode_locations()
+                                .into_iter()
+                                .map(|location| location.certificate_hash),
+                        );
+                    }
+                }
+                for blob_id in blob_ids {
+                    if !query.request_blobs.contains(&blob_id) {
+                        query.request_blobs.push(blob_id);
+                    }
+                }
+                for hash in value_hashes {
+                    match self.0.storage.read_hashed_certificate_value(hash).await {
+                        Ok(value) => info.requested_hashed_certificate_values.push(value),
+                        Err(error) if error.is_not_found() => {}
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+            }
             info.requested_sent_certificate_hashes = keys;
         }
         if let Some(start) = query.request_received_log_excluding_first_nth {
@@ -759,6 +789,13 @@ where
         if query.request_manager_values {
             info.manager.add_values(chain.manager.get());
         }
+        for blob_id in query.request_blobs {
+            match self.0.storage.read_hashed_blob(blob_id).await {
+                Ok(blob) => info.requested_blobs.push(blob),
+                Err(error) if error.is_not_found() => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
         Ok(ChainInfoResponse::new(info, self.0.config.key_pair()))
     }
 }
@@ -1239,6 +1276,11 @@ where
         };
 
         self.state.chain.save().await?;
+        if !self.state.knows_chain_is_indexed {
+            let chain_id = self.state.chain_id();
+            self.state.storage.index_chain(chain_id).await?;
+            self.state.knows_chain_is_indexed = true;
+        }
         self.succeeded = true;
         Ok(())
     }
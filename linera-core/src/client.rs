@@ -54,7 +54,7 @@ use crate::{
     data_types::{
         BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome, RoundTimeout,
     },
-    local_node::{LocalNodeClient, LocalNodeError},
+    local_node::{LocalNodeClient, LocalNodeError, ValidatorSyncError},
     node::{
         CrossChainMessageDelivery, LocalValidatorNode, LocalValidatorNodeProvider, NodeError,
         NotificationStream, ValidatorNodeProvider,
@@ -622,7 +622,8 @@ where
                 .client
                 .local_node
                 .synchronize_chain_state(nodes, self.chain_id, &mut notifications)
-                .await?;
+                .await?
+                .final_info;
             self.handle_notifications(&mut notifications);
         }
         self.update_from_info(&info);
@@ -776,7 +777,9 @@ where
         nodes: &[(ValidatorName, <P as LocalValidatorNodeProvider>::Node)],
     ) -> Vec<HashedCertificateValue> {
         future::join_all(locations.iter().map(|location| {
-            LocalNodeClient::<S>::download_hashed_certificate_value(nodes.to_owned(), *location)
+            self.client
+                .local_node
+                .download_hashed_certificate_value(nodes.to_owned(), *location)
         }))
         .await
         .into_iter()
@@ -789,11 +792,11 @@ where
         blob_ids: &[BlobId],
         nodes: &[(ValidatorName, <P as LocalValidatorNodeProvider>::Node)],
     ) -> Vec<HashedBlob> {
-        future::join_all(
-            blob_ids
-                .iter()
-                .map(|blob_id| LocalNodeClient::<S>::download_blob(nodes.to_owned(), *blob_id)),
-        )
+        future::join_all(blob_ids.iter().map(|blob_id| {
+            self.client
+                .local_node
+                .download_blob(nodes.to_owned(), *blob_id)
+        }))
         .await
         .into_iter()
         .flatten()
@@ -2352,12 +2355,12 @@ where
                 {
                     debug!("Accepting redundant notification for new block");
                 }
-                local_node
+                let outcome = local_node
                     .try_synchronize_chain_state_from(name, node, chain_id, &mut notifications)
-                    .await
-                    .unwrap_or_else(|e| {
-                        error!("Fail to process notification: {e}");
-                    });
+                    .await;
+                if let Some(ValidatorSyncError::LocalNodeError(error)) = outcome.error {
+                    error!("Fail to process notification: {error}");
+                }
                 self.0.lock().await.handle_notifications(&mut notifications);
                 let local_height = self
                     .local_next_block_height(chain_id, &mut local_node)
@@ -2374,10 +2377,10 @@ where
                         debug!("Accepting redundant notification for new round");
                     }
                 }
-                if let Err(error) = local_node
+                let outcome = local_node
                     .try_synchronize_chain_state_from(name, node, chain_id, &mut notifications)
-                    .await
-                {
+                    .await;
+                if let Some(ValidatorSyncError::LocalNodeError(error)) = outcome.error {
                     error!("Fail to process notification: {error}");
                 }
                 self.0.lock().await.handle_notifications(&mut notifications);
@@ -6,11 +6,11 @@ use std::collections::BTreeMap;
 
 use linera_base::{
     crypto::{BcsSignable, CryptoError, CryptoHash, KeyPair, Signature},
-    data_types::{Amount, BlockHeight, Round, Timestamp},
-    identifiers::{ChainDescription, ChainId, Owner},
+    data_types::{Amount, BlockHeight, HashedBlob, Round, Timestamp},
+    identifiers::{BlobId, ChainDescription, ChainId, Owner},
 };
 use linera_chain::{
-    data_types::{ChainAndHeight, IncomingMessage, Medium, MessageBundle},
+    data_types::{ChainAndHeight, HashedCertificateValue, IncomingMessage, Medium, MessageBundle},
     manager::ChainManagerInfo,
     ChainStateView,
 };
@@ -58,6 +58,9 @@ pub struct ChainInfoQuery {
     pub request_pending_messages: bool,
     /// Query a range of certificate hashes sent from the chain.
     pub request_sent_certificate_hashes_in_range: Option<BlockHeightRange>,
+    /// Also query the blobs and bytecodes referenced by the certificates in
+    /// `request_sent_certificate_hashes_in_range`.
+    pub request_sent_certificate_dependencies: bool,
     /// Query new certificate sender chain IDs and block heights received from the chain.
     pub request_received_log_excluding_first_nth: Option<u64>,
     /// Query values from the chain manager, not just votes.
@@ -66,6 +69,8 @@ pub struct ChainInfoQuery {
     pub request_leader_timeout: bool,
     /// Include a vote to switch to fallback mode, if appropriate.
     pub request_fallback: bool,
+    /// Query the blobs with the given IDs, among those the validator has.
+    pub request_blobs: Vec<BlobId>,
 }
 
 impl ChainInfoQuery {
@@ -77,10 +82,12 @@ impl ChainInfoQuery {
             request_owner_balance: None,
             request_pending_messages: false,
             request_sent_certificate_hashes_in_range: None,
+            request_sent_certificate_dependencies: false,
             request_received_log_excluding_first_nth: None,
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: false,
+            request_blobs: Vec::new(),
         }
     }
 
@@ -109,6 +116,14 @@ impl ChainInfoQuery {
         self
     }
 
+    /// Also requests the blobs and bytecodes that the certificates in the range depend on.
+    /// This saves a round-trip when processing the certificates, at the cost of a larger
+    /// response.
+    pub fn with_sent_certificate_dependencies(mut self) -> Self {
+        self.request_sent_certificate_dependencies = true;
+        self
+    }
+
     pub fn with_received_log_excluding_first_nth(mut self, n: u64) -> Self {
         self.request_received_log_excluding_first_nth = Some(n);
         self
@@ -128,6 +143,11 @@ impl ChainInfoQuery {
         self.request_fallback = true;
         self
     }
+
+    pub fn with_blobs(mut self, blob_ids: Vec<BlobId>) -> Self {
+        self.request_blobs = blob_ids;
+        self
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -163,6 +183,13 @@ pub struct ChainInfo {
     pub count_received_log: usize,
     /// The response to `request_received_certificates_excluding_first_nth`
     pub requested_received_log: Vec<ChainAndHeight>,
+    /// The response to `request_blobs`: the requested blobs that the validator has. This
+    /// includes the blobs referenced by the sent certificates if
+    /// `request_sent_certificate_dependencies` is set.
+    pub requested_blobs: Vec<HashedBlob>,
+    /// The response to `request_sent_certificate_dependencies`: the values of the
+    /// certificates publishing the bytecodes used by the sent certificates.
+    pub requested_hashed_certificate_values: Vec<HashedCertificateValue>,
 }
 
 /// The response to an `ChainInfoQuery`
@@ -241,6 +268,8 @@ where
             requested_sent_certificate_hashes: Vec::new(),
             count_received_log: view.received_log.count(),
             requested_received_log: Vec::new(),
+            requested_blobs: Vec::new(),
+            requested_hashed_certificate_values: Vec::new(),
         }
     }
 }
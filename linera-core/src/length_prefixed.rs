@@ -0,0 +1,67 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Files of records, each written as its length in bytes, as a little-endian `u64`,
+//! followed by its bytes.
+//!
+//! This is the format of the certificate archives of
+//! [`LocalNodeClient::import_certificates_from_file`](crate::local_node::LocalNodeClient::import_certificates_from_file)
+//! and of the recordings of validator responses.
+
+#[cfg(test)]
+#[path = "unit_tests/length_prefixed_tests.rs"]
+mod unit_tests;
+
+use std::io::{self, Read, Write};
+
+/// The length in bytes of the largest record that is read. A longer length is assumed to be
+/// corrupt, rather than allocating that much memory for it.
+pub const MAX_RECORD_LENGTH: u64 = 1 << 30;
+
+/// Appends a record to `writer`.
+pub fn write_record(mut writer: impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+/// Reads the next record of `reader`, or returns `None` if it has no bytes left.
+///
+/// A record cut short, including its length, fails with [`io::ErrorKind::UnexpectedEof`],
+/// and a length above [`MAX_RECORD_LENGTH`] with [`io::ErrorKind::InvalidData`].
+pub fn read_record(mut reader: impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len_bytes = [0; 8];
+    let mut len_read = 0;
+    while len_read < len_bytes.len() {
+        match reader.read(&mut len_bytes[len_read..]) {
+            Ok(0) if len_read == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("the length of the last record is cut short after {len_read} bytes"),
+                ))
+            }
+            Ok(read) => len_read += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    let len = u64::from_le_bytes(len_bytes);
+    if len > MAX_RECORD_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("a record of {len} bytes exceeds the maximum of {MAX_RECORD_LENGTH}"),
+        ));
+    }
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "the last record is cut short after {} of its {len} bytes",
+                bytes.len()
+            ),
+        ));
+    }
+    Ok(Some(bytes))
+}
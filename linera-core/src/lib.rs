@@ -13,11 +13,14 @@ mod join_set_ext;
 pub mod local_node;
 pub mod node;
 pub mod notifier;
+#[cfg(with_replay)]
+pub mod replay;
 #[cfg(with_testing)]
 #[path = "unit_tests/test_utils.rs"]
 pub mod test_utils;
 pub mod worker;
 
+pub(crate) mod length_prefixed;
 pub(crate) mod updater;
 pub(crate) mod value_cache;
 
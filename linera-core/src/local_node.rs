@@ -2,12 +2,29 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, VecDeque},
+    future::Future,
+    mem,
+    sync::Arc,
+};
 
-use futures::{future, lock::Mutex};
+use async_trait::async_trait;
+use futures::{
+    future::{self, AbortHandle, Abortable, Aborted},
+    lock::Mutex,
+    stream::{self, FuturesUnordered, Stream, StreamExt},
+    FutureExt as _,
+};
 use linera_base::{
+    crypto::CryptoHash,
     data_types::{ArithmeticError, Blob, BlockHeight, HashedBlob},
+    ensure,
     identifiers::{BlobId, ChainId, MessageId},
+    redaction::redact,
+    task,
+    time::{timer, Duration, Instant},
 };
 use linera_chain::{
     data_types::{
@@ -16,22 +33,65 @@ use linera_chain::{
     ChainStateView,
 };
 use linera_execution::{
-    committee::ValidatorName, BytecodeLocation, Query, Response, UserApplicationDescription,
+    committee::{Committee, ValidatorName},
+    BytecodeLocation, Operation, Query, Response, SystemOperation, UserApplicationDescription,
     UserApplicationId,
 };
-use linera_storage::Storage;
-use linera_views::views::ViewError;
-use rand::prelude::SliceRandom;
+use linera_storage::{BlobStream, Storage};
+use linera_version::{features, negotiate_features};
+use linera_views::{
+    batch::Batch,
+    common::{Context as _, KeyValueIterable as _, MaintenanceOp},
+    views::{View as _, ViewError},
+};
+use rand::{prelude::SliceRandom, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::sync::OwnedRwLockReadGuard;
+use tokio::sync::{mpsc, OwnedRwLockReadGuard};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::instrument;
+
+#[cfg(with_metrics)]
+use {
+    linera_base::{prometheus_util, sync::Lazy},
+    prometheus::HistogramVec,
+};
 
 use crate::{
     data_types::{BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse},
-    node::{LocalValidatorNode, NodeError},
+    length_prefixed,
+    node::{LocalValidatorNode, LocalValidatorNodeProvider, NodeError, NotificationStream},
+    notifier::Notifier,
     value_cache::ValueCache,
-    worker::{Notification, ValidatorWorker, WorkerError, WorkerState},
+    worker::{Notification, Reason, ValidatorWorker, WorkerError, WorkerState},
 };
 
+#[cfg(with_metrics)]
+static LOCAL_NODE_LOCK_WAIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "local_node_lock_wait_latency",
+        "Time spent waiting for the local node lock",
+        &["method"],
+        Some(vec![
+            0.001, 0.01, 0.1, 1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+#[cfg(with_metrics)]
+static LOCAL_NODE_LOCK_HOLD_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    prometheus_util::register_histogram_vec(
+        "local_node_lock_hold_latency",
+        "Time the local node lock is held for",
+        &["method"],
+        Some(vec![
+            0.001, 0.01, 0.1, 1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
 /// A local node with a single worker, typically used by clients.
 pub struct LocalNode<S> {
     state: WorkerState<S>,
@@ -41,6 +101,505 @@ pub struct LocalNode<S> {
 #[derive(Clone)]
 pub struct LocalNodeClient<S> {
     node: Arc<Mutex<LocalNode<S>>>,
+    config: DownloadConfig,
+    notification_queue: Option<Arc<std::sync::Mutex<NotificationQueue>>>,
+    validator_health: Arc<std::sync::Mutex<HashMap<ValidatorName, ValidatorHealth>>>,
+    validator_features: Arc<std::sync::Mutex<HashMap<ValidatorName, BTreeSet<String>>>>,
+    rng: Option<Arc<std::sync::Mutex<StdRng>>>,
+    deadline: Option<Deadline>,
+    notifier: Arc<Notifier<Notification>>,
+    chain_sync_locks: Arc<std::sync::Mutex<HashMap<ChainId, Arc<tokio::sync::Mutex<()>>>>>,
+    trusts_snapshots: bool,
+    error_classifier: ErrorClassifier,
+    downloads: Arc<std::sync::Mutex<DownloadRegistry>>,
+}
+
+/// A lock on the [`LocalNode`] of a client. The time it is held for is measured when
+/// metrics are enabled.
+struct LocalNodeGuard<'a, S> {
+    guard: futures::lock::MutexGuard<'a, LocalNode<S>>,
+    #[cfg(with_metrics)]
+    method: &'static str,
+    #[cfg(with_metrics)]
+    acquired_at: Instant,
+}
+
+impl<S> std::ops::Deref for LocalNodeGuard<'_, S> {
+    type Target = LocalNode<S>;
+
+    fn deref(&self) -> &LocalNode<S> {
+        &self.guard
+    }
+}
+
+impl<S> std::ops::DerefMut for LocalNodeGuard<'_, S> {
+    fn deref_mut(&mut self) -> &mut LocalNode<S> {
+        &mut self.guard
+    }
+}
+
+#[cfg(with_metrics)]
+impl<S> Drop for LocalNodeGuard<'_, S> {
+    fn drop(&mut self) {
+        LOCAL_NODE_LOCK_HOLD_LATENCY
+            .with_label_values(&[self.method])
+            .observe(self.acquired_at.elapsed().as_secs_f64() * 1000.0);
+    }
+}
+
+/// The notifications queued by a [`LocalNodeClient`] until they are drained.
+struct NotificationQueue {
+    notifications: VecDeque<Notification>,
+    capacity: usize,
+}
+
+/// What a validator reported when synchronizing a chain from it.
+struct ValidatorChainReport {
+    /// The next block height and the last block hash of the chain.
+    tip: (BlockHeight, Option<CryptoHash>),
+    /// The validated certificate that the validator is locked on, if it wasn't applied yet.
+    locked_certificate: Option<Box<Certificate>>,
+}
+
+/// The downloads in progress of a [`LocalNodeClient`] and its clones, to cancel them.
+#[derive(Default)]
+struct DownloadRegistry {
+    next_id: u64,
+    downloads: HashMap<u64, AbortHandle>,
+}
+
+/// Removes a download from the [`DownloadRegistry`] when it completes or is dropped.
+struct DownloadRegistration<'a> {
+    registry: &'a std::sync::Mutex<DownloadRegistry>,
+    id: u64,
+}
+
+impl Drop for DownloadRegistration<'_> {
+    fn drop(&mut self) {
+        self.registry.lock().unwrap().downloads.remove(&self.id);
+    }
+}
+
+/// A query to a validator admitted by its circuit breaker, whose outcome is reported with
+/// [`ValidatorAdmission::record`].
+///
+/// If the query probes the validator and is dropped before its outcome is recorded, e.g.
+/// because it was cancelled, the validator is skipped again with its cooldown over, so
+/// that the next query probes it.
+pub(crate) struct ValidatorAdmission<'a, S> {
+    client: &'a LocalNodeClient<S>,
+    name: ValidatorName,
+    /// When the circuit breaker opened, if this query is a probe.
+    probe_since: Option<Instant>,
+}
+
+impl<S> ValidatorAdmission<'_, S> {
+    /// Updates the circuit breaker of the validator after the query succeeded or failed.
+    fn record(mut self, succeeded: bool) {
+        self.probe_since = None;
+        self.client.record_validator_outcome(self.name, succeeded);
+    }
+}
+
+impl<S> Drop for ValidatorAdmission<'_, S> {
+    fn drop(&mut self) {
+        let Some(since) = self.probe_since else {
+            return;
+        };
+        let mut validator_health = self.client.validator_health.lock().unwrap();
+        if let Some(health @ ValidatorHealth::HalfOpen) = validator_health.get_mut(&self.name) {
+            tracing::debug!("Abandoned the probe of validator {}", redact(self.name));
+            *health = ValidatorHealth::Open { since };
+        }
+    }
+}
+
+/// The parameters of downloads from validators.
+///
+/// Missing fields are set to their default values when deserializing, so a download
+/// profile only needs to list the parameters it changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadConfig {
+    /// The smallest number of certificates requested at once. If a request for a batch
+    /// fails with an error classified as [`ErrorClass::ShrinkBatch`], the batch size is
+    /// halved until it reaches this value.
+    pub min_batch_size: u64,
+    /// The largest number of certificates requested at once.
+    pub max_batch_size: u64,
+    /// How long to wait for a validator to answer a request for a batch of certificates.
+    pub validator_timeout: Duration,
+    /// The maximum number of concurrent downloads from a validator, e.g. of missing blobs.
+    pub max_parallelism: usize,
+    /// How long to wait before trying the next validator when one didn't provide all the
+    /// certificates.
+    pub backoff: Duration,
+    /// The size in bytes of the largest blob accepted from a validator, if any.
+    pub max_blob_size: Option<u64>,
+    /// The order in which validators are tried.
+    pub validator_selection: ValidatorSelection,
+    /// Whether to ask validators for the blobs and bytecodes that the certificates depend
+    /// on together with the certificates, instead of only when they turn out to be missing.
+    pub include_certificate_dependencies: bool,
+    /// When to stop querying validators that keep failing, if ever.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// The number of blocks downloaded for a chain before moving on to the next one, when
+    /// synchronizing several chains.
+    pub chain_slice_size: u64,
+    /// The largest number of certificates held in memory at once while they are processed.
+    /// The next ones are only downloaded once the previous ones are processed.
+    pub max_pending_certificates: usize,
+    /// The maximum number of validators queried concurrently when synchronizing a chain.
+    pub max_concurrent_validators: usize,
+    /// How many times in a row a request for a batch of certificates is repeated when it
+    /// fails with an error classified as [`ErrorClass::Retry`].
+    pub max_retries: u32,
+    /// Which of the locked certificates reported by validators are applied when
+    /// synchronizing a chain.
+    pub locked_certificate_policy: LockedCertificatePolicy,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            min_batch_size: 10,
+            max_batch_size: 1000,
+            validator_timeout: Duration::from_secs(60),
+            max_parallelism: 10,
+            backoff: Duration::ZERO,
+            max_blob_size: None,
+            validator_selection: ValidatorSelection::Random,
+            include_certificate_dependencies: false,
+            circuit_breaker: None,
+            chain_slice_size: 100,
+            max_pending_certificates: 100,
+            max_concurrent_validators: 10,
+            max_retries: 2,
+            locked_certificate_policy: LockedCertificatePolicy::ApplyAll,
+        }
+    }
+}
+
+/// When to stop querying a validator that keeps failing, i.e. sending invalid responses
+/// or not answering in time.
+///
+/// After `failure_threshold` consecutive failures, the validator is skipped for the
+/// `cooldown`. Then a single query is sent to probe it: the validator is queried again
+/// as usual if the probe succeeds, and skipped for another cooldown otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failures after which a validator is skipped.
+    pub failure_threshold: u32,
+    /// How long a validator is skipped before it is probed.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The state of the circuit breaker of a validator.
+#[derive(Clone, Copy, Debug)]
+enum ValidatorHealth {
+    /// The validator is queried, and failed this many times in a row.
+    Closed { consecutive_failures: u32 },
+    /// The validator is skipped since the given time, until the cooldown is over.
+    ///
+    /// The cooldown is only applied when admitting queries, so that the clients sharing
+    /// this state with different configurations each use their own.
+    Open { since: Instant },
+    /// A single query is probing the validator.
+    HalfOpen,
+}
+
+/// The state of a chain in the storage of a node, to bootstrap another node without
+/// downloading and executing the blocks of the chain.
+///
+/// See [`LocalNodeClient::export_chain_snapshot`] and
+/// [`LocalNodeClient::apply_trusted_snapshot`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainStateSnapshot {
+    /// The chain of the snapshot.
+    pub chain_id: ChainId,
+    /// The keys and values of the state of the chain, relative to its base key.
+    pub key_values: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// The number of certificates processed with each lock on the local node by
+/// [`LocalNodeClient::import_certificates_from_file`].
+pub const IMPORT_BATCH_SIZE: usize = 100;
+
+/// A certificate with the values and blobs needed to process it, as stored in the
+/// archives imported with [`LocalNodeClient::import_certificates_from_file`].
+///
+/// An archive is a sequence of certificates, each written with
+/// [`ArchivedCertificate::write`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedCertificate {
+    /// The certificate.
+    pub certificate: Certificate,
+    /// The certificate values that the block of the certificate uses.
+    pub hashed_certificate_values: Vec<HashedCertificateValue>,
+    /// The blobs that the block of the certificate uses.
+    pub blobs: Vec<Blob>,
+}
+
+impl ArchivedCertificate {
+    /// Appends the certificate to an archive, as its length in bytes, as a little-endian
+    /// `u64`, followed by its BCS serialization.
+    pub fn write(&self, writer: impl std::io::Write) -> Result<(), LocalNodeError> {
+        let bytes = bcs::to_bytes(self).map_err(ViewError::from)?;
+        length_prefixed::write_record(writer, &bytes).map_err(ViewError::from)?;
+        Ok(())
+    }
+
+    /// Reads the next certificate of an archive, or returns `None` at the end of the
+    /// archive. A certificate cut short is an error.
+    fn read(reader: impl std::io::Read) -> Result<Option<Self>, LocalNodeError> {
+        let Some(bytes) = length_prefixed::read_record(reader).map_err(ViewError::from)? else {
+            return Ok(None);
+        };
+        Ok(Some(bcs::from_bytes(&bytes).map_err(ViewError::from)?))
+    }
+}
+
+/// The progress of [`LocalNodeClient::import_certificates_from_file`], reported after each
+/// certificate that is applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// The number of certificates applied so far.
+    pub applied: usize,
+    /// The chain of the last certificate applied.
+    pub chain_id: ChainId,
+    /// The height of the block of the last certificate applied.
+    pub height: BlockHeight,
+}
+
+/// The result of [`LocalNodeClient::import_certificates_from_file`].
+#[derive(Debug)]
+pub struct ImportOutcome {
+    /// The number of certificates applied.
+    pub applied: usize,
+    /// The error that stopped the import before the end of the archive, if any.
+    pub error: Option<LocalNodeError>,
+}
+
+/// How far the local node is behind the validators on a chain, as estimated by
+/// [`LocalNodeClient::estimate_sync_work`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyncEstimate {
+    /// The next block height of the chain in the local node.
+    pub local_next_block_height: BlockHeight,
+    /// The highest next block height reported by a validator.
+    pub tip_next_block_height: BlockHeight,
+    /// The number of blocks to download to catch up with the tip.
+    pub blocks_behind: u64,
+    /// The number of validators that reported the same tip.
+    pub agreeing_validators: usize,
+    /// The number of validators that sent a valid response.
+    pub responding_validators: usize,
+}
+
+/// The outcome of [`LocalNodeClient::synchronize_chain_state`].
+#[derive(Debug)]
+pub struct SyncReport {
+    /// The chain information of the local node after the synchronization.
+    pub final_info: Box<ChainInfo>,
+    /// What happened with each validator, in the order in which they completed. This is
+    /// empty if the chain was just synchronized by another caller.
+    pub per_validator: Vec<ValidatorSyncOutcome>,
+}
+
+/// What happened when synchronizing a chain from one validator.
+#[derive(Debug)]
+pub struct ValidatorSyncOutcome {
+    /// The validator.
+    pub name: ValidatorName,
+    /// The next block height and the last block hash of the chain reported by the
+    /// validator, if it sent a valid response.
+    pub tip: Option<(BlockHeight, Option<CryptoHash>)>,
+    /// The number of certificates from the validator that the local node processed.
+    pub certificates: usize,
+    /// Why the synchronization from the validator stopped early, if it did.
+    pub error: Option<ValidatorSyncError>,
+}
+
+impl ValidatorSyncOutcome {
+    fn new(name: ValidatorName) -> Self {
+        ValidatorSyncOutcome {
+            name,
+            tip: None,
+            certificates: 0,
+            error: None,
+        }
+    }
+
+    fn failed(mut self, error: impl Into<ValidatorSyncError>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+}
+
+/// Why the synchronization of a chain from a validator stopped early.
+#[derive(Debug, Error)]
+pub enum ValidatorSyncError {
+    #[error("The validator was skipped after repeated failures")]
+    Skipped,
+
+    #[error("The validator sent an invalid response")]
+    InvalidResponse,
+
+    #[error("The validator returned an error: {0}")]
+    NodeError(#[from] NodeError),
+
+    #[error("The local node couldn't process all the certificates sent by the validator")]
+    InvalidCertificates,
+
+    #[error(transparent)]
+    LocalNodeError(#[from] LocalNodeError),
+}
+
+/// A point in time by which a sequence of operations of a [`LocalNodeClient`] must
+/// complete, set with [`LocalNodeClient::with_deadline`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline(pub Instant);
+
+impl Deadline {
+    /// Returns the deadline that is `duration` from now.
+    pub fn after(duration: Duration) -> Self {
+        Deadline(Instant::now() + duration)
+    }
+
+    /// Returns the time left before the deadline, or `None` if it has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        let remaining = self.0.saturating_duration_since(Instant::now());
+        (!remaining.is_zero()).then_some(remaining)
+    }
+}
+
+/// A batch of certificates downloaded from a validator, with the dependencies it sent
+/// along with them.
+struct CertificateBatch {
+    certificate_hashes: Vec<CryptoHash>,
+    hashed_certificate_values: Vec<HashedCertificateValue>,
+    hashed_blobs: Vec<HashedBlob>,
+}
+
+/// The sending end of a stream of downloaded certificates.
+type CertificateSender = mpsc::UnboundedSender<Result<Certificate, LocalNodeError>>;
+
+/// What to do after a request for a batch of certificates failed, as decided by the
+/// error classifier of a [`LocalNodeClient`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The batch may have been too large for the validator: request a smaller one.
+    ShrinkBatch,
+    /// The error is likely transient: repeat the same request, up to
+    /// [`DownloadConfig::max_retries`] times.
+    Retry,
+    /// The validator can't provide the certificates: stop querying it.
+    AbandonValidator,
+}
+
+/// A function deciding how to react to an error from a validator, set with
+/// [`LocalNodeClient::with_error_classifier`].
+pub type ErrorClassifier = Arc<dyn Fn(&NodeError) -> ErrorClass + Send + Sync>;
+
+/// The default error classifier.
+///
+/// Transport errors and undecodable responses may be caused by a batch that is too large,
+/// network failures are retried, and all other errors mean that the validator can't help,
+/// e.g. because it doesn't know the chain or it rejects the client.
+pub fn classify_error(error: &NodeError) -> ErrorClass {
+    match error {
+        NodeError::GrpcError { .. } | NodeError::InvalidDecoding => ErrorClass::ShrinkBatch,
+        NodeError::ClientIoError { .. } => ErrorClass::Retry,
+        _ => ErrorClass::AbandonValidator,
+    }
+}
+
+/// The order in which validators are tried when downloading certificates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidatorSelection {
+    /// A random order, which spreads the load across validators.
+    #[default]
+    Random,
+    /// The order in which the validators are given.
+    InOrder,
+}
+
+/// Which of the locked certificates reported by validators are applied when synchronizing
+/// a chain, i.e. the validated blocks they are locked on in the current round.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockedCertificatePolicy {
+    /// Every locked certificate is applied as soon as it is received. If validators report
+    /// different certificates, the outcome depends on the order of their responses.
+    #[default]
+    ApplyAll,
+    /// Once the validators have answered, the locked certificate with the highest round is
+    /// applied. None is applied if different certificates are reported in that round.
+    HighestRound,
+    /// Once the validators have answered, a locked certificate is applied only if it was
+    /// reported by validators with a quorum of the votes in the latest committee known
+    /// to the local node.
+    Quorum,
+}
+
+/// A destination for the notifications produced while downloading and processing
+/// certificates.
+///
+/// Every type that can be extended with notifications is a sink that accumulates them in
+/// memory.
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+pub trait NotificationSink: Send {
+    /// Delivers the given notifications, waiting until the sink can accept them.
+    async fn deliver(&mut self, notifications: Vec<Notification>);
+}
+
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl<T> NotificationSink for T
+where
+    T: Extend<Notification> + Send,
+{
+    async fn deliver(&mut self, notifications: Vec<Notification>) {
+        self.extend(notifications);
+    }
+}
+
+/// A [`NotificationSink`] that sends notifications through a bounded channel.
+///
+/// Delivering waits while the channel is full, so processing further certificates pauses
+/// until the receiver catches up, instead of buffering the notifications of a whole
+/// download in memory.
+pub struct BoundedNotifications {
+    sender: mpsc::Sender<Notification>,
+}
+
+impl BoundedNotifications {
+    /// Creates a sink sending notifications to `sender`.
+    pub fn new(sender: mpsc::Sender<Notification>) -> Self {
+        Self { sender }
+    }
+}
+
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl NotificationSink for BoundedNotifications {
+    async fn deliver(&mut self, notifications: Vec<Notification>) {
+        for notification in notifications {
+            if self.sender.send(notification).await.is_err() {
+                tracing::debug!("Dropping notifications: the receiver was closed");
+                return;
+            }
+        }
+    }
 }
 
 /// Error type for the operations on a local node.
@@ -73,10 +632,138 @@ pub enum LocalNodeError {
     #[error("The chain info response received from the local node is invalid")]
     InvalidChainInfoResponse,
 
+    #[error("The deadline of the operation was exceeded")]
+    DeadlineExceeded,
+
+    #[error("The download was cancelled")]
+    DownloadCancelled,
+
+    #[error("Chain state snapshots can only be applied by a client that trusts them")]
+    UntrustedSnapshot,
+
+    #[error("The snapshot is of chain {found:?} instead of {expected:?}")]
+    SnapshotChainMismatch { expected: ChainId, found: ChainId },
+
+    #[error(
+        "The imported certificate for block {found} of chain {chain_id:?} doesn't follow \
+         block {expected}"
+    )]
+    NonContiguousImport {
+        chain_id: ChainId,
+        expected: BlockHeight,
+        found: BlockHeight,
+    },
+
     #[error(transparent)]
     NodeError(#[from] NodeError),
 }
 
+/// The kind of a [`LocalNodeError`], for bindings (e.g. the Web client or FFI) that need to
+/// tell errors apart without depending on their structure.
+///
+/// The numeric values and the names returned by [`LocalNodeErrorCode::as_str`] are stable:
+/// new codes may be added, but existing ones are never changed or reused.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+#[repr(u16)]
+pub enum LocalNodeErrorCode {
+    ArithmeticError = 1,
+    ViewError = 2,
+    WorkerError = 3,
+    CannotDownloadCertificates = 4,
+    CannotReadLocalBlob = 5,
+    InactiveChain = 6,
+    InvalidChainInfoResponse = 7,
+    DeadlineExceeded = 8,
+    DownloadCancelled = 9,
+    UntrustedSnapshot = 10,
+    SnapshotChainMismatch = 11,
+    NodeError = 12,
+    NonContiguousImport = 13,
+}
+
+impl LocalNodeErrorCode {
+    /// Returns the name of the code, e.g. `"InactiveChain"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocalNodeErrorCode::ArithmeticError => "ArithmeticError",
+            LocalNodeErrorCode::ViewError => "ViewError",
+            LocalNodeErrorCode::WorkerError => "WorkerError",
+            LocalNodeErrorCode::CannotDownloadCertificates => "CannotDownloadCertificates",
+            LocalNodeErrorCode::CannotReadLocalBlob => "CannotReadLocalBlob",
+            LocalNodeErrorCode::InactiveChain => "InactiveChain",
+            LocalNodeErrorCode::InvalidChainInfoResponse => "InvalidChainInfoResponse",
+            LocalNodeErrorCode::DeadlineExceeded => "DeadlineExceeded",
+            LocalNodeErrorCode::DownloadCancelled => "DownloadCancelled",
+            LocalNodeErrorCode::UntrustedSnapshot => "UntrustedSnapshot",
+            LocalNodeErrorCode::SnapshotChainMismatch => "SnapshotChainMismatch",
+            LocalNodeErrorCode::NodeError => "NodeError",
+            LocalNodeErrorCode::NonContiguousImport => "NonContiguousImport",
+        }
+    }
+}
+
+impl From<LocalNodeErrorCode> for u16 {
+    fn from(code: LocalNodeErrorCode) -> u16 {
+        code as u16
+    }
+}
+
+impl LocalNodeError {
+    /// Returns the stable code of the kind of error.
+    pub fn code(&self) -> LocalNodeErrorCode {
+        match self {
+            LocalNodeError::ArithmeticError(_) => LocalNodeErrorCode::ArithmeticError,
+            LocalNodeError::ViewError(_) => LocalNodeErrorCode::ViewError,
+            LocalNodeError::WorkerError(_) => LocalNodeErrorCode::WorkerError,
+            LocalNodeError::CannotDownloadCertificates { .. } => {
+                LocalNodeErrorCode::CannotDownloadCertificates
+            }
+            LocalNodeError::CannotReadLocalBlob { .. } => LocalNodeErrorCode::CannotReadLocalBlob,
+            LocalNodeError::InactiveChain(_) => LocalNodeErrorCode::InactiveChain,
+            LocalNodeError::InvalidChainInfoResponse => {
+                LocalNodeErrorCode::InvalidChainInfoResponse
+            }
+            LocalNodeError::DeadlineExceeded => LocalNodeErrorCode::DeadlineExceeded,
+            LocalNodeError::DownloadCancelled => LocalNodeErrorCode::DownloadCancelled,
+            LocalNodeError::UntrustedSnapshot => LocalNodeErrorCode::UntrustedSnapshot,
+            LocalNodeError::SnapshotChainMismatch { .. } => {
+                LocalNodeErrorCode::SnapshotChainMismatch
+            }
+            LocalNodeError::NodeError(_) => LocalNodeErrorCode::NodeError,
+            LocalNodeError::NonContiguousImport { .. } => LocalNodeErrorCode::NonContiguousImport,
+        }
+    }
+
+    /// Returns whether the error is due to a missing entry in the local storage.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            LocalNodeError::ViewError(error)
+            | LocalNodeError::WorkerError(WorkerError::ViewError(error)) => error.is_not_found(),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the error is due to the storage backend throttling requests, in
+    /// which case the operation should be retried after [`Self::retry_after`].
+    pub fn is_throttled(&self) -> bool {
+        match self {
+            LocalNodeError::ViewError(error)
+            | LocalNodeError::WorkerError(WorkerError::ViewError(error)) => error.is_throttled(),
+            _ => false,
+        }
+    }
+
+    /// Returns how long the storage backend asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LocalNodeError::ViewError(error)
+            | LocalNodeError::WorkerError(WorkerError::ViewError(error)) => error.retry_after(),
+            _ => None,
+        }
+    }
+}
+
 impl<S> LocalNodeClient<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
@@ -86,7 +773,7 @@ where
         &self,
         proposal: BlockProposal,
     ) -> Result<ChainInfoResponse, LocalNodeError> {
-        let mut node = self.node.lock().await;
+        let mut node = self.lock_node("handle_block_proposal").await;
         // In local nodes, we can trust fully_handle_certificate to carry all actions eventually.
         let (response, _actions) = node.state.handle_block_proposal(proposal).await?;
         Ok(response)
@@ -97,18 +784,88 @@ where
         certificate: LiteCertificate<'_>,
         notifications: &mut impl Extend<Notification>,
     ) -> Result<ChainInfoResponse, LocalNodeError> {
-        let mut node = self.node.lock().await;
+        let mut node = self.lock_node("handle_lite_certificate").await;
         let full_cert = node.state.full_certificate(certificate).await?;
-        let response = node
+        let mut new_notifications = Vec::new();
+        let result = node
             .state
             .fully_handle_certificate_with_notifications(
                 full_cert,
-                vec![],
-                vec![],
-                Some(notifications),
+                &[],
+                &[],
+                Some(&mut new_notifications),
             )
-            .await?;
-        Ok(response)
+            .await;
+        self.route_notifications(new_notifications, notifications);
+        Ok(result?)
+    }
+
+    /// Processes a lite certificate like [`LocalNodeClient::handle_lite_certificate`], but if
+    /// its value is not in the cache, reads it from storage or else downloads it from the
+    /// `validators`, instead of failing.
+    pub async fn handle_lite_certificate_with_download<A>(
+        &self,
+        validators: Vec<(ValidatorName, A)>,
+        certificate: LiteCertificate<'_>,
+        notifications: &mut impl Extend<Notification>,
+    ) -> Result<ChainInfoResponse, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let value_hash = certificate.value.value_hash;
+        let cached_value = self
+            .lock_node("handle_lite_certificate_with_download")
+            .await
+            .state
+            .recent_hashed_certificate_value(&value_hash)
+            .await;
+        let value = match cached_value {
+            Some(value) => value,
+            None => {
+                let storage = self.storage_client().await;
+                self.read_or_download_certificate_value(storage, validators, value_hash)
+                    .await?
+                    .ok_or(WorkerError::MissingCertificateValue)?
+            }
+        };
+        let certificate = certificate
+            .with_value(value)
+            .ok_or(WorkerError::InvalidLiteCertificate)?;
+        self.handle_certificate(certificate, vec![], vec![], notifications)
+            .await
+    }
+
+    /// Upgrades and processes the given lite certificates in order, holding the lock on the
+    /// local node for the whole batch.
+    ///
+    /// Returns one result per certificate: a certificate that fails doesn't prevent the
+    /// following ones from being processed.
+    pub async fn handle_lite_certificates(
+        &self,
+        certificates: Vec<LiteCertificate<'_>>,
+        notifications: &mut impl Extend<Notification>,
+    ) -> Vec<Result<ChainInfoResponse, LocalNodeError>> {
+        let mut node = self.lock_node("handle_lite_certificates").await;
+        let mut results = Vec::with_capacity(certificates.len());
+        let mut new_notifications = Vec::new();
+        for certificate in certificates {
+            let result = match node.state.full_certificate(certificate).await {
+                Ok(full_cert) => node
+                    .state
+                    .fully_handle_certificate_with_notifications(
+                        full_cert,
+                        &[],
+                        &[],
+                        Some(&mut new_notifications),
+                    )
+                    .await
+                    .map_err(LocalNodeError::from),
+                Err(error) => Err(error.into()),
+            };
+            results.push(result);
+        }
+        self.route_notifications(new_notifications, notifications);
+        results
     }
 
     pub async fn handle_certificate(
@@ -118,28 +875,266 @@ where
         hashed_blobs: Vec<HashedBlob>,
         notifications: &mut impl Extend<Notification>,
     ) -> Result<ChainInfoResponse, LocalNodeError> {
-        let mut node = self.node.lock().await;
-        let response = node
+        self.handle_certificate_with_dependencies(
+            certificate,
+            &hashed_certificate_values,
+            &hashed_blobs,
+            notifications,
+        )
+        .await
+    }
+
+    /// Processes a certificate like [`LocalNodeClient::handle_certificate`], but borrows
+    /// its dependencies, so that the caller keeps them in case it needs to retry.
+    pub(crate) async fn handle_certificate_with_dependencies(
+        &self,
+        certificate: Certificate,
+        hashed_certificate_values: &[HashedCertificateValue],
+        hashed_blobs: &[HashedBlob],
+        notifications: &mut impl Extend<Notification>,
+    ) -> Result<ChainInfoResponse, LocalNodeError> {
+        let mut node = self.lock_node("handle_certificate").await;
+        let mut new_notifications = Vec::new();
+        let result = node
             .state
             .fully_handle_certificate_with_notifications(
                 certificate,
                 hashed_certificate_values,
                 hashed_blobs,
-                Some(notifications),
+                Some(&mut new_notifications),
             )
-            .await?;
-        Ok(response)
+            .await;
+        self.route_notifications(new_notifications, notifications);
+        Ok(result?)
+    }
+
+    /// Processes a certificate like [`LocalNodeClient::handle_certificate`], but without
+    /// delivering its outgoing messages to the recipient chains.
+    ///
+    /// **This is only safe for a trusted chain history**, e.g. when rehydrating a node from
+    /// an archive: the messages stay in the outboxes, and the recipient chains on this node
+    /// won't receive them until they are delivered by some other means.
+    pub async fn handle_certificate_import_only(
+        &self,
+        certificate: Certificate,
+        hashed_certificate_values: Vec<HashedCertificateValue>,
+        hashed_blobs: Vec<HashedBlob>,
+        notifications: &mut impl Extend<Notification>,
+    ) -> Result<ChainInfoResponse, LocalNodeError> {
+        let mut node = self.lock_node("handle_certificate_import_only").await;
+        let mut new_notifications = Vec::new();
+        let result = node
+            .state
+            .handle_certificate_import_only(
+                certificate,
+                hashed_certificate_values,
+                hashed_blobs,
+                Some(&mut new_notifications),
+            )
+            .await;
+        self.route_notifications(new_notifications, notifications);
+        Ok(result?)
     }
 
     pub async fn handle_chain_info_query(
         &self,
         query: ChainInfoQuery,
     ) -> Result<ChainInfoResponse, LocalNodeError> {
-        let node = self.node.lock().await;
+        let node = self.lock_node("handle_chain_info_query").await;
         // In local nodes, we can trust fully_handle_certificate to carry all actions eventually.
         let (response, _actions) = node.state.handle_chain_info_query(query).await?;
         Ok(response)
     }
+
+    /// Writes a [`ChainStateSnapshot`] of the state of the chain in the local node,
+    /// serialized with BCS.
+    pub async fn export_chain_snapshot(
+        &self,
+        chain_id: ChainId,
+        mut snapshot_writer: impl std::io::Write,
+    ) -> Result<(), LocalNodeError> {
+        let storage = self.storage_client().await;
+        let base_key = storage.chain_state_base_key(chain_id)?;
+        let chain = storage.load_chain(chain_id).await?;
+        let context = chain.context();
+        let key_values = context
+            .find_key_values_by_prefix(&base_key)
+            .await
+            .map_err(ViewError::from)?
+            .into_iterator_owned()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ViewError::from)?;
+        let snapshot = ChainStateSnapshot {
+            chain_id,
+            key_values,
+        };
+        bcs::serialize_into(&mut snapshot_writer, &snapshot).map_err(ViewError::Serialization)?;
+        Ok(())
+    }
+
+    /// Replaces the state of the chain in the local node with a [`ChainStateSnapshot`],
+    /// serialized with BCS, e.g. to bootstrap a node without executing the whole history
+    /// of the chain. Returns the resulting chain information, including the block height
+    /// of the snapshot.
+    ///
+    /// **This is only safe for snapshots from a trusted source**: the state is not
+    /// verified against any certificate. It fails with [`LocalNodeError::UntrustedSnapshot`]
+    /// unless the client was built [`LocalNodeClient::with_trusted_snapshots`].
+    ///
+    /// Only the state of the chain is imported: the certificates and blobs of its history
+    /// are not, and the other chains are unaffected.
+    pub async fn apply_trusted_snapshot(
+        &self,
+        chain_id: ChainId,
+        snapshot_reader: impl std::io::Read,
+    ) -> Result<Box<ChainInfo>, LocalNodeError> {
+        ensure!(self.trusts_snapshots, LocalNodeError::UntrustedSnapshot);
+        let snapshot: ChainStateSnapshot =
+            bcs::from_reader(snapshot_reader).map_err(ViewError::Serialization)?;
+        ensure!(
+            snapshot.chain_id == chain_id,
+            LocalNodeError::SnapshotChainMismatch {
+                expected: chain_id,
+                found: snapshot.chain_id,
+            }
+        );
+        let lock = self.chain_sync_lock(chain_id);
+        let _sync_guard = lock.lock().await;
+        {
+            // Don't let the node process certificates of the chain while its state is
+            // replaced.
+            let node = self.lock_node("apply_trusted_snapshot").await;
+            let storage = node.state.storage_client();
+            let base_key = storage.chain_state_base_key(chain_id)?;
+            let chain = storage.load_chain(chain_id).await?;
+            let context = chain.context();
+            let mut batch = Batch::new();
+            batch.delete_key_prefix(base_key.clone());
+            for (key, value) in snapshot.key_values {
+                batch.put_key_value_bytes([base_key.as_slice(), &key].concat(), value);
+            }
+            context.write_batch(batch).await.map_err(ViewError::from)?;
+            storage.index_chain(chain_id).await?;
+        }
+        self.local_chain_info(chain_id).await
+    }
+
+    /// Reads the [`ArchivedCertificate`]s of an archive, e.g. exported from another node,
+    /// and processes them in order. `progress` is called after each certificate applied.
+    ///
+    /// The certificates are processed in batches of [`IMPORT_BATCH_SIZE`], holding the lock
+    /// on the local node for each batch. The blocks of each chain must follow each other
+    /// and the blocks already in the local node: certificates of blocks below the next
+    /// block height of their chain are skipped, and a gap stops the import with
+    /// [`LocalNodeError::NonContiguousImport`].
+    ///
+    /// The archive should contain certificates of confirmed blocks, like those downloaded
+    /// from validators. The import stops at the first certificate that can't be read or
+    /// fails to be processed, e.g. because it doesn't verify. The certificates applied
+    /// before are kept.
+    ///
+    /// The archive is read off the asynchronous executor, with
+    /// [`spawn_blocking`](linera_base::task::spawn_blocking).
+    pub async fn import_certificates_from_file(
+        &self,
+        mut archive_reader: impl std::io::Read + Send + 'static,
+        notifications: &mut impl Extend<Notification>,
+        mut progress: impl FnMut(ImportProgress),
+    ) -> ImportOutcome {
+        let mut applied = 0;
+        let mut next_heights = HashMap::<ChainId, BlockHeight>::new();
+        loop {
+            let (reader, batch, read_error) = task::spawn_blocking(move || {
+                let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+                let mut read_error = None;
+                while batch.len() < IMPORT_BATCH_SIZE {
+                    match ArchivedCertificate::read(&mut archive_reader) {
+                        Ok(Some(archived)) => batch.push(archived),
+                        Ok(None) => break,
+                        Err(error) => {
+                            read_error = Some(error);
+                            break;
+                        }
+                    }
+                }
+                (archive_reader, batch, read_error)
+            })
+            .await;
+            archive_reader = reader;
+            let is_last_batch = batch.len() < IMPORT_BATCH_SIZE;
+            let mut node = self.lock_node("import_certificates_from_file").await;
+            let mut new_notifications = Vec::new();
+            let mut error = None;
+            for archived in batch {
+                let value = archived.certificate.value();
+                let (chain_id, height) = (value.chain_id(), value.height());
+                let next_height = match next_heights.get(&chain_id) {
+                    Some(next_height) => *next_height,
+                    None => {
+                        let query = ChainInfoQuery::new(chain_id);
+                        match node.state.handle_chain_info_query(query).await {
+                            Ok((response, _actions)) => response.info.next_block_height,
+                            Err(worker_error) => {
+                                error = Some(worker_error.into());
+                                break;
+                            }
+                        }
+                    }
+                };
+                if height < next_height {
+                    next_heights.insert(chain_id, next_height);
+                    continue;
+                }
+                if height > next_height {
+                    error = Some(LocalNodeError::NonContiguousImport {
+                        chain_id,
+                        expected: next_height,
+                        found: height,
+                    });
+                    break;
+                }
+                let hashed_blobs = archived
+                    .blobs
+                    .into_iter()
+                    .map(Blob::into_hashed)
+                    .collect::<Vec<_>>();
+                if let Err(worker_error) = node
+                    .state
+                    .fully_handle_certificate_with_notifications(
+                        archived.certificate,
+                        &archived.hashed_certificate_values,
+                        &hashed_blobs,
+                        Some(&mut new_notifications),
+                    )
+                    .await
+                {
+                    error = Some(worker_error.into());
+                    break;
+                }
+                next_heights.insert(chain_id, height.try_add_one().unwrap_or(height));
+                applied += 1;
+                progress(ImportProgress {
+                    applied,
+                    chain_id,
+                    height,
+                });
+            }
+            drop(node);
+            self.route_notifications(new_notifications, notifications);
+            if let Some(error) = error.or(read_error) {
+                return ImportOutcome {
+                    applied,
+                    error: Some(error),
+                };
+            }
+            if is_last_batch {
+                return ImportOutcome {
+                    applied,
+                    error: None,
+                };
+            }
+        }
+    }
 }
 
 impl<S> LocalNodeClient<S> {
@@ -148,8 +1143,336 @@ impl<S> LocalNodeClient<S> {
 
         Self {
             node: Arc::new(Mutex::new(node)),
+            config: DownloadConfig::default(),
+            notification_queue: None,
+            validator_health: Arc::default(),
+            validator_features: Arc::default(),
+            rng: None,
+            deadline: None,
+            notifier: Arc::default(),
+            chain_sync_locks: Arc::default(),
+            trusts_snapshots: false,
+            error_classifier: Arc::new(classify_error),
+            downloads: Arc::default(),
+        }
+    }
+
+    /// Returns this client with the given download configuration.
+    ///
+    /// # Panics
+    ///
+    /// If the batch sizes, the parallelism, the number of pending certificates or the number
+    /// of concurrent validators are zero, or if the minimum batch size is larger than the
+    /// maximum.
+    pub fn with_config(mut self, config: DownloadConfig) -> Self {
+        assert!(
+            0 < config.min_batch_size && config.min_batch_size <= config.max_batch_size,
+            "invalid download batch sizes: {config:?}"
+        );
+        assert!(
+            config.max_parallelism > 0,
+            "the download parallelism must be positive"
+        );
+        assert!(
+            config.max_pending_certificates > 0,
+            "the number of pending certificates must be positive"
+        );
+        assert!(
+            config.max_concurrent_validators > 0,
+            "the number of concurrent validators must be positive"
+        );
+        self.config = config;
+        self
+    }
+
+    /// Returns this client with a queue of at most `capacity` notifications, to be polled
+    /// with [`LocalNodeClient::drain_notifications`].
+    ///
+    /// This is an alternative to the notification sinks passed to each call: while the
+    /// queue is set, notifications are queued instead of being delivered to those sinks.
+    /// The queue is shared with the clones of this client. When it is full, the oldest
+    /// notifications are dropped.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn with_notification_queue(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the notification queue capacity must be positive"
+        );
+        self.notification_queue = Some(Arc::new(std::sync::Mutex::new(NotificationQueue {
+            notifications: VecDeque::with_capacity(capacity),
+            capacity,
+        })));
+        self
+    }
+
+    /// Returns this client with a random number generator for the order in which
+    /// validators are tried, instead of the thread's one. This is meant for tests, where a
+    /// seeded generator makes the order reproducible.
+    ///
+    /// The generator is shared with the clones of this client.
+    pub fn with_rng(mut self, rng: StdRng) -> Self {
+        self.rng = Some(Arc::new(std::sync::Mutex::new(rng)));
+        self
+    }
+
+    /// Returns this client with a deadline for its synchronizations, downloads and
+    /// application queries, e.g. to bound a sequence of operations by a single budget.
+    ///
+    /// Operations fail with [`LocalNodeError::DeadlineExceeded`] if they are started after
+    /// the deadline, or if they don't complete before it.
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Returns this client, allowing it to apply chain state snapshots with
+    /// [`LocalNodeClient::apply_trusted_snapshot`].
+    ///
+    /// **This is only safe if all the snapshots applied come from a trusted source**: they
+    /// are not verified.
+    pub fn with_trusted_snapshots(mut self) -> Self {
+        self.trusts_snapshots = true;
+        self
+    }
+
+    /// Returns this client with a function deciding how to react to the errors of
+    /// validators when downloading certificates, instead of [`classify_error`].
+    pub fn with_error_classifier(
+        mut self,
+        classifier: impl Fn(&NodeError) -> ErrorClass + Send + Sync + 'static,
+    ) -> Self {
+        self.error_classifier = Arc::new(classifier);
+        self
+    }
+
+    /// Removes and returns up to `max` of the oldest queued notifications.
+    ///
+    /// Returns no notifications unless the client has a queue, as set with
+    /// [`LocalNodeClient::with_notification_queue`].
+    pub fn drain_notifications(&self, max: usize) -> Vec<Notification> {
+        let Some(queue) = &self.notification_queue else {
+            return Vec::new();
+        };
+        let mut queue = queue.lock().unwrap();
+        let count = max.min(queue.notifications.len());
+        queue.notifications.drain(..count).collect()
+    }
+
+    /// Subscribes to the notifications for the given chain, produced as this node processes
+    /// certificates.
+    ///
+    /// The subscription is shared with the clones of this client, and it is independent of
+    /// the notification sinks and of the notification queue.
+    pub fn subscribe_chain(&self, chain_id: ChainId) -> NotificationStream {
+        Box::pin(UnboundedReceiverStream::new(
+            self.notifier.subscribe(vec![chain_id]),
+        ))
+    }
+
+    /// Queues the `new_notifications` if the client has a queue, or delivers them to `sink`.
+    fn route_notifications(
+        &self,
+        new_notifications: Vec<Notification>,
+        sink: &mut impl Extend<Notification>,
+    ) {
+        self.notifier.handle_notifications(&new_notifications);
+        let Some(queue) = &self.notification_queue else {
+            sink.extend(new_notifications);
+            return;
+        };
+        let mut queue = queue.lock().unwrap();
+        for notification in new_notifications {
+            if queue.notifications.len() == queue.capacity {
+                tracing::debug!("Dropping the oldest notification: the queue is full");
+                queue.notifications.pop_front();
+            }
+            queue.notifications.push_back(notification);
+        }
+    }
+
+    /// Locks the local node. With metrics enabled, the time spent waiting for the lock and
+    /// holding it are recorded, labeled with the name of the calling `method`.
+    #[cfg_attr(not(with_metrics), allow(unused_variables))]
+    async fn lock_node(&self, method: &'static str) -> LocalNodeGuard<'_, S> {
+        #[cfg(with_metrics)]
+        let started_at = Instant::now();
+        let guard = self.node.lock().await;
+        #[cfg(with_metrics)]
+        let acquired_at = {
+            let acquired_at = Instant::now();
+            LOCAL_NODE_LOCK_WAIT_LATENCY
+                .with_label_values(&[method])
+                .observe((acquired_at - started_at).as_secs_f64() * 1000.0);
+            acquired_at
+        };
+        LocalNodeGuard {
+            guard,
+            #[cfg(with_metrics)]
+            method,
+            #[cfg(with_metrics)]
+            acquired_at,
+        }
+    }
+
+    /// Returns the lock that serializes the synchronizations of the given chain, shared
+    /// with the clones of this client.
+    fn chain_sync_lock(&self, chain_id: ChainId) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.chain_sync_locks.lock().unwrap();
+        // Forget the locks that nobody holds or waits for anymore.
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(chain_id).or_default().clone()
+    }
+
+    /// Acquires the synchronization lock of the chain. Returns `None` if another
+    /// synchronization of the chain was in progress, after waiting for it to finish.
+    async fn start_chain_sync(
+        &self,
+        chain_id: ChainId,
+    ) -> Option<tokio::sync::OwnedMutexGuard<()>> {
+        let lock = self.chain_sync_lock(chain_id);
+        match lock.clone().try_lock_owned() {
+            Ok(guard) => Some(guard),
+            Err(_) => {
+                drop(lock.lock_owned().await);
+                None
+            }
+        }
+    }
+
+    /// Runs the `operation`, unless the deadline of this client has passed, and cancels it
+    /// if the deadline passes before it completes.
+    async fn run_before_deadline<T>(
+        &self,
+        operation: impl Future<Output = Result<T, LocalNodeError>>,
+    ) -> Result<T, LocalNodeError> {
+        let Some(deadline) = self.deadline else {
+            return operation.await;
+        };
+        let remaining = deadline
+            .remaining()
+            .ok_or(LocalNodeError::DeadlineExceeded)?;
+        timer::timeout(remaining, operation)
+            .await
+            .map_err(|_| LocalNodeError::DeadlineExceeded)?
+    }
+
+    /// Returns the number of downloads of certificates, certificate values and blobs in
+    /// progress, by this client and its clones.
+    pub fn in_flight_downloads(&self) -> usize {
+        self.downloads.lock().unwrap().downloads.len()
+    }
+
+    /// Cancels the downloads in progress, by this client and its clones.
+    ///
+    /// Cancelled downloads of certificates fail with [`LocalNodeError::DownloadCancelled`],
+    /// and cancelled downloads of certificate values or blobs return nothing. The
+    /// certificates that were already processed are kept. Downloads started after this
+    /// call are not affected.
+    ///
+    /// The reads from storage of the cancelled downloads are aborted as well, except for
+    /// the calls already made to a backend that can't interrupt them, such as RocksDB.
+    pub fn cancel_all_downloads(&self) {
+        let downloads = mem::take(&mut self.downloads.lock().unwrap().downloads);
+        for handle in downloads.into_values() {
+            handle.abort();
+        }
+    }
+
+    /// Runs the `download`, unless it is cancelled with
+    /// [`LocalNodeClient::cancel_all_downloads`].
+    async fn run_download<F: Future>(&self, download: F) -> Result<F::Output, Aborted> {
+        let (handle, abort_registration) = AbortHandle::new_pair();
+        let _registration = {
+            let mut registry = self.downloads.lock().unwrap();
+            let id = registry.next_id;
+            registry.next_id += 1;
+            registry.downloads.insert(id, handle);
+            DownloadRegistration {
+                registry: &self.downloads,
+                id,
+            }
+        };
+        Abortable::new(download, abort_registration).await
+    }
+
+    /// Shuffles the validators, to spread the load across them.
+    pub(crate) fn shuffle_validators<T>(&self, validators: &mut [T]) {
+        match &self.rng {
+            Some(rng) => validators.shuffle(&mut *rng.lock().unwrap()),
+            None => validators.shuffle(&mut rand::thread_rng()),
+        }
+    }
+
+    /// Returns whether the validator may be queried, according to its circuit breaker, and
+    /// if so, the admission with which to record the outcome of the query.
+    ///
+    /// If the validator's cooldown is over, this lets a single query through to probe it.
+    pub(crate) fn admit_validator(&self, name: ValidatorName) -> Option<ValidatorAdmission<'_, S>> {
+        let mut admission = ValidatorAdmission {
+            client: self,
+            name,
+            probe_since: None,
+        };
+        let Some(CircuitBreakerConfig { cooldown, .. }) = self.config.circuit_breaker else {
+            return Some(admission);
+        };
+        let mut validator_health = self.validator_health.lock().unwrap();
+        let Some(health) = validator_health.get_mut(&name) else {
+            return Some(admission);
+        };
+        match *health {
+            ValidatorHealth::Closed { .. } => Some(admission),
+            ValidatorHealth::Open { since } if since.elapsed() >= cooldown => {
+                tracing::debug!("Probing validator {}", redact(name));
+                *health = ValidatorHealth::HalfOpen;
+                admission.probe_since = Some(since);
+                Some(admission)
+            }
+            ValidatorHealth::Open { .. } | ValidatorHealth::HalfOpen => None,
         }
     }
+
+    /// Updates the circuit breaker of the validator after a query succeeded or failed.
+    fn record_validator_outcome(&self, name: ValidatorName, succeeded: bool) {
+        let Some(config) = self.config.circuit_breaker else {
+            return;
+        };
+        let mut validator_health = self.validator_health.lock().unwrap();
+        if succeeded {
+            validator_health.remove(&name);
+            return;
+        }
+        let health = validator_health
+            .entry(name)
+            .or_insert(ValidatorHealth::Closed {
+                consecutive_failures: 0,
+            });
+        let consecutive_failures = match *health {
+            ValidatorHealth::Closed {
+                consecutive_failures,
+            } => consecutive_failures + 1,
+            ValidatorHealth::HalfOpen => config.failure_threshold,
+            // A query that was sent before the validator was skipped.
+            ValidatorHealth::Open { .. } => return,
+        };
+        *health = if consecutive_failures >= config.failure_threshold {
+            tracing::info!(
+                "Skipping validator {} for {:?} after {consecutive_failures} failures",
+                redact(name),
+                config.cooldown
+            );
+            ValidatorHealth::Open {
+                since: Instant::now(),
+            }
+        } else {
+            ValidatorHealth::Closed {
+                consecutive_failures,
+            }
+        };
+    }
 }
 
 impl<S> LocalNodeClient<S>
@@ -157,7 +1480,7 @@ where
     S: Clone,
 {
     pub(crate) async fn storage_client(&self) -> S {
-        let node = self.node.lock().await;
+        let node = self.lock_node("storage_client").await;
         node.state.storage_client().clone()
     }
 }
@@ -171,7 +1494,7 @@ where
         &self,
         block: Block,
     ) -> Result<(ExecutedBlock, ChainInfoResponse), LocalNodeError> {
-        let mut node = self.node.lock().await;
+        let mut node = self.lock_node("stage_block_execution").await;
         let (executed_block, info) = node.state.stage_block_execution(block).await?;
         Ok((executed_block, info))
     }
@@ -185,20 +1508,29 @@ where
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        future::join_all(locations.iter().map(|location| {
-            let mut node = node.clone();
-            async move {
-                Self::try_download_hashed_certificate_value_from(&mut node, name, *location).await
-            }
-        }))
-        .await
-        .into_iter()
-        .flatten()
+        let hashes = locations
+            .iter()
+            .map(|location| location.certificate_hash)
+            .collect::<Vec<_>>();
+        stream::iter(
+            hashes.into_iter().map(|hash| {
+                let mut node = node.clone();
+                async move {
+                    Self::try_download_hashed_certificate_value_from(&mut node, name, hash).await
+                }
+            }),
+        )
+        .buffer_unordered(self.config.max_parallelism)
+        .filter_map(future::ready)
         .collect::<Vec<_>>()
+        .await
     }
 
+    /// Downloads the given blobs from the validator, all at once with a single query if it
+    /// supports it, and one by one otherwise.
     async fn find_missing_blobs<A>(
         &self,
+        chain_id: ChainId,
         blob_ids: &[BlobId],
         node: &mut A,
         name: ValidatorName,
@@ -206,14 +1538,79 @@ where
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        future::join_all(blob_ids.iter().map(|blob_id| {
+        let mut blobs = if self
+            .negotiated_features(name, node)
+            .await
+            .contains(features::BLOB_QUERIES)
+        {
+            Self::try_query_blobs_from(name, node, chain_id, blob_ids).await
+        } else {
+            Vec::new()
+        };
+        blobs.retain(|blob| self.has_acceptable_size(name, blob));
+        let missing_blob_ids = blob_ids
+            .iter()
+            .filter(|blob_id| !blobs.iter().any(|blob| blob.id() == **blob_id))
+            .copied()
+            .collect::<Vec<_>>();
+        let missing_blobs = stream::iter(missing_blob_ids.into_iter().map(|blob_id| {
             let mut node = node.clone();
-            async move { Self::try_download_blob_from(name, &mut node, *blob_id).await }
+            async move { Self::try_download_blob_from(name, &mut node, blob_id).await }
         }))
-        .await
-        .into_iter()
-        .flatten()
+        .buffer_unordered(self.config.max_parallelism)
+        .filter_map(|blob| future::ready(blob.filter(|blob| self.has_acceptable_size(name, blob))))
         .collect::<Vec<_>>()
+        .await;
+        blobs.extend(missing_blobs);
+        blobs
+    }
+
+    /// Returns the optional capabilities supported by both the validator and this client.
+    ///
+    /// The validator is asked for its features the first time, and the result is kept for
+    /// the lifetime of the client. If the validator can't be asked, it is assumed to support
+    /// none of them, until it is asked again next time.
+    async fn negotiated_features<A>(&self, name: ValidatorName, node: &mut A) -> BTreeSet<String>
+    where
+        A: LocalValidatorNode,
+    {
+        if let Some(features) = self.validator_features.lock().unwrap().get(&name) {
+            return features.clone();
+        }
+        match node.get_features().await {
+            Ok(features) => {
+                let features = negotiate_features(features.iter().map(String::as_str));
+                self.validator_features
+                    .lock()
+                    .unwrap()
+                    .insert(name, features.clone());
+                features
+            }
+            Err(error) => {
+                tracing::debug!(
+                    "Failed to get the features of validator {}: {error}",
+                    redact(name)
+                );
+                BTreeSet::new()
+            }
+        }
+    }
+
+    /// Returns whether the blob sent by the validator is within the configured maximum size.
+    fn has_acceptable_size(&self, name: ValidatorName, blob: &HashedBlob) -> bool {
+        let size = blob.blob().bytes.len() as u64;
+        match self.config.max_blob_size {
+            Some(max_blob_size) if size > max_blob_size => {
+                tracing::info!(
+                    "Validator {} sent blob {} of {size} bytes, more than the maximum of \
+                     {max_blob_size}.",
+                    redact(name),
+                    redact(blob.id()),
+                );
+                false
+            }
+            _ => true,
+        }
     }
 
     async fn try_process_certificates<A>(
@@ -221,53 +1618,126 @@ where
         name: ValidatorName,
         node: &mut A,
         chain_id: ChainId,
-        certificates: Vec<Certificate>,
-        notifications: &mut impl Extend<Notification>,
-    ) -> Option<Box<ChainInfo>>
+        batch: CertificateBatch,
+        notifications: &mut impl NotificationSink,
+        certificate_sender: Option<&CertificateSender>,
+    ) -> (Option<Box<ChainInfo>>, usize)
     where
         A: LocalValidatorNode + Clone + 'static,
     {
+        let CertificateBatch {
+            certificate_hashes,
+            mut hashed_certificate_values,
+            mut hashed_blobs,
+        } = batch;
         let mut info = None;
-        for certificate in certificates {
-            let hash = certificate.hash();
-            if !certificate.value().is_confirmed() || certificate.value().chain_id() != chain_id {
-                // The certificate is not as expected. Give up.
-                tracing::warn!("Failed to process network certificate {}", hash);
-                return info;
-            }
-            let mut result = self
-                .handle_certificate(certificate.clone(), vec![], vec![], notifications)
-                .await;
+        let mut processed = 0;
+        // Only download a bounded number of certificates at once, and release them once
+        // they are processed, so that long ranges don't use unbounded memory.
+        for hashes in certificate_hashes.chunks(self.config.max_pending_certificates) {
+            let certificates = future::try_join_all(hashes.iter().map(|hash| {
+                let mut node = node.clone();
+                async move { node.download_certificate(*hash).await }
+            }))
+            .await;
+            let certificates = match certificates {
+                Ok(certificates) => certificates,
+                Err(error) => {
+                    tracing::warn!("Failed to download certificates: {}", error);
+                    return (info, processed);
+                }
+            };
+            for certificate in certificates {
+                let hash = certificate.hash();
+                let Some(block) = certificate.value().block().filter(|block| {
+                    certificate.value().is_confirmed() && block.chain_id == chain_id
+                }) else {
+                    // The certificate is not as expected. Give up.
+                    tracing::warn!("Failed to process network certificate {}", redact(hash));
+                    return (info, processed);
+                };
+                // Only pass the dependencies sent along with the batch that this block uses, and
+                // release them: once the block is processed, they are in the local storage.
+                let locations = block.bytecode_locations();
+                let (mut values, remaining_values) = mem::take(&mut hashed_certificate_values)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|value| {
+                        locations
+                            .iter()
+                            .any(|location| location.certificate_hash == value.hash())
+                    });
+                hashed_certificate_values = remaining_values;
+                let blob_ids = block.blob_ids();
+                let (mut blobs, remaining_blobs) = mem::take(&mut hashed_blobs)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|blob| blob_ids.contains(&blob.id()));
+                hashed_blobs = remaining_blobs;
+                // Deliver the notifications of each certificate before processing the next one.
+                let mut new_notifications = Vec::new();
+                let mut result = self
+                    .handle_certificate_with_dependencies(
+                        certificate.clone(),
+                        &values,
+                        &blobs,
+                        &mut new_notifications,
+                    )
+                    .await;
 
-            result = match &result {
-                Err(LocalNodeError::WorkerError(
-                    WorkerError::ApplicationBytecodesOrBlobsNotFound(locations, blob_ids),
-                )) => {
-                    let values = self
-                        .find_missing_application_bytecodes(locations, node, name)
-                        .await;
-                    let blobs = self.find_missing_blobs(blob_ids, node, name).await;
-                    if values.len() != locations.len() || blobs.len() != blob_ids.len() {
-                        result
-                    } else {
-                        self.handle_certificate(certificate, values, blobs, notifications)
+                result = match &result {
+                    Err(LocalNodeError::WorkerError(
+                        WorkerError::ApplicationBytecodesOrBlobsNotFound(locations, blob_ids),
+                    )) => {
+                        let missing_values = self
+                            .find_missing_application_bytecodes(locations, node, name)
+                            .await;
+                        // Blobs may have arrived concurrently since the worker checked.
+                        let missing_blob_ids = self.missing_blob_ids(blob_ids).await;
+                        let missing_blobs = self
+                            .find_missing_blobs(chain_id, &missing_blob_ids, node, name)
+                            .await;
+                        if missing_values.len() != locations.len()
+                            || missing_blobs.len() != missing_blob_ids.len()
+                        {
+                            result
+                        } else {
+                            values.extend(missing_values);
+                            blobs.extend(missing_blobs);
+                            self.handle_certificate_with_dependencies(
+                                certificate.clone(),
+                                &values,
+                                &blobs,
+                                &mut new_notifications,
+                            )
                             .await
+                        }
                     }
-                }
-                _ => result,
-            };
+                    _ => result,
+                };
+                notifications.deliver(new_notifications).await;
 
-            match result {
-                Ok(response) => info = Some(response.info),
-                Err(error) => {
-                    // The certificate is not as expected. Give up.
-                    tracing::warn!("Failed to process network certificate {}: {}", hash, error);
-                    return info;
-                }
-            };
+                match result {
+                    Ok(response) => {
+                        if let Some(sender) = certificate_sender {
+                            // The receiver may have been dropped, but the download goes on.
+                            let _ = sender.send(Ok(certificate));
+                        }
+                        info = Some(response.info);
+                        processed += 1;
+                    }
+                    Err(error) => {
+                        // The certificate is not as expected. Give up.
+                        tracing::warn!(
+                            "Failed to process network certificate {}: {}",
+                            redact(hash),
+                            error
+                        );
+                        return (info, processed);
+                    }
+                };
+            }
         }
         // Done with all certificates.
-        info
+        (info, processed)
     }
 
     /// Returns a read-only view of the [`ChainStateView`] of a chain referenced by its
@@ -279,10 +1749,108 @@ where
         &self,
         chain_id: ChainId,
     ) -> Result<OwnedRwLockReadGuard<ChainStateView<S::Context>>, WorkerError> {
-        let node = self.node.lock().await;
+        let node = self.lock_node("chain_state_view").await;
         node.state.chain_state_view(chain_id).await
     }
 
+    /// Checks that a certificate is signed by a quorum of the current committee of its chain,
+    /// as known by the local node, without applying it.
+    ///
+    /// This doesn't change the state of the local node.
+    pub async fn verify_certificate(
+        &self,
+        certificate: &Certificate,
+    ) -> Result<(), LocalNodeError> {
+        let chain_id = certificate.value().chain_id();
+        let chain = self.chain_state_view(chain_id).await?;
+        chain.ensure_is_active().map_err(WorkerError::from)?;
+        let (chain_epoch, committee) = chain
+            .execution_state
+            .system
+            .current_committee()
+            .expect("chain is active");
+        let epoch = certificate.value().epoch();
+        ensure!(
+            epoch == chain_epoch,
+            WorkerError::InvalidEpoch {
+                chain_id,
+                chain_epoch,
+                epoch
+            }
+        );
+        certificate.check(committee).map_err(WorkerError::from)?;
+        Ok(())
+    }
+
+    /// Deletes the certificates of the blocks of a chain below the given height, and returns
+    /// how many were deleted.
+    ///
+    /// Certificates that are still needed are kept: the one of the latest block, those with
+    /// messages not yet delivered or still sent to new channel subscribers, those publishing
+    /// bytecode, and those in the cache of recent values.
+    pub async fn prune_certificates(
+        &self,
+        chain_id: ChainId,
+        below: BlockHeight,
+    ) -> Result<u64, LocalNodeError> {
+        let storage = self.storage_client().await;
+        let recent_values = self
+            .node
+            .lock()
+            .await
+            .state
+            .recent_hashed_certificate_values();
+        let chain = self.chain_state_view(chain_id).await?;
+        let mut protected_heights = chain
+            .outbox_counters
+            .get()
+            .keys()
+            .copied()
+            .collect::<BTreeSet<_>>();
+        for name in chain.channels.indices().await? {
+            if let Some(channel) = chain.channels.try_load_entry(&name).await? {
+                protected_heights.extend(*channel.block_height.get());
+            }
+        }
+        let tip_height = chain.tip_state.get().next_block_height.try_sub_one();
+        let end = match tip_height {
+            Ok(tip_height) => below.min(tip_height),
+            Err(_) => return Ok(0),
+        };
+        let hashes = chain.confirmed_log.read(..usize::try_from(end)?).await?;
+        let mut pruned = Vec::new();
+        for (height, hash) in (0..).map(BlockHeight).zip(hashes) {
+            if protected_heights.contains(&height) || recent_values.contains(&hash).await {
+                continue;
+            }
+            let value = match storage.read_hashed_certificate_value(hash).await {
+                Ok(value) => value,
+                // Already pruned.
+                Err(error) if error.is_not_found() => continue,
+                Err(error) => return Err(error.into()),
+            };
+            let publishes_bytecode = value.inner().block().is_some_and(|block| {
+                block.operations.iter().any(|operation| {
+                    matches!(
+                        operation,
+                        Operation::System(SystemOperation::PublishBytecode { .. })
+                    )
+                })
+            });
+            if !publishes_bytecode {
+                pruned.push(hash);
+            }
+        }
+        storage.delete_certificates(&pruned).await?;
+        Ok(pruned.len() as u64)
+    }
+
+    /// Runs the maintenance operation `op` of the storage backend, e.g. to compact RocksDB
+    /// during a low-traffic window rather than whenever it compacts in the background.
+    pub async fn maintenance(&self, op: MaintenanceOp) -> Result<(), LocalNodeError> {
+        Ok(self.storage_client().await.maintenance(op).await?)
+    }
+
     pub(crate) async fn local_chain_info(
         &self,
         chain_id: ChainId,
@@ -291,14 +1859,51 @@ where
         Ok(self.handle_chain_info_query(query).await?.info)
     }
 
+    /// Waits until the local node has the blocks of the chain below `height`, i.e. until
+    /// the next block height of the chain is at least `height`.
+    ///
+    /// Returns immediately if the height is already reached. Otherwise, the height is
+    /// checked again whenever this client processes a new block of the chain. The waiting
+    /// can be bounded with [`LocalNodeClient::with_deadline`].
+    pub async fn wait_for_height(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<(), LocalNodeError> {
+        self.run_before_deadline(async move {
+            // Subscribe before checking the height, so that no new block is missed.
+            let mut notifications = self.subscribe_chain(chain_id);
+            if self.local_chain_info(chain_id).await?.next_block_height >= height {
+                return Ok(());
+            }
+            while let Some(notification) = notifications.next().await {
+                if matches!(notification.reason, Reason::NewBlock { .. })
+                    && self.local_chain_info(chain_id).await?.next_block_height >= height
+                {
+                    return Ok(());
+                }
+            }
+            unreachable!("the notifier lives as long as the client");
+        })
+        .await
+    }
+
+    /// Returns the IDs of all the chains the local node has a state for, in ascending order.
+    pub async fn local_chains(&self) -> Result<Vec<ChainId>, LocalNodeError> {
+        Ok(self.storage_client().await.list_chain_ids().await?)
+    }
+
     pub async fn query_application(
         &self,
         chain_id: ChainId,
         query: Query,
     ) -> Result<Response, LocalNodeError> {
-        let mut node = self.node.lock().await;
-        let response = node.state.query_application(chain_id, query).await?;
-        Ok(response)
+        self.run_before_deadline(async move {
+            let mut node = self.lock_node("query_application").await;
+            let response = node.state.query_application(chain_id, query).await?;
+            Ok(response)
+        })
+        .await
     }
 
     pub async fn describe_application(
@@ -306,7 +1911,7 @@ where
         chain_id: ChainId,
         application_id: UserApplicationId,
     ) -> Result<UserApplicationDescription, LocalNodeError> {
-        let mut node = self.node.lock().await;
+        let mut node = self.lock_node("describe_application").await;
         let response = node
             .state
             .describe_application(chain_id, application_id)
@@ -315,39 +1920,173 @@ where
     }
 
     pub async fn recent_blob(&self, blob_id: &BlobId) -> Option<HashedBlob> {
-        let mut node = self.node.lock().await;
+        let mut node = self.lock_node("recent_blob").await;
         node.state.recent_blob(blob_id).await
     }
 
-    pub async fn recent_hashed_blobs(&self) -> Arc<ValueCache<BlobId, HashedBlob>> {
-        let node = self.node.lock().await;
-        node.state.recent_hashed_blobs()
+    /// Reads the content of a blob as a stream of chunks, checked against the blob ID as
+    /// they are read, from the cache of recent blobs or else from storage.
+    pub async fn read_blob_stream(&self, blob_id: BlobId) -> Result<BlobStream, LocalNodeError> {
+        if let Some(hashed_blob) = self.recent_blob(&blob_id).await {
+            return Ok(BlobStream::from_blob(hashed_blob));
+        }
+        Ok(self
+            .storage_client()
+            .await
+            .read_blob_stream(blob_id)
+            .await?)
+    }
+
+    pub async fn recent_hashed_blobs(&self) -> Arc<ValueCache<BlobId, HashedBlob>> {
+        let node = self.lock_node("recent_hashed_blobs").await;
+        node.state.recent_hashed_blobs()
+    }
+
+    /// Returns a copy of the recently used blobs, for a point-in-time view of the cache.
+    ///
+    /// Neither the local node nor the cache stay locked after this returns.
+    pub async fn snapshot_recent_blobs(&self) -> Vec<(BlobId, HashedBlob)> {
+        let recent_hashed_blobs = self.recent_hashed_blobs().await;
+        recent_hashed_blobs.entries().await
+    }
+
+    pub async fn cache_recent_blob(&self, hashed_blob: &HashedBlob) -> bool {
+        let mut node = self.lock_node("cache_recent_blob").await;
+        node.state
+            .cache_recent_blob(Cow::Borrowed(hashed_blob))
+            .await
+    }
+
+    /// Inserts a [`HashedBlob`] into the cache after checking that its ID is the hash of the
+    /// blob, even in release builds.
+    ///
+    /// Returns [`false`] without inserting it if the ID is wrong, or if it was already present.
+    pub async fn cache_recent_blob_verified(&self, hashed_blob: &HashedBlob) -> bool {
+        if !hashed_blob.has_valid_id() {
+            tracing::error!(
+                "Refusing to cache blob {}: wrong ID",
+                redact(hashed_blob.id())
+            );
+            return false;
+        }
+        self.cache_recent_blob(hashed_blob).await
+    }
+
+    /// Returns the given blob IDs that are neither in the cache of recent blobs nor in
+    /// storage, checked in a single pass while the local node is locked.
+    ///
+    /// Blobs whose presence in storage can't be determined are reported as missing.
+    pub async fn missing_blob_ids(&self, blob_ids: &[BlobId]) -> Vec<BlobId> {
+        let node = self.lock_node("missing_blob_ids").await;
+        let uncached_blob_ids: Vec<BlobId> = node
+            .state
+            .recent_hashed_blobs()
+            .subtract_cached_items_from(blob_ids.iter().copied(), |blob_id| blob_id)
+            .await;
+        let storage = node.state.storage_client();
+        let stored = future::join_all(
+            uncached_blob_ids
+                .iter()
+                .map(|blob_id| storage.contains_blob(*blob_id)),
+        )
+        .await;
+        uncached_blob_ids
+            .into_iter()
+            .zip(stored)
+            .filter(|(_, stored)| !matches!(stored, Ok(true)))
+            .map(|(blob_id, _)| blob_id)
+            .collect()
+    }
+
+    pub async fn download_certificates<A>(
+        &self,
+        validators: Vec<(ValidatorName, A)>,
+        chain_id: ChainId,
+        target_next_block_height: BlockHeight,
+        notifications: &mut impl NotificationSink,
+    ) -> Result<Box<ChainInfo>, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        self.run_download(self.run_before_deadline(Box::pin(
+            self.download_certificates_with_sender(
+                validators,
+                chain_id,
+                target_next_block_height,
+                notifications,
+                None,
+            ),
+        )))
+        .await
+        .unwrap_or(Err(LocalNodeError::DownloadCancelled))
     }
 
-    pub async fn cache_recent_blob(&self, hashed_blob: &HashedBlob) -> bool {
-        let mut node = self.node.lock().await;
-        node.state
-            .cache_recent_blob(Cow::Borrowed(hashed_blob))
-            .await
+    /// Downloads the certificates like [`LocalNodeClient::download_certificates`], and
+    /// yields each of them as soon as it is processed by the local node, without waiting
+    /// for the whole range.
+    ///
+    /// The stream ends with an error if the local node couldn't reach the target height.
+    pub fn download_certificates_stream<'a, A>(
+        &'a self,
+        validators: Vec<(ValidatorName, A)>,
+        chain_id: ChainId,
+        target_next_block_height: BlockHeight,
+        notifications: &'a mut impl NotificationSink,
+    ) -> impl Stream<Item = Result<Certificate, LocalNodeError>> + 'a
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let download = async move {
+            let result = self
+                .run_download(self.run_before_deadline(Box::pin(
+                    self.download_certificates_with_sender(
+                        validators,
+                        chain_id,
+                        target_next_block_height,
+                        notifications,
+                        Some(&sender),
+                    ),
+                )))
+                .await
+                .unwrap_or(Err(LocalNodeError::DownloadCancelled));
+            if let Err(error) = result {
+                // Sent through the channel, so that it comes after the certificates.
+                let _ = sender.send(Err(error));
+            }
+        };
+        // The download only drives the channel, and yields no items itself.
+        stream::select(
+            UnboundedReceiverStream::new(receiver),
+            download.into_stream().filter_map(|()| future::ready(None)),
+        )
     }
 
-    pub async fn download_certificates<A>(
+    async fn download_certificates_with_sender<A>(
         &self,
         mut validators: Vec<(ValidatorName, A)>,
         chain_id: ChainId,
         target_next_block_height: BlockHeight,
-        notifications: &mut impl Extend<Notification>,
+        notifications: &mut impl NotificationSink,
+        certificate_sender: Option<&CertificateSender>,
     ) -> Result<Box<ChainInfo>, LocalNodeError>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        // Sequentially try each validator in random order.
-        validators.shuffle(&mut rand::thread_rng());
-        for (name, node) in validators {
+        // Wait for the other downloads of this chain, which may make this one unnecessary.
+        let _guard = self.chain_sync_lock(chain_id).lock_owned().await;
+        // Sequentially try each validator.
+        if self.config.validator_selection == ValidatorSelection::Random {
+            self.shuffle_validators(&mut validators);
+        }
+        for (index, (name, node)) in validators.into_iter().enumerate() {
             let info = self.local_chain_info(chain_id).await?;
             if target_next_block_height <= info.next_block_height {
                 return Ok(info);
             }
+            if index > 0 {
+                timer::sleep(self.config.backoff).await;
+            }
             self.try_download_certificates_from(
                 name,
                 node,
@@ -355,6 +2094,7 @@ where
                 info.next_block_height,
                 target_next_block_height,
                 notifications,
+                certificate_sender,
             )
             .await?;
         }
@@ -372,6 +2112,9 @@ where
     /// Downloads and stores the specified hashed certificate values, unless they are already in the cache or storage.
     ///
     /// Does not fail if a hashed certificate value can't be downloaded; it just gets omitted from the result.
+    ///
+    /// The values are downloaded concurrently, and each one is stored and cached as soon as
+    /// it is available: if this call is cancelled, the values obtained so far are kept.
     pub async fn read_or_download_hashed_certificate_values<A>(
         &self,
         validators: Vec<(ValidatorName, A)>,
@@ -382,7 +2125,10 @@ where
     {
         let mut values = vec![];
         let mut tasks = vec![];
-        let mut node = self.node.lock().await;
+        let mut node = self
+            .lock_node("read_or_download_hashed_certificate_values")
+            .await;
+        let cache = node.state.recent_hashed_certificate_values();
         for location in hashed_certificate_value_locations {
             if let Some(value) = node
                 .state
@@ -393,29 +2139,27 @@ where
             } else {
                 let validators = validators.clone();
                 let storage = node.state.storage_client().clone();
-                tasks.push(Self::read_or_download_hashed_certificate_value(
-                    storage, validators, location,
-                ));
+                let cache = cache.clone();
+                tasks.push(async move {
+                    let value = self
+                        .read_or_download_hashed_certificate_value(storage, validators, location)
+                        .await?;
+                    if let Some(value) = &value {
+                        cache.insert(Cow::Borrowed(value)).await;
+                    }
+                    Ok::<_, LocalNodeError>(value)
+                });
             }
         }
         drop(node); // Free the lock while awaiting the tasks.
-        if tasks.is_empty() {
-            return Ok(values);
-        }
-        let results = future::join_all(tasks).await;
-        let mut node = self.node.lock().await;
-        for result in results {
-            if let Some(value) = result? {
-                node.state
-                    .cache_recent_hashed_certificate_value(Cow::Borrowed(&value))
-                    .await;
-                values.push(value);
-            }
+        for result in future::join_all(tasks).await {
+            values.extend(result?);
         }
         Ok(values)
     }
 
     pub async fn read_or_download_hashed_certificate_value<A>(
+        &self,
         storage: S,
         validators: Vec<(ValidatorName, A)>,
         location: BytecodeLocation,
@@ -423,18 +2167,30 @@ where
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        match storage
-            .read_hashed_certificate_value(location.certificate_hash)
+        self.read_or_download_certificate_value(storage, validators, location.certificate_hash)
             .await
-        {
+    }
+
+    /// Reads the hashed certificate value with the given hash from storage, or downloads it
+    /// from the validators and stores it.
+    async fn read_or_download_certificate_value<A>(
+        &self,
+        storage: S,
+        validators: Vec<(ValidatorName, A)>,
+        hash: CryptoHash,
+    ) -> Result<Option<HashedCertificateValue>, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        match storage.read_hashed_certificate_value(hash).await {
             Ok(hashed_certificate_value) => return Ok(Some(hashed_certificate_value)),
-            Err(ViewError::NotFound(..)) => {}
-            Err(err) => Err(err)?,
+            Err(error) if error.is_not_found() => {}
+            Err(error) => return Err(error.into()),
         }
-        match Self::download_hashed_certificate_value(validators, location).await {
+        match self.download_certificate_value(validators, hash).await {
             Some(hashed_certificate_value) => {
                 storage
-                    .write_hashed_certificate_value(&hashed_certificate_value)
+                    .write_hashed_certificate_value_if_absent(&hashed_certificate_value)
                     .await?;
                 Ok(Some(hashed_certificate_value))
             }
@@ -447,12 +2203,24 @@ where
         &self,
         message_id: &MessageId,
     ) -> Result<Certificate, LocalNodeError> {
+        let storage = self.storage_client().await;
+        if let Some(hash) = storage
+            .read_certificate_hash_for_message(message_id)
+            .await?
+        {
+            match storage.read_certificate(hash).await {
+                Ok(certificate) if certificate.value().has_message(message_id) => {
+                    return Ok(certificate)
+                }
+                Ok(_) | Err(ViewError::NotFound(_)) => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        // The index is absent for certificates written before it was introduced.
         let query = ChainInfoQuery::new(message_id.chain_id)
             .with_sent_certificate_hashes_in_range(BlockHeightRange::single(message_id.height));
         let info = self.handle_chain_info_query(query).await?.info;
-        let certificates = self
-            .storage_client()
-            .await
+        let certificates = storage
             .read_certificates(info.requested_sent_certificate_hashes)
             .await?;
         let certificate = certificates
@@ -464,42 +2232,98 @@ where
         Ok(certificate)
     }
 
-    async fn try_download_certificates_from<A>(
+    #[instrument(skip_all, fields(
+        chain_id = format!("{:.8}", chain_id),
+        validator = %redact(name),
+        start = %start,
+        stop = %stop,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn try_download_certificates_from<A>(
         &self,
         name: ValidatorName,
         mut node: A,
         chain_id: ChainId,
         mut start: BlockHeight,
         stop: BlockHeight,
-        notifications: &mut impl Extend<Notification>,
+        notifications: &mut impl NotificationSink,
+        certificate_sender: Option<&CertificateSender>,
     ) -> Result<(), LocalNodeError>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        while start < stop {
-            // TODO(#2045): Analyze network errors instead of guessing the batch size.
-            let limit = u64::from(stop)
-                .checked_sub(u64::from(start))
-                .ok_or(ArithmeticError::Overflow)?
-                .min(1000);
-            let Some(certificates) = self
-                .try_query_certificates_from(name, &mut node, chain_id, start, limit)
-                .await?
-            else {
+        let mut batch_size = self.config.max_batch_size;
+        let mut retries = 0;
+        // Nothing is queried if the local node already has the certificates up to the target.
+        while let Some(remaining) = u64::from(stop)
+            .checked_sub(u64::from(start))
+            .filter(|remaining| *remaining > 0)
+        {
+            let Some(admission) = self.admit_validator(name) else {
+                tracing::debug!("Skipping validator after repeated failures");
                 break;
             };
-            let Some(info) = self
-                .try_process_certificates(name, &mut node, chain_id, certificates, notifications)
+            let limit = remaining.min(batch_size);
+            let query = self.try_query_certificates_from(name, &mut node, chain_id, start, limit);
+            let batch = match timer::timeout(self.config.validator_timeout, query).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::debug!("Timed out querying certificates");
+                    Ok(Err(ErrorClass::ShrinkBatch))
+                }
+            };
+            admission.record(matches!(batch, Ok(Ok(_))));
+            let batch = match batch? {
+                Ok(batch) => batch,
+                Err(ErrorClass::ShrinkBatch) if batch_size > self.config.min_batch_size => {
+                    batch_size = (batch_size / 2).max(self.config.min_batch_size);
+                    continue;
+                }
+                Err(ErrorClass::Retry) if retries < self.config.max_retries => {
+                    retries += 1;
+                    continue;
+                }
+                Err(class) => {
+                    tracing::debug!(?class, "Giving up on validator");
+                    break;
+                }
+            };
+            retries = 0;
+            // A validator that doesn't have the later blocks yet legitimately returns fewer
+            // certificates than requested.
+            let is_partial = (batch.certificate_hashes.len() as u64) < limit;
+            let (Some(info), _) = self
+                .try_process_certificates(
+                    name,
+                    &mut node,
+                    chain_id,
+                    batch,
+                    notifications,
+                    certificate_sender,
+                )
                 .await
             else {
                 break;
             };
-            assert!(info.next_block_height > start);
+            if info.next_block_height <= start {
+                tracing::warn!("Validator sent certificates below the requested range");
+                break;
+            }
             start = info.next_block_height;
+            if is_partial {
+                // Keep what was returned, and let the next validator provide the rest.
+                break;
+            }
         }
         Ok(())
     }
 
+    #[instrument(skip_all, fields(
+        chain_id = format!("{:.8}", chain_id),
+        validator = %redact(name),
+        start = %start,
+        limit = limit,
+    ))]
     async fn try_query_certificates_from<A>(
         &self,
         name: ValidatorName,
@@ -507,163 +2331,587 @@ where
         chain_id: ChainId,
         start: BlockHeight,
         limit: u64,
-    ) -> Result<Option<Vec<Certificate>>, LocalNodeError>
+    ) -> Result<Result<CertificateBatch, ErrorClass>, LocalNodeError>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        tracing::debug!(?name, ?chain_id, ?start, ?limit, "Querying certificates");
+        tracing::debug!("Querying certificates");
         let range = BlockHeightRange {
             start,
             limit: Some(limit),
         };
-        let query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
-        if let Ok(response) = node.handle_chain_info_query(query).await {
-            if response.check(name).is_err() {
-                return Ok(None);
-            }
-            let ChainInfo {
-                requested_sent_certificate_hashes,
-                ..
-            } = *response.info;
-
-            let certificates =
-                future::try_join_all(requested_sent_certificate_hashes.into_iter().map(|hash| {
-                    let mut node = node.clone();
-                    async move { node.download_certificate(hash).await }
-                }))
-                .await?;
-            Ok(Some(certificates))
-        } else {
-            Ok(None)
+        let mut query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
+        if self.config.include_certificate_dependencies {
+            query = query.with_sent_certificate_dependencies();
+        }
+        let response = match node.handle_chain_info_query(query).await {
+            Ok(response) => response,
+            Err(error) => {
+                tracing::debug!(%error, "Failed to query certificates");
+                return Ok(Err((self.error_classifier)(&error)));
+            }
+        };
+        if response.check(name).is_err() {
+            tracing::warn!("Ignoring invalid response from validator");
+            return Ok(Err(ErrorClass::AbandonValidator));
         }
+        let ChainInfo {
+            requested_sent_certificate_hashes,
+            requested_hashed_certificate_values,
+            mut requested_blobs,
+            ..
+        } = *response.info;
+        requested_blobs.retain(|blob| self.has_acceptable_size(name, blob));
+        Ok(Ok(CertificateBatch {
+            certificate_hashes: requested_sent_certificate_hashes,
+            hashed_certificate_values: requested_hashed_certificate_values,
+            hashed_blobs: requested_blobs,
+        }))
+    }
+
+    /// Estimates how many blocks of the chain the local node is missing, by comparing its
+    /// height with the tips reported by the validators, without downloading certificates.
+    ///
+    /// Validators that don't answer within the configured timeout, or whose response isn't
+    /// properly signed, are ignored. If none of them answers, the tip is assumed to be the
+    /// local height.
+    #[instrument(skip_all, fields(chain_id = format!("{:.8}", chain_id)))]
+    pub async fn estimate_sync_work<A>(
+        &self,
+        chain_id: ChainId,
+        validators: Vec<(ValidatorName, A)>,
+    ) -> Result<SyncEstimate, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let local_next_block_height = self.local_chain_info(chain_id).await?.next_block_height;
+        let tips = stream::iter(validators.into_iter().map(|(name, mut node)| async move {
+            let query = node.handle_chain_info_query(ChainInfoQuery::new(chain_id));
+            match timer::timeout(self.config.validator_timeout, query).await {
+                Ok(Ok(response)) if response.check(name).is_ok() => {
+                    Some((response.info.next_block_height, response.info.block_hash))
+                }
+                Ok(Ok(_)) => {
+                    tracing::debug!("Ignoring invalid response from {}", redact(name));
+                    None
+                }
+                Ok(Err(error)) => {
+                    tracing::debug!("Failed to query {}: {error}", redact(name));
+                    None
+                }
+                Err(_) => {
+                    tracing::debug!("Timed out querying {}", redact(name));
+                    None
+                }
+            }
+        }))
+        .buffer_unordered(self.config.max_parallelism)
+        .filter_map(future::ready)
+        .collect::<Vec<_>>()
+        .await;
+        let tip = tips.iter().copied().max_by_key(|(height, _)| *height);
+        let tip_next_block_height = tip.map_or(local_next_block_height, |(height, _)| height);
+        let blocks_behind =
+            u64::from(tip_next_block_height).saturating_sub(u64::from(local_next_block_height));
+        Ok(SyncEstimate {
+            local_next_block_height,
+            tip_next_block_height,
+            blocks_behind,
+            agreeing_validators: tips.iter().filter(|other| Some(**other) == tip).count(),
+            responding_validators: tips.len(),
+        })
     }
 
+    /// Synchronizes the chain state from all the `validators`, and reports what happened
+    /// with each of them.
+    #[instrument(skip_all, fields(chain_id = format!("{:.8}", chain_id)))]
     pub async fn synchronize_chain_state<A>(
         &self,
         validators: Vec<(ValidatorName, A)>,
         chain_id: ChainId,
-        notifications: &mut impl Extend<Notification>,
-    ) -> Result<Box<ChainInfo>, LocalNodeError>
+        notifications: &mut impl NotificationSink,
+    ) -> Result<SyncReport, LocalNodeError>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        let mut futures = vec![];
-
-        for (name, node) in validators {
-            let client = self.clone();
-            let mut notifications = vec![];
-            futures.push(async move {
-                (
-                    client
-                        .try_synchronize_chain_state_from(name, node, chain_id, &mut notifications)
-                        .await,
-                    notifications,
-                )
-            });
-        }
+        self.run_before_deadline(Box::pin(async move {
+            let Some(_guard) = self.start_chain_sync(chain_id).await else {
+                // The chain was just synchronized by another caller.
+                return Ok(SyncReport {
+                    final_info: self.local_chain_info(chain_id).await?,
+                    per_validator: Vec::new(),
+                });
+            };
+            let mut results = stream::iter(validators.into_iter().map(|(name, node)| {
+                let client = self.clone();
+                let mut notifications = vec![];
+                async move {
+                    (
+                        name,
+                        client
+                            .synchronize_chain_state_and_get_tip_from(
+                                name,
+                                node,
+                                chain_id,
+                                &mut notifications,
+                            )
+                            .await,
+                        notifications,
+                    )
+                }
+            }))
+            .buffer_unordered(self.config.max_concurrent_validators);
+
+            let mut locked_certificates = Vec::new();
+            let mut per_validator = Vec::new();
+            while let Some((name, (outcome, report), notifications_)) = results.next().await {
+                if let Some(report) = report {
+                    locked_certificates.extend(report.locked_certificate.map(|cert| (name, cert)));
+                }
+                if let Some(ValidatorSyncError::LocalNodeError(e)) = &outcome.error {
+                    tracing::error!(?e, "Error synchronizing chain state");
+                }
+                per_validator.push(outcome);
 
-        for (result, notifications_) in future::join_all(futures).await {
-            if let Err(e) = result {
-                tracing::error!(?e, "Error synchronizing chain state");
+                notifications.deliver(notifications_).await;
             }
+            self.apply_locked_certificates(chain_id, locked_certificates, notifications)
+                .await;
 
-            notifications.extend(notifications_);
-        }
+            Ok(SyncReport {
+                final_info: self.local_chain_info(chain_id).await?,
+                per_validator,
+            })
+        }))
+        .await
+    }
 
-        self.local_chain_info(chain_id).await
+    /// Synchronizes the chain from the `bootstrap_validators`, and returns the validators of
+    /// the committee of its current epoch, ready to be passed to the download methods.
+    ///
+    /// The committee is read from the local chain state once it has been synchronized, so
+    /// it is only trusted as far as the certificates that the bootstrap validators sent.
+    pub async fn refresh_committee<P>(
+        &self,
+        chain_id: ChainId,
+        bootstrap_validators: Vec<(ValidatorName, P::Node)>,
+        node_provider: &P,
+        notifications: &mut impl NotificationSink,
+    ) -> Result<Vec<(ValidatorName, P::Node)>, LocalNodeError>
+    where
+        P: LocalValidatorNodeProvider,
+    {
+        self.synchronize_chain_state(bootstrap_validators, chain_id, notifications)
+            .await?;
+        let query = ChainInfoQuery::new(chain_id).with_committees();
+        let response = self.handle_chain_info_query(query).await?;
+        let committee = response
+            .latest_committee()
+            .ok_or(LocalNodeError::InactiveChain(chain_id))?;
+        Ok(node_provider.make_nodes(committee)?)
+    }
+
+    /// Downloads the missing certificates of several chains, taking turns between the
+    /// chains so that they all make progress: each turn downloads at most
+    /// [`DownloadConfig::chain_slice_size`] blocks of a chain.
+    ///
+    /// The tips of the chains are those reported by the validators when the synchronization
+    /// starts. The chains are synchronized independently: a chain that fails is skipped,
+    /// without preventing the others from progressing.
+    ///
+    /// Returns, in the order of `chain_ids`, the chain information of the local node, or
+    /// the error that stopped the synchronization of the chain.
+    #[instrument(skip_all)]
+    pub async fn synchronize_chains<A>(
+        &self,
+        validators: Vec<(ValidatorName, A)>,
+        chain_ids: Vec<ChainId>,
+        notifications: &mut impl NotificationSink,
+    ) -> Result<Vec<Result<Box<ChainInfo>, LocalNodeError>>, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        self.run_before_deadline(Box::pin(async move {
+            let estimates = future::join_all(
+                chain_ids
+                    .iter()
+                    .map(|chain_id| self.estimate_sync_work(*chain_id, validators.clone())),
+            )
+            .await;
+            let mut errors = HashMap::new();
+            let mut pending = VecDeque::new();
+            for (chain_id, estimate) in chain_ids.iter().zip(estimates) {
+                match estimate {
+                    Ok(estimate) if estimate.blocks_behind > 0 => {
+                        pending.push_back((*chain_id, estimate.tip_next_block_height))
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        tracing::warn!(%chain_id, %error, "Failed to estimate sync work");
+                        errors.insert(*chain_id, error);
+                    }
+                }
+            }
+            let slice_size = self.config.chain_slice_size.max(1);
+            while let Some((chain_id, tip)) = pending.pop_front() {
+                let result = match self.local_chain_info(chain_id).await {
+                    Ok(info) => {
+                        let next_block_height = info.next_block_height.0;
+                        let target = BlockHeight(next_block_height.saturating_add(slice_size));
+                        self.download_certificates(
+                            validators.clone(),
+                            chain_id,
+                            target.min(tip),
+                            notifications,
+                        )
+                        .await
+                    }
+                    Err(error) => Err(error),
+                };
+                match result {
+                    Ok(info) if info.next_block_height < tip => pending.push_back((chain_id, tip)),
+                    Ok(_) => {}
+                    Err(error) => {
+                        tracing::warn!(%chain_id, %error, "Failed to synchronize chain");
+                        errors.insert(chain_id, error);
+                    }
+                }
+            }
+            Ok(future::join_all(chain_ids.into_iter().map(|chain_id| {
+                let error = errors.remove(&chain_id);
+                async move {
+                    match error {
+                        Some(error) => Err(error),
+                        None => self.local_chain_info(chain_id).await,
+                    }
+                }
+            }))
+            .await)
+        }))
+        .await
+    }
+
+    /// Synchronizes the chain state from validators until a quorum of them agree on the tip
+    /// of the chain, rather than from all of them.
+    ///
+    /// Only enough validators to reach a quorum are queried at first, in the order given by
+    /// the download configuration. More are queried if some of them fail or report
+    /// conflicting tips. The queries still in flight when a quorum agrees are cancelled.
+    #[instrument(skip_all, fields(chain_id = format!("{:.8}", chain_id)))]
+    pub async fn synchronize_chain_state_from_quorum<A>(
+        &self,
+        mut validators: Vec<(ValidatorName, A)>,
+        committee: &Committee,
+        chain_id: ChainId,
+        notifications: &mut impl NotificationSink,
+    ) -> Result<Box<ChainInfo>, LocalNodeError>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        self.run_before_deadline(Box::pin(async move {
+            let Some(_guard) = self.start_chain_sync(chain_id).await else {
+                // The chain was just synchronized by another caller.
+                return self.local_chain_info(chain_id).await;
+            };
+            if self.config.validator_selection == ValidatorSelection::Random {
+                self.shuffle_validators(&mut validators);
+            }
+            let quorum_threshold = committee.quorum_threshold();
+            let mut validators = validators.into_iter();
+            let mut futures = FuturesUnordered::new();
+            let mut pending_weight = 0;
+            let mut weights_by_tip = HashMap::<_, u64>::new();
+            let mut locked_certificates = Vec::new();
+            loop {
+                // Query more validators until those in flight could complete a quorum.
+                let best_weight = weights_by_tip.values().copied().max().unwrap_or(0);
+                while pending_weight + best_weight < quorum_threshold {
+                    let Some((name, node)) = validators.next() else {
+                        break;
+                    };
+                    let weight = committee.weight(&name);
+                    pending_weight += weight;
+                    let client = self.clone();
+                    futures.push(async move {
+                        let mut notifications = vec![];
+                        let (outcome, report) = client
+                            .synchronize_chain_state_and_get_tip_from(
+                                name,
+                                node,
+                                chain_id,
+                                &mut notifications,
+                            )
+                            .await;
+                        if let Some(ValidatorSyncError::LocalNodeError(e)) = outcome.error {
+                            tracing::error!(?e, "Error synchronizing chain state");
+                        }
+                        (name, weight, report, notifications)
+                    });
+                }
+                let Some((name, weight, report, new_notifications)) = futures.next().await else {
+                    tracing::warn!("Validators don't agree on the tip of the chain");
+                    break;
+                };
+                pending_weight -= weight;
+                notifications.deliver(new_notifications).await;
+                if let Some(report) = report {
+                    locked_certificates.extend(report.locked_certificate.map(|cert| (name, cert)));
+                    let tip_weight = weights_by_tip.entry(report.tip).or_default();
+                    *tip_weight += weight;
+                    if *tip_weight >= quorum_threshold {
+                        break;
+                    }
+                }
+            }
+            self.apply_locked_certificates(chain_id, locked_certificates, notifications)
+                .await;
+            self.local_chain_info(chain_id).await
+        }))
+        .await
     }
 
+    /// Synchronizes the chain state from a single validator, and reports what happened.
+    #[instrument(skip_all, fields(
+        chain_id = format!("{:.8}", chain_id),
+        validator = %redact(name),
+    ))]
     pub async fn try_synchronize_chain_state_from<A>(
+        &self,
+        name: ValidatorName,
+        node: A,
+        chain_id: ChainId,
+        notifications: &mut impl NotificationSink,
+    ) -> ValidatorSyncOutcome
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let (outcome, report) = self
+            .synchronize_chain_state_and_get_tip_from(name, node, chain_id, notifications)
+            .await;
+        let locked_certificates = report
+            .and_then(|report| report.locked_certificate)
+            .map(|cert| (name, cert))
+            .into_iter()
+            .collect();
+        self.apply_locked_certificates(chain_id, locked_certificates, notifications)
+            .await;
+        outcome
+    }
+
+    /// Synchronizes the chain state from a validator, and reports what happened, together
+    /// with what the validator reported unless its response or certificates were invalid.
+    ///
+    /// The locked certificate is applied right away with
+    /// [`LockedCertificatePolicy::ApplyAll`], and returned otherwise.
+    async fn synchronize_chain_state_and_get_tip_from<A>(
         &self,
         name: ValidatorName,
         mut node: A,
         chain_id: ChainId,
-        notifications: &mut impl Extend<Notification>,
-    ) -> Result<(), LocalNodeError>
+        notifications: &mut impl NotificationSink,
+    ) -> (ValidatorSyncOutcome, Option<ValidatorChainReport>)
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        let local_info = self.local_chain_info(chain_id).await?;
+        let mut outcome = ValidatorSyncOutcome::new(name);
+        let local_info = match self.local_chain_info(chain_id).await {
+            Ok(local_info) => local_info,
+            Err(error) => return (outcome.failed(error), None),
+        };
+        let Some(admission) = self.admit_validator(name) else {
+            tracing::debug!("Skipping validator after repeated failures");
+            return (outcome.failed(ValidatorSyncError::Skipped), None);
+        };
         let range = BlockHeightRange {
             start: local_info.next_block_height,
             limit: None,
         };
-        let query = ChainInfoQuery::new(chain_id)
+        let mut query = ChainInfoQuery::new(chain_id)
             .with_sent_certificate_hashes_in_range(range)
             .with_manager_values();
-        let info = match node.handle_chain_info_query(query).await {
-            Ok(response) if response.check(name).is_ok() => response.info,
+        if self.config.include_certificate_dependencies {
+            query = query.with_sent_certificate_dependencies();
+        }
+        let mut info = match node.handle_chain_info_query(query).await {
+            Ok(response) if response.check(name).is_ok() => {
+                admission.record(true);
+                response.info
+            }
             Ok(_) => {
                 tracing::warn!("Ignoring invalid response from validator");
+                admission.record(false);
                 // Give up on this validator.
-                return Ok(());
+                return (outcome.failed(ValidatorSyncError::InvalidResponse), None);
             }
             Err(err) => {
                 tracing::warn!("Ignoring error from validator: {}", err);
-                return Ok(());
+                admission.record(false);
+                return (outcome.failed(err), None);
             }
         };
+        let tip = (info.next_block_height, info.block_hash);
+        outcome.tip = Some(tip);
 
-        let certificates = future::try_join_all(
-            info.requested_sent_certificate_hashes
-                .into_iter()
-                .map(|hash| {
-                    let mut node = node.clone();
-                    async move { node.download_certificate(hash).await }
-                }),
-        )
-        .await?;
-
-        if !certificates.is_empty()
-            && self
-                .try_process_certificates(name, &mut node, chain_id, certificates, notifications)
-                .await
-                .is_none()
-        {
-            return Ok(());
+        let mut hashed_blobs = mem::take(&mut info.requested_blobs);
+        hashed_blobs.retain(|blob| self.has_acceptable_size(name, blob));
+        let batch = CertificateBatch {
+            certificate_hashes: mem::take(&mut info.requested_sent_certificate_hashes),
+            hashed_certificate_values: mem::take(&mut info.requested_hashed_certificate_values),
+            hashed_blobs,
         };
+
+        if !batch.certificate_hashes.is_empty() {
+            let count = batch.certificate_hashes.len();
+            let (processed_info, processed) = self
+                .try_process_certificates(name, &mut node, chain_id, batch, notifications, None)
+                .await;
+            outcome.certificates = processed;
+            if processed < count {
+                outcome.error = Some(ValidatorSyncError::InvalidCertificates);
+            }
+            if processed_info.is_none() {
+                return (outcome, None);
+            }
+        }
         if let Some(proposal) = info.manager.requested_proposed {
             if proposal.content.block.chain_id == chain_id {
                 let owner = proposal.owner;
                 if let Err(error) = self.handle_block_proposal(*proposal).await {
-                    tracing::warn!("Skipping proposal from {}: {}", owner, error);
+                    tracing::warn!("Skipping proposal from {}: {}", redact(owner), error);
                 }
             }
         }
-        if let Some(cert) = info.manager.requested_locked {
-            if cert.value().is_validated() && cert.value().chain_id() == chain_id {
-                let hash = cert.hash();
-                if let Err(error) = self
-                    .handle_certificate(*cert, vec![], vec![], notifications)
-                    .await
-                {
-                    tracing::warn!("Skipping certificate {}: {}", hash, error);
+        let mut locked_certificate = info
+            .manager
+            .requested_locked
+            .filter(|cert| cert.value().is_validated() && cert.value().chain_id() == chain_id);
+        if self.config.locked_certificate_policy == LockedCertificatePolicy::ApplyAll {
+            if let Some(cert) = locked_certificate.take() {
+                self.apply_locked_certificate(*cert, notifications).await;
+            }
+        }
+        let report = ValidatorChainReport {
+            tip,
+            locked_certificate,
+        };
+        (outcome, Some(report))
+    }
+
+    /// Applies the locked certificates reported by validators that the
+    /// [`LockedCertificatePolicy`] accepts.
+    async fn apply_locked_certificates(
+        &self,
+        chain_id: ChainId,
+        locked_certificates: Vec<(ValidatorName, Box<Certificate>)>,
+        notifications: &mut impl NotificationSink,
+    ) {
+        let certificate = match self.config.locked_certificate_policy {
+            LockedCertificatePolicy::ApplyAll => {
+                for (_, cert) in locked_certificates {
+                    self.apply_locked_certificate(*cert, notifications).await;
                 }
+                return;
             }
+            LockedCertificatePolicy::HighestRound => {
+                let Some(round) = locked_certificates.iter().map(|(_, cert)| cert.round).max()
+                else {
+                    return;
+                };
+                let mut hashes = locked_certificates
+                    .iter()
+                    .filter(|(_, cert)| cert.round == round)
+                    .map(|(_, cert)| cert.hash())
+                    .collect::<BTreeSet<_>>();
+                if hashes.len() > 1 {
+                    tracing::warn!(?round, "Validators are locked on different blocks");
+                    return;
+                }
+                let hash = hashes.pop_first();
+                locked_certificates
+                    .into_iter()
+                    .find(|(_, cert)| Some(cert.hash()) == hash)
+            }
+            LockedCertificatePolicy::Quorum => {
+                if locked_certificates.is_empty() {
+                    return;
+                }
+                let query = ChainInfoQuery::new(chain_id).with_committees();
+                let response = self.handle_chain_info_query(query).await.ok();
+                let Some(committee) = response.as_ref().and_then(|r| r.latest_committee()) else {
+                    tracing::warn!("Ignoring locked certificates: the committee is unknown");
+                    return;
+                };
+                let mut weights = HashMap::<_, u64>::new();
+                for (name, cert) in &locked_certificates {
+                    *weights.entry(cert.hash()).or_default() += committee.weight(name);
+                }
+                locked_certificates
+                    .into_iter()
+                    .find(|(_, cert)| weights[&cert.hash()] >= committee.quorum_threshold())
+            }
+        };
+        if let Some((_, cert)) = certificate {
+            self.apply_locked_certificate(*cert, notifications).await;
+        }
+    }
+
+    /// Applies a validated certificate that a validator is locked on, logging any error.
+    async fn apply_locked_certificate(
+        &self,
+        cert: Certificate,
+        notifications: &mut impl NotificationSink,
+    ) {
+        let hash = cert.hash();
+        let mut new_notifications = Vec::new();
+        let result = self
+            .handle_certificate(cert, vec![], vec![], &mut new_notifications)
+            .await;
+        notifications.deliver(new_notifications).await;
+        if let Err(error) = result {
+            tracing::warn!("Skipping certificate {}: {}", redact(hash), error);
         }
-        Ok(())
     }
 
     pub async fn download_hashed_certificate_value<A>(
-        mut validators: Vec<(ValidatorName, A)>,
+        &self,
+        validators: Vec<(ValidatorName, A)>,
         location: BytecodeLocation,
     ) -> Option<HashedCertificateValue>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        self.download_certificate_value(validators, location.certificate_hash)
+            .await
+    }
+
+    /// Downloads the hashed certificate value with the given hash from the first of the
+    /// validators that has it.
+    async fn download_certificate_value<A>(
+        &self,
+        mut validators: Vec<(ValidatorName, A)>,
+        hash: CryptoHash,
+    ) -> Option<HashedCertificateValue>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
         // Sequentially try each validator in random order, to improve efficiency.
-        validators.shuffle(&mut rand::thread_rng());
-        for (name, mut node) in validators {
-            if let Some(value) =
-                Self::try_download_hashed_certificate_value_from(&mut node, name, location).await
-            {
-                return Some(value);
+        self.shuffle_validators(&mut validators);
+        self.run_download(async move {
+            for (name, mut node) in validators {
+                if let Some(value) =
+                    Self::try_download_hashed_certificate_value_from(&mut node, name, hash).await
+                {
+                    return Some(value);
+                }
             }
-        }
-        None
+            None
+        })
+        .await
+        .unwrap_or(None)
     }
 
     pub async fn download_blob<A>(
+        &self,
         mut validators: Vec<(ValidatorName, A)>,
         blob_id: BlobId,
     ) -> Option<HashedBlob>
@@ -671,15 +2919,20 @@ where
         A: LocalValidatorNode + Clone + 'static,
     {
         // Sequentially try each validator in random order.
-        validators.shuffle(&mut rand::thread_rng());
-        for (name, mut node) in validators {
-            if let Some(blob) = Self::try_download_blob_from(name, &mut node, blob_id).await {
-                return Some(blob);
+        self.shuffle_validators(&mut validators);
+        self.run_download(async move {
+            for (name, mut node) in validators {
+                if let Some(blob) = Self::try_download_blob_from(name, &mut node, blob_id).await {
+                    return Some(blob);
+                }
             }
-        }
-        None
+            None
+        })
+        .await
+        .unwrap_or(None)
     }
 
+    #[instrument(skip_all, fields(validator = %redact(name), blob_id = %redact(blob_id)))]
     async fn try_download_blob_from<A>(
         name: ValidatorName,
         node: &mut A,
@@ -688,35 +2941,89 @@ where
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        match node.download_blob(blob_id).await.map(Blob::into_hashed) {
-            Ok(hashed_blob) if hashed_blob.id() == blob_id => Some(hashed_blob),
+        // The blob is hashed with the algorithm of the requested ID.
+        match node.download_blob(blob_id).await {
+            Ok(blob) if blob_id.is_id_of(&blob) => Some(blob.with_hash_unchecked(blob_id)),
             Ok(_) => {
-                tracing::info!("Validator {name} sent an invalid blob {blob_id}.");
+                tracing::info!(
+                    "Validator {} sent an invalid blob {}.",
+                    redact(name),
+                    redact(blob_id)
+                );
                 None
             }
             Err(error) => {
-                tracing::debug!("Failed to fetch blob {blob_id} from validator {name}: {error}");
+                tracing::debug!(
+                    "Failed to fetch blob {} from validator {}: {error}",
+                    redact(blob_id),
+                    redact(name)
+                );
                 None
             }
         }
     }
 
+    /// Queries the validator for all the given blobs at once, and returns the ones it sent
+    /// among them.
+    async fn try_query_blobs_from<A>(
+        name: ValidatorName,
+        node: &mut A,
+        chain_id: ChainId,
+        blob_ids: &[BlobId],
+    ) -> Vec<HashedBlob>
+    where
+        A: LocalValidatorNode + Clone + 'static,
+    {
+        let query = ChainInfoQuery::new(chain_id).with_blobs(blob_ids.to_vec());
+        match node.handle_chain_info_query(query).await {
+            Ok(response) => {
+                let mut blobs = Vec::new();
+                // Blobs are content-addressed, so they don't need the response to be signed.
+                for blob in response.info.requested_blobs {
+                    if !blob_ids.contains(&blob.id()) {
+                        tracing::info!(
+                            "Validator {} sent blob {}, which was not requested.",
+                            redact(name),
+                            redact(blob.id())
+                        );
+                    } else if blobs
+                        .iter()
+                        .all(|known: &HashedBlob| known.id() != blob.id())
+                    {
+                        blobs.push(blob);
+                    }
+                }
+                blobs
+            }
+            Err(error) => {
+                tracing::debug!(
+                    "Failed to query blobs from validator {}: {error}",
+                    redact(name)
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    #[instrument(skip_all, fields(
+        validator = %redact(name),
+        hash = %redact(hash),
+    ))]
     async fn try_download_hashed_certificate_value_from<A>(
         node: &mut A,
         name: ValidatorName,
-        location: BytecodeLocation,
+        hash: CryptoHash,
     ) -> Option<HashedCertificateValue>
     where
         A: LocalValidatorNode + Clone + 'static,
     {
-        match node
-            .download_certificate_value(location.certificate_hash)
-            .await
-        {
+        match node.download_certificate_value(hash).await {
             Ok(hashed_certificate_value) => Some(hashed_certificate_value),
             Err(error) => {
                 tracing::debug!(
-                    "Failed to fetch certificate value {location:?} from validator {name}: {error}"
+                    "Failed to fetch certificate value {} from validator {}: {error}",
+                    redact(hash),
+                    redact(name)
                 );
                 None
             }
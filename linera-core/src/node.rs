@@ -16,7 +16,7 @@ use linera_execution::{
     committee::{Committee, ValidatorName},
     BytecodeLocation,
 };
-use linera_version::VersionInfo;
+use linera_version::{IncompatibleVersionError, VersionInfo};
 use linera_views::views::ViewError;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -75,6 +75,10 @@ pub trait LocalValidatorNode {
     /// Gets the version info for this validator node.
     async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError>;
 
+    /// Gets the names of the optional capabilities supported by this validator node, as
+    /// listed in [`linera_version::features`].
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError>;
+
     /// Subscribes to receiving notifications for a collection of chains.
     async fn subscribe(
         &mut self,
@@ -209,6 +213,9 @@ pub enum NodeError {
 
     #[error("Failed to make a chain info query on the local node: {error}")]
     LocalNodeQuery { error: String },
+
+    #[error("Incompatible versions: {error}")]
+    IncompatibleVersion { error: String },
 }
 
 impl From<tonic::Status> for NodeError {
@@ -237,6 +244,14 @@ impl CrossChainMessageDelivery {
     }
 }
 
+impl From<IncompatibleVersionError> for NodeError {
+    fn from(error: IncompatibleVersionError) -> Self {
+        Self::IncompatibleVersion {
+            error: error.to_string(),
+        }
+    }
+}
+
 impl From<ViewError> for NodeError {
     fn from(error: ViewError) -> Self {
         Self::ViewError {
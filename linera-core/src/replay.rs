@@ -0,0 +1,532 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recording the responses of validators to a file, and replaying them instead of querying
+//! the validators.
+//!
+//! A synchronization issue observed with real validators can be recorded by passing
+//! [`Recorder::wrap`]ped validators to the download methods of a
+//! [`LocalNodeClient`](crate::local_node::LocalNodeClient), and reproduced offline by
+//! passing the [`Replay::validators`] instead.
+//!
+//! Only the queries are recorded: chain information queries, version queries and downloads.
+//! The other requests are forwarded unrecorded, and fail when replaying. Replayed nodes
+//! send no notifications.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, BufReader, BufWriter, Write as _},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use futures::stream;
+use linera_base::{
+    crypto::CryptoHash, data_types::Blob, data_types::HashedBlob, identifiers::BlobId, task,
+};
+use linera_chain::data_types::{
+    BlockProposal, Certificate, HashedCertificateValue, LiteCertificate,
+};
+use linera_execution::committee::ValidatorName;
+use linera_version::VersionInfo;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    data_types::{ChainInfoQuery, ChainInfoResponse},
+    length_prefixed,
+    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+};
+
+/// A query to a validator whose response is recorded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RecordedRequest {
+    ChainInfoQuery(ChainInfoQuery),
+    VersionInfo,
+    DownloadBlob(BlobId),
+    DownloadCertificateValue(CryptoHash),
+    DownloadCertificate(CryptoHash),
+    BlobLastUsedBy(BlobId),
+    Features,
+}
+
+/// The response of a validator to a [`RecordedRequest`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RecordedResponse {
+    ChainInfo(Result<ChainInfoResponse, NodeError>),
+    VersionInfo(Result<VersionInfo, NodeError>),
+    Blob(Result<Blob, NodeError>),
+    CertificateValue(Result<HashedCertificateValue, NodeError>),
+    Certificate(Result<Certificate, NodeError>),
+    BlobLastUsedBy(Result<CryptoHash, NodeError>),
+    Features(Result<Vec<String>, NodeError>),
+}
+
+/// A query to a validator and its response, as recorded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    /// The validator that was queried.
+    pub validator: ValidatorName,
+    /// The query.
+    pub request: RecordedRequest,
+    /// The response of the validator.
+    pub response: RecordedResponse,
+}
+
+/// An error reading a recording.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Failed to read the recording: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to deserialize the recording: {0}")]
+    Deserialization(#[from] bcs::Error),
+}
+
+/// Appends the exchanges with validators to a file, each as its length in bytes, as a
+/// little-endian `u64`, followed by its BCS serialization.
+///
+/// Each exchange is written as soon as the response is received, so that a recording is
+/// kept up to the point where a client crashed. The file is written off the asynchronous
+/// executor, with [`spawn_blocking`](linera_base::task::spawn_blocking).
+#[derive(Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl Recorder {
+    /// Creates a recorder writing to a new file at `path`, replacing any existing file.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Recorder {
+            file: Arc::new(Mutex::new(BufWriter::new(file))),
+        })
+    }
+
+    /// Returns the `validators` with their responses recorded by this recorder.
+    pub fn wrap<A>(
+        &self,
+        validators: Vec<(ValidatorName, A)>,
+    ) -> Vec<(ValidatorName, RecordingNode<A>)> {
+        validators
+            .into_iter()
+            .map(|(name, node)| {
+                let node = RecordingNode {
+                    name,
+                    node,
+                    recorder: self.clone(),
+                };
+                (name, node)
+            })
+            .collect()
+    }
+
+    /// Appends an exchange to the file. Failures are logged rather than returned, so that
+    /// recording doesn't change the behavior of the client.
+    async fn record(
+        &self,
+        validator: ValidatorName,
+        request: RecordedRequest,
+        response: RecordedResponse,
+    ) {
+        let exchange = RecordedExchange {
+            validator,
+            request,
+            response,
+        };
+        let result = match bcs::to_bytes(&exchange) {
+            Ok(bytes) => {
+                let file = self.file.clone();
+                task::spawn_blocking(move || {
+                    let mut file = file.lock().unwrap();
+                    length_prefixed::write_record(&mut *file, &bytes)?;
+                    file.flush()
+                })
+                .await
+            }
+            Err(error) => Err(io::Error::other(error)),
+        };
+        if let Err(error) = result {
+            tracing::warn!("Failed to record a response of validator {validator}: {error}");
+        }
+    }
+}
+
+/// Reads the exchanges written by a [`Recorder`], in the order in which they were
+/// recorded.
+///
+/// The last exchange is skipped if it is cut short, e.g. because the client crashed while
+/// writing it, so that the complete exchanges before it can still be replayed.
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedExchange>, ReplayError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut exchanges = Vec::new();
+    loop {
+        match length_prefixed::read_record(&mut reader) {
+            Ok(Some(bytes)) => exchanges.push(bcs::from_bytes(&bytes)?),
+            Ok(None) => break,
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                tracing::warn!("Skipping the last exchange of the recording: {error}");
+                break;
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(exchanges)
+}
+
+/// A validator whose responses to queries are recorded.
+#[derive(Clone)]
+pub struct RecordingNode<A> {
+    name: ValidatorName,
+    node: A,
+    recorder: Recorder,
+}
+
+impl<A> ValidatorNode for RecordingNode<A>
+where
+    A: ValidatorNode + Send,
+{
+    type NotificationStream = A::NotificationStream;
+
+    async fn handle_block_proposal(
+        &mut self,
+        proposal: BlockProposal,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.node.handle_block_proposal(proposal).await
+    }
+
+    async fn handle_lite_certificate(
+        &mut self,
+        certificate: LiteCertificate<'_>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.node
+            .handle_lite_certificate(certificate, delivery)
+            .await
+    }
+
+    async fn handle_certificate(
+        &mut self,
+        certificate: Certificate,
+        hashed_certificate_values: Vec<HashedCertificateValue>,
+        hashed_blobs: Vec<HashedBlob>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.node
+            .handle_certificate(
+                certificate,
+                hashed_certificate_values,
+                hashed_blobs,
+                delivery,
+            )
+            .await
+    }
+
+    async fn handle_chain_info_query(
+        &mut self,
+        query: ChainInfoQuery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let result = self.node.handle_chain_info_query(query.clone()).await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::ChainInfoQuery(query),
+                RecordedResponse::ChainInfo(result.clone()),
+            )
+            .await;
+        result
+    }
+
+    async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
+        let result = self.node.get_version_info().await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::VersionInfo,
+                RecordedResponse::VersionInfo(result.clone()),
+            )
+            .await;
+        result
+    }
+
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        let result = self.node.get_features().await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::Features,
+                RecordedResponse::Features(result.clone()),
+            )
+            .await;
+        result
+    }
+
+    async fn subscribe(
+        &mut self,
+        chains: Vec<linera_base::identifiers::ChainId>,
+    ) -> Result<Self::NotificationStream, NodeError> {
+        self.node.subscribe(chains).await
+    }
+
+    async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
+        let result = self.node.download_blob(blob_id).await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::DownloadBlob(blob_id),
+                RecordedResponse::Blob(result.clone()),
+            )
+            .await;
+        result
+    }
+
+    async fn download_certificate_value(
+        &mut self,
+        hash: CryptoHash,
+    ) -> Result<HashedCertificateValue, NodeError> {
+        let result = self.node.download_certificate_value(hash).await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::DownloadCertificateValue(hash),
+                RecordedResponse::CertificateValue(result.clone()),
+            )
+            .await;
+        result
+    }
+
+    async fn download_certificate(&mut self, hash: CryptoHash) -> Result<Certificate, NodeError> {
+        let result = self.node.download_certificate(hash).await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::DownloadCertificate(hash),
+                RecordedResponse::Certificate(result.clone()),
+            )
+            .await;
+        result
+    }
+
+    async fn blob_last_used_by(&mut self, blob_id: BlobId) -> Result<CryptoHash, NodeError> {
+        let result = self.node.blob_last_used_by(blob_id).await;
+        self.recorder
+            .record(
+                self.name,
+                RecordedRequest::BlobLastUsedBy(blob_id),
+                RecordedResponse::BlobLastUsedBy(result.clone()),
+            )
+            .await;
+        result
+    }
+}
+
+/// The responses left to serve, by validator and [`request_key`].
+type RecordedResponses = HashMap<(ValidatorName, Vec<u8>), VecDeque<RecordedResponse>>;
+
+/// The recorded responses of validators, served in place of the validators.
+///
+/// The responses of a validator to identical queries are served in the order in which they
+/// were recorded. A query without a response left fails with [`NodeError::ClientIoError`].
+#[derive(Clone)]
+pub struct Replay {
+    validators: Vec<ValidatorName>,
+    responses: Arc<Mutex<RecordedResponses>>,
+}
+
+impl Replay {
+    /// Loads the recording written by a [`Recorder`] to the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Ok(Self::new(read_recording(path)?))
+    }
+
+    /// Creates a replay of the given exchanges.
+    pub fn new(exchanges: impl IntoIterator<Item = RecordedExchange>) -> Self {
+        let mut validators = Vec::new();
+        let mut responses = RecordedResponses::new();
+        for exchange in exchanges {
+            if !validators.contains(&exchange.validator) {
+                validators.push(exchange.validator);
+            }
+            let key = (exchange.validator, request_key(&exchange.request));
+            responses
+                .entry(key)
+                .or_default()
+                .push_back(exchange.response);
+        }
+        Replay {
+            validators,
+            responses: Arc::new(Mutex::new(responses)),
+        }
+    }
+
+    /// Returns the validators of the recording, in the order in which they first
+    /// responded, serving their recorded responses.
+    pub fn validators(&self) -> Vec<(ValidatorName, ReplayNode)> {
+        self.validators
+            .iter()
+            .map(|name| {
+                let node = ReplayNode {
+                    name: *name,
+                    replay: self.clone(),
+                };
+                (*name, node)
+            })
+            .collect()
+    }
+
+    /// Returns the next recorded response of the validator to the request.
+    fn respond(
+        &self,
+        validator: ValidatorName,
+        request: &RecordedRequest,
+    ) -> Option<RecordedResponse> {
+        let key = (validator, request_key(request));
+        self.responses.lock().unwrap().get_mut(&key)?.pop_front()
+    }
+}
+
+/// Returns the key identifying identical requests. Not all requests can be compared, but
+/// they can all be serialized.
+fn request_key(request: &RecordedRequest) -> Vec<u8> {
+    bcs::to_bytes(request).expect("requests should be serializable")
+}
+
+/// A validator serving the responses of a [`Replay`].
+#[derive(Clone)]
+pub struct ReplayNode {
+    name: ValidatorName,
+    replay: Replay,
+}
+
+impl ReplayNode {
+    /// Returns the next recorded response to the request, extracted with `extract`.
+    fn replay<T>(
+        &self,
+        request: RecordedRequest,
+        extract: impl FnOnce(RecordedResponse) -> Option<Result<T, NodeError>>,
+    ) -> Result<T, NodeError> {
+        self.replay
+            .respond(self.name, &request)
+            .and_then(extract)
+            .unwrap_or_else(|| {
+                Err(NodeError::ClientIoError {
+                    error: format!(
+                        "No recorded response of validator {} to {request:?}",
+                        self.name
+                    ),
+                })
+            })
+    }
+
+    fn not_recorded<T>(&self, request: &str) -> Result<T, NodeError> {
+        Err(NodeError::ClientIoError {
+            error: format!("{request} is not replayed"),
+        })
+    }
+}
+
+impl ValidatorNode for ReplayNode {
+    type NotificationStream = NotificationStream;
+
+    async fn handle_block_proposal(
+        &mut self,
+        _proposal: BlockProposal,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.not_recorded("Block proposal")
+    }
+
+    async fn handle_lite_certificate(
+        &mut self,
+        _certificate: LiteCertificate<'_>,
+        _delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.not_recorded("Lite certificate")
+    }
+
+    async fn handle_certificate(
+        &mut self,
+        _certificate: Certificate,
+        _hashed_certificate_values: Vec<HashedCertificateValue>,
+        _hashed_blobs: Vec<HashedBlob>,
+        _delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.not_recorded("Certificate")
+    }
+
+    async fn handle_chain_info_query(
+        &mut self,
+        query: ChainInfoQuery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.replay(
+            RecordedRequest::ChainInfoQuery(query),
+            |response| match response {
+                RecordedResponse::ChainInfo(result) => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
+        self.replay(RecordedRequest::VersionInfo, |response| match response {
+            RecordedResponse::VersionInfo(result) => Some(result),
+            _ => None,
+        })
+    }
+
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        self.replay(RecordedRequest::Features, |response| match response {
+            RecordedResponse::Features(result) => Some(result),
+            _ => None,
+        })
+    }
+
+    async fn subscribe(
+        &mut self,
+        _chains: Vec<linera_base::identifiers::ChainId>,
+    ) -> Result<NotificationStream, NodeError> {
+        Ok(Box::pin(stream::empty()))
+    }
+
+    async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
+        self.replay(
+            RecordedRequest::DownloadBlob(blob_id),
+            |response| match response {
+                RecordedResponse::Blob(result) => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    async fn download_certificate_value(
+        &mut self,
+        hash: CryptoHash,
+    ) -> Result<HashedCertificateValue, NodeError> {
+        self.replay(
+            RecordedRequest::DownloadCertificateValue(hash),
+            |response| match response {
+                RecordedResponse::CertificateValue(result) => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    async fn download_certificate(&mut self, hash: CryptoHash) -> Result<Certificate, NodeError> {
+        self.replay(
+            RecordedRequest::DownloadCertificate(hash),
+            |response| match response {
+                RecordedResponse::Certificate(result) => Some(result),
+                _ => None,
+            },
+        )
+    }
+
+    async fn blob_last_used_by(&mut self, blob_id: BlobId) -> Result<CryptoHash, NodeError> {
+        self.replay(
+            RecordedRequest::BlobLastUsedBy(blob_id),
+            |response| match response {
+                RecordedResponse::BlobLastUsedBy(result) => Some(result),
+                _ => None,
+            },
+        )
+    }
+}
@@ -5,16 +5,27 @@
 #[path = "./wasm_client_tests.rs"]
 mod wasm;
 
+use std::{
+    io::{Cursor, Write as _},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
 use assert_matches::assert_matches;
-use futures::StreamExt;
+use futures::{FutureExt as _, StreamExt};
 use linera_base::{
     crypto::*,
     data_types::*,
     identifiers::{Account, ChainDescription, ChainId, MessageId, Owner},
     ownership::{ChainOwnership, TimeoutConfig},
+    time::Duration,
 };
 use linera_chain::{
-    data_types::{CertificateValue, Event, ExecutedBlock, IncomingMessage, Medium, Origin},
+    data_types::{
+        Certificate, CertificateValue, Event, ExecutedBlock, IncomingMessage, Medium, Origin,
+    },
     ChainError, ChainExecutionContext,
 };
 use linera_execution::{
@@ -25,7 +36,9 @@ use linera_execution::{
 };
 use linera_storage::Storage;
 use linera_views::views::ViewError;
+use rand::{rngs::StdRng, SeedableRng as _};
 use test_case::test_case;
+use tokio::sync::mpsc;
 
 #[cfg(feature = "dynamodb")]
 use crate::test_utils::DynamoDbStorageBuilder;
@@ -37,15 +50,24 @@ use crate::test_utils::ScyllaDbStorageBuilder;
 use crate::test_utils::ServiceStorageBuilder;
 use crate::{
     client::{ArcChainClient, ChainClientError, ClientOutcome, MessageAction, MessagePolicy},
-    local_node::LocalNodeError,
+    data_types::{BlockHeightRange, ChainInfoQuery},
+    local_node::{
+        classify_error, ArchivedCertificate, BoundedNotifications, CircuitBreakerConfig, Deadline,
+        DownloadConfig, ErrorClass, LocalNodeClient, LocalNodeError, LockedCertificatePolicy,
+        SyncEstimate, ValidatorSelection, ValidatorSyncError, ValidatorSyncOutcome,
+    },
     node::{
         CrossChainMessageDelivery,
         NodeError::{self, ClientIoError},
         ValidatorNode,
     },
-    test_utils::{FaultType, MemoryStorageBuilder, StorageBuilder, TestBuilder},
+    replay::{Recorder, Replay},
+    test_utils::{
+        FaultType, MemoryStorageBuilder, MemoryValidatorNode, StorageBuilder, TestBuilder,
+    },
     updater::CommunicationError,
-    worker::{Notification, Reason, WorkerError},
+    value_cache::ValueCache,
+    worker::{DeliveryNotifiers, Notification, Reason, WorkerError, WorkerState},
 };
 
 #[test_case(MemoryStorageBuilder::default(); "memory")]
@@ -1937,3 +1959,2074 @@ where
 
     Ok(())
 }
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_local_chains<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::ZERO)
+        .await?;
+    let mut client = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::from_tokens(3))
+        .await?;
+    let chain_ids = client.client.local_node.local_chains().await?;
+    assert!(chain_ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(chain_ids.contains(&ChainId::root(1)));
+    assert!(chain_ids.contains(&ChainId::root(2)));
+    assert!(!chain_ids.contains(&ChainId::root(3)));
+
+    // The state of the recipient is created in the local node when the transfer is sent.
+    client
+        .transfer_to_account(
+            None,
+            Amount::ONE,
+            Account::chain(ChainId::root(3)),
+            UserData(None),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_ids = client.client.local_node.local_chains().await?;
+    assert!(chain_ids.contains(&ChainId::root(3)));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_lite_certificates<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let certificate = sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut unknown_certificate = certificate.lite_certificate().cloned();
+    unknown_certificate.value.value_hash = CryptoHash::test_hash("unknown value");
+
+    let mut notifications = Vec::new();
+    let results = sender
+        .client
+        .local_node
+        .handle_lite_certificates(
+            vec![unknown_certificate, certificate.lite_certificate()],
+            &mut notifications,
+        )
+        .await;
+    assert_eq!(results.len(), 2);
+    assert_matches!(
+        results[0],
+        Err(LocalNodeError::WorkerError(
+            WorkerError::MissingCertificateValue
+        ))
+    );
+    let response = results[1]
+        .as_ref()
+        .expect("the known certificate should be handled");
+    assert_eq!(response.info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_lite_certificate_with_download<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let certificate = sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = &client.client.local_node;
+
+    // The new client doesn't have the value of the certificate.
+    let mut notifications = Vec::new();
+    assert_matches!(
+        local_node
+            .handle_lite_certificate(certificate.lite_certificate(), &mut notifications)
+            .await,
+        Err(LocalNodeError::WorkerError(
+            WorkerError::MissingCertificateValue
+        ))
+    );
+    let response = local_node
+        .handle_lite_certificate_with_download(
+            validators,
+            certificate.lite_certificate(),
+            &mut notifications,
+        )
+        .await?;
+    assert_eq!(response.info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_refresh_committee<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut client = builder
+        .make_client(
+            sender.chain_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    let mut validators = client.validator_nodes().await?;
+    validators.truncate(1);
+
+    let mut notifications = Vec::new();
+    let validators = client
+        .client
+        .local_node
+        .refresh_committee(
+            sender.chain_id,
+            validators,
+            &client.validator_node_provider,
+            &mut notifications,
+        )
+        .await?;
+    let names = validators.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    let committee = client.local_committee().await?;
+    assert_eq!(names.len(), 4);
+    assert!(names
+        .iter()
+        .all(|name| committee.validators().contains_key(name)));
+    assert_eq!(
+        client
+            .client
+            .local_node
+            .local_chain_info(sender.chain_id)
+            .await?
+            .next_block_height,
+        BlockHeight::from(1)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_record_and_replay_synchronization<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let dir = tempfile::TempDir::new()?;
+    let path = dir.path().join("recording");
+
+    let mut client = builder
+        .make_client(
+            sender.chain_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    let recorder = Recorder::create(&path)?;
+    let validators = recorder.wrap(client.validator_nodes().await?);
+    let info = client
+        .client
+        .local_node
+        .synchronize_chain_state(validators, sender.chain_id, &mut Vec::new())
+        .await?
+        .final_info;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+
+    // A new client reaches the same state from the recording alone, even if the last
+    // exchange was cut short.
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(&[1, 0, 0])?;
+    drop(file);
+    let replay = Replay::load(&path)?;
+    assert!(!replay.validators().is_empty());
+    let client = builder
+        .make_client(
+            sender.chain_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    let info = client
+        .client
+        .local_node
+        .synchronize_chain_state(replay.validators(), sender.chain_id, &mut Vec::new())
+        .await?
+        .final_info;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+
+    // Requests that weren't recorded fail.
+    let (_, mut node) = replay.validators().remove(0);
+    assert_matches!(
+        node.download_certificate(CryptoHash::test_hash("missing"))
+            .await,
+        Err(ClientIoError { .. })
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_verify_certificate<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let certificate = sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let local_node = &sender.client.local_node;
+    local_node.verify_certificate(&certificate).await?;
+
+    let unsigned_certificate = Certificate::new(
+        certificate.value.clone(),
+        certificate.round,
+        certificate.signatures()[..1].to_vec(),
+    );
+    assert_matches!(
+        local_node.verify_certificate(&unsigned_certificate).await,
+        Err(LocalNodeError::WorkerError(WorkerError::ChainError(error)))
+            if matches!(*error, ChainError::CertificateRequiresQuorum)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_cache_recent_blob_verified<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let client = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let local_node = &client.client.local_node;
+    let blob = HashedBlob::test_blob("blob1");
+    let other_blob_id = HashedBlob::test_blob("blob2").id();
+    let wrong_blob = blob.blob().clone().with_hash_unchecked(other_blob_id);
+
+    assert!(!local_node.cache_recent_blob_verified(&wrong_blob).await);
+    assert!(local_node.recent_blob(&other_blob_id).await.is_none());
+    assert!(local_node.cache_recent_blob_verified(&blob).await);
+    assert_eq!(local_node.recent_blob(&blob.id()).await, Some(blob));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_missing_blob_ids<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let client = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let local_node = &client.client.local_node;
+    let cached_blob = HashedBlob::test_blob("cached");
+    let stored_blob = HashedBlob::test_blob("stored");
+    let missing_blob_id = HashedBlob::test_blob("missing").id();
+    local_node.cache_recent_blob(&cached_blob).await;
+    local_node
+        .storage_client()
+        .await
+        .write_hashed_blob(&stored_blob, &CryptoHash::test_hash("certificate"))
+        .await?;
+
+    let blob_ids = [cached_blob.id(), missing_blob_id, stored_blob.id()];
+    assert_eq!(
+        local_node.missing_blob_ids(&blob_ids).await,
+        vec![missing_blob_id]
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_prune_certificates<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let chain_id = sender.chain_id;
+    let mut certificates = Vec::new();
+    for _ in 0..3 {
+        let certificate = sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+        certificates.push(certificate);
+    }
+    // The certificates are still in the cache of recent values.
+    let local_node = &sender.client.local_node;
+    assert_eq!(
+        local_node
+            .prune_certificates(chain_id, BlockHeight::from(3))
+            .await?,
+        0
+    );
+
+    // A local node with empty caches prunes everything except the latest block.
+    let storage = local_node.storage_client().await;
+    let state = WorkerState::new_for_client(
+        "Pruning node".to_string(),
+        storage.clone(),
+        Arc::new(ValueCache::default()),
+        Arc::new(ValueCache::default()),
+        Arc::new(tokio::sync::Mutex::new(DeliveryNotifiers::default())),
+    );
+    let local_node = LocalNodeClient::new(state);
+    assert_eq!(
+        local_node
+            .prune_certificates(chain_id, BlockHeight::from(3))
+            .await?,
+        2
+    );
+    assert!(!storage.contains_certificate(certificates[0].hash()).await?);
+    assert!(!storage.contains_certificate(certificates[1].hash()).await?);
+    assert!(storage.contains_certificate(certificates[2].hash()).await?);
+    assert_eq!(
+        local_node
+            .prune_certificates(chain_id, BlockHeight::from(3))
+            .await?,
+        0
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_certificates_with_bounded_notifications<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+
+    // With a capacity of one, the download can only finish if the notifications are
+    // received while it is running.
+    let (notification_sender, mut notification_receiver) = mpsc::channel(1);
+    let mut notifications = BoundedNotifications::new(notification_sender);
+    let download = tokio::spawn(async move {
+        local_node
+            .download_certificates(
+                validators,
+                chain_id,
+                BlockHeight::from(2),
+                &mut notifications,
+            )
+            .await
+    });
+    let mut heights = Vec::new();
+    while let Some(notification) = notification_receiver.recv().await {
+        if let Reason::NewBlock { height, .. } = notification.reason {
+            heights.push(height);
+        }
+    }
+    let info = download.await??;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    assert_eq!(heights, [BlockHeight::from(0), BlockHeight::from(1)]);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_drain_notifications<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone().with_notification_queue(2);
+
+    let mut notifications = Vec::new();
+    local_node
+        .download_certificates(
+            validators,
+            chain_id,
+            BlockHeight::from(3),
+            &mut notifications,
+        )
+        .await?;
+    // The notifications are queued instead of being delivered to the sink, and the oldest
+    // one was dropped to respect the capacity.
+    assert!(notifications.is_empty());
+    let mut drained = local_node.drain_notifications(1);
+    assert_eq!(drained.len(), 1);
+    drained.extend(local_node.drain_notifications(10));
+    assert!(local_node.drain_notifications(10).is_empty());
+    let heights = drained
+        .into_iter()
+        .map(|notification| match notification.reason {
+            Reason::NewBlock { height, .. } => height,
+            reason => panic!("unexpected notification: {reason:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [BlockHeight::from(1), BlockHeight::from(2)]);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_subscribe_chain<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+    let mut notifications = local_node.subscribe_chain(chain_id);
+    let mut other_notifications = local_node.subscribe_chain(ChainId::root(2));
+
+    local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(2), &mut vec![])
+        .await?;
+    for expected_height in [BlockHeight::from(0), BlockHeight::from(1)] {
+        let notification = notifications.next().await.unwrap();
+        assert_eq!(notification.chain_id, chain_id);
+        assert_matches!(
+            notification.reason,
+            Reason::NewBlock { height, .. } if height == expected_height
+        );
+    }
+    assert!(notifications.next().now_or_never().is_none());
+    assert!(other_notifications.next().now_or_never().is_none());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_wait_for_height<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+
+    // The height is already reached.
+    local_node
+        .wait_for_height(chain_id, BlockHeight::ZERO)
+        .await?;
+    // The height is never reached before the deadline.
+    let result = local_node
+        .clone()
+        .with_deadline(Deadline::after(Duration::from_millis(10)))
+        .wait_for_height(chain_id, BlockHeight::from(1))
+        .await;
+    assert_matches!(result, Err(LocalNodeError::DeadlineExceeded));
+
+    // The height is reached by a download while waiting for it.
+    let mut notifications = vec![];
+    let (waited, downloaded) = futures::join!(
+        local_node.wait_for_height(chain_id, BlockHeight::from(2)),
+        local_node.download_certificates(
+            validators,
+            chain_id,
+            BlockHeight::from(2),
+            &mut notifications
+        ),
+    );
+    waited?;
+    assert_eq!(downloaded?.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_certificates_in_small_batches<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let config = DownloadConfig {
+        min_batch_size: 1,
+        max_batch_size: 2,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+    let info = local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(3), &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_synchronize_chain_state_from_quorum<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    // One validator doesn't respond, so the client needs to query all of them.
+    builder.set_fault_type([0], FaultType::Offline).await;
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let committee = client.local_committee().await?;
+    let config = DownloadConfig {
+        validator_selection: ValidatorSelection::InOrder,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+    let info = local_node
+        .synchronize_chain_state_from_quorum(validators, &committee, chain_id, &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_certificates_with_dependencies<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let blob = HashedBlob::test_blob("blob");
+    let blob_id = blob.id();
+    sender.publish_blob(blob.clone()).await.unwrap().unwrap();
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let mut validators = client.validator_nodes().await?;
+
+    // The validators that have the certificate send the blob it uses along with its hash.
+    let query = ChainInfoQuery::new(chain_id)
+        .with_sent_certificate_hashes_in_range(BlockHeightRange::single(BlockHeight::ZERO))
+        .with_sent_certificate_dependencies();
+    for (_, node) in &mut validators {
+        let response = node.handle_chain_info_query(query.clone()).await?;
+        if !response.info.requested_sent_certificate_hashes.is_empty() {
+            assert_eq!(response.info.requested_blobs, vec![blob.clone()]);
+        }
+    }
+
+    let config = DownloadConfig {
+        include_certificate_dependencies: true,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+    let info = local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(1), &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    assert!(client.storage_client().contains_blob(blob_id).await?);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_certificates_from_lagging_validator<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    // Validator 1 only has the first block: the quorums for the later ones exclude it.
+    builder.set_fault_type([0], FaultType::Offline).await;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    builder.set_fault_type([0], FaultType::Honest).await;
+    builder.set_fault_type([1], FaultType::Offline).await;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    builder.set_fault_type([1], FaultType::Honest).await;
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+
+    // Ask the lagging validator first.
+    let mut validators = Vec::new();
+    for (name, mut node) in client.validator_nodes().await? {
+        let response = node
+            .handle_chain_info_query(ChainInfoQuery::new(chain_id))
+            .await?;
+        validators.push((response.info.next_block_height, name, node));
+    }
+    validators.sort_by_key(|(next_block_height, _, _)| *next_block_height);
+    assert_eq!(validators[0].0, BlockHeight::from(1));
+    let validators = validators
+        .into_iter()
+        .map(|(_, name, node)| (name, node))
+        .collect();
+
+    let config = DownloadConfig {
+        validator_selection: ValidatorSelection::InOrder,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+    let info = local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(3), &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_estimate_sync_work<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    // With one validator offline, the three others all have every block.
+    builder.set_fault_type([0], FaultType::Offline).await;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+    let estimate = local_node
+        .estimate_sync_work(chain_id, validators.clone())
+        .await?;
+    assert_eq!(
+        estimate,
+        SyncEstimate {
+            local_next_block_height: BlockHeight::ZERO,
+            tip_next_block_height: BlockHeight::from(3),
+            blocks_behind: 3,
+            agreeing_validators: 3,
+            responding_validators: 3,
+        }
+    );
+
+    // Nothing is left to download after synchronizing.
+    local_node
+        .download_certificates(
+            validators.clone(),
+            chain_id,
+            BlockHeight::from(3),
+            &mut vec![],
+        )
+        .await?;
+    let estimate = local_node.estimate_sync_work(chain_id, validators).await?;
+    assert_eq!(estimate.blocks_behind, 0);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_circuit_breaker_skips_failing_validators<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let make_local_node = |cooldown| {
+        let config = DownloadConfig {
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown,
+            }),
+            ..DownloadConfig::default()
+        };
+        client.client.local_node.clone().with_config(config)
+    };
+    let skipping_local_node = make_local_node(Duration::from_secs(3600));
+    let probing_local_node = make_local_node(Duration::ZERO);
+
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Offline)
+        .await;
+    for local_node in [&skipping_local_node, &probing_local_node] {
+        let result = local_node
+            .download_certificates(
+                validators.clone(),
+                chain_id,
+                BlockHeight::from(1),
+                &mut vec![],
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(LocalNodeError::CannotDownloadCertificates { .. })
+        );
+    }
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Honest)
+        .await;
+
+    // The validators are still skipped during the cooldown, even though they are back.
+    let result = skipping_local_node
+        .download_certificates(
+            validators.clone(),
+            chain_id,
+            BlockHeight::from(1),
+            &mut vec![],
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(LocalNodeError::CannotDownloadCertificates { .. })
+    );
+    // A probe that is dropped before its outcome is recorded, e.g. because it was
+    // cancelled, doesn't keep the validator skipped.
+    let name = validators[0].0;
+    let probe = probing_local_node.admit_validator(name);
+    assert!(probe.is_some());
+    assert!(probing_local_node.admit_validator(name).is_none());
+    drop(probe);
+    assert!(probing_local_node.admit_validator(name).is_some());
+    // After the cooldown, a probe finds out that they are back.
+    let info = probing_local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(1), &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_seeded_rng_makes_validator_order_reproducible<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut client = builder
+        .make_client(
+            sender.chain_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    let names = client
+        .validator_nodes()
+        .await?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let shuffled = |seed| {
+        let mut names = names.clone();
+        client
+            .client
+            .local_node
+            .clone()
+            .with_rng(StdRng::seed_from_u64(seed))
+            .shuffle_validators(&mut names);
+        names
+    };
+    assert_eq!(shuffled(42), shuffled(42));
+
+    // Downloads with a seeded generator work as with the thread's one.
+    let local_node = client
+        .client
+        .local_node
+        .clone()
+        .with_rng(StdRng::seed_from_u64(42));
+    let info = local_node
+        .download_certificates(
+            client.validator_nodes().await?,
+            sender.chain_id,
+            BlockHeight::from(1),
+            &mut vec![],
+        )
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_certificate_import_only<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+    let certificate = sender
+        .transfer_to_account(
+            None,
+            Amount::ONE,
+            Account::chain(ChainId::root(2)),
+            UserData::default(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Imported certificates extend the chain but don't deliver their messages.
+    let importer = builder
+        .make_client(
+            sender.chain_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    let response = importer
+        .client
+        .local_node
+        .handle_certificate_import_only(certificate.clone(), vec![], vec![], &mut vec![])
+        .await?;
+    assert_eq!(response.info.next_block_height, BlockHeight::from(1));
+    let recipient = importer
+        .client
+        .local_node
+        .chain_state_view(ChainId::root(2))
+        .await?;
+    assert!(recipient.inboxes.indices().await?.is_empty());
+
+    // Handling the certificate normally delivers the messages.
+    let handler = builder
+        .make_client(
+            sender.chain_id,
+            KeyPair::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    handler
+        .client
+        .local_node
+        .handle_certificate(certificate, vec![], vec![], &mut vec![])
+        .await?;
+    let recipient = handler
+        .client
+        .local_node
+        .chain_state_view(ChainId::root(2))
+        .await?;
+    assert!(!recipient.inboxes.indices().await?.is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_concurrent_syncs_of_a_chain_are_coalesced<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+
+    let mut first_notifications = Vec::new();
+    let mut second_notifications = Vec::new();
+    let (first, second) = futures::join!(
+        local_node.synchronize_chain_state(validators.clone(), chain_id, &mut first_notifications),
+        local_node.synchronize_chain_state(validators, chain_id, &mut second_notifications),
+    );
+    assert_eq!(first?.final_info.next_block_height, BlockHeight::from(2));
+    assert_eq!(second?.final_info.next_block_height, BlockHeight::from(2));
+    // Only the first synchronization downloaded the blocks: the second one waited for it.
+    assert!(!first_notifications.is_empty());
+    assert!(second_notifications.is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_certificates_stream<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+
+    let mut notifications = Vec::new();
+    let certificates = local_node
+        .download_certificates_stream(
+            validators.clone(),
+            chain_id,
+            BlockHeight::from(2),
+            &mut notifications,
+        )
+        .collect::<Vec<_>>()
+        .await;
+    let heights = certificates
+        .into_iter()
+        .map(|certificate| Ok(certificate?.value().height()))
+        .collect::<Result<Vec<_>, LocalNodeError>>()?;
+    assert_eq!(heights, [BlockHeight::from(0), BlockHeight::from(1)]);
+
+    // The stream ends with an error if the target height can't be reached.
+    let results = local_node
+        .download_certificates_stream(validators, chain_id, BlockHeight::from(5), &mut vec![])
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[0].as_ref().unwrap().value().height(),
+        BlockHeight::from(2)
+    );
+    assert_matches!(
+        results[1],
+        Err(LocalNodeError::CannotDownloadCertificates { .. })
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_synchronize_chains_takes_turns<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut chains = Vec::new();
+    for (index, block_count) in [(1, 3), (2, 2)] {
+        let mut sender = builder
+            .add_initial_chain(ChainDescription::Root(index), Amount::from_tokens(4))
+            .await?;
+        for _ in 0..block_count {
+            sender
+                .burn(None, Amount::ONE, UserData::default())
+                .await
+                .unwrap()
+                .unwrap();
+        }
+        chains.push(sender.chain_id);
+    }
+    // The blob of this chain's block is too large for the local node to accept it.
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(3), Amount::from_tokens(4))
+        .await?;
+    sender
+        .publish_blob(HashedBlob::test_blob("blob"))
+        .await
+        .unwrap()
+        .unwrap();
+    chains.push(sender.chain_id);
+    let mut client = builder
+        .make_client(chains[0], KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let config = DownloadConfig {
+        chain_slice_size: 1,
+        max_blob_size: Some(0),
+        validator_selection: ValidatorSelection::InOrder,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+
+    let mut notifications = Vec::new();
+    let mut results = local_node
+        .synchronize_chains(validators, chains.clone(), &mut notifications)
+        .await?;
+    // The chain that fails doesn't keep the others from synchronizing.
+    assert_matches!(
+        results.pop(),
+        Some(Err(LocalNodeError::CannotDownloadCertificates { .. }))
+    );
+    let heights = results
+        .into_iter()
+        .map(|info| Ok(info?.next_block_height))
+        .collect::<Result<Vec<_>, LocalNodeError>>()?;
+    assert_eq!(heights, [BlockHeight::from(3), BlockHeight::from(2)]);
+    // The chains took turns, one block at a time.
+    let blocks = notifications
+        .into_iter()
+        .map(|notification| match notification.reason {
+            Reason::NewBlock { height, .. } => (notification.chain_id, height),
+            reason => panic!("unexpected notification: {reason:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        blocks,
+        [
+            (chains[0], BlockHeight::from(0)),
+            (chains[1], BlockHeight::from(0)),
+            (chains[0], BlockHeight::from(1)),
+            (chains[1], BlockHeight::from(1)),
+            (chains[0], BlockHeight::from(2)),
+        ]
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_operations_respect_the_deadline<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+
+    // Nothing is started after the deadline.
+    let expired_local_node = client
+        .client
+        .local_node
+        .clone()
+        .with_deadline(Deadline::after(Duration::ZERO));
+    let result = expired_local_node
+        .download_certificates(
+            validators.clone(),
+            chain_id,
+            BlockHeight::from(1),
+            &mut vec![],
+        )
+        .await;
+    assert_matches!(result, Err(LocalNodeError::DeadlineExceeded));
+    let result = expired_local_node
+        .synchronize_chain_state(validators.clone(), chain_id, &mut vec![])
+        .await;
+    assert_matches!(result, Err(LocalNodeError::DeadlineExceeded));
+    let info = client.client.local_node.local_chain_info(chain_id).await?;
+    assert_eq!(info.next_block_height, BlockHeight::ZERO);
+
+    // A sequence of operations shares a single deadline.
+    let local_node = client
+        .client
+        .local_node
+        .clone()
+        .with_deadline(Deadline::after(Duration::from_secs(60)));
+    let info = local_node
+        .download_certificates(
+            validators.clone(),
+            chain_id,
+            BlockHeight::from(1),
+            &mut vec![],
+        )
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    let info = local_node
+        .synchronize_chain_state(validators, chain_id, &mut vec![])
+        .await?
+        .final_info;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_sync_with_one_pending_certificate_at_a_time<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let config = DownloadConfig {
+        max_pending_certificates: 1,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+
+    let mut notifications = Vec::new();
+    let info = local_node
+        .synchronize_chain_state(validators, chain_id, &mut notifications)
+        .await?
+        .final_info;
+    assert_eq!(info.next_block_height, BlockHeight::from(3));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_synchronize_chain_state_report<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    builder.set_fault_type([0], FaultType::Offline).await;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    // Query the validators one at a time, so that only the first honest one has blocks to send.
+    let config = DownloadConfig {
+        max_concurrent_validators: 1,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+
+    let report = local_node
+        .synchronize_chain_state(validators, chain_id, &mut vec![])
+        .await?;
+    assert_eq!(report.final_info.next_block_height, BlockHeight::from(3));
+    assert_eq!(report.per_validator.len(), 4);
+    let (failed, synchronized): (Vec<_>, Vec<_>) = report
+        .per_validator
+        .iter()
+        .partition(|outcome| outcome.error.is_some());
+    assert_matches!(
+        failed.as_slice(),
+        [ValidatorSyncOutcome {
+            tip: None,
+            certificates: 0,
+            error: Some(ValidatorSyncError::NodeError(_)),
+            ..
+        }]
+    );
+    assert!(synchronized
+        .iter()
+        .all(|outcome| outcome.tip.map(|(height, _)| height) == Some(BlockHeight::from(3))));
+    assert_eq!(
+        synchronized
+            .iter()
+            .map(|outcome| outcome.certificates)
+            .collect::<Vec<_>>(),
+        vec![3, 0, 0]
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_sync_querying_one_validator_at_a_time<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let config = DownloadConfig {
+        max_concurrent_validators: 1,
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+
+    let mut notifications = Vec::new();
+    let info = local_node
+        .synchronize_chain_state(validators, chain_id, &mut notifications)
+        .await?
+        .final_info;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_apply_trusted_snapshot<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_id = sender.chain_id;
+    let mut snapshot = Vec::new();
+    sender
+        .client
+        .local_node
+        .export_chain_snapshot(chain_id, &mut snapshot)
+        .await?;
+
+    let client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let local_node = client.client.local_node.clone();
+    let result = local_node
+        .apply_trusted_snapshot(chain_id, snapshot.as_slice())
+        .await;
+    assert_matches!(result, Err(LocalNodeError::UntrustedSnapshot));
+    let result = local_node
+        .clone()
+        .with_trusted_snapshots()
+        .apply_trusted_snapshot(ChainId::root(2), snapshot.as_slice())
+        .await;
+    assert_matches!(result, Err(LocalNodeError::SnapshotChainMismatch { .. }));
+    assert_eq!(
+        local_node
+            .local_chain_info(chain_id)
+            .await?
+            .next_block_height,
+        BlockHeight::ZERO
+    );
+
+    let info = local_node
+        .with_trusted_snapshots()
+        .apply_trusted_snapshot(chain_id, snapshot.as_slice())
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    assert_eq!(info.chain_balance, sender.chain_info().await?.chain_balance);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_when_already_past_the_target<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+    let mut notifications = Vec::new();
+    local_node
+        .synchronize_chain_state(validators.clone(), chain_id, &mut notifications)
+        .await?;
+
+    // The start is past the stop, so there is nothing to download.
+    let (name, node) = validators[0].clone();
+    local_node
+        .try_download_certificates_from(
+            name,
+            node,
+            chain_id,
+            BlockHeight::from(2),
+            BlockHeight::from(1),
+            &mut notifications,
+            None,
+        )
+        .await?;
+    let info = local_node.local_chain_info(chain_id).await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocksdb"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamodb"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylladb"))]
+#[test_log::test(tokio::test)]
+async fn test_download_follows_the_error_classifier<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let offline_error = ClientIoError {
+        error: "offline".to_string(),
+    };
+    assert_eq!(classify_error(&offline_error), ErrorClass::Retry);
+    assert_eq!(
+        classify_error(&NodeError::InvalidDecoding),
+        ErrorClass::ShrinkBatch
+    );
+    assert_eq!(
+        classify_error(&NodeError::InactiveChain(ChainId::root(1))),
+        ErrorClass::AbandonValidator
+    );
+
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Offline)
+        .await;
+
+    // Each of the four validators is queried once, plus once per retry.
+    for (class, expected_queries) in [(ErrorClass::AbandonValidator, 4), (ErrorClass::Retry, 12)] {
+        let queries = Arc::new(AtomicUsize::new(0));
+        let local_node = client
+            .client
+            .local_node
+            .clone()
+            .with_config(DownloadConfig {
+                max_retries: 2,
+                ..DownloadConfig::default()
+            })
+            .with_error_classifier({
+                let queries = queries.clone();
+                move |_| {
+                    queries.fetch_add(1, Ordering::Relaxed);
+                    class
+                }
+            });
+        let result = local_node
+            .download_certificates(
+                validators.clone(),
+                chain_id,
+                BlockHeight::from(1),
+                &mut vec![],
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(LocalNodeError::CannotDownloadCertificates { .. })
+        );
+        assert_eq!(queries.load(Ordering::Relaxed), expected_queries);
+    }
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_cancel_all_downloads<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    sender
+        .burn(None, Amount::ONE, UserData::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let chain_id = sender.chain_id;
+    let mut client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let validators = client.validator_nodes().await?;
+    let local_node = client.client.local_node.clone();
+
+    // The validators answer in spawned tasks, so the download is pending until it yields.
+    let mut notifications = vec![];
+    let mut download = std::pin::pin!(local_node.download_certificates(
+        validators.clone(),
+        chain_id,
+        BlockHeight::from(1),
+        &mut notifications,
+    ));
+    assert!(futures::poll!(&mut download).is_pending());
+    assert_eq!(local_node.in_flight_downloads(), 1);
+    local_node.cancel_all_downloads();
+    assert_matches!(download.await, Err(LocalNodeError::DownloadCancelled));
+    assert_eq!(local_node.in_flight_downloads(), 0);
+
+    // Later downloads are not affected.
+    let info = local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(1), &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_import_certificates_from_file<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..3 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+    let range = BlockHeightRange {
+        start: BlockHeight::ZERO,
+        limit: None,
+    };
+    let query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
+    let hashes = sender
+        .client
+        .local_node
+        .handle_chain_info_query(query)
+        .await?
+        .info
+        .requested_sent_certificate_hashes;
+    let certificates = sender.storage_client().read_certificates(hashes).await?;
+    let write_archive = |indices: &[usize]| -> anyhow::Result<Vec<u8>> {
+        let mut archive = Vec::new();
+        for index in indices {
+            let archived = ArchivedCertificate {
+                certificate: certificates[*index].clone(),
+                hashed_certificate_values: vec![],
+                blobs: vec![],
+            };
+            archived.write(&mut archive)?;
+        }
+        Ok(archive)
+    };
+
+    let client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let local_node = client.client.local_node.clone();
+
+    // A gap in the heights stops the import.
+    let archive = write_archive(&[0, 2])?;
+    let outcome = local_node
+        .import_certificates_from_file(Cursor::new(archive), &mut vec![], |_| {})
+        .await;
+    assert_eq!(outcome.applied, 1);
+    assert_matches!(
+        outcome.error,
+        Some(LocalNodeError::NonContiguousImport { expected, found, .. })
+            if expected == BlockHeight::from(1) && found == BlockHeight::from(2)
+    );
+
+    // A certificate cut short is an error rather than the end of the archive.
+    let mut archive = write_archive(&[1])?;
+    archive.pop();
+    let outcome = local_node
+        .import_certificates_from_file(Cursor::new(archive), &mut vec![], |_| {})
+        .await;
+    assert_eq!(outcome.applied, 0);
+    assert_matches!(
+        outcome.error,
+        Some(LocalNodeError::ViewError(ViewError::Io(error)))
+            if error.kind() == std::io::ErrorKind::UnexpectedEof
+    );
+
+    // Blocks that the local node already has are skipped.
+    let archive = write_archive(&[0, 1, 2])?;
+    let mut heights = Vec::new();
+    let outcome = local_node
+        .import_certificates_from_file(Cursor::new(archive), &mut vec![], |progress| {
+            heights.push(progress.height)
+        })
+        .await;
+    assert_eq!(outcome.applied, 2);
+    assert!(outcome.error.is_none());
+    assert_eq!(heights, [BlockHeight::from(1), BlockHeight::from(2)]);
+    assert_eq!(
+        local_node
+            .local_chain_info(chain_id)
+            .await?
+            .next_block_height,
+        BlockHeight::from(3)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_from_memory_validator<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let mut sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    for _ in 0..2 {
+        sender
+            .burn(None, Amount::ONE, UserData::default())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let chain_id = sender.chain_id;
+
+    // Serve the sender's certificates from a memory validator.
+    let range = BlockHeightRange {
+        start: BlockHeight::ZERO,
+        limit: None,
+    };
+    let query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
+    let hashes = sender
+        .client
+        .local_node
+        .handle_chain_info_query(query)
+        .await?
+        .info
+        .requested_sent_certificate_hashes;
+    let memory = MemoryValidatorNode::new();
+    for certificate in sender.storage_client().read_certificates(hashes).await? {
+        memory.add_certificate(certificate).await;
+    }
+    let validators = vec![(memory.name(), memory.clone())];
+
+    let client = builder
+        .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+        .await?;
+    let config = DownloadConfig {
+        validator_timeout: Duration::from_millis(100),
+        ..DownloadConfig::default()
+    };
+    let local_node = client.client.local_node.clone().with_config(config);
+
+    // Responses that aren't signed by the validator are ignored.
+    memory.set_invalid_signatures(true).await;
+    assert_matches!(
+        local_node
+            .download_certificates(
+                validators.clone(),
+                chain_id,
+                BlockHeight::from(2),
+                &mut vec![]
+            )
+            .await,
+        Err(LocalNodeError::CannotDownloadCertificates { .. })
+    );
+
+    // So are responses that never arrive.
+    memory.set_invalid_signatures(false).await;
+    memory.set_drop_responses(true).await;
+    assert_matches!(
+        local_node
+            .download_certificates(
+                validators.clone(),
+                chain_id,
+                BlockHeight::from(2),
+                &mut vec![]
+            )
+            .await,
+        Err(LocalNodeError::CannotDownloadCertificates { .. })
+    );
+
+    // Slow responses are fine as long as they arrive in time.
+    memory.set_drop_responses(false).await;
+    memory.set_delay(Duration::from_millis(10)).await;
+    let info = local_node
+        .download_certificates(validators, chain_id, BlockHeight::from(2), &mut vec![])
+        .await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(2));
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage_service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_locked_certificate_policy<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    // Configure a chain with two regular and no super owners.
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let description = ChainDescription::Root(1);
+    let chain_id = ChainId::from(description);
+    let mut client0 = builder
+        .add_initial_chain(description, Amount::from_tokens(10))
+        .await?;
+    let pub_key0 = client0.public_key().await.unwrap();
+    let pub_key1 = KeyPair::generate().public();
+    let owner_change_op = SystemOperation::ChangeOwnership {
+        super_owners: Vec::new(),
+        owners: vec![(pub_key0, 100), (pub_key1, 100)],
+        multi_leader_rounds: 10,
+        timeout_config: TimeoutConfig::default(),
+    }
+    .into();
+    client0.execute_operation(owner_change_op).await.unwrap();
+
+    // Validators 1 and 2 vote for a block but don't accept the validated block certificate,
+    // and validator 3 is offline. Make sure validator 0 is locked on the validated block.
+    builder.set_fault_type([1, 2], FaultType::NoConfirm).await;
+    builder.set_fault_type([3], FaultType::Offline).await;
+    let result = client0
+        .burn(None, Amount::from_tokens(3), UserData::default())
+        .await;
+    assert!(result.is_err());
+    let manager = client0
+        .chain_info_with_manager_values()
+        .await
+        .unwrap()
+        .manager;
+    builder
+        .node(0)
+        .handle_certificate(
+            *manager.requested_locked.unwrap(),
+            Vec::new(),
+            Vec::new(),
+            CrossChainMessageDelivery::Blocking,
+        )
+        .await
+        .unwrap();
+
+    // Only validator 0, which is not a quorum, reports the locked certificate.
+    builder.set_fault_type([1, 2, 3], FaultType::Offline).await;
+    for (policy, is_applied) in [
+        (LockedCertificatePolicy::ApplyAll, true),
+        (LockedCertificatePolicy::HighestRound, true),
+        (LockedCertificatePolicy::Quorum, false),
+    ] {
+        let mut client = builder
+            .make_client(chain_id, KeyPair::generate(), None, BlockHeight::ZERO)
+            .await?;
+        let validators = client.validator_nodes().await?;
+        let config = DownloadConfig {
+            locked_certificate_policy: policy,
+            ..DownloadConfig::default()
+        };
+        let local_node = client.client.local_node.clone().with_config(config);
+        let info = local_node
+            .synchronize_chain_state(validators, chain_id, &mut vec![])
+            .await?
+            .final_info;
+        assert_eq!(info.next_block_height, BlockHeight::from(1));
+        let query = ChainInfoQuery::new(chain_id).with_manager_values();
+        let manager = local_node
+            .handle_chain_info_query(query)
+            .await?
+            .info
+            .manager;
+        assert_eq!(manager.requested_locked.is_some(), is_applied, "{policy:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_download_config_uses_defaults_for_missing_fields() {
+    let config: DownloadConfig =
+        serde_json::from_str(r#"{ "max_batch_size": 50, "validator_selection": "InOrder" }"#)
+            .unwrap();
+    assert_eq!(
+        config,
+        DownloadConfig {
+            max_batch_size: 50,
+            validator_selection: ValidatorSelection::InOrder,
+            ..DownloadConfig::default()
+        }
+    );
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(
+        serde_json::from_str::<DownloadConfig>(&json).unwrap(),
+        config
+    );
+}
@@ -0,0 +1,44 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io;
+
+use super::{read_record, write_record, MAX_RECORD_LENGTH};
+
+/// Tests reading back the records written, up to the end of the file.
+#[test]
+fn test_write_and_read_records() -> io::Result<()> {
+    let mut file = Vec::new();
+    write_record(&mut file, b"first")?;
+    write_record(&mut file, b"")?;
+    write_record(&mut file, b"third")?;
+
+    let mut reader = file.as_slice();
+    assert_eq!(read_record(&mut reader)?.as_deref(), Some(&b"first"[..]));
+    assert_eq!(read_record(&mut reader)?.as_deref(), Some(&b""[..]));
+    assert_eq!(read_record(&mut reader)?.as_deref(), Some(&b"third"[..]));
+    assert_eq!(read_record(&mut reader)?, None);
+    Ok(())
+}
+
+/// Tests that a record cut short, in its length or in its bytes, is an error rather than
+/// the end of the file.
+#[test]
+fn test_read_truncated_record() -> io::Result<()> {
+    let mut file = Vec::new();
+    write_record(&mut file, b"record")?;
+
+    for len in 1..file.len() {
+        let error = read_record(&file[..len]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "length {len}");
+    }
+    Ok(())
+}
+
+/// Tests that a record longer than the maximum is rejected before it is read.
+#[test]
+fn test_read_oversized_record() {
+    let file = (MAX_RECORD_LENGTH + 1).to_le_bytes();
+    let error = read_record(file.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
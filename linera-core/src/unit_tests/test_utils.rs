@@ -7,11 +7,12 @@ use std::{
 };
 
 use async_trait::async_trait;
-use futures::{lock::Mutex, Future};
+use futures::{future, lock::Mutex, stream, Future};
 use linera_base::{
     crypto::*,
     data_types::*,
     identifiers::{BlobId, ChainDescription, ChainId},
+    time::Duration,
 };
 use linera_chain::data_types::{
     BlockProposal, Certificate, HashedCertificateValue, LiteCertificate,
@@ -21,7 +22,7 @@ use linera_execution::{
     ResourceControlPolicy, WasmRuntime,
 };
 use linera_storage::{MemoryStorage, Storage, TestClock};
-use linera_version::VersionInfo;
+use linera_version::{VersionInfo, SUPPORTED_FEATURES};
 use linera_views::{memory::TEST_MEMORY_MAX_STREAM_QUERIES, views::ViewError};
 use tokio::sync::oneshot;
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -154,6 +155,13 @@ where
         Ok(Default::default())
     }
 
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        Ok(SUPPORTED_FEATURES
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect())
+    }
+
     async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
         self.spawn_and_receive(move |validator, sender| validator.do_download_blob(blob_id, sender))
             .await
@@ -267,8 +275,8 @@ where
                     .state
                     .fully_handle_certificate_with_notifications(
                         cert,
-                        vec![],
-                        vec![],
+                        &[],
+                        &[],
                         Some(&mut notifications),
                     )
                     .await
@@ -303,8 +311,8 @@ where
                 .state
                 .fully_handle_certificate_with_notifications(
                     certificate,
-                    hashed_certificate_values,
-                    hashed_blobs,
+                    &hashed_certificate_values,
+                    &hashed_blobs,
                     Some(&mut notifications),
                 )
                 .await
@@ -462,6 +470,257 @@ impl<S> FromIterator<LocalValidatorClient<S>> for NodeProvider<S> {
     }
 }
 
+/// The chains, certificates and blobs held by a [`MemoryValidatorNode`], and how it
+/// misbehaves.
+#[derive(Default)]
+struct MemoryValidatorState {
+    /// The certificates of each chain, by height.
+    chains: HashMap<ChainId, Vec<Certificate>>,
+    certificates: HashMap<CryptoHash, Certificate>,
+    values: HashMap<CryptoHash, HashedCertificateValue>,
+    blobs: HashMap<BlobId, HashedBlob>,
+    delay: Duration,
+    drop_responses: bool,
+    invalid_signatures: bool,
+}
+
+/// A validator node serving chains, certificates and blobs from memory, without
+/// executing anything. It is meant to test the synchronization logic of the
+/// [`LocalNodeClient`](crate::local_node::LocalNodeClient) against validators that
+/// are slow, unresponsive or dishonest.
+///
+/// The node is read-only: proposals and certificates are rejected.
+#[derive(Clone)]
+pub struct MemoryValidatorNode {
+    key_pair: Arc<KeyPair>,
+    state: Arc<Mutex<MemoryValidatorState>>,
+}
+
+impl Default for MemoryValidatorNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryValidatorNode {
+    /// Creates an empty node with a new key pair.
+    pub fn new() -> Self {
+        Self {
+            key_pair: Arc::new(KeyPair::generate()),
+            state: Arc::default(),
+        }
+    }
+
+    /// Returns the name of the validator, i.e. its public key.
+    pub fn name(&self) -> ValidatorName {
+        ValidatorName(self.key_pair.public())
+    }
+
+    /// Adds a certificate to its chain, and its value to the stored values. The
+    /// certificates of a chain must be added in order, starting at height zero.
+    pub async fn add_certificate(&self, certificate: Certificate) {
+        let mut state = self.state.lock().await;
+        let chain = state
+            .chains
+            .entry(certificate.value().chain_id())
+            .or_default();
+        assert_eq!(
+            BlockHeight::from(chain.len() as u64),
+            certificate.value().height(),
+            "certificates must be added in order"
+        );
+        chain.push(certificate.clone());
+        state
+            .values
+            .insert(certificate.hash(), certificate.value.clone());
+        state.certificates.insert(certificate.hash(), certificate);
+    }
+
+    /// Adds a certificate value that is not part of a stored certificate.
+    pub async fn add_hashed_certificate_value(&self, value: HashedCertificateValue) {
+        self.state.lock().await.values.insert(value.hash(), value);
+    }
+
+    /// Adds a blob.
+    pub async fn add_blob(&self, blob: HashedBlob) {
+        self.state.lock().await.blobs.insert(blob.id(), blob);
+    }
+
+    /// Delays every response by `delay`.
+    pub async fn set_delay(&self, delay: Duration) {
+        self.state.lock().await.delay = delay;
+    }
+
+    /// If `drop_responses` is set, requests never get a response.
+    pub async fn set_drop_responses(&self, drop_responses: bool) {
+        self.state.lock().await.drop_responses = drop_responses;
+    }
+
+    /// If `invalid_signatures` is set, chain info responses are signed with a key that
+    /// doesn't match the validator's name.
+    pub async fn set_invalid_signatures(&self, invalid_signatures: bool) {
+        self.state.lock().await.invalid_signatures = invalid_signatures;
+    }
+
+    /// Waits for the configured delay, or forever if responses are dropped, then returns
+    /// the result of `f` on the state.
+    async fn respond<T>(&self, f: impl FnOnce(&MemoryValidatorState) -> T) -> T {
+        let (delay, drop_responses) = {
+            let state = self.state.lock().await;
+            (state.delay, state.drop_responses)
+        };
+        if drop_responses {
+            future::pending::<()>().await;
+        }
+        if delay > Duration::ZERO {
+            linera_base::time::timer::sleep(delay).await;
+        }
+        f(&*self.state.lock().await)
+    }
+
+    fn chain_info_response(
+        &self,
+        state: &MemoryValidatorState,
+        query: ChainInfoQuery,
+    ) -> ChainInfoResponse {
+        let certificates = state
+            .chains
+            .get(&query.chain_id)
+            .map_or(&[][..], Vec::as_slice);
+        let requested_sent_certificate_hashes = match query.request_sent_certificate_hashes_in_range
+        {
+            Some(range) => {
+                let start = usize::try_from(u64::from(range.start)).unwrap_or(usize::MAX);
+                let limit = range
+                    .limit
+                    .and_then(|limit| usize::try_from(limit).ok())
+                    .unwrap_or(usize::MAX);
+                certificates
+                    .iter()
+                    .skip(start)
+                    .take(limit)
+                    .map(Certificate::hash)
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let requested_blobs = query
+            .request_blobs
+            .iter()
+            .filter_map(|blob_id| state.blobs.get(blob_id).cloned())
+            .collect();
+        let info = ChainInfo {
+            chain_id: query.chain_id,
+            epoch: None,
+            description: None,
+            manager: Box::default(),
+            chain_balance: Amount::ZERO,
+            block_hash: certificates.last().map(Certificate::hash),
+            timestamp: Timestamp::default(),
+            next_block_height: BlockHeight::from(certificates.len() as u64),
+            state_hash: None,
+            requested_owner_balance: None,
+            requested_committees: None,
+            requested_pending_messages: Vec::new(),
+            requested_sent_certificate_hashes,
+            count_received_log: 0,
+            requested_received_log: Vec::new(),
+            requested_blobs,
+            requested_hashed_certificate_values: Vec::new(),
+        };
+        if state.invalid_signatures {
+            ChainInfoResponse::new(info, Some(&KeyPair::generate()))
+        } else {
+            ChainInfoResponse::new(info, Some(&self.key_pair))
+        }
+    }
+}
+
+impl ValidatorNode for MemoryValidatorNode {
+    type NotificationStream = NotificationStream;
+
+    async fn handle_block_proposal(
+        &mut self,
+        _proposal: BlockProposal,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
+    async fn handle_lite_certificate(
+        &mut self,
+        _certificate: LiteCertificate<'_>,
+        _delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
+    async fn handle_certificate(
+        &mut self,
+        _certificate: Certificate,
+        _hashed_certificate_values: Vec<HashedCertificateValue>,
+        _hashed_blobs: Vec<HashedBlob>,
+        _delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
+    async fn handle_chain_info_query(
+        &mut self,
+        query: ChainInfoQuery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        Ok(self
+            .respond(|state| self.chain_info_response(state, query))
+            .await)
+    }
+
+    async fn subscribe(&mut self, _chains: Vec<ChainId>) -> Result<NotificationStream, NodeError> {
+        Ok(Box::pin(stream::empty()))
+    }
+
+    async fn get_version_info(&mut self) -> Result<VersionInfo, NodeError> {
+        Ok(Default::default())
+    }
+
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        Ok(SUPPORTED_FEATURES
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect())
+    }
+
+    async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
+        self.respond(|state| match state.blobs.get(&blob_id) {
+            Some(blob) => Ok(blob.blob().clone()),
+            None => Err(ViewError::not_found("blob", blob_id).into()),
+        })
+        .await
+    }
+
+    async fn download_certificate_value(
+        &mut self,
+        hash: CryptoHash,
+    ) -> Result<HashedCertificateValue, NodeError> {
+        self.respond(|state| match state.values.get(&hash) {
+            Some(value) => Ok(value.clone()),
+            None => Err(ViewError::not_found("certificate value", hash).into()),
+        })
+        .await
+    }
+
+    async fn download_certificate(&mut self, hash: CryptoHash) -> Result<Certificate, NodeError> {
+        self.respond(|state| match state.certificates.get(&hash) {
+            Some(certificate) => Ok(certificate.clone()),
+            None => Err(ViewError::not_found("certificate", hash).into()),
+        })
+        .await
+    }
+
+    /// Blob usage isn't tracked, so this always fails.
+    async fn blob_last_used_by(&mut self, blob_id: BlobId) -> Result<CryptoHash, NodeError> {
+        Err(ViewError::not_found("blob state", blob_id).into())
+    }
+}
+
 // NOTE:
 // * To communicate with a quorum of validators, chain clients iterate over a copy of
 // `validator_clients` to spawn I/O tasks.
@@ -1041,10 +1300,7 @@ impl StorageBuilder for ScyllaDbStorageBuilder {
         let namespace = generate_test_namespace();
         let namespace = format!("{}_{}", namespace, self.instance_counter);
         let common_config = create_scylla_db_common_config();
-        let store_config = ScyllaDbStoreConfig {
-            uri: self.uri.clone(),
-            common_config,
-        };
+        let store_config = ScyllaDbStoreConfig::new(self.uri.clone(), common_config);
         let storage = ScyllaDbStorage::new_for_testing(
             store_config,
             &namespace,
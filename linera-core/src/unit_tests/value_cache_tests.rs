@@ -51,6 +51,20 @@ async fn test_insert_single_hashed_blob() {
     assert_eq!(cache.keys::<BTreeSet<_>>().await, BTreeSet::from([blob_id]));
 }
 
+/// Tests that inserting a hashed blob with the wrong ID fails in debug builds.
+#[cfg(debug_assertions)]
+#[tokio::test]
+#[should_panic(expected = "was constructed with the wrong ID")]
+async fn test_insert_hashed_blob_with_wrong_id() {
+    let cache = ValueCache::<BlobId, HashedBlob>::default();
+    let other_id = create_dummy_hashed_blob(1).id();
+    let value = create_dummy_hashed_blob(0)
+        .into_inner()
+        .with_hash_unchecked(other_id);
+
+    cache.insert(Cow::Owned(value)).await;
+}
+
 /// Tests inserting many certificate values in the cache, one-by-one.
 #[tokio::test]
 async fn test_insert_many_certificate_values_individually() {
@@ -496,6 +510,26 @@ async fn test_filtering_out_cached_items() {
     );
 }
 
+/// Tests that a copy of the entries doesn't change their recency.
+#[tokio::test]
+async fn test_entries() {
+    let cache = ValueCache::<BlobId, HashedBlob>::default();
+    let blobs = create_dummy_hashed_blobs();
+    for blob in &blobs {
+        cache.insert(Cow::Borrowed(blob)).await;
+    }
+
+    let entries = cache.entries::<Vec<_>>().await;
+    assert_eq!(entries.len(), blobs.len());
+    assert!(entries.iter().all(|(blob_id, blob)| *blob_id == blob.id()));
+
+    // The oldest blob is still the first one to be evicted.
+    let new_blob = create_dummy_hashed_blob(DEFAULT_VALUE_CACHE_SIZE);
+    cache.insert(Cow::Borrowed(&new_blob)).await;
+    assert!(!cache.contains(&blobs[0].id()).await);
+    assert!(cache.contains(&blobs[1].id()).await);
+}
+
 /// Creates multiple dummy [`HashedCertificateValue`]s to use in the tests.
 fn create_dummy_certificate_values<Heights>(
     heights: Heights,
@@ -770,16 +770,16 @@ where
     worker
         .fully_handle_certificate_with_notifications(
             certificate0.clone(),
-            vec![],
-            vec![],
+            &[],
+            &[],
             Some(&mut notifications),
         )
         .await?;
     worker
         .fully_handle_certificate_with_notifications(
             certificate1.clone(),
-            vec![],
-            vec![],
+            &[],
+            &[],
             Some(&mut notifications),
         )
         .await?;
@@ -1958,6 +1958,45 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_chain_info_query_with_blobs<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+    ViewError: From<<B::Storage as Storage>::ContextError>,
+{
+    let key_pair = KeyPair::generate();
+    let (_, worker) = init_worker_with_chain(
+        storage_builder.build().await?,
+        ChainDescription::Root(1),
+        key_pair.public(),
+        Amount::from_tokens(5),
+    )
+    .await;
+    let blob = Blob {
+        bytes: b"known blob".to_vec(),
+    }
+    .into_hashed();
+    let unknown_blob_id = Blob {
+        bytes: b"unknown blob".to_vec(),
+    }
+    .into_hashed()
+    .id();
+    worker
+        .storage
+        .write_hashed_blob(&blob, &CryptoHash::test_hash("certificate"))
+        .await?;
+
+    // Only the blobs that the validator has are returned.
+    let query = ChainInfoQuery::new(ChainId::root(1)).with_blobs(vec![unknown_blob_id, blob.id()]);
+    let (response, _actions) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(response.info.requested_blobs, vec![blob]);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
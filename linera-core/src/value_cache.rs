@@ -89,6 +89,22 @@ where
             .collect()
     }
 
+    /// Returns a `Collection` of copies of the entries in the cache, without changing their
+    /// recency.
+    ///
+    /// The cache is only locked while the entries are copied.
+    pub async fn entries<Collection>(&self) -> Collection
+    where
+        Collection: FromIterator<(K, V)>,
+    {
+        self.cache
+            .lock()
+            .await
+            .iter()
+            .map(|(key, value)| (*key, value.clone()))
+            .collect()
+    }
+
     /// Returns [`true`] if the cache contains the `V` with the
     /// requested `K`.
     pub async fn contains(&self, key: &K) -> bool {
@@ -245,8 +261,16 @@ impl ValueCache<BlobId, HashedBlob> {
     /// inserted in the cache.
     ///
     /// Returns [`true`] if the value was not already present in the cache.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, if the blob ID is not the hash of the blob.
     pub async fn insert<'a>(&self, value: Cow<'a, HashedBlob>) -> bool {
         let blob_id = (*value).id();
+        debug_assert!(
+            value.has_valid_id(),
+            "Blob {blob_id} was constructed with the wrong ID"
+        );
         let mut cache = self.cache.lock().await;
         if cache.contains(&blob_id) {
             // Promote the re-inserted value in the cache, as if it was accessed again.
@@ -346,6 +346,12 @@ impl<StorageClient> WorkerState<StorageClient> {
         self.recent_hashed_blobs.clone()
     }
 
+    pub(crate) fn recent_hashed_certificate_values(
+        &self,
+    ) -> Arc<ValueCache<CryptoHash, HashedCertificateValue>> {
+        self.recent_hashed_certificate_values.clone()
+    }
+
     /// Returns the storage client so that it can be manipulated or queried.
     #[cfg(not(feature = "test"))]
     pub(crate) fn storage_client(&self) -> &StorageClient {
@@ -401,8 +407,8 @@ where
     ) -> Result<ChainInfoResponse, WorkerError> {
         self.fully_handle_certificate_with_notifications(
             certificate,
-            hashed_certificate_values,
-            hashed_blobs,
+            &hashed_certificate_values,
+            &hashed_blobs,
             None::<&mut Vec<Notification>>,
         )
         .await
@@ -412,12 +418,17 @@ where
     pub(crate) async fn fully_handle_certificate_with_notifications(
         &mut self,
         certificate: Certificate,
-        hashed_certificate_values: Vec<HashedCertificateValue>,
-        hashed_blobs: Vec<HashedBlob>,
+        hashed_certificate_values: &[HashedCertificateValue],
+        hashed_blobs: &[HashedBlob],
         mut notifications: Option<&mut impl Extend<Notification>>,
     ) -> Result<ChainInfoResponse, WorkerError> {
         let (response, actions) = self
-            .handle_certificate(certificate, hashed_certificate_values, hashed_blobs, None)
+            .handle_certificate_with_dependencies(
+                certificate,
+                hashed_certificate_values,
+                hashed_blobs,
+                None,
+            )
             .await?;
         if let Some(ref mut notifications) = notifications {
             notifications.extend(actions.notifications);
@@ -433,6 +444,107 @@ where
         Ok(response)
     }
 
+    /// Processes a certificate without delivering its outgoing messages to the recipient
+    /// chains. The messages stay in the outboxes of the chain.
+    ///
+    /// This is only meant for bulk loads of a trusted chain history, e.g. from an archive:
+    /// the recipient chains on this worker will not see the messages until they are
+    /// delivered by some other means.
+    pub(crate) async fn handle_certificate_import_only(
+        &mut self,
+        certificate: Certificate,
+        hashed_certificate_values: Vec<HashedCertificateValue>,
+        hashed_blobs: Vec<HashedBlob>,
+        notifications: Option<&mut impl Extend<Notification>>,
+    ) -> Result<ChainInfoResponse, WorkerError> {
+        let (response, actions) = self
+            .handle_certificate(certificate, hashed_certificate_values, hashed_blobs, None)
+            .await?;
+        if let Some(notifications) = notifications {
+            notifications.extend(actions.notifications);
+        }
+        Ok(response)
+    }
+
+    /// Processes a certificate, like [`ValidatorWorker::handle_certificate`], with
+    /// dependencies that are only copied if the certificate needs them.
+    #[instrument(skip_all, fields(
+        nick = self.nickname,
+        chain_id = format!("{:.8}", certificate.value().chain_id()),
+        height = %certificate.value().height(),
+    ))]
+    pub(crate) async fn handle_certificate_with_dependencies(
+        &mut self,
+        certificate: Certificate,
+        hashed_certificate_values: &[HashedCertificateValue],
+        hashed_blobs: &[HashedBlob],
+        notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        trace!("{} <-- {:?}", self.nickname, certificate);
+        ensure!(
+            certificate.value().is_confirmed() || hashed_certificate_values.is_empty(),
+            WorkerError::UnneededValue {
+                value_hash: hashed_certificate_values[0].hash(),
+            }
+        );
+
+        #[cfg(with_metrics)]
+        let (round, log_str, mut confirmed_transactions, mut duplicated) = (
+            certificate.round,
+            certificate.value().to_log_str(),
+            0u64,
+            false,
+        );
+
+        let (info, actions) = match certificate.value() {
+            CertificateValue::ValidatedBlock { .. } => {
+                // Confirm the validated block.
+                let validation_outcomes = self.process_validated_block(certificate).await?;
+                #[cfg(with_metrics)]
+                {
+                    duplicated = validation_outcomes.2;
+                }
+                let (info, actions, _) = validation_outcomes;
+                (info, actions)
+            }
+            CertificateValue::ConfirmedBlock {
+                executed_block: _executed_block,
+            } => {
+                #[cfg(with_metrics)]
+                {
+                    confirmed_transactions = (_executed_block.block.incoming_messages.len()
+                        + _executed_block.block.operations.len())
+                        as u64;
+                }
+                // Execute the confirmed block.
+                self.process_confirmed_block(
+                    certificate,
+                    hashed_certificate_values,
+                    hashed_blobs,
+                    notify_when_messages_are_delivered,
+                )
+                .await?
+            }
+            CertificateValue::Timeout { .. } => {
+                // Handle the leader timeout.
+                self.process_timeout(certificate).await?
+            }
+        };
+
+        #[cfg(with_metrics)]
+        if !duplicated {
+            NUM_ROUNDS_IN_CERTIFICATE
+                .with_label_values(&[log_str, round.type_name()])
+                .observe(round.number() as f64);
+            if confirmed_transactions > 0 {
+                TRANSACTION_COUNT
+                    .with_label_values(&[])
+                    .inc_by(confirmed_transactions);
+            }
+        }
+        Ok((info, actions))
+    }
+
     /// Tries to execute a block proposal without any verification other than block execution.
     pub async fn stage_block_execution(
         &mut self,
@@ -609,14 +721,6 @@ where
         .await
     }
 
-    /// Inserts a [`HashedCertificateValue`] into the worker's cache.
-    pub(crate) async fn cache_recent_hashed_certificate_value<'a>(
-        &mut self,
-        value: Cow<'a, HashedCertificateValue>,
-    ) -> bool {
-        self.recent_hashed_certificate_values.insert(value).await
-    }
-
     /// Inserts a [`HashedBlob`] into the worker's cache.
     pub async fn cache_recent_blob<'a>(&mut self, hashed_blob: Cow<'a, HashedBlob>) -> bool {
         self.recent_hashed_blobs.insert(hashed_blob).await
@@ -802,12 +906,6 @@ where
         .await
     }
 
-    /// Processes a certificate.
-    #[instrument(skip_all, fields(
-        nick = self.nickname,
-        chain_id = format!("{:.8}", certificate.value().chain_id()),
-        height = %certificate.value().height(),
-    ))]
     async fn handle_certificate(
         &mut self,
         certificate: Certificate,
@@ -815,69 +913,13 @@ where
         hashed_blobs: Vec<HashedBlob>,
         notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
-        trace!("{} <-- {:?}", self.nickname, certificate);
-        ensure!(
-            certificate.value().is_confirmed() || hashed_certificate_values.is_empty(),
-            WorkerError::UnneededValue {
-                value_hash: hashed_certificate_values[0].hash(),
-            }
-        );
-
-        #[cfg(with_metrics)]
-        let (round, log_str, mut confirmed_transactions, mut duplicated) = (
-            certificate.round,
-            certificate.value().to_log_str(),
-            0u64,
-            false,
-        );
-
-        let (info, actions) = match certificate.value() {
-            CertificateValue::ValidatedBlock { .. } => {
-                // Confirm the validated block.
-                let validation_outcomes = self.process_validated_block(certificate).await?;
-                #[cfg(with_metrics)]
-                {
-                    duplicated = validation_outcomes.2;
-                }
-                let (info, actions, _) = validation_outcomes;
-                (info, actions)
-            }
-            CertificateValue::ConfirmedBlock {
-                executed_block: _executed_block,
-            } => {
-                #[cfg(with_metrics)]
-                {
-                    confirmed_transactions = (_executed_block.block.incoming_messages.len()
-                        + _executed_block.block.operations.len())
-                        as u64;
-                }
-                // Execute the confirmed block.
-                self.process_confirmed_block(
-                    certificate,
-                    &hashed_certificate_values,
-                    &hashed_blobs,
-                    notify_when_messages_are_delivered,
-                )
-                .await?
-            }
-            CertificateValue::Timeout { .. } => {
-                // Handle the leader timeout.
-                self.process_timeout(certificate).await?
-            }
-        };
-
-        #[cfg(with_metrics)]
-        if !duplicated {
-            NUM_ROUNDS_IN_CERTIFICATE
-                .with_label_values(&[log_str, round.type_name()])
-                .observe(round.number() as f64);
-            if confirmed_transactions > 0 {
-                TRANSACTION_COUNT
-                    .with_label_values(&[])
-                    .inc_by(confirmed_transactions);
-            }
-        }
-        Ok((info, actions))
+        self.handle_certificate_with_dependencies(
+            certificate,
+            &hashed_certificate_values,
+            &hashed_blobs,
+            notify_when_messages_are_delivered,
+        )
+        .await
     }
 
     #[instrument(skip_all, fields(
@@ -236,6 +236,11 @@ where
         }
         Ok(result)
     }
+
+    /// Returns the version information on this indexer
+    pub async fn version(&self) -> linera_version::VersionInfo {
+        linera_version::VersionInfo::default()
+    }
 }
 
 impl<C> State<C>
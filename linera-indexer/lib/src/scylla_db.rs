@@ -41,10 +41,7 @@ impl ScyllaDbRunner {
             cache_size: config.client.cache_size,
         };
         let namespace = config.client.table.clone();
-        let store_config = ScyllaDbStoreConfig {
-            uri: config.client.uri.clone(),
-            common_config,
-        };
+        let store_config = ScyllaDbStoreConfig::new(config.client.uri.clone(), common_config);
         let store = ScyllaDbStore::connect(&store_config, &namespace).await?;
         Self::new(config, store).await
     }
@@ -146,6 +146,15 @@ impl ValidatorNode for Client {
         })
     }
 
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        Ok(match self {
+            Client::Grpc(grpc_client) => grpc_client.get_features().await?,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.get_features().await?,
+        })
+    }
+
     async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
         Ok(match self {
             Client::Grpc(grpc_client) => grpc_client.download_blob(blob_id).await?,
@@ -266,6 +266,18 @@ impl ValidatorNode for GrpcClient {
         Ok(self.client.get_version_info(()).await?.into_inner().into())
     }
 
+    /// Gets the features sent along with the version information. Validators that predate
+    /// them don't send any.
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        Ok(self
+            .client
+            .get_version_info(())
+            .await?
+            .into_inner()
+            .features)
+    }
+
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
     async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
         Ok(self
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_base::{
-    crypto::{CryptoError, CryptoHash, PublicKey, Signature},
+    crypto::{CryptoError, CryptoHash, HashAlgorithm, PublicKey, Signature},
     data_types::{Blob, BlockHeight},
     ensure,
     identifiers::{BlobId, ChainId, Owner},
@@ -100,6 +100,7 @@ impl From<linera_version::VersionInfo> for api::VersionInfo {
             rpc_hash: version_info.rpc_hash.into(),
             graphql_hash: version_info.graphql_hash.into(),
             wit_hash: version_info.wit_hash.into(),
+            features: Vec::new(),
         }
     }
 }
@@ -389,12 +390,19 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
             request_pending_messages: chain_info_query.request_pending_messages,
             chain_id: try_proto_convert(chain_info_query.chain_id)?,
             request_sent_certificate_hashes_in_range,
+            request_sent_certificate_dependencies: chain_info_query
+                .request_sent_certificate_dependencies,
             request_received_log_excluding_first_nth: chain_info_query
                 .request_received_log_excluding_first_nth,
             test_next_block_height: chain_info_query.test_next_block_height.map(Into::into),
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_blobs: chain_info_query
+                .request_blobs
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -415,11 +423,18 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             request_pending_messages: chain_info_query.request_pending_messages,
             test_next_block_height: chain_info_query.test_next_block_height.map(Into::into),
             request_sent_certificate_hashes_in_range,
+            request_sent_certificate_dependencies: chain_info_query
+                .request_sent_certificate_dependencies,
             request_received_log_excluding_first_nth: chain_info_query
                 .request_received_log_excluding_first_nth,
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_blobs: chain_info_query
+                .request_blobs
+                .into_iter()
+                .map(Into::into)
+                .collect(),
         })
     }
 }
@@ -546,14 +561,24 @@ impl TryFrom<api::BlobId> for BlobId {
     type Error = GrpcProtoConversionError;
 
     fn try_from(blob_id: api::BlobId) -> Result<Self, Self::Error> {
-        Ok(Self(CryptoHash::try_from(blob_id.bytes.as_slice())?))
+        // Peers that predate hash algorithms leave the algorithm empty.
+        let algorithm = if blob_id.algorithm.is_empty() {
+            HashAlgorithm::default()
+        } else {
+            blob_id.algorithm.parse()?
+        };
+        Ok(Self {
+            hash: CryptoHash::try_from(blob_id.bytes.as_slice())?,
+            algorithm,
+        })
     }
 }
 
 impl From<BlobId> for api::BlobId {
     fn from(blob_id: BlobId) -> Self {
         Self {
-            bytes: blob_id.0.as_bytes().to_vec(),
+            bytes: blob_id.hash.as_bytes().to_vec(),
+            algorithm: blob_id.algorithm.to_string(),
         }
     }
 }
@@ -617,7 +642,7 @@ pub mod tests {
     use std::{borrow::Cow, fmt::Debug};
 
     use linera_base::{
-        crypto::{BcsSignable, CryptoHash, KeyPair},
+        crypto::{BcsSignable, CryptoHash, HashAlgorithm, KeyPair},
         data_types::{Amount, Round, Timestamp},
     };
     use linera_chain::{
@@ -709,6 +734,8 @@ pub mod tests {
             requested_sent_certificate_hashes: vec![],
             count_received_log: 0,
             requested_received_log: vec![],
+            requested_blobs: vec![],
+            requested_hashed_certificate_values: vec![],
         });
 
         let chain_info_response_none = ChainInfoResponse {
@@ -743,14 +770,31 @@ pub mod tests {
                     limit: Some(5),
                 },
             ),
+            request_sent_certificate_dependencies: true,
             request_received_log_excluding_first_nth: None,
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: true,
+            request_blobs: vec![BlobId {
+                hash: CryptoHash::new(&Foo("blob".into())),
+                algorithm: HashAlgorithm::Keccak256,
+            }],
         };
         round_trip_check::<_, api::ChainInfoQuery>(chain_info_query_some);
     }
 
+    #[test]
+    pub fn test_blob_id_without_algorithm() {
+        let hash = CryptoHash::new(&Foo("blob".into()));
+        let blob_id = api::BlobId {
+            bytes: hash.as_bytes().to_vec(),
+            algorithm: String::new(),
+        };
+        let blob_id = BlobId::try_from(blob_id).unwrap();
+        assert_eq!(blob_id.hash, hash);
+        assert_eq!(blob_id.algorithm, HashAlgorithm::Sha3_256);
+    }
+
     #[test]
     pub fn test_lite_certificate() {
         let key_pair = KeyPair::generate();
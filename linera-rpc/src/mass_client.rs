@@ -10,6 +10,9 @@ use crate::RpcMessage;
 pub enum MassClientError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[cfg(with_simple_network)]
+    #[error("simple transport error: {0}")]
+    Simple(#[from] crate::simple::Error),
     #[error("tonic transport: {0}")]
     TonicTransport(#[from] crate::grpc::transport::Error),
     #[error("conversion error: {0}")]
@@ -43,6 +43,13 @@ pub enum RpcMessage {
 
     // Internal to a validator
     CrossChainRequest(Box<CrossChainRequest>),
+
+    // First message in each direction of a TCP connection
+    Handshake(Box<VersionInfo>),
+
+    // Optional capabilities of a validator, queried separately from its version
+    FeaturesQuery,
+    FeaturesResponse(Vec<String>),
 }
 
 impl RpcMessage {
@@ -70,7 +77,10 @@ impl RpcMessage {
             | DownloadCertificate(_)
             | BlobLastUsedBy(_)
             | BlobLastUsedByResponse(_)
-            | DownloadCertificateResponse(_) => {
+            | DownloadCertificateResponse(_)
+            | Handshake(_)
+            | FeaturesQuery
+            | FeaturesResponse(_) => {
                 return None;
             }
         };
@@ -85,6 +95,7 @@ impl RpcMessage {
 
         match self {
             VersionInfoQuery
+            | FeaturesQuery
             | DownloadBlob(_)
             | DownloadCertificateValue(_)
             | BlobLastUsedBy(_)
@@ -101,7 +112,9 @@ impl RpcMessage {
             | DownloadBlobResponse(_)
             | DownloadCertificateValueResponse(_)
             | BlobLastUsedByResponse(_)
-            | DownloadCertificateResponse(_) => false,
+            | DownloadCertificateResponse(_)
+            | Handshake(_)
+            | FeaturesResponse(_) => false,
         }
     }
 }
@@ -130,6 +143,18 @@ impl TryFrom<RpcMessage> for VersionInfo {
     }
 }
 
+impl TryFrom<RpcMessage> for Vec<String> {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        use RpcMessage::*;
+        match message {
+            FeaturesResponse(features) => Ok(features),
+            Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
 impl TryFrom<RpcMessage> for Blob {
     type Error = NodeError;
     fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
@@ -137,6 +137,10 @@ impl ValidatorNode for SimpleClient {
         self.query(RpcMessage::VersionInfoQuery).await
     }
 
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        self.query(RpcMessage::FeaturesQuery).await
+    }
+
     async fn download_blob(&mut self, blob_id: BlobId) -> Result<Blob, NodeError> {
         self.query(RpcMessage::DownloadBlob(Box::new(blob_id)))
             .await
@@ -5,6 +5,7 @@ use std::{io, mem, ops::DerefMut};
 
 use bytes::{Buf, BufMut, BytesMut};
 use linera_core::node::NodeError;
+use linera_version::IncompatibleVersionError;
 use thiserror::Error;
 use tokio_util::codec::{Decoder, Encoder};
 
@@ -99,6 +100,15 @@ pub enum Error {
         message is {size} bytes but can't be larger than {max} bytes.",
         max = u32::MAX)]
     MessageTooBig { size: usize },
+
+    #[error(transparent)]
+    IncompatibleVersion(#[from] IncompatibleVersionError),
+
+    #[error("The peer rejected the connection: {0}")]
+    HandshakeRejected(NodeError),
+
+    #[error("The peer did not start the connection with a handshake")]
+    MissingHandshake,
 }
 
 impl From<Error> for NodeError {
@@ -107,6 +117,9 @@ impl From<Error> for NodeError {
             Error::Io(io_error) => NodeError::ClientIoError {
                 error: format!("{}", io_error),
             },
+            Error::IncompatibleVersion(error) => error.into(),
+            Error::HandshakeRejected(error) => error,
+            Error::MissingHandshake => NodeError::UnexpectedMessage,
             err => {
                 tracing::error!("Unexpected decoding error: {err}");
                 NodeError::InvalidDecoding
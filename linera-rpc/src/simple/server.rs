@@ -294,6 +294,13 @@ where
 
             RpcMessage::VersionInfoQuery => Ok(Some(linera_version::VersionInfo::default().into())),
 
+            RpcMessage::FeaturesQuery => Ok(Some(RpcMessage::FeaturesResponse(
+                linera_version::SUPPORTED_FEATURES
+                    .iter()
+                    .map(|feature| feature.to_string())
+                    .collect(),
+            ))),
+
             RpcMessage::Vote(_)
             | RpcMessage::Error(_)
             | RpcMessage::ChainInfoResponse(_)
@@ -305,7 +312,9 @@ where
             | RpcMessage::BlobLastUsedBy(_)
             | RpcMessage::BlobLastUsedByResponse(_)
             | RpcMessage::DownloadCertificate(_)
-            | RpcMessage::DownloadCertificateResponse(_) => Err(NodeError::UnexpectedMessage),
+            | RpcMessage::DownloadCertificateResponse(_)
+            | RpcMessage::Handshake(_)
+            | RpcMessage::FeaturesResponse(_) => Err(NodeError::UnexpectedMessage),
         };
 
         self.server.packets_processed += 1;
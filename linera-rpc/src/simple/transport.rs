@@ -10,7 +10,8 @@ use futures::{
     stream::{self, FuturesUnordered, SplitSink, SplitStream},
     Sink, SinkExt, Stream, StreamExt, TryStreamExt,
 };
-use linera_core::{JoinSetExt as _, TaskHandle};
+use linera_core::{node::NodeError, JoinSetExt as _, TaskHandle};
+use linera_version::VersionInfo;
 use serde::{Deserialize, Serialize};
 use tokio::{
     io::AsyncWriteExt,
@@ -112,12 +113,38 @@ impl<T> Transport for T where
 {
 }
 
+/// Performs the client side of the handshake that starts every TCP connection.
+///
+/// The client sends its [`VersionInfo`] and the server replies with its own, or with an
+/// error before closing the connection if the client's version is not known to be
+/// compatible with the server's. The client checks the server's version as well.
+async fn handshake<T>(transport: &mut T) -> Result<(), codec::Error>
+where
+    T: Transport + Unpin,
+{
+    let version = VersionInfo::default();
+    transport
+        .send(RpcMessage::Handshake(Box::new(version.clone())))
+        .await?;
+    match transport.next().await.transpose()? {
+        Some(RpcMessage::Handshake(server_version)) => {
+            Ok(version.require_compatible(&server_version)?)
+        }
+        Some(RpcMessage::Error(error)) => Err(codec::Error::HandshakeRejected(*error)),
+        Some(_) => Err(codec::Error::MissingHandshake),
+        None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    }
+}
+
 impl TransportProtocol {
     /// Creates a transport for this protocol.
+    ///
+    /// TCP connections start with a version handshake, which fails if the client and the
+    /// server are not known to be compatible. UDP is connectionless and has no handshake.
     pub async fn connect(
         self,
         address: impl ToSocketAddrs,
-    ) -> Result<impl Transport, std::io::Error> {
+    ) -> Result<impl Transport, codec::Error> {
         let mut addresses = lookup_host(address)
             .await
             .expect("Invalid address to connect to");
@@ -136,8 +163,10 @@ impl TransportProtocol {
             }
             TransportProtocol::Tcp => {
                 let stream = TcpStream::connect(address).await?;
+                let mut transport = Framed::new(stream, Codec);
+                handshake(&mut transport).await?;
 
-                Framed::new(stream, Codec).right_stream()
+                transport.right_stream()
             }
         };
 
@@ -329,12 +358,11 @@ impl TcpConnectionPool {
     async fn get_stream(
         &mut self,
         address: &str,
-    ) -> Result<&mut Framed<TcpStream, Codec>, io::Error> {
+    ) -> Result<&mut Framed<TcpStream, Codec>, codec::Error> {
         if !self.streams.contains_key(address) {
-            match TcpStream::connect(address).await {
-                Ok(s) => {
-                    self.streams
-                        .insert(address.to_string(), Framed::new(s, Codec));
+            match Self::connect(address).await {
+                Ok(stream) => {
+                    self.streams.insert(address.to_string(), stream);
                 }
                 Err(error) => {
                     error!("Failed to open connection to {}: {}", address, error);
@@ -344,6 +372,12 @@ impl TcpConnectionPool {
         };
         Ok(self.streams.get_mut(address).unwrap())
     }
+
+    async fn connect(address: &str) -> Result<Framed<TcpStream, Codec>, codec::Error> {
+        let mut stream = Framed::new(TcpStream::connect(address).await?, Codec);
+        handshake(&mut stream).await?;
+        Ok(stream)
+    }
 }
 
 impl ConnectionPool for TcpConnectionPool {
@@ -368,6 +402,7 @@ pub struct TcpServer<State> {
     connection: Framed<TcpStream, Codec>,
     handler: State,
     shutdown_signal: CancellationToken,
+    handshake_done: bool,
 }
 
 impl<State> TcpServer<State>
@@ -439,6 +474,7 @@ where
             connection: Framed::new(tcp_stream, Codec),
             handler,
             shutdown_signal,
+            handshake_done: false,
         }
     }
 
@@ -458,7 +494,12 @@ where
                     return;
                 }
                 result = self.connection.next() => match result {
-                    Some(Ok(message)) => self.handle_message(message).await,
+                    Some(Ok(message)) if self.handshake_done => self.handle_message(message).await,
+                    Some(Ok(message)) => {
+                        if !self.handle_handshake(message).await {
+                            return;
+                        }
+                    }
                     Some(Err(error)) => {
                         self.handle_error(error);
                         return;
@@ -469,6 +510,37 @@ where
         }
     }
 
+    /// Handles the first message of the connection, which must be the client's
+    /// [`VersionInfo`].
+    ///
+    /// Replies with the server's version if the client's version is compatible with it.
+    /// Otherwise, replies with an error and returns `false` so that the connection is
+    /// closed.
+    async fn handle_handshake(&mut self, message: RpcMessage) -> bool {
+        let version = VersionInfo::default();
+        let result = match message {
+            RpcMessage::Handshake(client_version) => client_version
+                .require_compatible(&version)
+                .map_err(NodeError::from),
+            _ => Err(NodeError::UnexpectedMessage),
+        };
+        let reply = match result {
+            Ok(()) => {
+                self.handshake_done = true;
+                RpcMessage::Handshake(Box::new(version))
+            }
+            Err(error) => {
+                warn!("Rejecting a connection without a compatible handshake: {error}");
+                RpcMessage::Error(Box::new(error))
+            }
+        };
+        if let Err(error) = self.connection.send(reply).await {
+            error!("Failed to send handshake response: {error}");
+            return false;
+        }
+        self.handshake_done
+    }
+
     /// Handles a single request message from a client.
     async fn handle_message(&mut self, message: RpcMessage) {
         if let Some(reply) = self.handler.handle_message(message).await {
@@ -493,3 +565,81 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures::{SinkExt as _, StreamExt as _};
+    use linera_core::node::NodeError;
+    use linera_version::VersionInfo;
+    use tokio::{net::TcpStream, task::JoinSet};
+    use tokio_util::{codec::Framed, sync::CancellationToken};
+
+    use super::{MessageHandler, TransportProtocol};
+    use crate::{simple::codec::Codec, RpcMessage};
+
+    /// A handler that answers version queries.
+    #[derive(Clone)]
+    struct VersionHandler;
+
+    #[async_trait]
+    impl MessageHandler for VersionHandler {
+        async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+            match message {
+                RpcMessage::VersionInfoQuery => Some(VersionInfo::default().into()),
+                _ => None,
+            }
+        }
+    }
+
+    /// Starts a TCP server on a free local port and returns its address.
+    async fn start_server(
+        shutdown_signal: CancellationToken,
+        join_set: &mut JoinSet<()>,
+    ) -> String {
+        let address = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        TransportProtocol::Tcp.spawn_server(address, VersionHandler, shutdown_signal, join_set);
+        // Wait for the server to listen.
+        while TcpStream::connect(address).await.is_err() {
+            tokio::task::yield_now().await;
+        }
+        address.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_starts_with_handshake() {
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let address = start_server(shutdown_signal.clone(), &mut join_set).await;
+
+        let mut transport = TransportProtocol::Tcp.connect(&address).await.unwrap();
+        transport.send(RpcMessage::VersionInfoQuery).await.unwrap();
+        let response = transport.next().await.unwrap().unwrap();
+        assert_eq!(response, VersionInfo::default().into());
+
+        shutdown_signal.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_without_handshake_is_rejected() {
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let address = start_server(shutdown_signal.clone(), &mut join_set).await;
+
+        let stream = TcpStream::connect(&address).await.unwrap();
+        let mut transport = Framed::new(stream, Codec);
+        transport.send(RpcMessage::VersionInfoQuery).await.unwrap();
+        let response = transport.next().await.unwrap().unwrap();
+        assert_eq!(
+            response,
+            RpcMessage::Error(Box::new(NodeError::UnexpectedMessage))
+        );
+        assert!(transport.next().await.is_none());
+
+        shutdown_signal.cancel();
+    }
+}
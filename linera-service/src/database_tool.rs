@@ -3,6 +3,7 @@
 
 use std::process;
 
+use linera_base::tracing_config::TracingConfig;
 use linera_service::storage::StorageConfigNamespace;
 use linera_views::common::CommonStoreConfig;
 
@@ -127,13 +128,7 @@ async fn evaluate_options(options: DatabaseToolOptions) -> Result<i32, anyhow::E
 }
 
 fn main() {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
-        .from_env_lossy();
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    linera_service::util::init_tracing(&TracingConfig::from_env());
 
     let options = <DatabaseToolOptions as clap::Parser>::parse();
 
@@ -397,7 +397,13 @@ where
         _request: Request<()>,
     ) -> Result<Response<VersionInfo>, Status> {
         // We assume each shard is running the same version as the proxy
-        Ok(Response::new(linera_version::VersionInfo::default().into()))
+        Ok(Response::new(VersionInfo {
+            features: linera_version::SUPPORTED_FEATURES
+                .iter()
+                .map(|feature| feature.to_string())
+                .collect(),
+            ..linera_version::VersionInfo::default().into()
+        }))
     }
 
     #[instrument(skip_all, err(Display))]
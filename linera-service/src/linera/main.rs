@@ -18,6 +18,7 @@ use linera_base::{
     data_types::{ApplicationPermissions, Timestamp},
     identifiers::{ChainDescription, ChainId, MessageId, Owner},
     ownership::ChainOwnership,
+    tracing_config::TracingConfig,
 };
 use linera_chain::data_types::{CertificateValue, ExecutedBlock};
 use linera_core::{
@@ -1200,13 +1201,7 @@ impl Job {
 }
 
 fn main() -> anyhow::Result<()> {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
-        .from_env_lossy();
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    linera_service::util::init_tracing(&TracingConfig::from_env());
     let options = ClientOptions::init()?;
 
     let mut runtime = if options.tokio_threads == Some(1) {
@@ -8,6 +8,7 @@ use std::{net::SocketAddr, path::PathBuf, time::Duration};
 use anyhow::{bail, Result};
 use async_trait::async_trait;
 use futures::{FutureExt as _, SinkExt, StreamExt};
+use linera_base::tracing_config::TracingConfig;
 use linera_core::{node::NodeError, JoinSetExt as _};
 use linera_rpc::{
     config::{
@@ -287,6 +288,12 @@ where
                 // We assume each shard is running the same version as the proxy
                 Ok(Some(linera_version::VersionInfo::default().into()))
             }
+            FeaturesQuery => Ok(Some(FeaturesResponse(
+                linera_version::SUPPORTED_FEATURES
+                    .iter()
+                    .map(|feature| feature.to_string())
+                    .collect(),
+            ))),
             DownloadBlob(blob_id) => Ok(Some(
                 self.storage
                     .read_hashed_blob(*blob_id)
@@ -319,21 +326,15 @@ where
             | DownloadBlobResponse(_)
             | BlobLastUsedByResponse(_)
             | DownloadCertificateValueResponse(_)
-            | DownloadCertificateResponse(_) => {
-                Err(anyhow::Error::from(NodeError::UnexpectedMessage))
-            }
+            | DownloadCertificateResponse(_)
+            | Handshake(_)
+            | FeaturesResponse(_) => Err(anyhow::Error::from(NodeError::UnexpectedMessage)),
         }
     }
 }
 
 fn main() -> Result<()> {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
-        .from_env_lossy();
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    util::init_tracing(&TracingConfig::from_env());
 
     let options = <ProxyOptions as clap::Parser>::parse();
 
@@ -74,6 +74,10 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn get_features(&mut self) -> Result<Vec<String>, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn download_blob(&mut self, _: BlobId) -> Result<Blob, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
@@ -9,7 +9,10 @@ use std::{path::PathBuf, time::Duration};
 use anyhow::bail;
 use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, FutureExt as _, StreamExt, TryFutureExt as _};
-use linera_base::crypto::{CryptoRng, KeyPair};
+use linera_base::{
+    crypto::{CryptoRng, KeyPair},
+    tracing_config::TracingConfig,
+};
 use linera_core::{worker::WorkerState, JoinSetExt as _};
 use linera_execution::{committee::ValidatorName, WasmRuntime, WithWasmDefault};
 use linera_rpc::{
@@ -392,13 +395,7 @@ enum ServerCommand {
 }
 
 fn main() {
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
-        .from_env_lossy();
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    util::init_tracing(&TracingConfig::from_env());
 
     let options = <ServerOptions as clap::Parser>::parse();
 
@@ -313,10 +313,7 @@ impl StorageConfigNamespace {
             }
             #[cfg(feature = "scylladb")]
             StorageConfig::ScyllaDb { uri } => {
-                let config = ScyllaDbStoreConfig {
-                    uri: uri.to_string(),
-                    common_config,
-                };
+                let config = ScyllaDbStoreConfig::new(uri.to_string(), common_config);
                 Ok(StoreConfig::ScyllaDb(config, namespace))
             }
         }
@@ -11,10 +11,14 @@ use axum::response::{self, IntoResponse};
 use http::Uri;
 #[cfg(test)]
 use linera_base::command::parse_version_message;
-use linera_base::data_types::TimeDelta;
+use linera_base::{
+    data_types::TimeDelta,
+    tracing_config::{SpanEvents, TracingConfig},
+};
 use tokio::signal::unix;
 use tokio_util::sync::CancellationToken;
 use tracing::debug;
+use tracing_subscriber::fmt::format::FmtSpan;
 
 /// Extension trait for [`tokio::process::Child`].
 pub trait ChildExt: std::fmt::Debug {
@@ -35,6 +39,40 @@ impl ChildExt for tokio::process::Child {
     }
 }
 
+/// Initializes tracing for the native binaries according to `config`, writing the logs to
+/// the standard error.
+pub fn init_tracing(config: &TracingConfig) {
+    let env_filter = tracing_subscriber::EnvFilter::builder()
+        .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
+        .parse_lossy(&config.filter);
+    let builder = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_span_events(fmt_span(config.span_events))
+        .with_env_filter(env_filter);
+    if config.json {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+    config.apply_redaction();
+}
+
+/// Converts [`SpanEvents`] into the equivalent [`FmtSpan`] of `tracing-subscriber`.
+fn fmt_span(span_events: SpanEvents) -> FmtSpan {
+    let mut fmt_span = FmtSpan::NONE;
+    for (enabled, event) in [
+        (span_events.new, FmtSpan::NEW),
+        (span_events.enter, FmtSpan::ENTER),
+        (span_events.exit, FmtSpan::EXIT),
+        (span_events.close, FmtSpan::CLOSE),
+    ] {
+        if enabled {
+            fmt_span |= event;
+        }
+    }
+    fmt_span
+}
+
 /// Listens for shutdown signals, and notifies the [`CancellationToken`] if one is
 /// received.
 pub async fn listen_for_shutdown_signals(shutdown_sender: CancellationToken) {
@@ -10,6 +10,8 @@ use linera_base::sync::Lazy;
 use linera_views::metering::KeyValueStoreMetrics;
 use linera_views::{
     common::{CommonStoreConfig, MIN_VIEW_TAG},
+    namespaced::NamespacedStoreError,
+    sharded::ShardedStoreError,
     value_splitting::DatabaseConsistencyError,
 };
 use thiserror::Error;
@@ -72,6 +74,14 @@ pub enum ServiceContextError {
     /// The database is not consistent
     #[error(transparent)]
     DatabaseConsistencyError(#[from] DatabaseConsistencyError),
+
+    /// The configuration of a sharded store is invalid
+    #[error(transparent)]
+    ShardedStoreError(#[from] ShardedStoreError),
+
+    /// The configuration of a namespaced store is invalid
+    #[error(transparent)]
+    NamespacedStoreError(#[from] NamespacedStoreError),
 }
 
 impl From<ServiceContextError> for linera_views::views::ViewError {
@@ -0,0 +1,94 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streams of the content of blobs, checked against the blob IDs as they are read.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt as _,
+};
+use linera_base::{
+    data_types::{BlobIdHasher, HashedBlob},
+    identifiers::BlobId,
+};
+use linera_views::views::ViewError;
+
+/// The size in bytes of the chunks of a [`BlobStream`] made from a whole blob.
+pub const BLOB_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The content of a blob, as a stream of chunks.
+///
+/// The chunks are hashed as they are read. If they don't match the blob ID, the stream
+/// ends with [`ViewError::InconsistentEntries`] after the last chunk, so the content must
+/// not be trusted until the stream has ended without an error.
+pub struct BlobStream {
+    len: usize,
+    chunks: BoxStream<'static, Result<Bytes, ViewError>>,
+}
+
+impl BlobStream {
+    /// Creates a stream of the `len` bytes of the blob `blob_id`, read as `chunks`.
+    pub fn new(
+        blob_id: BlobId,
+        len: usize,
+        chunks: impl Stream<Item = Result<Bytes, ViewError>> + Send + 'static,
+    ) -> Self {
+        let hasher = BlobIdHasher::new(len, blob_id.algorithm);
+        let state = Some((chunks.boxed(), hasher));
+        let chunks = stream::unfold(state, move |state| async move {
+            let (mut chunks, mut hasher) = state?;
+            match chunks.next().await {
+                Some(Ok(chunk)) => {
+                    hasher.update(&chunk);
+                    Some((Ok(chunk), Some((chunks, hasher))))
+                }
+                Some(Err(error)) => Some((Err(error), None)),
+                None if hasher.finalize() == Some(blob_id) => None,
+                None => Some((Err(ViewError::InconsistentEntries), None)),
+            }
+        })
+        .boxed();
+        BlobStream { len, chunks }
+    }
+
+    /// Creates a stream of the content of a blob that was read whole, in chunks of
+    /// [`BLOB_STREAM_CHUNK_SIZE`] bytes.
+    pub fn from_blob(hashed_blob: HashedBlob) -> Self {
+        let blob_id = hashed_blob.id();
+        let bytes = Bytes::from(hashed_blob.into_inner().bytes);
+        let len = bytes.len();
+        let chunks = (0..len).step_by(BLOB_STREAM_CHUNK_SIZE).map(move |start| {
+            let end = len.min(start + BLOB_STREAM_CHUNK_SIZE);
+            Ok(bytes.slice(start..end))
+        });
+        Self::new(blob_id, len, stream::iter(chunks))
+    }
+
+    /// Returns the size of the blob in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the blob is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Stream for BlobStream {
+    type Item = Result<Bytes, ViewError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.chunks.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+#[path = "unit_tests/blob_stream.rs"]
+mod unit_tests;
@@ -1,14 +1,14 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::BTreeSet, fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Blob, BlobState, HashedBlob, TimeDelta, Timestamp},
-    identifiers::{BlobId, ChainId},
+    data_types::{Blob, BlobState, BlockHeight, HashedBlob, TimeDelta, Timestamp},
+    identifiers::{BlobId, ChainId, MessageId},
 };
 use linera_chain::{
     data_types::{Certificate, CertificateValue, HashedCertificateValue, LiteCertificate},
@@ -19,7 +19,7 @@ use linera_execution::{
 };
 use linera_views::{
     batch::Batch,
-    common::{AdminKeyValueStore, ContextFromStore, KeyValueStore},
+    common::{AdminKeyValueStore, ContextFromStore, KeyIterable, KeyValueStore, MaintenanceOp},
     value_splitting::DatabaseConsistencyError,
     views::{View, ViewError},
 };
@@ -250,6 +250,13 @@ enum BaseKey {
     Value(CryptoHash),
     BlobId(BlobId),
     BlobStateId(BlobId),
+    /// The hash of the confirmed certificate for a block, indexing the messages it sent.
+    ConfirmedBlock(ChainId, BlockHeight),
+    /// An empty value marking that the chain has a state, listed by `list_chain_ids`.
+    ChainIndex(ChainId),
+    /// An empty value marking that the chains written before they were indexed were added
+    /// to the index.
+    ChainIndexBackfilled,
 }
 
 /// A clock that can be used to get the current `Timestamp`.
@@ -388,6 +395,10 @@ where
         &self.clock
     }
 
+    fn chain_state_base_key(&self, chain_id: ChainId) -> Result<Vec<u8>, ViewError> {
+        Ok(bcs::to_bytes(&BaseKey::ChainState(chain_id))?)
+    }
+
     async fn load_chain(
         &self,
         chain_id: ChainId,
@@ -405,11 +416,33 @@ where
             _chain_guard: Arc::new(guard),
         };
         let client = self.client.client.clone();
-        let base_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
+        let base_key = self.chain_state_base_key(chain_id)?;
         let context = ContextFromStore::create(client, base_key, runtime_context).await?;
         ChainStateView::load(context).await
     }
 
+    async fn list_chain_ids(&self) -> Result<Vec<ChainId>, ViewError> {
+        let backfilled_key = bcs::to_bytes(&BaseKey::ChainIndexBackfilled)?;
+        if !self.client.client.contains_key(&backfilled_key).await? {
+            self.backfill_chain_index().await?;
+        }
+        // All index keys start with the same variant tag, followed by the chain ID.
+        let sample_key = bcs::to_bytes(&BaseKey::ChainIndex(ChainId::root(0)))?;
+        let prefix = &sample_key[..1];
+        let keys = self.client.client.find_keys_by_prefix(prefix).await?;
+        let mut chain_ids = BTreeSet::new();
+        for key in keys.iterator() {
+            chain_ids.insert(bcs::from_bytes(key?)?);
+        }
+        Ok(chain_ids.into_iter().collect())
+    }
+
+    async fn index_chain(&self, chain_id: ChainId) -> Result<(), ViewError> {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(bcs::to_bytes(&BaseKey::ChainIndex(chain_id))?, Vec::new());
+        self.write_batch(batch).await
+    }
+
     async fn contains_hashed_certificate_value(&self, hash: CryptoHash) -> Result<bool, ViewError> {
         let value_key = bcs::to_bytes(&BaseKey::Value(hash))?;
         let test = self.client.client.contains_key(&value_key).await?;
@@ -581,6 +614,33 @@ where
         self.write_batch(batch).await
     }
 
+    async fn delete_certificates(&self, hashes: &[CryptoHash]) -> Result<(), ViewError> {
+        // Certificates are indexed by hash, so they can't be deleted by prefix.
+        let mut batch = Batch::new();
+        for hash in hashes {
+            batch.delete_key(bcs::to_bytes(&BaseKey::Certificate(*hash))?);
+            batch.delete_key(bcs::to_bytes(&BaseKey::Value(*hash))?);
+        }
+        self.write_batch(batch).await
+    }
+
+    async fn read_certificate_hash_for_message(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Option<CryptoHash>, ViewError> {
+        // Messages are indexed by block, since a block's messages all have the same
+        // certificate.
+        let key = bcs::to_bytes(&BaseKey::ConfirmedBlock(
+            message_id.chain_id,
+            message_id.height,
+        ))?;
+        Ok(self.client.client.read_value::<CryptoHash>(&key).await?)
+    }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), ViewError> {
+        Ok(self.client.client.maintenance(op).await?)
+    }
+
     fn wasm_runtime(&self) -> Option<WasmRuntime> {
         self.client.wasm_runtime
     }
@@ -650,6 +710,12 @@ where
         let value_key = bcs::to_bytes(&BaseKey::Value(hash))?;
         batch.put_key_value(cert_key.to_vec(), &certificate.lite_certificate())?;
         batch.put_key_value(value_key.to_vec(), &certificate.value)?;
+        let value = certificate.value();
+        if value.is_confirmed() {
+            let block_key =
+                bcs::to_bytes(&BaseKey::ConfirmedBlock(value.chain_id(), value.height()))?;
+            batch.put_key_value(block_key, &hash)?;
+        }
         Ok(())
     }
 
@@ -658,6 +724,34 @@ where
         Ok(())
     }
 
+    /// Adds the chains whose state was written before chains were indexed to the index
+    /// listed by `list_chain_ids`, by scanning the keys of all chain states. This is done
+    /// once per database.
+    async fn backfill_chain_index(&self) -> Result<(), ViewError> {
+        // All chain state keys start with the same variant tag, followed by the chain ID.
+        let sample_key = bcs::to_bytes(&BaseKey::ChainState(ChainId::root(0)))?;
+        let (prefix, chain_id_bytes) = sample_key.split_at(1);
+        let chain_id_len = chain_id_bytes.len();
+        let mut chain_ids = BTreeSet::new();
+        {
+            let keys = self.client.client.find_keys_by_prefix(prefix).await?;
+            for key in keys.iterator() {
+                let chain_id_bytes = key?
+                    .get(..chain_id_len)
+                    .ok_or(ViewError::InconsistentEntries)?;
+                chain_ids.insert(bcs::from_bytes::<ChainId>(chain_id_bytes)?);
+            }
+        } // The keys aren't `Send`, so they must be dropped before writing the index.
+        let mut batch = Batch::new();
+        for chain_id in chain_ids {
+            let key = bcs::to_bytes(&BaseKey::ChainIndex(chain_id))?;
+            batch.put_key_value_bytes(key, Vec::new());
+        }
+        let backfilled_key = bcs::to_bytes(&BaseKey::ChainIndexBackfilled)?;
+        batch.put_key_value_bytes(backfilled_key, Vec::new());
+        self.write_batch(batch).await
+    }
+
     pub fn create(storage: DbStorageInner<Client>, clock: C) -> Self {
         Self {
             client: Arc::new(storage),
@@ -5,6 +5,7 @@
 
 #![deny(clippy::large_futures)]
 
+mod blob_stream;
 mod chain_guards;
 mod db_storage;
 #[cfg(with_dynamodb)]
@@ -22,11 +23,10 @@ use std::{fmt::Debug, sync::Arc};
 use async_trait::async_trait;
 use chain_guards::ChainGuard;
 use dashmap::{mapref::entry::Entry, DashMap};
-use futures::future;
 use linera_base::{
     crypto::{CryptoHash, PublicKey},
     data_types::{Amount, BlobState, BlockHeight, HashedBlob, Timestamp},
-    identifiers::{BlobId, ChainDescription, ChainId, GenericApplicationId},
+    identifiers::{BlobId, ChainDescription, ChainId, GenericApplicationId, MessageId},
     ownership::ChainOwnership,
 };
 use linera_chain::{
@@ -40,9 +40,10 @@ use linera_execution::{
     UserApplicationDescription, UserApplicationId, UserContractCode, UserServiceCode, WasmRuntime,
 };
 use linera_views::{
-    common::Context,
+    common::{Context, MaintenanceOp},
     views::{CryptoHashView, RootView, ViewError},
 };
+use tokio::task::JoinSet;
 #[cfg(with_wasm_runtime)]
 use {
     linera_chain::data_types::CertificateValue,
@@ -65,6 +66,7 @@ pub use crate::scylla_db::ScyllaDbStorage;
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::service::ServiceStorage;
 pub use crate::{
+    blob_stream::{BlobStream, BLOB_STREAM_CHUNK_SIZE},
     db_storage::{Clock, DbStorage, WallClock},
     memory::MemoryStorage,
 };
@@ -85,11 +87,26 @@ pub trait Storage: Sized {
     /// Returns the current wall clock time.
     fn clock(&self) -> &dyn Clock;
 
+    /// Returns the prefix of all the keys of the state of a chain, as loaded by
+    /// [`Storage::load_chain`].
+    fn chain_state_base_key(&self, id: ChainId) -> Result<Vec<u8>, ViewError>;
+
     /// Loads the view of a chain state.
     async fn load_chain(&self, id: ChainId) -> Result<ChainStateView<Self::Context>, ViewError>
     where
         ViewError: From<Self::ContextError>;
 
+    /// Lists the IDs of all the chains that have a state in storage, in ascending order.
+    ///
+    /// This reads an index with one entry per chain, maintained by [`Storage::index_chain`].
+    /// The first call on a database written before chains were indexed adds its chains to
+    /// the index, scanning the keys of all chain states.
+    async fn list_chain_ids(&self) -> Result<Vec<ChainId>, ViewError>;
+
+    /// Adds the chain to those listed by [`Storage::list_chain_ids`]. This must be called
+    /// when the state of the chain is first saved, and has no effect if it is already listed.
+    async fn index_chain(&self, chain_id: ChainId) -> Result<(), ViewError>;
+
     /// Tests existence of a hashed certificate value with the given hash.
     async fn contains_hashed_certificate_value(&self, hash: CryptoHash) -> Result<bool, ViewError>;
 
@@ -105,6 +122,16 @@ pub trait Storage: Sized {
     /// Reads the blob with the given blob ID.
     async fn read_hashed_blob(&self, blob_id: BlobId) -> Result<HashedBlob, ViewError>;
 
+    /// Reads the content of the blob with the given blob ID as a stream of chunks, checked
+    /// against the blob ID as they are read.
+    ///
+    /// This reads the whole blob, since the stores can't read part of a value. Backends
+    /// that can should override it to avoid holding large blobs in memory.
+    async fn read_blob_stream(&self, blob_id: BlobId) -> Result<BlobStream, ViewError> {
+        let hashed_blob = self.read_hashed_blob(blob_id).await?;
+        Ok(BlobStream::from_blob(hashed_blob))
+    }
+
     /// Reads the blob state with the given blob ID.
     async fn read_blob_state(&self, blob_id: BlobId) -> Result<BlobState, ViewError>;
 
@@ -121,6 +148,22 @@ pub trait Storage: Sized {
         value: &HashedCertificateValue,
     ) -> Result<(), ViewError>;
 
+    /// Writes the given hashed certificate value, unless a value with the same hash is
+    /// already stored. Returns whether the value was written.
+    ///
+    /// Values are content-addressed, so a stored value with the same hash is identical and
+    /// rewriting it is only wasted work, e.g. when several downloads fetch the same value.
+    async fn write_hashed_certificate_value_if_absent(
+        &self,
+        value: &HashedCertificateValue,
+    ) -> Result<bool, ViewError> {
+        if self.contains_hashed_certificate_value(value.hash()).await? {
+            return Ok(false);
+        }
+        self.write_hashed_certificate_value(value).await?;
+        Ok(true)
+    }
+
     /// Writes the given blob.
     async fn write_hashed_blob(
         &self,
@@ -153,6 +196,22 @@ pub trait Storage: Sized {
     /// Writes a vector of certificates.
     async fn write_certificates(&self, certificate: &[Certificate]) -> Result<(), ViewError>;
 
+    /// Deletes the certificates with the given hashes, together with their values.
+    async fn delete_certificates(&self, hashes: &[CryptoHash]) -> Result<(), ViewError>;
+
+    /// Returns the hash of the confirmed certificate of the block that sent the given
+    /// message, if that certificate was written to this storage.
+    ///
+    /// The certificate must still be checked to contain the message.
+    async fn read_certificate_hash_for_message(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Option<CryptoHash>, ViewError>;
+
+    /// Runs the maintenance operation `op` of the storage backend, e.g. a compaction.
+    /// This does nothing on backends that don't need maintenance.
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), ViewError>;
+
     /// Loads the view of a chain state and checks that it is active.
     async fn load_active_chain(
         &self,
@@ -168,6 +227,9 @@ pub trait Storage: Sized {
     }
 
     /// Reads a number of certificates in parallel.
+    ///
+    /// The reads are aborted if the returned future is dropped, e.g. because the download
+    /// needing them was cancelled.
     async fn read_certificates<I: IntoIterator<Item = CryptoHash> + Send>(
         &self,
         keys: I,
@@ -175,20 +237,20 @@ pub trait Storage: Sized {
     where
         Self: Clone + Send + 'static,
     {
-        let mut tasks = Vec::new();
-        for key in keys {
+        // Dropping the `JoinSet` aborts the reads that are still running.
+        let mut tasks = JoinSet::new();
+        for (index, key) in keys.into_iter().enumerate() {
             // TODO: remove clone using scoped threads
             let client = self.clone();
-            tasks.push(tokio::task::spawn(async move {
-                client.read_certificate(key).await
-            }));
+            tasks.spawn(async move { (index, client.read_certificate(key).await) });
         }
-        let results = future::join_all(tasks).await;
-        let mut certs = Vec::new();
-        for result in results {
-            certs.push(result.expect("storage access should not cancel or crash")?);
+        let mut certs = Vec::with_capacity(tasks.len());
+        certs.resize_with(tasks.len(), || None);
+        while let Some(result) = tasks.join_next().await {
+            let (index, cert) = result.expect("storage access should not cancel or crash");
+            certs[index] = Some(cert?);
         }
-        Ok(certs)
+        Ok(certs.into_iter().flatten().collect())
     }
 
     /// Initializes a chain in a simple way (used for testing and to create a genesis state).
@@ -244,11 +306,13 @@ pub trait Storage: Sized {
                 channel.subscribers.insert(&id)?;
             } // Make channel go out of scope, so we can call save.
             admin_chain.save().await?;
+            self.index_chain(admin_id).await?;
         }
 
         let state_hash = chain.execution_state.crypto_hash().await?;
         chain.execution_state_hash.set(Some(state_hash));
         chain.save().await?;
+        self.index_chain(id).await?;
         Ok(())
     }
 
@@ -335,11 +399,14 @@ async fn read_publish_bytecode_operation(
     let value = storage
         .read_hashed_certificate_value(bytecode_location.certificate_hash)
         .await
-        .map_err(|error| match error {
-            ViewError::NotFound(_) => ExecutionError::ApplicationBytecodeNotFound(Box::new(
-                application_description.clone(),
-            )),
-            _ => error.into(),
+        .map_err(|error| {
+            if error.is_not_found() {
+                ExecutionError::ApplicationBytecodeNotFound(Box::new(
+                    application_description.clone(),
+                ))
+            } else {
+                error.into()
+            }
         })?
         .into_inner();
     let operations = match value {
@@ -11,6 +11,10 @@ use {
 
 use crate::db_storage::DbStorage;
 
+#[cfg(test)]
+#[path = "unit_tests/memory.rs"]
+mod tests;
+
 pub type MemoryStorage<C> = DbStorage<MemoryStore, C>;
 
 #[cfg(with_testing)]
@@ -0,0 +1,52 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use bytes::Bytes;
+use futures::{stream, StreamExt as _, TryStreamExt as _};
+use linera_base::data_types::{Blob, HashedBlob};
+use linera_views::views::ViewError;
+
+use super::{BlobStream, BLOB_STREAM_CHUNK_SIZE};
+use crate::{MemoryStorage, Storage};
+
+/// Tests that a blob larger than a chunk is streamed back from storage in several chunks.
+#[tokio::test]
+async fn read_blob_stream() -> Result<(), anyhow::Error> {
+    let storage = MemoryStorage::make_test_storage(None).await;
+    let bytes = (0..BLOB_STREAM_CHUNK_SIZE * 2 + 1)
+        .map(|i| i as u8)
+        .collect::<Vec<_>>();
+    let hashed_blob = Blob {
+        bytes: bytes.clone(),
+    }
+    .into_hashed();
+    storage
+        .write_hashed_blob(&hashed_blob, &hashed_blob.id().hash)
+        .await?;
+
+    let blob_stream = storage.read_blob_stream(hashed_blob.id()).await?;
+    assert_eq!(blob_stream.len(), bytes.len());
+    let chunks = blob_stream.try_collect::<Vec<_>>().await?;
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks.concat(), bytes);
+    Ok(())
+}
+
+/// Tests that a stream whose chunks don't match the blob ID ends with an error.
+#[tokio::test]
+async fn blob_stream_with_wrong_content() {
+    let hashed_blob = HashedBlob::test_blob("blob");
+    let len = hashed_blob.blob().bytes.len();
+    let wrong_chunks = [Ok(Bytes::from(vec![0; len]))];
+    let mut blob_stream = BlobStream::new(hashed_blob.id(), len, stream::iter(wrong_chunks));
+    assert!(blob_stream.next().await.unwrap().is_ok());
+    assert!(matches!(
+        blob_stream.next().await,
+        Some(Err(ViewError::InconsistentEntries))
+    ));
+    assert!(blob_stream.next().await.is_none());
+
+    let blob_stream = BlobStream::from_blob(hashed_blob.clone());
+    let chunks = blob_stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(chunks.concat(), hashed_blob.blob().bytes);
+}
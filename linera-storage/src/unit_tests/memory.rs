@@ -0,0 +1,110 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    data_types::{BlockHeight, Round},
+    identifiers::{ChainId, MessageId},
+};
+use linera_chain::{
+    data_types::{BlockExecutionOutcome, Certificate, ExecutedBlock, HashedCertificateValue},
+    test::make_first_block,
+};
+use linera_execution::committee::Epoch;
+use linera_views::views::RootView;
+
+use super::MemoryStorage;
+use crate::Storage;
+
+/// Tests that a hashed certificate value is only written if it isn't stored yet.
+#[tokio::test]
+async fn write_hashed_certificate_value_if_absent() -> Result<(), anyhow::Error> {
+    let storage = MemoryStorage::make_test_storage(None).await;
+    let value = HashedCertificateValue::new_timeout(ChainId::root(1), BlockHeight(0), Epoch::ZERO);
+    assert!(
+        !storage
+            .contains_hashed_certificate_value(value.hash())
+            .await?
+    );
+    assert!(
+        storage
+            .write_hashed_certificate_value_if_absent(&value)
+            .await?
+    );
+    assert!(
+        !storage
+            .write_hashed_certificate_value_if_absent(&value)
+            .await?
+    );
+    assert_eq!(
+        storage.read_hashed_certificate_value(value.hash()).await?,
+        value
+    );
+    Ok(())
+}
+
+/// Tests that confirmed certificates are indexed by the height of their block, to find the
+/// messages they sent.
+#[tokio::test]
+async fn read_certificate_hash_for_message() -> Result<(), anyhow::Error> {
+    let storage = MemoryStorage::make_test_storage(None).await;
+    let chain_id = ChainId::root(1);
+    let executed_block = ExecutedBlock {
+        block: make_first_block(chain_id),
+        outcome: BlockExecutionOutcome::default(),
+    };
+    let message_id = MessageId {
+        chain_id,
+        height: BlockHeight::ZERO,
+        index: 0,
+    };
+    let validated = Certificate::new(
+        HashedCertificateValue::new_validated(executed_block.clone()),
+        Round::Fast,
+        vec![],
+    );
+    storage.write_certificate(&validated).await?;
+    assert_eq!(
+        storage
+            .read_certificate_hash_for_message(&message_id)
+            .await?,
+        None
+    );
+    let confirmed = Certificate::new(
+        HashedCertificateValue::new_confirmed(executed_block),
+        Round::Fast,
+        vec![],
+    );
+    storage.write_certificate(&confirmed).await?;
+    assert_eq!(
+        storage
+            .read_certificate_hash_for_message(&message_id)
+            .await?,
+        Some(confirmed.hash())
+    );
+    Ok(())
+}
+
+/// Tests that the chains whose state was written before chains were indexed are listed.
+#[tokio::test]
+async fn list_chain_ids_written_without_index() -> Result<(), anyhow::Error> {
+    let storage = MemoryStorage::make_test_storage(None).await;
+    for chain_id in [ChainId::root(2), ChainId::root(1)] {
+        let mut chain = storage.load_chain(chain_id).await?;
+        chain.execution_state.system.epoch.set(Some(Epoch::ZERO));
+        chain.save().await?;
+    }
+    assert_eq!(
+        storage.list_chain_ids().await?,
+        [ChainId::root(1), ChainId::root(2)]
+    );
+    // Once the index is complete, the chains are listed when they are indexed.
+    let mut chain = storage.load_chain(ChainId::root(3)).await?;
+    chain.execution_state.system.epoch.set(Some(Epoch::ZERO));
+    chain.save().await?;
+    storage.index_chain(ChainId::root(3)).await?;
+    assert_eq!(
+        storage.list_chain_ids().await?,
+        [ChainId::root(1), ChainId::root(2), ChainId::root(3)]
+    );
+    Ok(())
+}
@@ -4,6 +4,8 @@
 mod r#type;
 pub use r#type::*;
 
+use std::collections::BTreeSet;
+
 pub static VERSION_INFO: VersionInfo = include!(env!("LINERA_VERSION_STATIC_PATH"));
 
 use crate::serde_pretty::Pretty;
@@ -88,6 +90,120 @@ impl VersionInfo {
                 .value
                 .is_compatible_with(&other.crate_version.value)
     }
+
+    /// Returns the canonical binary form of this version information, as exchanged in
+    /// handshakes.
+    ///
+    /// This is the BCS serialization, which is deterministic: the fields are encoded in
+    /// declaration order, the crate version as three little-endian `u32`s, and strings with a
+    /// ULEB128 length prefix. Builds with the same version information therefore produce the
+    /// same bytes, as long as the fields of [`VersionInfo`] and [`CrateVersion`] don't change.
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        bcs::to_bytes(self).expect("version information should be serializable")
+    }
+
+    /// Parses version information from its canonical binary form, as produced by
+    /// [`VersionInfo::to_canonical_bytes`].
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, bcs::Error> {
+        bcs::from_bytes(bytes)
+    }
+
+    /// Returns an error describing both versions unless this version is known to be
+    /// (remote!) API-compatible with `other`, as in [`VersionInfo::is_compatible_with`].
+    pub fn require_compatible(&self, other: &VersionInfo) -> Result<(), IncompatibleVersionError> {
+        if self.is_compatible_with(other) {
+            Ok(())
+        } else {
+            Err(IncompatibleVersionError {
+                version: Box::new(self.clone()),
+                other: Box::new(other.clone()),
+            })
+        }
+    }
+}
+
+/// Returns the optional capabilities, among the `features` advertised by a peer, that this
+/// build supports as well, i.e. those that can be used when talking to that peer.
+///
+/// Peers that predate feature negotiation advertise none.
+pub fn negotiate_features<'a>(features: impl IntoIterator<Item = &'a str>) -> BTreeSet<String> {
+    features
+        .into_iter()
+        .filter(|feature| SUPPORTED_FEATURES.contains(feature))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The error returned by [`VersionInfo::require_compatible`].
+#[derive(Clone, Debug, thiserror::Error)]
+#[error(
+    "Linera protocol v{} (RPC API hash {}) is not known to be compatible with v{} \
+     (RPC API hash {})",
+    .version.crate_version,
+    .version.rpc_hash,
+    .other.crate_version,
+    .other.rpc_hash
+)]
+pub struct IncompatibleVersionError {
+    /// The version that was checked.
+    pub version: Box<VersionInfo>,
+    /// The version it was checked against.
+    pub other: Box<VersionInfo>,
+}
+
+/// How a GraphQL client can query a server, depending on whether the server's GraphQL
+/// schema is the one the client was built against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphqlCompatibility {
+    /// The schemas are the same.
+    Exact,
+    /// The schemas differ, but the server's crate version is compatible with the client's,
+    /// so the server is expected to still provide the fields that the client was built
+    /// against. The client should tolerate unknown fields and enum variants in responses.
+    Compatible,
+}
+
+impl GraphqlCompatibility {
+    /// Returns how a GraphQL client built with the `local` version can query a server
+    /// with the `remote` version, or an error if the server's schema may be missing fields
+    /// that the client uses.
+    ///
+    /// GraphQL clients should call this after fetching the server's [`VersionInfo`], so
+    /// that schema changes are reported before issuing queries rather than as failures of
+    /// individual queries.
+    pub fn check(local: &VersionInfo, remote: &VersionInfo) -> Result<Self, SchemaMismatch> {
+        if local.graphql_hash == remote.graphql_hash {
+            Ok(GraphqlCompatibility::Exact)
+        } else if local
+            .crate_version
+            .value
+            .is_compatible_with(&remote.crate_version.value)
+        {
+            Ok(GraphqlCompatibility::Compatible)
+        } else {
+            Err(SchemaMismatch {
+                local: Box::new(local.clone()),
+                remote: Box::new(remote.clone()),
+            })
+        }
+    }
+}
+
+/// The error returned by [`GraphqlCompatibility::check`].
+#[derive(Clone, Debug, thiserror::Error)]
+#[error(
+    "The GraphQL API of Linera protocol v{} (GraphQL API hash {}) is not known to be \
+     compatible with v{} (GraphQL API hash {})",
+    .remote.crate_version,
+    .remote.graphql_hash,
+    .local.crate_version,
+    .local.graphql_hash
+)]
+pub struct SchemaMismatch {
+    /// The version the client was built with.
+    pub local: Box<VersionInfo>,
+    /// The version of the server.
+    pub remote: Box<VersionInfo>,
 }
 
 impl Default for VersionInfo {
@@ -95,3 +211,91 @@ impl Default for VersionInfo {
         VERSION_INFO.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        features, negotiate_features, CrateVersion, GraphqlCompatibility, Pretty, VersionInfo,
+    };
+
+    fn sample_version_info() -> VersionInfo {
+        VersionInfo {
+            crate_version: Pretty::new(CrateVersion {
+                major: 0,
+                minor: 12,
+                patch: 1,
+            }),
+            git_commit: "0123abcdef".into(),
+            git_dirty: true,
+            rpc_hash: "rpc".into(),
+            graphql_hash: "gql".into(),
+            wit_hash: "wit".into(),
+        }
+    }
+
+    #[test]
+    fn test_canonical_bytes_layout() {
+        let mut expected = vec![];
+        expected.extend([0, 0, 0, 0, 12, 0, 0, 0, 1, 0, 0, 0]);
+        expected.push(10);
+        expected.extend(b"0123abcdef");
+        expected.push(1);
+        for hash in [b"rpc", b"gql", b"wit"] {
+            expected.push(3);
+            expected.extend(hash);
+        }
+        assert_eq!(sample_version_info().to_canonical_bytes(), expected);
+    }
+
+    #[test]
+    fn test_canonical_bytes_roundtrip() {
+        let version_info = sample_version_info();
+        let bytes = version_info.to_canonical_bytes();
+        assert_eq!(
+            VersionInfo::from_canonical_bytes(&bytes).unwrap(),
+            version_info
+        );
+        assert!(VersionInfo::from_canonical_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let version_info = sample_version_info();
+        let json = serde_json::to_value(&version_info).unwrap();
+        assert_eq!(json["crate_version"], "0.12.1");
+        assert_eq!(
+            serde_json::from_value::<VersionInfo>(json).unwrap(),
+            version_info
+        );
+    }
+
+    #[test]
+    fn test_graphql_compatibility() {
+        let local = sample_version_info();
+        let mut remote = sample_version_info();
+        remote.rpc_hash = "other rpc".into();
+        assert_eq!(
+            GraphqlCompatibility::check(&local, &remote).unwrap(),
+            GraphqlCompatibility::Exact
+        );
+
+        remote.graphql_hash = "other gql".into();
+        remote.crate_version.value.patch = 2;
+        assert_eq!(
+            GraphqlCompatibility::check(&local, &remote).unwrap(),
+            GraphqlCompatibility::Compatible
+        );
+
+        // An older server may lack the fields added since.
+        remote.crate_version.value.patch = 0;
+        let error = GraphqlCompatibility::check(&local, &remote).unwrap_err();
+        assert_eq!(error.remote.graphql_hash, remote.graphql_hash);
+    }
+
+    #[test]
+    fn test_negotiate_features() {
+        let negotiated = negotiate_features(["unknown", features::BLOB_QUERIES]);
+        assert_eq!(negotiated, [features::BLOB_QUERIES.to_owned()].into());
+        assert!(negotiate_features([]).is_empty());
+    }
+}
@@ -45,6 +45,18 @@ impl From<CrateVersion> for semver::Version {
 
 pub type Hash = std::borrow::Cow<'static, str>;
 
+/// The names of the optional capabilities that a build may advertise.
+///
+/// They are exchanged separately from the [`VersionInfo`], whose binary format older
+/// builds must still be able to parse.
+pub mod features {
+    /// Answering chain information queries for blobs with the requested blobs.
+    pub const BLOB_QUERIES: &str = "blob-queries";
+}
+
+/// The optional capabilities supported by this build.
+pub const SUPPORTED_FEATURES: &[&str] = &[features::BLOB_QUERIES];
+
 #[cfg_attr(
     linera_version_building,
     derive(async_graphql::SimpleObject, serde::Deserialize, serde::Serialize)
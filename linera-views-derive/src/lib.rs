@@ -7,7 +7,10 @@ use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
 use syn::{
-    parse_macro_input, parse_quote, punctuated::Punctuated, Attribute, ItemStruct, Lit, LitStr,
+    parse::{Parse, ParseStream},
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    Attribute, Fields, Generics, Ident, Item, ItemEnum, ItemStruct, Lit, LitStr, Meta,
     MetaNameValue, Token, Type, TypePath, WhereClause,
 };
 
@@ -34,11 +37,9 @@ fn get_type_field(field: syn::Field) -> Option<syn::Ident> {
 }
 
 fn custom_attribute(attributes: &[Attribute], key: &str) -> Option<LitStr> {
-    attributes
-        .iter()
-        .filter(|attribute| attribute.path().is_ident("view"))
-        .filter_map(|attribute| match attribute.parse_args() {
-            Ok(MetaNameValue {
+    view_attributes(attributes)
+        .filter_map(|meta| match meta {
+            Meta::NameValue(MetaNameValue {
                 path,
                 value:
                     syn::Expr::Lit(syn::ExprLit {
@@ -47,12 +48,49 @@ fn custom_attribute(attributes: &[Attribute], key: &str) -> Option<LitStr> {
                     }),
                 ..
             }) => path.is_ident(key).then_some(value),
+            _ => None,
+        })
+        .next()
+}
+
+fn custom_path_attribute(attributes: &[Attribute], key: &str) -> Option<syn::Path> {
+    view_attributes(attributes)
+        .filter_map(|meta| match meta {
+            Meta::NameValue(MetaNameValue {
+                path,
+                value: syn::Expr::Path(syn::ExprPath { path: value, .. }),
+                ..
+            }) => path.is_ident(key).then_some(value),
+            _ => None,
+        })
+        .next()
+}
+
+fn custom_flag(attributes: &[Attribute], key: &str) -> bool {
+    view_attributes(attributes).any(|meta| matches!(meta, Meta::Path(path) if path.is_ident(key)))
+}
+
+fn view_attributes(attributes: &[Attribute]) -> impl Iterator<Item = Meta> + '_ {
+    attributes
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("view"))
+        .map(|attribute| match attribute.parse_args() {
+            Ok(
+                meta @ (Meta::Path(_)
+                | Meta::NameValue(MetaNameValue {
+                    value:
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: Lit::Str(_), ..
+                        })
+                        | syn::Expr::Path(_),
+                    ..
+                })),
+            ) => meta,
             _ => panic!(
                 r#"Invalid `view` attribute syntax. \
-                Expected syntax: `#[view(key = "value")]`"#,
+                Expected syntax: `#[view(key = "value")]`, `#[view(key = path)]` or `#[view(flag)]`"#,
             ),
         })
-        .next()
 }
 
 fn context_and_constraints(
@@ -84,6 +122,171 @@ fn context_and_constraints(
     (context, constraints)
 }
 
+/// Adds the constraint that the subviews marked with `#[view(encrypted)]` need, if any:
+/// they take their key from the user data of the context.
+fn add_encryption_constraint(where_clause: &mut WhereClause, context: &Type, fields: &Fields) {
+    if fields
+        .iter()
+        .any(|field| custom_flag(&field.attrs, "encrypted"))
+    {
+        where_clause.predicates.push(parse_quote! {
+            <#context as linera_views::common::Context>::Extra:
+                linera_views::encryption::HasEncryptionKey
+        });
+        // Without it, the compiler only tries the constraint on the error of the context,
+        // and fails to see that the errors of the encrypted contexts are `ViewError`s.
+        where_clause.predicates.push(parse_quote! {
+            linera_views::views::ViewError: From<linera_views::views::ViewError>
+        });
+    }
+}
+
+/// Returns the BCS serialization of the index of a subview of a struct, which comes after
+/// the tag `MIN_VIEW_TAG` in the keys of the subview.
+///
+/// The index is an `i32`, the type of the untyped integer literal used when loading the
+/// subviews.
+fn subview_index_bytes(index: i32) -> [u8; 4] {
+    index.to_le_bytes()
+}
+
+/// Returns the indices of the subviews of a struct, which determine their key prefixes.
+///
+/// The index of a subview is its position, unless it is set with `#[view(index = "N")]`.
+/// Subviews with the same index would overwrite each other's data, so each of them is
+/// reported as an error.
+fn subview_indices(fields: &Fields) -> syn::Result<Vec<i32>> {
+    let mut indices = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut push_error = |error: syn::Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+    for (position, field) in fields.iter().enumerate() {
+        let index = match custom_attribute(&field.attrs, "index") {
+            Some(literal) => match literal.value().parse::<i32>() {
+                Ok(index) if index >= 0 => index,
+                _ => {
+                    push_error(syn::Error::new_spanned(
+                        literal,
+                        "the index of a subview must be a non-negative `i32`",
+                    ));
+                    continue;
+                }
+            },
+            None => position as i32,
+        };
+        indices.push((index, field));
+    }
+    for (i, (index, field)) in indices.iter().enumerate() {
+        for (other_index, other_field) in indices.iter().take(i) {
+            if index == other_index {
+                let name = field.ident.as_ref().expect("subviews should be named");
+                let other_name = other_field
+                    .ident
+                    .as_ref()
+                    .expect("subviews should be named");
+                push_error(syn::Error::new_spanned(
+                    other_field,
+                    format!("subview `{other_name}` has the same key prefix as `{name}`"),
+                ));
+                push_error(syn::Error::new_spanned(
+                    field,
+                    format!("subview `{name}` has the same key prefix as `{other_name}`"),
+                ));
+            }
+        }
+    }
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(indices.into_iter().map(|(index, _)| index).collect()),
+    }
+}
+
+/// Returns the type `W` of the subview if `ty` is `LazyView<C, W>`.
+fn lazy_subview_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.last() {
+        Some(syn::GenericArgument::Type(ty)) if segment.ident == "LazyView" => Some(ty),
+        _ => None,
+    }
+}
+
+/// Returns the type `W` of the partitions and the type `I` of the indices of their entries
+/// if `ty` is `CollectionView<C, P, W>` or `CustomCollectionView<C, P, W>`.
+///
+/// The indices of the entries are the argument after the context of the partition type,
+/// e.g. `I` in `MapView<C, I, V>`, like for all the collection views.
+fn partitioned_subview_types(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "CollectionView" && segment.ident != "CustomCollectionView" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    let Some(syn::GenericArgument::Type(partition_type)) = arguments.args.iter().nth(2) else {
+        return None;
+    };
+    let Type::Path(TypePath {
+        qself: None,
+        path: partition_path,
+    }) = partition_type
+    else {
+        return None;
+    };
+    let syn::PathArguments::AngleBracketed(partition_arguments) =
+        &partition_path.segments.last()?.arguments
+    else {
+        return None;
+    };
+    match partition_arguments.args.iter().nth(1) {
+        Some(syn::GenericArgument::Type(index_type)) => Some((partition_type, index_type)),
+        _ => None,
+    }
+}
+
+/// Returns the name of a type as it would usually be written, e.g. `MapView<C, u64, u64>`.
+fn type_name(ty: &Type) -> LitStr {
+    let name = quote! { #ty }
+        .to_string()
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace(" ::", "::")
+        .replace(":: ", "::");
+    LitStr::new(&name, Span::call_site())
+}
+
+/// The input of the derive macros that support both structs and enums.
+enum ViewInput {
+    Struct(ItemStruct),
+    Enum(ItemEnum),
+}
+
+impl Parse for ViewInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        match input.parse()? {
+            Item::Struct(item) => Ok(ViewInput::Struct(item)),
+            Item::Enum(item) => Ok(ViewInput::Enum(item)),
+            item => Err(syn::Error::new_spanned(
+                item,
+                "views can only be derived for structs and enums",
+            )),
+        }
+    }
+}
+
 /// Returns an empty [`WhereClause`].
 fn empty_where_clause() -> WhereClause {
     WhereClause {
@@ -94,10 +297,11 @@ fn empty_where_clause() -> WhereClause {
 
 fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
     let struct_name = input.ident;
+    let attrs = input.attrs;
     let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
     let template_vect = get_seq_parameter(input.generics.clone());
 
-    let (context, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+    let (context, context_constraints) = context_and_constraints(&attrs, &template_vect);
 
     let mut where_clause = maybe_where_clause
         .cloned()
@@ -107,35 +311,162 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
         .extend(context_constraints.predicates);
 
     let mut name_quotes = Vec::new();
+    add_encryption_constraint(&mut where_clause, &context, &input.fields);
+
     let mut load_future_quotes = Vec::new();
     let mut load_ident_quotes = Vec::new();
     let mut load_result_quotes = Vec::new();
+    let mut load_sequential_quotes = Vec::new();
+    let mut has_pending_changes_quotes = Vec::new();
     let mut rollback_quotes = Vec::new();
     let mut flush_quotes = Vec::new();
     let mut test_flush_quotes = Vec::new();
     let mut clear_quotes = Vec::new();
+    let mut layout_quotes = Vec::new();
+    let mut accessor_quotes = Vec::new();
+    let mut diff_quotes = Vec::new();
+    let indices = match subview_indices(&input.fields) {
+        Ok(indices) => indices,
+        Err(error) => return error.to_compile_error(),
+    };
     for (idx, e) in input.fields.into_iter().enumerate() {
         let name = e.clone().ident.unwrap();
         let fut = format_ident!("{}_fut", name.to_string());
         let test_flush_ident = format_ident!("deleted{}", idx);
         let idx_lit = syn::LitInt::new(&idx.to_string(), Span::call_site());
+        let index_lit = syn::LitInt::new(&indices[idx].to_string(), Span::call_site());
+        let key_prefix = subview_index_bytes(indices[idx]);
+        let type_name = type_name(&e.ty);
+        layout_quotes.push(quote! {
+            linera_views::introspection::FieldLayout {
+                name: stringify!(#name),
+                key_prefix: &[linera_views::common::MIN_VIEW_TAG, #(#key_prefix),*],
+                type_name: #type_name,
+            }
+        });
+        // A field marked with `#[view(lazy)]` is a `LazyView`, which is loaded like any
+        // other subview, and gets an accessor loading its subview on first access.
+        if custom_flag(&e.attrs, "lazy") {
+            let Some(subview_type) = lazy_subview_type(&e.ty) else {
+                return syn::Error::new_spanned(
+                    &e.ty,
+                    "a subview marked with `#[view(lazy)]` must be a `LazyView`",
+                )
+                .to_compile_error();
+            };
+            let accessor = format_ident!("load_{}", name);
+            let accessor_doc =
+                format!("Returns the subview `{name}`, loading it from storage on first access.");
+            accessor_quotes.push(quote! {
+                #[doc = #accessor_doc]
+                pub async fn #accessor(&mut self) -> Result<&mut #subview_type, linera_views::views::ViewError> {
+                    self.#name.get_mut().await
+                }
+            });
+        }
+        // A field marked with `#[view(partition_by = f)]` is a collection of partitions,
+        // and gets accessors to the partition of an entry, whose key is computed by `f`.
+        if let Some(partition_fn) = custom_path_attribute(&e.attrs, "partition_by") {
+            let Some((partition_type, index_type)) = partitioned_subview_types(&e.ty) else {
+                return syn::Error::new_spanned(
+                    &e.ty,
+                    "a subview marked with `#[view(partition_by = ...)]` must be a \
+                     `CollectionView` of collection views",
+                )
+                .to_compile_error();
+            };
+            let accessor = format_ident!("{}_partition", name);
+            let accessor_doc = format!(
+                "Returns the partition of `{name}` holding the entry `index`, if it exists."
+            );
+            let mut_accessor = format_ident!("load_{}_partition", name);
+            let mut_accessor_doc = format!(
+                "Returns the partition of `{name}` holding the entry `index`, loading it from \
+                 storage or creating it if needed."
+            );
+            accessor_quotes.push(quote! {
+                #[doc = #accessor_doc]
+                pub async fn #accessor(
+                    &self,
+                    index: &#index_type,
+                ) -> Result<
+                    Option<linera_views::collection_view::ReadGuardedView<#partition_type>>,
+                    linera_views::views::ViewError,
+                > {
+                    let partition = #partition_fn(index);
+                    self.#name.try_load_entry(&partition).await
+                }
+
+                #[doc = #mut_accessor_doc]
+                pub async fn #mut_accessor(
+                    &mut self,
+                    index: &#index_type,
+                ) -> Result<&mut #partition_type, linera_views::views::ViewError> {
+                    let partition = #partition_fn(index);
+                    self.#name.load_entry_mut(&partition).await
+                }
+            });
+        }
+        // A field marked with `#[view(encrypted)]` is loaded with an `EncryptedContext`,
+        // and its values are encrypted when it is flushed.
+        let encrypted = custom_flag(&e.attrs, "encrypted");
+        if encrypted && idx == 0 {
+            return syn::Error::new_spanned(
+                &e.ty,
+                "the first subview can't be marked with `#[view(encrypted)]`, since the \
+                 view has the same context",
+            )
+            .to_compile_error();
+        }
+        let field_context_type = if encrypted {
+            quote! { linera_views::encryption::EncryptedContext<#context> }
+        } else {
+            quote! { #context }
+        };
+        diff_quotes.push(quote! {
+            let diff = linera_views::diff::DiffableView::<#field_context_type>::diff(&self.#name, &other.#name).await?;
+            if let Some(diff) = diff {
+                subviews.push((stringify!(#name), diff));
+            }
+        });
         let type_ident = get_type_field(e).expect("Failed to find the type");
+        let field_context = if encrypted {
+            quote! {
+                linera_views::encryption::EncryptedContext::new(context.clone_with_base_key(base_key))
+            }
+        } else {
+            quote! { context.clone_with_base_key(base_key) }
+        };
         load_future_quotes.push(quote! {
-            let index = #idx_lit;
+            let index = #index_lit;
             let base_key = context.derive_tag_key(linera_views::common::MIN_VIEW_TAG, &index)?;
-            let #fut = #type_ident::load(context.clone_with_base_key(base_key));
+            let #fut = #type_ident::load(#field_context);
         });
+        has_pending_changes_quotes.push(quote! {
+            if self.#name.has_pending_changes().await {
+                return true;
+            }
+        });
+        rollback_quotes.push(quote! { self.#name.rollback(); });
+        if encrypted {
+            flush_quotes.push(quote! {
+                let #test_flush_ident = linera_views::encryption::flush_encrypted_view(&mut self.#name, batch)?;
+            });
+        } else {
+            flush_quotes.push(quote! { let #test_flush_ident = self.#name.flush(batch)?; });
+        }
+        clear_quotes.push(quote! { self.#name.clear(); });
         load_ident_quotes.push(quote! {
             #fut
         });
         load_result_quotes.push(quote! {
             let #name = result.#idx_lit?;
         });
+        load_sequential_quotes.push(quote! {
+            let #name = #fut.await?;
+        });
         name_quotes.push(quote! { #name });
-        rollback_quotes.push(quote! { self.#name.rollback(); });
-        flush_quotes.push(quote! { let #test_flush_ident = self.#name.flush(batch)?; });
         test_flush_quotes.push(quote! { #test_flush_ident });
-        clear_quotes.push(quote! { self.#name.clear(); });
     }
     let first_name_quote = name_quotes
         .first()
@@ -154,6 +485,76 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
         quote! {}
     };
 
+    // The subviews use distinct key prefixes, so they are loaded concurrently unless the
+    // view opts out.
+    let (load_imports, load_subviews) = if custom_flag(&attrs, "sequential_load") {
+        (
+            quote! { use linera_views::common::Context; },
+            quote! { #(#load_sequential_quotes)* },
+        )
+    } else {
+        (
+            quote! { use linera_views::{futures::join, common::Context}; },
+            quote! {
+                let result = join!(#(#load_ident_quotes),*);
+                #(#load_result_quotes)*
+            },
+        )
+    };
+
+    let context_accessor = if custom_flag(&attrs, "expose_context") {
+        quote! {
+            impl #impl_generics #struct_name #type_generics
+            #where_clause
+            {
+                /// Returns the context that this view was loaded with.
+                pub fn context(&self) -> &#context {
+                    linera_views::views::View::context(self)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let introspection = if custom_flag(&attrs, "introspect") {
+        generate_introspection_code(&struct_name, &input.generics, &where_clause, &layout_quotes)
+    } else {
+        quote! {}
+    };
+
+    let diff = if custom_flag(&attrs, "diff") {
+        quote! {
+            #[linera_views::async_trait]
+            impl #impl_generics linera_views::diff::DiffableView<#context> for #struct_name #type_generics
+            #where_clause
+            {
+                async fn diff(
+                    &self,
+                    other: &Self,
+                ) -> Result<Option<linera_views::diff::ViewDiff>, linera_views::views::ViewError> {
+                    let mut subviews = Vec::new();
+                    #(#diff_quotes)*
+                    Ok((!subviews.is_empty()).then_some(linera_views::diff::ViewDiff::Subviews(subviews)))
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let accessors = if accessor_quotes.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            impl #impl_generics #struct_name #type_generics
+            #where_clause
+            {
+                #(#accessor_quotes)*
+            }
+        }
+    };
+
     quote! {
         #[linera_views::async_trait]
         impl #impl_generics linera_views::views::View<#context> for #struct_name #type_generics
@@ -165,15 +566,20 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
             }
 
             async fn load(context: #context) -> Result<Self, linera_views::views::ViewError> {
-                use linera_views::{futures::join, common::Context};
+                #load_imports
                 #increment_counter
                 #(#load_future_quotes)*
-                let result = join!(#(#load_ident_quotes),*);
-                #(#load_result_quotes)*
+                #load_subviews
                 Ok(Self {#(#name_quotes),*})
             }
 
 
+            async fn has_pending_changes(&self) -> bool {
+                use linera_views::views::View;
+                #(#has_pending_changes_quotes)*
+                false
+            }
+
             fn rollback(&mut self) {
                 #(#rollback_quotes)*
             }
@@ -188,15 +594,83 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
                 #(#clear_quotes)*
             }
         }
+
+        #context_accessor
+
+        #accessors
+
+        #diff
+
+        #introspection
+    }
+}
+
+/// Generates the [`IntrospectableView`] implementation of a type annotated with
+/// `#[view(introspect)]`, given the layouts of its subviews.
+fn generate_introspection_code(
+    name: &Ident,
+    generics: &Generics,
+    where_clause: &WhereClause,
+    field_layouts: &[TokenStream2],
+) -> TokenStream2 {
+    let (impl_generics, type_generics, _) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics linera_views::introspection::IntrospectableView for #name #type_generics
+        #where_clause
+        {
+            const LAYOUT: linera_views::introspection::ViewLayout =
+                linera_views::introspection::ViewLayout {
+                    name: stringify!(#name),
+                    fields: &[#(#field_layouts),*],
+                };
+        }
     }
 }
 
 fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
-    let struct_name = input.ident;
-    let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
-    let template_vect = get_seq_parameter(input.generics.clone());
+    let mut flushes = Vec::new();
+    if subview_indices(&input.fields).is_err() {
+        // The error is already reported in the `View` implementation.
+        return quote! {};
+    }
+    for e in input.fields.iter() {
+        let name = e.clone().ident.unwrap();
+        if custom_flag(&e.attrs, "encrypted") {
+            flushes.push(quote! {
+                if self.#name.has_pending_changes().await {
+                    linera_views::encryption::flush_encrypted_view(&mut self.#name, &mut batch)?;
+                }
+            });
+        } else {
+            flushes.push(quote! {
+                if self.#name.has_pending_changes().await {
+                    self.#name.flush(&mut batch)?;
+                }
+            });
+        }
+    }
+    generate_root_view_code(
+        &input.ident,
+        &input.generics,
+        &input.attrs,
+        &input.fields,
+        quote! { #(#flushes)* },
+    )
+}
 
-    let (context, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+/// Generates the [`RootView`] implementation of a type with the given subviews, given the
+/// code that flushes its pending changes into `batch`.
+fn generate_root_view_code(
+    name: &Ident,
+    generics: &Generics,
+    attributes: &[Attribute],
+    fields: &Fields,
+    flushes: TokenStream2,
+) -> TokenStream2 {
+    let (impl_generics, type_generics, maybe_where_clause) = generics.split_for_impl();
+    let template_vect = get_seq_parameter(generics.clone());
+
+    let (context, context_constraints) = context_and_constraints(attributes, &template_vect);
 
     let mut where_clause = maybe_where_clause
         .cloned()
@@ -204,21 +678,14 @@ fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
     where_clause
         .predicates
         .extend(context_constraints.predicates);
-
-    let mut flushes = Vec::new();
-    let mut deletes = Vec::new();
-    for e in input.fields {
-        let name = e.clone().ident.unwrap();
-        flushes.push(quote! { self.#name.flush(&mut batch)?; });
-        deletes.push(quote! { self.#name.delete(batch); });
-    }
+    add_encryption_constraint(&mut where_clause, &context, fields);
 
     let increment_counter = if cfg!(feature = "metrics") {
         quote! {
             #[cfg(not(target_arch = "wasm32"))]
             linera_views::increment_counter(
                 &linera_views::SAVE_VIEW_COUNTER,
-                stringify!(#struct_name),
+                stringify!(#name),
                 &self.context().base_key(),
             );
         }
@@ -228,14 +695,14 @@ fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
 
     quote! {
         #[linera_views::async_trait]
-        impl #impl_generics linera_views::views::RootView<#context> for #struct_name #type_generics
+        impl #impl_generics linera_views::views::RootView<#context> for #name #type_generics
         #where_clause
         {
             async fn save(&mut self) -> Result<(), linera_views::views::ViewError> {
                 use linera_views::{common::Context, batch::Batch, views::View};
                 #increment_counter
                 let mut batch = Batch::new();
-                #(#flushes)*
+                #flushes
                 self.context().write_batch(batch).await?;
                 Ok(())
             }
@@ -243,6 +710,123 @@ fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
     }
 }
 
+/// Generates the [`EnumVariants`] implementation of an enum, where each variant wraps
+/// exactly one subview. The enum is then stored through an `EnumView`, which loads the
+/// active variant with `load_variant`.
+fn generate_enum_view_code(input: ItemEnum) -> TokenStream2 {
+    let enum_name = input.ident;
+    let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
+    let template_vect = get_seq_parameter(input.generics.clone());
+
+    let (context, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+
+    let mut where_clause = maybe_where_clause
+        .cloned()
+        .unwrap_or_else(empty_where_clause);
+    where_clause
+        .predicates
+        .extend(context_constraints.predicates);
+
+    if input.variants.is_empty() || input.variants.len() > 256 {
+        return syn::Error::new_spanned(
+            &enum_name,
+            "a view enum must have between 1 and 256 variants",
+        )
+        .to_compile_error();
+    }
+
+    let introspect = custom_flag(&input.attrs, "introspect");
+    let mut names = Vec::new();
+    let mut indices = Vec::new();
+    let mut load_quotes = Vec::new();
+    let mut layout_quotes = Vec::new();
+    for (index, variant) in input.variants.into_iter().enumerate() {
+        let name = variant.ident;
+        let ty = match variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                fields.unnamed.into_iter().next().unwrap().ty
+            }
+            fields => {
+                return syn::Error::new_spanned(
+                    fields,
+                    "each variant of a view enum must have exactly one unnamed field",
+                )
+                .to_compile_error();
+            }
+        };
+        let index = syn::LitInt::new(&format!("{index}u8"), Span::call_site());
+        load_quotes.push(quote! {
+            #index => Ok(Self::#name(<#ty as linera_views::views::View<#context>>::load(context).await?)),
+        });
+        let type_name = type_name(&ty);
+        layout_quotes.push(quote! {
+            linera_views::introspection::FieldLayout {
+                name: stringify!(#name),
+                key_prefix: &[linera_views::common::MIN_VIEW_TAG + 1, #index],
+                type_name: #type_name,
+            }
+        });
+        names.push(name);
+        indices.push(index);
+    }
+
+    let introspection = if introspect {
+        generate_introspection_code(&enum_name, &input.generics, &where_clause, &layout_quotes)
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        #[linera_views::async_trait]
+        impl #impl_generics linera_views::enum_view::EnumVariants<#context> for #enum_name #type_generics
+        #where_clause
+        {
+            fn variant(&self) -> u8 {
+                match self {
+                    #(Self::#names(_) => #indices,)*
+                }
+            }
+
+            async fn load_variant(variant: u8, context: #context) -> Result<Self, linera_views::views::ViewError> {
+                match variant {
+                    #(#load_quotes)*
+                    _ => Err(linera_views::views::ViewError::InconsistentEntries),
+                }
+            }
+
+            async fn has_pending_changes(&self) -> bool {
+                use linera_views::views::View;
+                match self {
+                    #(Self::#names(view) => view.has_pending_changes().await,)*
+                }
+            }
+
+            fn rollback(&mut self) {
+                use linera_views::views::View;
+                match self {
+                    #(Self::#names(view) => view.rollback(),)*
+                }
+            }
+
+            fn clear(&mut self) {
+                use linera_views::views::View;
+                match self {
+                    #(Self::#names(view) => view.clear(),)*
+                }
+            }
+
+            fn flush(&mut self, batch: &mut linera_views::batch::Batch) -> Result<bool, linera_views::views::ViewError> {
+                use linera_views::views::View;
+                match self {
+                    #(Self::#names(view) => view.flush(batch),)*
+                }
+            }
+        }
+
+        #introspection
+    }
+}
+
 fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
     let struct_name = input.ident;
     let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
@@ -256,6 +840,7 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
     where_clause
         .predicates
         .extend(context_constraints.predicates);
+    add_encryption_constraint(&mut where_clause, &context, &input.fields);
 
     let mut field_hashes_mut = Vec::new();
     let mut field_hashes = Vec::new();
@@ -304,6 +889,7 @@ fn generate_crypto_hash_code(input: ItemStruct) -> TokenStream2 {
     where_clause
         .predicates
         .extend(context_constraints.predicates);
+    add_encryption_constraint(&mut where_clause, &context, &input.fields);
 
     let hash_type = syn::Ident::new(&format!("{}Hash", struct_name), Span::call_site());
     quote! {
@@ -336,7 +922,8 @@ fn generate_clonable_view_code(input: ItemStruct) -> TokenStream2 {
     let generics = input.generics;
     let template_vect = get_seq_parameter(generics.clone());
 
-    let (context, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+    let (context, mut context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+    add_encryption_constraint(&mut context_constraints, &context, &input.fields);
 
     let clone_unchecked_quotes = input.fields.iter().map(|field| {
         let name = &field.ident;
@@ -358,8 +945,10 @@ fn generate_clonable_view_code(input: ItemStruct) -> TokenStream2 {
 
 #[proc_macro_derive(View, attributes(view))]
 pub fn derive_view(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as ItemStruct);
-    generate_view_code(input, false).into()
+    match parse_macro_input!(input as ViewInput) {
+        ViewInput::Struct(input) => generate_view_code(input, false).into(),
+        ViewInput::Enum(input) => generate_enum_view_code(input).into(),
+    }
 }
 
 #[proc_macro_derive(HashableView, attributes(view))]
@@ -372,9 +961,22 @@ pub fn derive_hash_view(input: TokenStream) -> TokenStream {
 
 #[proc_macro_derive(RootView, attributes(view))]
 pub fn derive_root_view(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as ItemStruct);
-    let mut stream = generate_view_code(input.clone(), true);
-    stream.extend(generate_save_delete_view_code(input));
+    let stream = match parse_macro_input!(input as ViewInput) {
+        ViewInput::Struct(input) => {
+            let mut stream = generate_view_code(input.clone(), true);
+            stream.extend(generate_save_delete_view_code(input));
+            stream
+        }
+        // An enum is saved through its `EnumView`, which is the root view.
+        ViewInput::Enum(input) => {
+            let message = format!(
+                "`RootView` can't be derived for an enum: derive `View` instead, \
+                and use `EnumView<C, {}>` as the root view",
+                input.ident
+            );
+            syn::Error::new_spanned(input.ident, message).to_compile_error()
+        }
+    };
     stream.into()
 }
 
@@ -446,6 +1048,77 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_generate_view_code_with_exposed_context() {
+        for context in SpecificContextInfo::test_cases() {
+            let mut input = context.test_view_input();
+            input.attrs.push(parse_quote! { #[view(expose_context)] });
+            insta::assert_snapshot!(
+                format!(
+                    "test_generate_view_code_with_exposed_context_{}",
+                    context.name
+                ),
+                pretty(generate_view_code(input, false))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_view_code_with_sequential_load() {
+        for context in SpecificContextInfo::test_cases() {
+            let mut input = context.test_view_input();
+            input.attrs.push(parse_quote! { #[view(sequential_load)] });
+            insta::assert_snapshot!(
+                format!(
+                    "test_generate_view_code_with_sequential_load_{}",
+                    context.name
+                ),
+                pretty(generate_view_code(input, false))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_view_code_with_introspection() {
+        for context in SpecificContextInfo::test_cases() {
+            let mut input = context.test_view_input();
+            input.attrs.push(parse_quote! { #[view(introspect)] });
+            insta::assert_snapshot!(
+                format!(
+                    "test_generate_view_code_with_introspection_{}",
+                    context.name
+                ),
+                pretty(generate_view_code(input, false))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_enum_view_code_with_introspection() {
+        for context in SpecificContextInfo::test_cases() {
+            let mut input = context.test_enum_view_input();
+            input.attrs.push(parse_quote! { #[view(introspect)] });
+            insta::assert_snapshot!(
+                format!(
+                    "test_generate_enum_view_code_with_introspection_{}",
+                    context.name
+                ),
+                pretty(generate_enum_view_code(input))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_enum_view_code() {
+        for context in SpecificContextInfo::test_cases() {
+            let input = context.test_enum_view_input();
+            insta::assert_snapshot!(
+                format!("test_generate_enum_view_code_{}", context.name),
+                pretty(generate_enum_view_code(input))
+            );
+        }
+    }
+
     #[test]
     fn test_generate_hash_view_code() {
         for context in SpecificContextInfo::test_cases() {
@@ -492,6 +1165,55 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_subview_indices() {
+        let input: ItemStruct = parse_quote! {
+            struct TestView<C> {
+                register: RegisterView<C, usize>,
+                #[view(index = "3")]
+                collection: CollectionView<C, usize, RegisterView<C, usize>>,
+                log: LogView<C, usize>,
+            }
+        };
+        assert_eq!(subview_indices(&input.fields).unwrap(), vec![0, 3, 2]);
+    }
+
+    #[test]
+    fn test_subview_indices_detects_duplicate_prefixes() {
+        let input: ItemStruct = parse_quote! {
+            struct TestView<C> {
+                register: RegisterView<C, usize>,
+                #[view(index = "0")]
+                log: LogView<C, usize>,
+            }
+        };
+        let messages = subview_indices(&input.fields)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "subview `register` has the same key prefix as `log`",
+                "subview `log` has the same key prefix as `register`",
+            ]
+        );
+        let code = generate_view_code(input, false).to_string();
+        assert!(code.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_subview_indices_rejects_invalid_index() {
+        let input: ItemStruct = parse_quote! {
+            struct TestView<C> {
+                #[view(index = "-1")]
+                register: RegisterView<C, usize>,
+            }
+        };
+        assert!(subview_indices(&input.fields).is_err());
+    }
+
     #[derive(Clone)]
     pub struct SpecificContextInfo {
         name: String,
@@ -570,5 +1292,25 @@ pub mod tests {
                 }
             }
         }
+
+        pub fn test_enum_view_input(&self) -> ItemEnum {
+            let SpecificContextInfo {
+                attribute,
+                context,
+                generics,
+                where_clause,
+                ..
+            } = self;
+
+            parse_quote! {
+                #attribute
+                enum TestView #generics
+                #where_clause
+                {
+                    Register(RegisterView<#context, usize>),
+                    Collection(CollectionView<#context, usize, RegisterView<#context, usize>>),
+                }
+            }
+        }
     }
 }
@@ -392,6 +392,34 @@ pub trait SimplifiedBatch: Sized + Send + Sync {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Splits the batch into chunks of at most `max_len` entries and `max_num_bytes`
+    /// bytes, which have the same effect as the batch when written in order. A batch
+    /// that already fits is returned as a single chunk. An entry that is too large on its
+    /// own gets a chunk of its own.
+    fn into_chunks(self, max_len: usize, max_num_bytes: usize) -> Result<Vec<Self>, bcs::Error>
+    where
+        Self: Default,
+    {
+        if self.len() <= max_len && self.num_bytes() <= max_num_bytes {
+            return Ok(vec![self]);
+        }
+        let mut iter = self.into_iter();
+        let mut chunks = Vec::new();
+        let mut chunk = Self::default();
+        let mut chunk_size = 0;
+        while let Some(next_chunk_size) = iter.next_batch_size(&chunk, chunk_size)? {
+            if !chunk.is_empty() && (chunk.len() == max_len || next_chunk_size > max_num_bytes) {
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_size = 0;
+            }
+            iter.write_next_value(&mut chunk, &mut chunk_size)?;
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        Ok(chunks)
+    }
 }
 
 /// An iterator-like object that can write values one by one to a batch while updating the
@@ -623,7 +651,7 @@ impl BatchValueWriter<UnorderedBatch> for UnorderedBatchIter {
 #[cfg(test)]
 mod tests {
     use linera_views::{
-        batch::{Batch, SimpleUnorderedBatch, UnorderedBatch},
+        batch::{Batch, SimpleUnorderedBatch, SimplifiedBatch, UnorderedBatch},
         common::Context,
         memory::create_memory_context,
     };
@@ -727,4 +755,56 @@ mod tests {
         );
         assert!(unordered_batch.key_prefix_deletions.is_empty());
     }
+
+    #[test]
+    fn test_into_chunks() {
+        let mut batch = UnorderedBatch {
+            key_prefix_deletions: vec![vec![0]],
+            ..UnorderedBatch::default()
+        };
+        for i in 1..=5 {
+            batch.add_delete(vec![i]);
+            batch.add_insert(vec![i, 0], vec![i; 10]);
+        }
+        let num_bytes = batch.num_bytes();
+
+        let chunks = batch.into_chunks(4, num_bytes).unwrap();
+        assert_eq!(
+            chunks.iter().map(SimplifiedBatch::len).collect::<Vec<_>>(),
+            vec![4, 4, 3]
+        );
+        // The prefix deletion comes before the other operations.
+        assert_eq!(chunks[0].key_prefix_deletions, vec![vec![0]]);
+        assert!(chunks[1..]
+            .iter()
+            .all(|chunk| chunk.key_prefix_deletions.is_empty()));
+        let insertions = chunks
+            .iter()
+            .flat_map(|chunk| chunk.simple_unordered_batch.insertions.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            insertions,
+            (1..=5)
+                .map(|i| (vec![i, 0], vec![i; 10]))
+                .collect::<Vec<_>>()
+        );
+
+        let mut batch = SimpleUnorderedBatch::default();
+        for i in 0..4 {
+            batch.add_insert(vec![i], vec![i; 20]);
+        }
+        let chunks = batch.into_chunks(usize::MAX, 50).unwrap();
+        assert!(chunks.iter().all(|chunk| chunk.len() == 2));
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_into_chunks_keeps_fitting_batch() {
+        let mut batch = SimpleUnorderedBatch::default();
+        batch.add_insert(vec![1], vec![2; 100]);
+        batch.add_delete(vec![3]);
+        let chunks = batch.into_chunks(2, 102).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 2);
+    }
 }
@@ -0,0 +1,277 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Benchmarks of key-value stores under workloads resembling the access patterns of
+//! Linera, to compare backends and to detect performance regressions.
+//!
+//! A [`Workload`] first fills the store with values, then measures point reads, prefix
+//! scans and batch writes over them. The keys and values are generated deterministically,
+//! so the same workload does the same operations on every run and every backend.
+
+use linera_base::time::{Duration, Instant};
+
+use crate::{
+    batch::Batch,
+    common::{KeyValueIterable as _, LocalKeyValueStore},
+};
+
+/// The operations of a benchmark run with [`run_workload`].
+///
+/// The keys are grouped under prefixes of four bytes, like the entries of a collection
+/// under the key of the collection, and the prefix scans read one group at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Workload {
+    /// The number of prefixes that the keys are grouped under.
+    pub prefix_count: usize,
+    /// The number of keys under each prefix.
+    pub keys_per_prefix: usize,
+    /// The size of each value in bytes.
+    pub value_size: usize,
+    /// The number of values read by key.
+    pub point_reads: usize,
+    /// The number of prefixes whose keys and values are read.
+    pub range_scans: usize,
+    /// The number of batches written.
+    pub batch_writes: usize,
+    /// The number of values in each batch, including the batches filling the store.
+    pub batch_size: usize,
+}
+
+impl Workload {
+    /// A workload resembling the storage of certificates: values of a few kilobytes, mostly
+    /// read by hash, and written a few at a time as blocks are confirmed.
+    pub fn certificates() -> Self {
+        Workload {
+            prefix_count: 64,
+            keys_per_prefix: 64,
+            value_size: 2 * 1024,
+            point_reads: 10_000,
+            range_scans: 100,
+            batch_writes: 1_000,
+            batch_size: 4,
+        }
+    }
+
+    /// A workload resembling the storage of blobs: large values, read by hash and
+    /// written one at a time.
+    pub fn blobs() -> Self {
+        Workload {
+            prefix_count: 16,
+            keys_per_prefix: 16,
+            value_size: 64 * 1024,
+            point_reads: 1_000,
+            range_scans: 10,
+            batch_writes: 100,
+            batch_size: 1,
+        }
+    }
+
+    /// Returns the total number of keys in the store.
+    pub fn key_count(&self) -> usize {
+        self.prefix_count * self.keys_per_prefix
+    }
+
+    /// Returns the key of the entry with the given index.
+    fn key(&self, index: usize) -> Vec<u8> {
+        let prefix = (index / self.keys_per_prefix) as u32;
+        let suffix = (index % self.keys_per_prefix) as u32;
+        let mut key = prefix.to_be_bytes().to_vec();
+        key.extend(suffix.to_be_bytes());
+        key
+    }
+
+    /// Returns a value that doesn't compress well, so that backends compressing their data
+    /// are not favored.
+    fn value(&self, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        let mut value = Vec::with_capacity(self.value_size + 8);
+        while value.len() < self.value_size {
+            state = split_mix64(state);
+            value.extend(state.to_le_bytes());
+        }
+        value.truncate(self.value_size);
+        value
+    }
+}
+
+/// The latencies of the operations of one kind in a benchmark.
+#[derive(Clone, Debug, Default)]
+pub struct OperationStats {
+    latencies: Vec<Duration>,
+}
+
+impl OperationStats {
+    fn record(&mut self, latency: Duration) {
+        self.latencies.push(latency);
+    }
+
+    fn finish(mut self) -> Self {
+        self.latencies.sort();
+        self
+    }
+
+    /// Returns the number of operations.
+    pub fn count(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// Returns the total time spent in the operations.
+    pub fn total(&self) -> Duration {
+        self.latencies.iter().sum()
+    }
+
+    /// Returns the mean latency, or zero if there were no operations.
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.count()) {
+            Ok(0) | Err(_) => Duration::ZERO,
+            Ok(count) => self.total() / count,
+        }
+    }
+
+    /// Returns the latency that `percent` percent of the operations didn't exceed, or zero
+    /// if there were no operations.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        let Some(last) = self.count().checked_sub(1) else {
+            return Duration::ZERO;
+        };
+        let index = (last as f64 * percent.clamp(0.0, 100.0) / 100.0).round() as usize;
+        self.latencies[index]
+    }
+
+    /// Returns the number of operations per second.
+    pub fn throughput(&self) -> f64 {
+        let total = self.total().as_secs_f64();
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.count() as f64 / total
+    }
+}
+
+/// The measurements of a benchmark run with [`run_workload`].
+#[derive(Clone, Debug, Default)]
+pub struct WorkloadReport {
+    /// The time taken to fill the store before the measured operations.
+    pub setup: Duration,
+    /// The latencies of the point reads.
+    pub point_reads: OperationStats,
+    /// The latencies of the prefix scans.
+    pub range_scans: OperationStats,
+    /// The latencies of the batch writes.
+    pub batch_writes: OperationStats,
+    /// The number of entries returned by the prefix scans.
+    pub scanned_entries: usize,
+}
+
+/// Runs the `workload` on the `store`, and returns how long its operations took.
+///
+/// The store should be empty, e.g. a new namespace, since the workload overwrites some of
+/// its keys and the prefix scans would return other keys too. The values are left in the
+/// store.
+///
+/// # Panics
+///
+/// If the workload has no keys but has operations, or if its values are larger than the
+/// store allows.
+pub async fn run_workload<S: LocalKeyValueStore>(
+    store: &S,
+    workload: Workload,
+) -> Result<WorkloadReport, S::Error> {
+    let key_count = workload.key_count();
+    assert!(
+        key_count > 0 || workload.point_reads + workload.range_scans + workload.batch_writes == 0,
+        "The workload must have keys to operate on"
+    );
+    assert!(
+        workload.value_size <= S::MAX_VALUE_SIZE,
+        "The values of the workload are larger than the store allows"
+    );
+    let batch_size = workload.batch_size.max(1);
+    let mut report = WorkloadReport::default();
+
+    let start = Instant::now();
+    for first_index in (0..key_count).step_by(batch_size) {
+        let mut batch = Batch::new();
+        for index in first_index..key_count.min(first_index + batch_size) {
+            batch.put_key_value_bytes(workload.key(index), workload.value(index as u64));
+        }
+        store.write_batch(batch, &[]).await?;
+    }
+    report.setup = start.elapsed();
+
+    for i in 0..workload.point_reads {
+        let key = workload.key(pick(i as u64, key_count));
+        let start = Instant::now();
+        store.read_value_bytes(&key).await?;
+        report.point_reads.record(start.elapsed());
+    }
+
+    for i in 0..workload.range_scans {
+        let prefix = (pick(i as u64, workload.prefix_count) as u32).to_be_bytes();
+        let start = Instant::now();
+        let key_values = store.find_key_values_by_prefix(&prefix).await?;
+        report.scanned_entries += key_values.iterator().count();
+        report.range_scans.record(start.elapsed());
+    }
+
+    for i in 0..workload.batch_writes {
+        let mut batch = Batch::new();
+        for j in 0..batch_size {
+            let seed = (i * batch_size + j) as u64;
+            let key = workload.key(pick(seed, key_count));
+            batch.put_key_value_bytes(key, workload.value(seed.wrapping_add(key_count as u64)));
+        }
+        let start = Instant::now();
+        store.write_batch(batch, &[]).await?;
+        report.batch_writes.record(start.elapsed());
+    }
+
+    report.point_reads = report.point_reads.finish();
+    report.range_scans = report.range_scans.finish();
+    report.batch_writes = report.batch_writes.finish();
+    Ok(report)
+}
+
+/// Returns the `seed`-th pseudo-random index below `bound`.
+fn pick(seed: u64, bound: usize) -> usize {
+    (split_mix64(seed) % bound as u64) as usize
+}
+
+/// The SplitMix64 generator, which is enough to spread the operations over the keys.
+fn split_mix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_workload, Workload};
+    use crate::{common::ReadableKeyValueStore as _, memory::create_memory_store};
+
+    #[tokio::test]
+    async fn test_run_workload() {
+        let store = create_memory_store();
+        let workload = Workload {
+            prefix_count: 4,
+            keys_per_prefix: 8,
+            value_size: 100,
+            point_reads: 20,
+            range_scans: 3,
+            batch_writes: 5,
+            batch_size: 3,
+        };
+        let report = run_workload(&store, workload.clone()).await.unwrap();
+        assert_eq!(report.point_reads.count(), 20);
+        assert_eq!(report.range_scans.count(), 3);
+        assert_eq!(report.batch_writes.count(), 5);
+        assert_eq!(report.scanned_entries, 3 * 8);
+        assert!(report.point_reads.percentile(50.0) <= report.point_reads.percentile(100.0));
+
+        let keys = store.find_keys_by_prefix(&[]).await.unwrap();
+        assert_eq!(keys.len(), workload.key_count());
+        let value = store.read_value_bytes(&keys[0]).await.unwrap().unwrap();
+        assert_eq!(value.len(), 100);
+    }
+}
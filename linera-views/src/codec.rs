@@ -0,0 +1,104 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// An encoding of the values stored by views.
+///
+/// The views themselves always store their values with [`BcsCodec`]. Other codecs are meant
+/// for tools that inspect or export the stored values, e.g. human-readable dumps, and must
+/// not be used to write to the storage.
+pub trait ValueCodec {
+    /// The error produced when encoding or decoding fails.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Encodes a value.
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Self::Error>;
+
+    /// Decodes a value.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Self::Error>;
+}
+
+/// The BCS encoding, used by the views to store their values.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BcsCodec;
+
+impl ValueCodec for BcsCodec {
+    type Error = bcs::Error;
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Self::Error> {
+        bcs::to_bytes(value)
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Self::Error> {
+        bcs::from_bytes(bytes)
+    }
+}
+
+/// The JSON encoding, for human-readable dumps of the stored values.
+#[cfg(with_json)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+#[cfg(with_json)]
+impl ValueCodec for JsonCodec {
+    type Error = serde_json::Error;
+
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(value)
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Self::Error> {
+        serde_json::from_slice(bytes)
+    }
+}
+
+/// Decodes a value stored by a view and encodes it again with the codec `C`, e.g. to
+/// export it.
+pub fn transcode<T, C>(bytes: &[u8]) -> Result<Vec<u8>, TranscodeError<C::Error>>
+where
+    T: Serialize + DeserializeOwned,
+    C: ValueCodec,
+{
+    let value = BcsCodec::decode::<T>(bytes).map_err(TranscodeError::Decode)?;
+    C::encode(&value).map_err(TranscodeError::Encode)
+}
+
+/// An error when transcoding a stored value.
+#[derive(Debug, thiserror::Error)]
+pub enum TranscodeError<E> {
+    /// The stored value could not be decoded.
+    #[error("Failed to decode the stored value: {0}")]
+    Decode(bcs::Error),
+    /// The value could not be encoded with the target codec.
+    #[error("Failed to encode the value: {0}")]
+    Encode(E),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::{transcode, BcsCodec, ValueCodec as _};
+
+    #[test]
+    fn test_bcs_codec_round_trip() {
+        let value = (7u32, String::from("seven"));
+        let bytes = BcsCodec::encode(&value).unwrap();
+        assert_eq!(bytes, bcs::to_bytes(&value).unwrap());
+        assert_eq!(BcsCodec::decode::<(u32, String)>(&bytes).unwrap(), value);
+    }
+
+    #[cfg(with_json)]
+    #[test]
+    fn test_transcode_to_json() {
+        use crate::codec::JsonCodec;
+
+        let bytes = bcs::to_bytes(&(7u32, String::from("seven"))).unwrap();
+        let json = transcode::<(u32, String), JsonCodec>(&bytes).unwrap();
+        assert_eq!(json, br#"[7,"seven"]"#);
+    }
+
+    #[test]
+    fn test_transcode_rejects_invalid_values() {
+        assert!(transcode::<(u32, String), BcsCodec>(&[1, 2]).is_err());
+    }
+}
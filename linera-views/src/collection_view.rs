@@ -101,6 +101,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || !self.updates.read().await.is_empty()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.updates.get_mut().clear();
@@ -639,6 +643,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.collection.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.collection.rollback()
     }
@@ -974,6 +982,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.collection.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.collection.rollback()
     }
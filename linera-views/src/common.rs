@@ -22,9 +22,10 @@ use std::{
 };
 
 use async_trait::async_trait;
+use futures::{stream, Stream, TryStreamExt as _};
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{batch::Batch, views::ViewError};
+use crate::{batch::Batch, value_splitting::DatabaseConsistencyError, views::ViewError};
 
 #[cfg(test)]
 #[path = "unit_tests/common_tests.rs"]
@@ -41,6 +42,17 @@ pub(crate) enum Update<T> {
     Set(T),
 }
 
+/// A maintenance operation that operators can trigger on a store, e.g. during
+/// low-traffic windows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MaintenanceOp {
+    /// Compacts the stored data, so that the backend does not compact it in the
+    /// background later, e.g. a manual compaction of RocksDB.
+    Compact,
+    /// Persists the writes that the backend buffered, e.g. the memtables of RocksDB.
+    Flush,
+}
+
 /// The common initialization parameters for the `KeyValueStore`
 #[derive(Debug, Clone)]
 pub struct CommonStoreConfig {
@@ -357,6 +369,61 @@ pub trait LocalReadableKeyValueStore<E> {
             Ok(values)
         }
     }
+
+    /// Streams the `(key, value)` pairs matching the prefix, in the order of the keys. The
+    /// prefix is not included in the returned keys.
+    ///
+    /// Backends that support it page through the results lazily, so that large prefixes
+    /// can be iterated without holding all of the pairs in memory. By default, the pairs
+    /// are read with [`Self::find_key_values_by_prefix`].
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), E>> + 'a
+    where
+        Self: Sync,
+    {
+        stream::once(async move {
+            let key_values = self
+                .find_key_values_by_prefix(key_prefix)
+                .await?
+                .into_iterator_owned()
+                .collect::<Result<Vec<_>, E>>()?;
+            Ok(stream::iter(key_values.into_iter().map(Ok)))
+        })
+        .try_flatten()
+    }
+
+    /// Checks that the store is reachable, for instance before accepting traffic.
+    ///
+    /// Stores accessed over the network issue a trivial query. By default, the store is
+    /// assumed to be reachable.
+    fn health_check(&self) -> impl Future<Output = Result<(), E>> {
+        async { Ok(()) }
+    }
+
+    /// Returns an estimate of the number of keys matching the prefix, e.g. to monitor how
+    /// much space a view occupies.
+    ///
+    /// The result is approximate and depends on the backend: RocksDB extrapolates from its
+    /// statistics without reading the keys, while ScyllaDB and DynamoDB count the keys on
+    /// the server without sending them. Stores that split large values into several keys
+    /// report the keys of the underlying backend. By default, the keys are read with
+    /// [`Self::find_keys_by_prefix`] and counted exactly.
+    fn approximate_key_count(&self, key_prefix: &[u8]) -> impl Future<Output = Result<u64, E>>
+    where
+        Self: Sync,
+    {
+        async move {
+            let keys = self.find_keys_by_prefix(key_prefix).await?;
+            let mut count = 0;
+            for key in keys.iterator() {
+                key?;
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
 }
 
 /// Low-level, asynchronous write key-value operations. Useful for storage APIs not based on views.
@@ -371,6 +438,54 @@ pub trait LocalWritableKeyValueStore<E> {
     /// Clears any journal entry that may remain.
     /// The journal is located at the `base_key`.
     async fn clear_journal(&self, base_key: &[u8]) -> Result<(), E>;
+
+    /// Runs the maintenance operation `op` of the backend.
+    ///
+    /// Only RocksDB needs maintenance at the moment. By default, this does nothing.
+    fn maintenance(&self, _op: MaintenanceOp) -> impl Future<Output = Result<(), E>> {
+        async { Ok(()) }
+    }
+
+    /// Deletes all the keys starting with `key_prefix`.
+    ///
+    /// This writes a single prefix deletion, so backends supporting range deletions
+    /// (e.g. RocksDB and ScyllaDB) don't enumerate the keys. Other backends fall back to
+    /// scanning the prefix and deleting the keys found in batches. Any journal is kept
+    /// under `key_prefix`.
+    fn clear_prefix(&self, key_prefix: &[u8]) -> impl Future<Output = Result<(), E>>
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut batch = Batch::new();
+            batch.delete_key_prefix(key_prefix.to_vec());
+            self.write_batch(batch, key_prefix).await
+        }
+    }
+}
+
+/// Low-level, asynchronous compare-and-swap of single values, for optimistic concurrency
+/// control (e.g. leader election or single-writer guards).
+///
+/// On every backend, the operation is only atomic with respect to the other
+/// compare-and-swap operations: the keys used with `compare_and_swap` must only be written
+/// through it, and not with `write_batch`. In addition:
+/// * DynamoDB (conditional writes) and ScyllaDB (lightweight transactions) are atomic
+///   with respect to all the clients of the database. The batches of ScyllaDB don't use
+///   lightweight transactions, so they are not ordered with them.
+/// * The memory store is atomic, since it cannot be shared between processes.
+/// * RocksDB takes a lock in the store, so it is only atomic with respect to the
+///   compare-and-swap operations of the same process.
+#[trait_variant::make(CompareAndSwapKeyValueStore: Send)]
+pub trait LocalCompareAndSwapKeyValueStore<E> {
+    /// Sets the value of `key` to `new` if its current value is `expected`, where `None`
+    /// means that the key is missing. Returns whether the value was swapped.
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, E>;
 }
 
 /// Low-level trait for the administration of stores and their namespaces.
@@ -442,6 +557,36 @@ pub trait KeyValueStore:
 {
     /// The error type.
     type Error: Debug;
+
+    /// Exchanges the keys starting with `prefix1` and the keys starting with `prefix2`,
+    /// e.g. to replace a view with a copy that was written under another prefix.
+    ///
+    /// The memory store swaps the keys atomically. By default, both prefixes are read and
+    /// then rewritten with a single batch: the write is atomic if the backend's batches
+    /// are, but writes to the prefixes made between the reads and the write are lost. Any
+    /// journal is kept under the common prefix of `prefix1` and `prefix2`.
+    ///
+    /// Swapping a prefix with itself does nothing. Fails if one of the prefixes is a
+    /// strict prefix of the other.
+    fn swap_prefixes(
+        &self,
+        prefix1: &[u8],
+        prefix2: &[u8],
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send
+    where
+        Self: Sync,
+        Self::Error: From<DatabaseConsistencyError>,
+    {
+        async move {
+            swap_prefixes_by_batch(
+                prefix1,
+                prefix2,
+                |prefix| self.find_key_values_by_prefix(prefix),
+                |batch, base_key| self.write_batch(batch, base_key),
+            )
+            .await
+        }
+    }
 }
 
 /// Low-level, asynchronous write and read key-value operations, without a `Send` bound. Useful for storage APIs not based on views.
@@ -450,10 +595,120 @@ pub trait LocalKeyValueStore:
 {
     /// The error type.
     type Error: Debug;
+
+    /// Exchanges the keys starting with `prefix1` and the keys starting with `prefix2`.
+    /// See [`KeyValueStore::swap_prefixes`].
+    fn swap_prefixes(
+        &self,
+        prefix1: &[u8],
+        prefix2: &[u8],
+    ) -> impl Future<Output = Result<(), Self::Error>>
+    where
+        Self: Sync,
+        Self::Error: From<DatabaseConsistencyError>,
+    {
+        async move {
+            swap_prefixes_by_batch(
+                prefix1,
+                prefix2,
+                |prefix| self.find_key_values_by_prefix(prefix),
+                |batch, base_key| self.write_batch(batch, base_key),
+            )
+            .await
+        }
+    }
 }
 
 impl<S: KeyValueStore> LocalKeyValueStore for S {
     type Error = <Self as KeyValueStore>::Error;
+
+    fn swap_prefixes(
+        &self,
+        prefix1: &[u8],
+        prefix2: &[u8],
+    ) -> impl Future<Output = Result<(), <Self as KeyValueStore>::Error>>
+    where
+        Self: Sync,
+        <Self as KeyValueStore>::Error: From<DatabaseConsistencyError>,
+    {
+        KeyValueStore::swap_prefixes(self, prefix1, prefix2)
+    }
+}
+
+/// Checks that the keys of two distinct prefixes can be swapped, i.e. that neither is a
+/// prefix of the other.
+pub(crate) fn check_disjoint_prefixes(
+    prefix1: &[u8],
+    prefix2: &[u8],
+) -> Result<(), DatabaseConsistencyError> {
+    if prefix1.starts_with(prefix2) || prefix2.starts_with(prefix1) {
+        return Err(DatabaseConsistencyError::OverlappingPrefixes);
+    }
+    Ok(())
+}
+
+/// The default implementation of `swap_prefixes` for [`KeyValueStore`] and
+/// [`LocalKeyValueStore`], given the methods of the store reading the key-values under a
+/// prefix and writing a batch. The futures of these methods are `Send` only for the
+/// former, so they are passed as closures.
+async fn swap_prefixes_by_batch<'a, E, KeyValues, Read, ReadFuture, Write, WriteFuture>(
+    prefix1: &'a [u8],
+    prefix2: &'a [u8],
+    read: Read,
+    write: Write,
+) -> Result<(), E>
+where
+    E: From<DatabaseConsistencyError>,
+    KeyValues: KeyValueIterable<E>,
+    Read: Fn(&'a [u8]) -> ReadFuture,
+    ReadFuture: Future<Output = Result<KeyValues, E>>,
+    Write: FnOnce(Batch, &'a [u8]) -> WriteFuture,
+    WriteFuture: Future<Output = Result<(), E>>,
+{
+    if prefix1 == prefix2 {
+        return Ok(());
+    }
+    check_disjoint_prefixes(prefix1, prefix2)?;
+    let key_values1 = read(prefix1)
+        .await?
+        .into_iterator_owned()
+        .collect::<Result<Vec<_>, _>>()?;
+    let key_values2 = read(prefix2)
+        .await?
+        .into_iterator_owned()
+        .collect::<Result<Vec<_>, _>>()?;
+    let batch = swap_prefixes_batch(prefix1, prefix2, key_values1, key_values2);
+    write(batch, common_prefix(prefix1, prefix2)).await
+}
+
+/// Returns the longest common prefix of `prefix1` and `prefix2`.
+fn common_prefix<'a>(prefix1: &'a [u8], prefix2: &[u8]) -> &'a [u8] {
+    let len = prefix1
+        .iter()
+        .zip(prefix2)
+        .take_while(|(byte1, byte2)| byte1 == byte2)
+        .count();
+    &prefix1[..len]
+}
+
+/// Returns the batch moving the keys `key_values1`, found under `prefix1`, to `prefix2`
+/// and the keys `key_values2`, found under `prefix2`, to `prefix1`.
+fn swap_prefixes_batch(
+    prefix1: &[u8],
+    prefix2: &[u8],
+    key_values1: Vec<(Vec<u8>, Vec<u8>)>,
+    key_values2: Vec<(Vec<u8>, Vec<u8>)>,
+) -> Batch {
+    let mut batch = Batch::new();
+    batch.delete_key_prefix(prefix1.to_vec());
+    batch.delete_key_prefix(prefix2.to_vec());
+    for (suffix, value) in key_values1 {
+        batch.put_key_value_bytes([prefix2, &suffix].concat(), value);
+    }
+    for (suffix, value) in key_values2 {
+        batch.put_key_value_bytes([prefix1, &suffix].concat(), value);
+    }
+    batch
 }
 
 #[doc(hidden)]
@@ -582,6 +837,10 @@ pub trait Context: Clone {
         key_prefix: &[u8],
     ) -> Result<Self::KeyValues, Self::Error>;
 
+    /// Returns an estimate of the number of keys matching the `key_prefix`. See
+    /// [`ReadableKeyValueStore::approximate_key_count`].
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, Self::Error>;
+
     /// Applies the operations from the `batch`, persisting the changes.
     async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error>;
 
@@ -799,6 +1058,14 @@ where
         .await
     }
 
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, Self::Error> {
+        log_time_async(
+            self.store.approximate_key_count(key_prefix),
+            "approximate_key_count",
+        )
+        .await
+    }
+
     async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
         log_time_async(self.store.write_batch(batch, &self.base_key), "write_batch").await
     }
@@ -0,0 +1,77 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+
+use crate::{
+    collection_view::{ByteCollectionView, CollectionView, CustomCollectionView},
+    key_value_store_view::KeyValueStoreView,
+    log_view::LogView,
+    map_view::{ByteMapView, CustomMapView, MapView},
+    queue_view::QueueView,
+    reentrant_collection_view::{
+        ReentrantByteCollectionView, ReentrantCollectionView, ReentrantCustomCollectionView,
+    },
+    register_view::RegisterView,
+    set_view::{ByteSetView, CustomSetView, SetView},
+    views::{HashableView, Hasher, View, ViewError},
+};
+
+/// How two instances of a view differ, as computed by [`DiffableView::diff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ViewDiff {
+    /// The values of the views differ.
+    Changed,
+    /// Some subviews differ. They are listed by name, in the order of the fields.
+    Subviews(Vec<(&'static str, ViewDiff)>),
+}
+
+/// A view that can be compared with another instance of the same view, e.g. to inspect
+/// the changes made by a state transition.
+///
+/// Views derived with `#[view(diff)]` compare their subviews one by one. The other views
+/// compare their values, including their pending changes, through their hashes.
+#[async_trait]
+pub trait DiffableView<C>: View<C> {
+    /// Returns how `other` differs from this view, or `None` if they have the same
+    /// values.
+    async fn diff(&self, other: &Self) -> Result<Option<ViewDiff>, ViewError>;
+}
+
+/// Implements [`DiffableView`] by comparing the hashes of the views.
+macro_rules! impl_diffable_view_by_hash {
+    ($($view:ident<C $(, $parameter:ident)*>),* $(,)?) => {$(
+        #[async_trait]
+        impl<C $(, $parameter)*> DiffableView<C> for $view<C $(, $parameter)*>
+        where
+            C: Send + Sync,
+            Self: HashableView<C> + Sync,
+            <<Self as HashableView<C>>::Hasher as Hasher>::Output: Send,
+        {
+            async fn diff(&self, other: &Self) -> Result<Option<ViewDiff>, ViewError> {
+                let hash = self.hash().await?;
+                let other_hash = other.hash().await?;
+                Ok((hash != other_hash).then_some(ViewDiff::Changed))
+            }
+        }
+    )*};
+}
+
+impl_diffable_view_by_hash!(
+    RegisterView<C, T>,
+    LogView<C, T>,
+    QueueView<C, T>,
+    ByteMapView<C, V>,
+    MapView<C, I, V>,
+    CustomMapView<C, I, V>,
+    ByteSetView<C>,
+    SetView<C, I>,
+    CustomSetView<C, I>,
+    ByteCollectionView<C, W>,
+    CollectionView<C, I, W>,
+    CustomCollectionView<C, I, W>,
+    ReentrantByteCollectionView<C, W>,
+    ReentrantCollectionView<C, I, W>,
+    ReentrantCustomCollectionView<C, I, W>,
+    KeyValueStoreView<C>,
+);
@@ -6,26 +6,31 @@ use std::{collections::HashMap, env, sync::Arc};
 use async_lock::{Semaphore, SemaphoreGuard};
 use async_trait::async_trait;
 use aws_sdk_dynamodb::{
-    error::SdkError,
+    error::{ProvideErrorMetadata, SdkError},
     operation::{
         batch_write_item::BatchWriteItemError,
         create_table::CreateTableError,
         delete_table::DeleteTableError,
+        describe_table::DescribeTableError,
         get_item::GetItemError,
         list_tables::ListTablesError,
+        put_item::PutItemError,
         query::{QueryError, QueryOutput},
         transact_write_items::TransactWriteItemsError,
     },
     primitives::Blob,
     types::{
         AttributeDefinition, AttributeValue, Delete, KeySchemaElement, KeyType,
-        ProvisionedThroughput, Put, ScalarAttributeType, TransactWriteItem,
+        ProvisionedThroughput, Put, ScalarAttributeType, Select, TransactWriteItem,
     },
     Client,
 };
 use aws_smithy_types::error::operation::BuildError;
-use futures::future::{join_all, FutureExt as _};
-use linera_base::ensure;
+use futures::{
+    future::{join_all, FutureExt as _},
+    stream, Stream, TryStreamExt as _,
+};
+use linera_base::{ensure, time::Duration};
 use thiserror::Error;
 #[cfg(with_testing)]
 use {
@@ -40,16 +45,18 @@ use crate::metering::{
     MeteredStore, DYNAMO_DB_METRICS, LRU_CACHING_METRICS, VALUE_SPLITTING_METRICS,
 };
 use crate::{
-    batch::{Batch, SimpleUnorderedBatch},
+    batch::{Batch, SimpleUnorderedBatch, SimplifiedBatch},
     common::{
-        AdminKeyValueStore, CommonStoreConfig, ContextFromStore, KeyIterable, KeyValueIterable,
-        KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+        AdminKeyValueStore, CommonStoreConfig, CompareAndSwapKeyValueStore, ContextFromStore,
+        KeyIterable, KeyValueIterable, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
     },
     journaling::{
         DirectKeyValueStore, DirectWritableKeyValueStore, JournalConsistencyError,
         JournalingKeyValueStore,
     },
     lru_caching::LruCachingStore,
+    namespaced::NamespacedStoreError,
+    sharded::ShardedStoreError,
     value_splitting::{DatabaseConsistencyError, ValueSplittingStore},
 };
 
@@ -856,6 +863,91 @@ impl ReadableKeyValueStore<DynamoDbContextError> for DynamoDbStoreInternal {
             .await?;
         Ok(DynamoDbKeyValues { result_queries })
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), DynamoDbContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        let prefix_len = key_prefix.len();
+        // The state is the key to start the next page from, or `None` after the last page.
+        stream::try_unfold(Some(None), move |start_key| async move {
+            let Some(start_key) = start_key else {
+                return Ok::<_, DynamoDbContextError>(None);
+            };
+            ensure!(
+                !key_prefix.is_empty(),
+                DynamoDbContextError::ZeroLengthKeyPrefix
+            );
+            ensure!(
+                key_prefix.len() <= MAX_KEY_SIZE,
+                DynamoDbContextError::KeyPrefixTooLong
+            );
+            let response = self
+                .get_query_output(KEY_VALUE_ATTRIBUTE, key_prefix, start_key)
+                .await?;
+            let key_values = response
+                .items
+                .into_iter()
+                .flatten()
+                .map(move |mut item| extract_key_value_owned(prefix_len, &mut item));
+            let next_start_key = response.last_evaluated_key.map(Some);
+            Ok(Some((stream::iter(key_values), next_start_key)))
+        })
+        .try_flatten()
+    }
+
+    async fn health_check(&self) -> Result<(), DynamoDbContextError> {
+        let _guard = self.acquire().await;
+        self.client
+            .describe_table()
+            .table_name(&self.namespace)
+            .send()
+            .boxed()
+            .await?;
+        Ok(())
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, DynamoDbContextError> {
+        ensure!(
+            !key_prefix.is_empty(),
+            DynamoDbContextError::ZeroLengthKeyPrefix
+        );
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            DynamoDbContextError::KeyPrefixTooLong
+        );
+        // The items are counted by the server, one page at a time, without being sent.
+        let mut count = 0;
+        let mut start_key = None;
+        loop {
+            let _guard = self.acquire().await;
+            let response = self
+                .client
+                .query()
+                .table_name(&self.namespace)
+                .select(Select::Count)
+                .key_condition_expression(format!(
+                    "{PARTITION_ATTRIBUTE} = :partition and begins_with({KEY_ATTRIBUTE}, :prefix)"
+                ))
+                .expression_attribute_values(
+                    ":partition",
+                    AttributeValue::B(Blob::new(DUMMY_PARTITION_KEY)),
+                )
+                .expression_attribute_values(":prefix", AttributeValue::B(Blob::new(key_prefix)))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .boxed()
+                .await?;
+            count += response.count as u64;
+            match response.last_evaluated_key {
+                Some(key) => start_key = Some(key),
+                None => return Ok(count),
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -868,6 +960,19 @@ impl DirectWritableKeyValueStore<DynamoDbContextError> for DynamoDbStoreInternal
     type Batch = SimpleUnorderedBatch;
 
     async fn write_batch(&self, batch: Self::Batch) -> Result<(), DynamoDbContextError> {
+        for batch in batch.into_chunks(Self::MAX_BATCH_SIZE, Self::MAX_BATCH_TOTAL_SIZE)? {
+            self.write_transaction(batch).await?;
+        }
+        Ok(())
+    }
+}
+
+impl DynamoDbStoreInternal {
+    /// Writes a batch that fits in a single transaction.
+    async fn write_transaction(
+        &self,
+        batch: SimpleUnorderedBatch,
+    ) -> Result<(), DynamoDbContextError> {
         let mut builder = TransactionBuilder::default();
         for key in batch.deletions {
             builder.insert_delete_request(key, self)?;
@@ -892,6 +997,45 @@ impl DirectKeyValueStore for DynamoDbStoreInternal {
     type Error = DynamoDbContextError;
 }
 
+impl CompareAndSwapKeyValueStore<DynamoDbContextError> for DynamoDbStoreInternal {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, DynamoDbContextError> {
+        ensure!(!key.is_empty(), DynamoDbContextError::ZeroLengthKey);
+        ensure!(key.len() <= MAX_KEY_SIZE, DynamoDbContextError::KeyTooLong);
+        ensure!(
+            new.len() <= RAW_MAX_VALUE_SIZE,
+            DynamoDbContextError::ValueLengthTooLarge
+        );
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.namespace)
+            .set_item(Some(build_key_value(key.to_vec(), new.to_vec())));
+        let request = match expected {
+            None => request.condition_expression(format!("attribute_not_exists({KEY_ATTRIBUTE})")),
+            Some(expected) => request
+                .condition_expression(format!("{VALUE_ATTRIBUTE} = :expected"))
+                .expression_attribute_values(":expected", AttributeValue::B(Blob::new(expected))),
+        };
+        let _guard = self.acquire().await;
+        match request.send().boxed().await {
+            Ok(_) => Ok(true),
+            Err(error)
+                if error
+                    .as_service_error()
+                    .is_some_and(PutItemError::is_conditional_check_failed_exception) =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
 /// A shared DB client for DynamoDb implementing LruCaching
 #[derive(Clone)]
 #[allow(clippy::type_complexity)]
@@ -945,6 +1089,24 @@ impl ReadableKeyValueStore<DynamoDbContextError> for DynamoDbStore {
     ) -> Result<Self::KeyValues, DynamoDbContextError> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), DynamoDbContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn health_check(&self) -> Result<(), DynamoDbContextError> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, DynamoDbContextError> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl WritableKeyValueStore<DynamoDbContextError> for DynamoDbStore {
@@ -959,6 +1121,17 @@ impl WritableKeyValueStore<DynamoDbContextError> for DynamoDbStore {
     }
 }
 
+impl CompareAndSwapKeyValueStore<DynamoDbContextError> for DynamoDbStore {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, DynamoDbContextError> {
+        self.store.compare_and_swap(key, expected, new).await
+    }
+}
+
 impl KeyValueStore for DynamoDbStore {
     type Error = DynamoDbContextError;
 }
@@ -1045,6 +1218,10 @@ pub enum DynamoDbContextError {
     #[error(transparent)]
     Get(#[from] Box<SdkError<GetItemError>>),
 
+    /// An error occurred while putting an item.
+    #[error(transparent)]
+    PutItem(#[from] Box<SdkError<PutItemError>>),
+
     /// An error occurred while writing a batch of items.
     #[error(transparent)]
     BatchWriteItem(#[from] Box<SdkError<BatchWriteItemError>>),
@@ -1065,6 +1242,10 @@ pub enum DynamoDbContextError {
     #[error(transparent)]
     ListTables(#[from] Box<SdkError<ListTablesError>>),
 
+    /// An error occurred while describing a table
+    #[error(transparent)]
+    DescribeTable(#[from] Box<SdkError<DescribeTableError>>),
+
     /// The transact maximum size is MAX_TRANSACT_WRITE_ITEM_SIZE.
     #[error("The transact must have length at most MAX_TRANSACT_WRITE_ITEM_SIZE")]
     TransactUpperLimitSize,
@@ -1093,6 +1274,14 @@ pub enum DynamoDbContextError {
     #[error(transparent)]
     DatabaseConsistencyError(#[from] DatabaseConsistencyError),
 
+    /// The configuration of a sharded store is invalid
+    #[error(transparent)]
+    ShardedStoreError(#[from] ShardedStoreError),
+
+    /// The configuration of a namespaced store is invalid
+    #[error(transparent)]
+    NamespacedStoreError(#[from] NamespacedStoreError),
+
     /// The journal is not coherent
     #[error(transparent)]
     JournalConsistencyError(#[from] JournalConsistencyError),
@@ -1194,8 +1383,57 @@ impl DynamoDbContextError {
     }
 }
 
+/// The error codes of DynamoDB for requests rejected because a table or the account is
+/// over its capacity or quota.
+const THROTTLING_ERROR_CODES: &[&str] = &[
+    "ProvisionedThroughputExceededException",
+    "RequestLimitExceeded",
+    "ThrottlingException",
+];
+
+impl DynamoDbContextError {
+    /// Returns `Some` if DynamoDB throttled the request, with the delay it asked to wait
+    /// for before retrying, if any.
+    fn throttling(&self) -> Option<Option<Duration>> {
+        match self {
+            DynamoDbContextError::Get(error) => sdk_error_throttling(error),
+            DynamoDbContextError::PutItem(error) => sdk_error_throttling(error),
+            DynamoDbContextError::BatchWriteItem(error) => sdk_error_throttling(error),
+            DynamoDbContextError::TransactWriteItem(error) => sdk_error_throttling(error),
+            DynamoDbContextError::Query(error) => sdk_error_throttling(error),
+            DynamoDbContextError::DeleteTable(error) => sdk_error_throttling(error),
+            DynamoDbContextError::ListTables(error) => sdk_error_throttling(error),
+            DynamoDbContextError::DescribeTable(error) => sdk_error_throttling(error),
+            DynamoDbContextError::CreateTable(error) => sdk_error_throttling(error),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `Some` if the error is a throttling error, with the delay in seconds of the
+/// `Retry-After` header of the response, if any.
+fn sdk_error_throttling<E: ProvideErrorMetadata>(error: &SdkError<E>) -> Option<Option<Duration>> {
+    let SdkError::ServiceError(error) = error else {
+        return None;
+    };
+    let code = error.err().code()?;
+    if !THROTTLING_ERROR_CODES.contains(&code) {
+        return None;
+    }
+    let retry_after = error
+        .raw()
+        .headers()
+        .get("retry-after")
+        .and_then(|seconds| seconds.trim().parse().ok())
+        .map(Duration::from_secs);
+    Some(retry_after)
+}
+
 impl From<DynamoDbContextError> for crate::views::ViewError {
     fn from(error: DynamoDbContextError) -> Self {
+        if let Some(retry_after) = error.throttling() {
+            return Self::Throttled { retry_after };
+        }
         Self::ContextError {
             backend: "DynamoDB".to_string(),
             error: error.to_string(),
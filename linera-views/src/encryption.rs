@@ -0,0 +1,330 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The encryption of the values of subviews marked with `#[view(encrypted)]`.
+//!
+//! Such a subview is loaded with an [`EncryptedContext`], whose key is taken from the user
+//! data of the context of the parent view, which must implement [`HasEncryptionKey`]. The
+//! values written by the subview are sealed with ChaCha20-Poly1305 under a random nonce,
+//! with their key as associated data, so that a value can't be moved to another key
+//! without being detected. The keys themselves are left in cleartext, so that the subview
+//! can still scan them by prefix.
+//!
+//! # Position among the stores
+//!
+//! Encryption happens in the views, above every [`KeyValueStore`](crate::common::KeyValueStore):
+//! stores, and the stores wrapping them such as [`LruCachingStore`](crate::lru_caching::LruCachingStore)
+//! or [`RetryingStore`](crate::retrying::RetryingStore), only ever see ciphertext. A store
+//! compressing values gains nothing on encrypted subviews, since ciphertext doesn't
+//! compress, and a store checksumming values only detects corruption that the
+//! authentication tag would detect as well.
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    rand::{SecureRandom as _, SystemRandom},
+};
+
+use crate::{
+    batch::{Batch, WriteOperation},
+    common::{Context, KeyIterable as _, KeyValueIterable as _},
+    views::{View, ViewError},
+};
+
+/// The size in bytes of the authentication tag of an encrypted value.
+const TAG_LEN: usize = 16;
+
+/// The number of bytes added to each value by the encryption: the nonce and the
+/// authentication tag.
+pub const ENCRYPTION_OVERHEAD: usize = NONCE_LEN + TAG_LEN;
+
+/// A key to encrypt the values of subviews with.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    key: Arc<LessSafeKey>,
+}
+
+impl EncryptionKey {
+    /// Creates a key from its 32 bytes.
+    pub fn new(bytes: [u8; 32]) -> Self {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+            .expect("ChaCha20-Poly1305 keys have 32 bytes");
+        EncryptionKey {
+            key: Arc::new(LessSafeKey::new(key)),
+        }
+    }
+
+    /// Creates a random key.
+    pub fn generate() -> Self {
+        let mut bytes = [0; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .expect("the system random number generator should be available");
+        Self::new(bytes)
+    }
+
+    /// Encrypts the `value` of the entry with the given `key`.
+    fn seal(&self, key: &[u8], value: Vec<u8>) -> Result<Vec<u8>, ViewError> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .expect("the system random number generator should be available");
+        let mut sealed = Vec::with_capacity(value.len() + ENCRYPTION_OVERHEAD);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(value);
+        let tag = self
+            .key
+            .seal_in_place_separate_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(key),
+                &mut sealed[NONCE_LEN..],
+            )
+            .map_err(|_| ViewError::TooLargeValue)?;
+        sealed.extend_from_slice(tag.as_ref());
+        Ok(sealed)
+    }
+
+    /// Decrypts the `sealed` value of the entry with the given `key`.
+    fn open(&self, key: &[u8], mut sealed: Vec<u8>) -> Result<Vec<u8>, ViewError> {
+        if sealed.len() < ENCRYPTION_OVERHEAD {
+            return Err(ViewError::DecryptionFailed);
+        }
+        let nonce = Nonce::try_assume_unique_for_key(&sealed[..NONCE_LEN])
+            .map_err(|_| ViewError::DecryptionFailed)?;
+        let len = self
+            .key
+            .open_within(nonce, Aad::from(key), &mut sealed, NONCE_LEN..)
+            .map_err(|_| ViewError::DecryptionFailed)?
+            .len();
+        sealed.truncate(len);
+        Ok(sealed)
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// The user data of the contexts of views with subviews marked with `#[view(encrypted)]`.
+pub trait HasEncryptionKey {
+    /// Returns the key to encrypt the values of the subviews with.
+    fn encryption_key(&self) -> &EncryptionKey;
+}
+
+impl HasEncryptionKey for EncryptionKey {
+    fn encryption_key(&self) -> &EncryptionKey {
+        self
+    }
+}
+
+/// A context encrypting the values written through it, and decrypting the values read.
+#[derive(Clone, Debug)]
+pub struct EncryptedContext<C> {
+    context: C,
+    key: EncryptionKey,
+}
+
+impl<C> EncryptedContext<C>
+where
+    C: Context,
+    C::Extra: HasEncryptionKey,
+{
+    /// Creates a context encrypting the values of `context` with the key of its user data.
+    pub fn new(context: C) -> Self {
+        let key = context.extra().encryption_key().clone();
+        EncryptedContext { context, key }
+    }
+}
+
+impl<C> EncryptedContext<C> {
+    /// Creates a context encrypting the values of `context` with `key`.
+    pub fn with_key(context: C, key: EncryptionKey) -> Self {
+        EncryptedContext { context, key }
+    }
+
+    /// Returns the context storing the encrypted values.
+    pub fn inner(&self) -> &C {
+        &self.context
+    }
+
+    /// Encrypts the values written by `batch`, and appends its operations to `target`.
+    pub fn encrypt_batch(&self, batch: Batch, target: &mut Batch) -> Result<(), ViewError> {
+        for operation in batch.operations {
+            let operation = match operation {
+                WriteOperation::Put { key, value } => {
+                    let value = self.key.seal(&key, value)?;
+                    WriteOperation::Put { key, value }
+                }
+                operation => operation,
+            };
+            target.operations.push(operation);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C> Context for EncryptedContext<C>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+{
+    const MAX_VALUE_SIZE: usize = C::MAX_VALUE_SIZE.saturating_sub(ENCRYPTION_OVERHEAD);
+    const MAX_KEY_SIZE: usize = C::MAX_KEY_SIZE;
+    type Extra = C::Extra;
+    type Error = ViewError;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.context.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ViewError> {
+        match self.context.read_value_bytes(key).await? {
+            Some(value) => Ok(Some(self.key.open(key, value)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, ViewError> {
+        Ok(self.context.contains_key(key).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, ViewError> {
+        let values = self.context.read_multi_values_bytes(keys.clone()).await?;
+        keys.iter()
+            .zip(values)
+            .map(|(key, value)| value.map(|value| self.key.open(key, value)).transpose())
+            .collect()
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, ViewError> {
+        let keys = self.context.find_keys_by_prefix(key_prefix).await?;
+        let keys = keys
+            .iterator()
+            .map(|key| key.map(<[u8]>::to_vec))
+            .collect::<Result<_, C::Error>>()?;
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, ViewError> {
+        let key_values = self.context.find_key_values_by_prefix(key_prefix).await?;
+        let mut full_key = key_prefix.to_vec();
+        let mut decrypted = Vec::new();
+        for key_value in key_values.into_iterator_owned() {
+            let (key, value) = key_value?;
+            full_key.truncate(key_prefix.len());
+            full_key.extend_from_slice(&key);
+            decrypted.push((key, self.key.open(&full_key, value)?));
+        }
+        Ok(decrypted)
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, ViewError> {
+        Ok(self.context.approximate_key_count(key_prefix).await?)
+    }
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), ViewError> {
+        let mut encrypted = Batch::new();
+        self.encrypt_batch(batch, &mut encrypted)?;
+        Ok(self.context.write_batch(encrypted).await?)
+    }
+
+    fn extra(&self) -> &C::Extra {
+        self.context.extra()
+    }
+
+    fn clone_with_base_key(&self, base_key: Vec<u8>) -> Self {
+        EncryptedContext {
+            context: self.context.clone_with_base_key(base_key),
+            key: self.key.clone(),
+        }
+    }
+
+    fn base_key(&self) -> Vec<u8> {
+        self.context.base_key()
+    }
+}
+
+/// Flushes the changes of a subview marked with `#[view(encrypted)]` into `batch`, with
+/// their values encrypted.
+pub fn flush_encrypted_view<C, W>(view: &mut W, batch: &mut Batch) -> Result<bool, ViewError>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: View<EncryptedContext<C>>,
+{
+    let mut view_batch = Batch::new();
+    let delete_view = view.flush(&mut view_batch)?;
+    view.context().encrypt_batch(view_batch, batch)?;
+    Ok(delete_view)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncryptedContext, EncryptionKey, ENCRYPTION_OVERHEAD};
+    use crate::{
+        batch::Batch,
+        common::Context as _,
+        memory::{MemoryContext, TEST_MEMORY_MAX_STREAM_QUERIES},
+        views::ViewError,
+    };
+
+    #[tokio::test]
+    async fn test_encrypted_context_round_trip() -> Result<(), ViewError> {
+        let context = MemoryContext::new(TEST_MEMORY_MAX_STREAM_QUERIES, EncryptionKey::generate());
+        let encrypted = EncryptedContext::new(context.clone());
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1, 2], b"secret".to_vec());
+        batch.put_key_value_bytes(vec![1, 3], Vec::new());
+        encrypted.write_batch(batch).await?;
+
+        let stored = context.read_value_bytes(&[1, 2]).await?.unwrap();
+        assert_eq!(stored.len(), b"secret".len() + ENCRYPTION_OVERHEAD);
+        assert!(!stored.windows(6).any(|window| window == b"secret"));
+        assert_eq!(
+            encrypted.read_value_bytes(&[1, 2]).await?,
+            Some(b"secret".to_vec())
+        );
+        assert_eq!(
+            encrypted
+                .read_multi_values_bytes(vec![vec![1, 3], vec![1, 4]])
+                .await?,
+            vec![Some(Vec::new()), None]
+        );
+        assert_eq!(
+            encrypted.find_key_values_by_prefix(&[1]).await?,
+            vec![(vec![2], b"secret".to_vec()), (vec![3], Vec::new())]
+        );
+        assert_eq!(
+            encrypted.find_keys_by_prefix(&[1]).await?,
+            vec![vec![2], vec![3]]
+        );
+
+        // A value moved to another key, or read with another key, is rejected.
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1, 4], stored);
+        context.write_batch(batch).await?;
+        assert!(matches!(
+            encrypted.read_value_bytes(&[1, 4]).await,
+            Err(ViewError::DecryptionFailed)
+        ));
+        let other = EncryptedContext::with_key(context, EncryptionKey::generate());
+        assert!(matches!(
+            other.read_value_bytes(&[1, 2]).await,
+            Err(ViewError::DecryptionFailed)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+
+use crate::{
+    batch::Batch,
+    common::{Context, MIN_VIEW_TAG},
+    introspection::{IntrospectableView, ViewLayout},
+    views::{RootView, View, ViewError},
+};
+
+/// An enum whose variants each wrap exactly one subview.
+///
+/// This is implemented by `#[derive(View)]` on enums. The enum is stored through an
+/// [`EnumView`], which keeps track of the variant that is in storage.
+#[async_trait]
+pub trait EnumVariants<C>: Sized {
+    /// Returns the index of the active variant.
+    fn variant(&self) -> u8;
+
+    /// Loads the variant with the given index, whose subview has the given context.
+    async fn load_variant(variant: u8, context: C) -> Result<Self, ViewError>;
+
+    /// Returns `true` if flushing the subview of the active variant would persist changes
+    /// to storage.
+    async fn has_pending_changes(&self) -> bool;
+
+    /// Discards all pending changes of the subview of the active variant.
+    fn rollback(&mut self);
+
+    /// Clears the subview of the active variant.
+    fn clear(&mut self);
+
+    /// Persists the changes of the subview of the active variant. The returned boolean
+    /// indicates whether the subview was removed.
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError>;
+}
+
+/// A view storing one of the variants of the enum `E`, whose subviews are only loaded
+/// for the active variant.
+///
+/// The index of the variant in storage is kept under the tag `MIN_VIEW_TAG`, and the
+/// subview of the variant with index `i` under the tag `MIN_VIEW_TAG + 1` followed by
+/// the byte `i`. A missing index stands for the first variant.
+///
+/// The view remembers which variant is in storage, so that switching variants with
+/// [`EnumView::set_variant`] only removes the data of that variant when flushing, and
+/// rolling back restores it. Clearing the view switches back to the first variant, which
+/// is loaded along with the stored variant if they differ.
+#[derive(Debug)]
+pub struct EnumView<C, E> {
+    context: C,
+    stored_variant: u8,
+    stored_value: Option<E>,
+    /// An empty subview of the first variant, if that variant is neither active nor stored.
+    first_value: Option<E>,
+    value: E,
+}
+
+impl<C: Context, E> EnumView<C, E> {
+    /// Returns the context of the subview of the given variant.
+    fn variant_context(context: &C, variant: u8) -> C {
+        context.clone_with_base_key(context.base_tag_index(MIN_VIEW_TAG + 1, &[variant]))
+    }
+}
+
+impl<C, E: EnumVariants<C>> EnumView<C, E> {
+    /// Keeps `value`, which is no longer active, if it may be activated again: the value
+    /// of the stored variant, to roll back to, or the value of the first variant, to clear
+    /// the view.
+    fn set_aside(&mut self, mut value: E) {
+        if value.variant() == self.stored_variant {
+            self.stored_value = Some(value);
+        } else if value.variant() == 0 {
+            // Nothing is stored for the first variant, since it isn't the stored one.
+            value.clear();
+            self.first_value = Some(value);
+        }
+    }
+
+    /// Takes the value of the first variant, which must not be active.
+    fn take_first_value(&mut self) -> E {
+        match self.stored_value.take() {
+            Some(value) if self.stored_variant == 0 => value,
+            stored_value => {
+                self.stored_value = stored_value;
+                self.first_value
+                    .take()
+                    .expect("the first variant is active, stored or set aside")
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C, E> View<C> for EnumView<C, E>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    E: EnumVariants<C> + Send + Sync,
+{
+    fn context(&self) -> &C {
+        &self.context
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        let key = context.base_tag(MIN_VIEW_TAG);
+        let stored_variant = context.read_value::<u8>(&key).await?.unwrap_or_default();
+        let variant_context = Self::variant_context(&context, stored_variant);
+        let value = E::load_variant(stored_variant, variant_context).await?;
+        let first_value = if stored_variant == 0 {
+            None
+        } else {
+            let first_context = Self::variant_context(&context, 0);
+            Some(E::load_variant(0, first_context).await?)
+        };
+        Ok(Self {
+            context,
+            stored_variant,
+            stored_value: None,
+            first_value,
+            value,
+        })
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        self.value.variant() != self.stored_variant || self.value.has_pending_changes().await
+    }
+
+    fn rollback(&mut self) {
+        if let Some(value) = self.stored_value.take() {
+            let previous_value = std::mem::replace(&mut self.value, value);
+            self.set_aside(previous_value);
+        }
+        self.value.rollback();
+    }
+
+    fn clear(&mut self) {
+        if self.value.variant() != 0 {
+            let first_value = self.take_first_value();
+            let previous_value = std::mem::replace(&mut self.value, first_value);
+            self.set_aside(previous_value);
+        }
+        self.value.clear();
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        let variant = self.value.variant();
+        if variant != self.stored_variant {
+            let key_prefix = self
+                .context
+                .base_tag_index(MIN_VIEW_TAG + 1, &[self.stored_variant]);
+            batch.delete_key_prefix(key_prefix);
+        }
+        let delete_view = self.value.flush(batch)?;
+        if let Some(mut stored_value) = self.stored_value.take() {
+            if stored_value.variant() == 0 {
+                // Its data is deleted along with the rest of the stored variant.
+                stored_value.clear();
+                self.first_value = Some(stored_value);
+            }
+        }
+        if delete_view && variant == 0 {
+            batch.delete_key_prefix(self.context.base_key());
+            self.stored_variant = 0;
+            return Ok(true);
+        }
+        if variant != self.stored_variant {
+            // A missing index stands for the first variant.
+            let key = self.context.base_tag(MIN_VIEW_TAG);
+            if variant == 0 {
+                batch.delete_key(key);
+            } else {
+                batch.put_key_value(key, &variant)?;
+            }
+            self.stored_variant = variant;
+        }
+        Ok(false)
+    }
+}
+
+#[async_trait]
+impl<C, E> RootView<C> for EnumView<C, E>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    E: EnumVariants<C> + Send + Sync,
+{
+    async fn save(&mut self) -> Result<(), ViewError> {
+        let mut batch = Batch::new();
+        self.flush(&mut batch)?;
+        self.context.write_batch(batch).await?;
+        Ok(())
+    }
+}
+
+impl<C, E> EnumView<C, E>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    E: EnumVariants<C> + Send + Sync,
+{
+    /// Obtains a reference to the active variant.
+    pub fn get(&self) -> &E {
+        &self.value
+    }
+
+    /// Obtains a mutable reference to the active variant, to modify its subview. Use
+    /// [`EnumView::set_variant`] to switch to another variant.
+    pub fn get_mut(&mut self) -> &mut E {
+        &mut self.value
+    }
+
+    /// Switches to the variant with the given index, with an empty subview, and returns
+    /// it. Nothing changes if that variant is already active.
+    pub async fn set_variant(&mut self, variant: u8) -> Result<&mut E, ViewError> {
+        if variant == self.value.variant() {
+            return Ok(&mut self.value);
+        }
+        let mut value = match (self.stored_value.take(), self.first_value.take()) {
+            (Some(value), first_value) if variant == self.stored_variant => {
+                self.first_value = first_value;
+                value
+            }
+            (stored_value, Some(value)) if variant == 0 => {
+                self.stored_value = stored_value;
+                value
+            }
+            (stored_value, first_value) => {
+                // The subviews of the variants other than the stored one are empty.
+                self.stored_value = stored_value;
+                self.first_value = first_value;
+                let variant_context = Self::variant_context(&self.context, variant);
+                E::load_variant(variant, variant_context).await?
+            }
+        };
+        if variant == self.stored_variant {
+            value.clear();
+        }
+        let previous_value = std::mem::replace(&mut self.value, value);
+        self.set_aside(previous_value);
+        Ok(&mut self.value)
+    }
+}
+
+impl<C, E: IntrospectableView> IntrospectableView for EnumView<C, E> {
+    const LAYOUT: ViewLayout = E::LAYOUT;
+}
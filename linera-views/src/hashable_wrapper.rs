@@ -14,6 +14,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     batch::Batch,
     common::{Context, MIN_VIEW_TAG},
+    diff::{DiffableView, ViewDiff},
     views::{ClonableView, HashableView, Hasher, View, ViewError},
 };
 
@@ -40,7 +41,7 @@ impl<C, W, O> View<C> for WrappedHashableContainerView<C, W, O>
 where
     C: Context + Send + Sync,
     ViewError: From<C::Error>,
-    W: HashableView<C> + Send,
+    W: HashableView<C> + Send + Sync,
     O: Serialize + DeserializeOwned + Send + Sync + Copy + PartialEq,
     W::Hasher: Hasher<Output = O>,
 {
@@ -65,6 +66,14 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        if self.inner.has_pending_changes().await {
+            return true;
+        }
+        let hash = self.hash.lock().await;
+        self.stored_hash != *hash
+    }
+
     fn rollback(&mut self) {
         self.inner.rollback();
         *self.hash.get_mut() = self.stored_hash;
@@ -102,7 +111,7 @@ impl<C, W, O> ClonableView<C> for WrappedHashableContainerView<C, W, O>
 where
     C: Context + Send + Sync,
     ViewError: From<C::Error>,
-    W: HashableView<C> + ClonableView<C> + Send,
+    W: HashableView<C> + ClonableView<C> + Send + Sync,
     O: Serialize + DeserializeOwned + Send + Sync + Copy + PartialEq,
     W::Hasher: Hasher<Output = O>,
 {
@@ -153,6 +162,18 @@ where
     }
 }
 
+#[async_trait]
+impl<C, W, O> DiffableView<C> for WrappedHashableContainerView<C, W, O>
+where
+    C: Send + Sync,
+    W: DiffableView<C> + Sync,
+    Self: View<C> + Sync,
+{
+    async fn diff(&self, other: &Self) -> Result<Option<ViewDiff>, ViewError> {
+        self.inner.diff(&other.inner).await
+    }
+}
+
 impl<C, W, O> Deref for WrappedHashableContainerView<C, W, O> {
     type Target = W;
 
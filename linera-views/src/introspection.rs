@@ -0,0 +1,72 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{btree_map, BTreeMap};
+
+/// The storage layout of a view type, describing where each of its subviews is stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewLayout {
+    /// The name of the view type.
+    pub name: &'static str,
+    /// The subviews of the view: the fields of a struct, or the variants of an enum.
+    pub fields: &'static [FieldLayout],
+}
+
+/// The storage layout of a subview.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// The name of the field or variant.
+    pub name: &'static str,
+    /// The prefix of the keys of the subview, relative to the base key of the view.
+    pub key_prefix: &'static [u8],
+    /// The type of the subview, as written in the source code.
+    pub type_name: &'static str,
+}
+
+/// A view type whose storage layout is known statically.
+///
+/// This is implemented by the derive macros for the views annotated with
+/// `#[view(introspect)]`.
+pub trait IntrospectableView {
+    /// The storage layout of the view.
+    const LAYOUT: ViewLayout;
+}
+
+/// A registry of the layouts of view types, indexed by their names.
+///
+/// This lets tools enumerate the structure of the views of an application, e.g. for
+/// debugging or for documenting the storage layout.
+#[derive(Clone, Debug, Default)]
+pub struct ViewRegistry {
+    layouts: BTreeMap<&'static str, ViewLayout>,
+}
+
+impl ViewRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the layout of the view type `V`.
+    ///
+    /// Returns `false` if a different layout was already registered under the same name.
+    pub fn register<V: IntrospectableView>(&mut self) -> bool {
+        match self.layouts.entry(V::LAYOUT.name) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(V::LAYOUT);
+                true
+            }
+            btree_map::Entry::Occupied(entry) => *entry.get() == V::LAYOUT,
+        }
+    }
+
+    /// Returns the layout of the view type with the given name, if it was registered.
+    pub fn get(&self, name: &str) -> Option<&ViewLayout> {
+        self.layouts.get(name)
+    }
+
+    /// Returns the registered layouts, ordered by name.
+    pub fn layouts(&self) -> impl Iterator<Item = &ViewLayout> {
+        self.layouts.values()
+    }
+}
@@ -20,6 +20,7 @@
 use std::fmt::Debug;
 
 use async_trait::async_trait;
+use futures::Stream;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use static_assertions as sa;
 use thiserror::Error;
@@ -27,8 +28,8 @@ use thiserror::Error;
 use crate::{
     batch::{Batch, BatchValueWriter, DeletePrefixExpander, SimplifiedBatch},
     common::{
-        AdminKeyValueStore, KeyIterable, KeyValueStore, ReadableKeyValueStore,
-        WritableKeyValueStore, MIN_VIEW_TAG,
+        AdminKeyValueStore, CompareAndSwapKeyValueStore, KeyIterable, KeyValueStore,
+        ReadableKeyValueStore, WritableKeyValueStore, MIN_VIEW_TAG,
     },
 };
 
@@ -78,6 +79,11 @@ pub trait DirectWritableKeyValueStore<E> {
     type Batch: SimplifiedBatch + Serialize + DeserializeOwned + Default;
 
     /// Writes the batch to the database.
+    ///
+    /// A batch exceeding `MAX_BATCH_SIZE` or `MAX_BATCH_TOTAL_SIZE` may be split into
+    /// chunks with [`SimplifiedBatch::into_chunks`] and written chunk by chunk. Each chunk
+    /// is written atomically, but the batch as a whole is not: use a
+    /// [`JournalingKeyValueStore`] to write large batches atomically.
     async fn write_batch(&self, batch: Self::Batch) -> Result<(), E>;
 }
 
@@ -157,6 +163,24 @@ where
     ) -> Result<Self::KeyValues, K::Error> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), K::Error>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn health_check(&self) -> Result<(), K::Error> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, K::Error> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl<K> AdminKeyValueStore for JournalingKeyValueStore<K>
@@ -220,6 +244,22 @@ where
     }
 }
 
+/// The compare-and-swap operations write a single key directly to the inner store,
+/// bypassing the journal.
+impl<K> CompareAndSwapKeyValueStore<K::Error> for JournalingKeyValueStore<K>
+where
+    K: DirectKeyValueStore + CompareAndSwapKeyValueStore<K::Error> + Send + Sync,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, K::Error> {
+        self.store.compare_and_swap(key, expected, new).await
+    }
+}
+
 impl<K> KeyValueStore for JournalingKeyValueStore<K>
 where
     K: DirectKeyValueStore + Send + Sync,
@@ -181,6 +181,19 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        if self.delete_storage_first
+            || !self.updates.is_empty()
+            || !self.deleted_prefixes.is_empty()
+            || self.stored_total_size != self.total_size
+            || self.sizes.has_pending_changes().await
+        {
+            return true;
+        }
+        let hash = self.hash.lock().await;
+        self.stored_hash != *hash
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.updates.clear();
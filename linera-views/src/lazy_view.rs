@@ -0,0 +1,208 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+
+use crate::{
+    batch::Batch,
+    common::Context,
+    diff::{DiffableView, ViewDiff},
+    views::{ClonableView, HashableView, Hasher, View, ViewError},
+};
+
+/// The state of the subview of a [`LazyView`].
+#[derive(Debug)]
+enum LazyState<W> {
+    /// The subview has not been loaded, and is unchanged.
+    Unloaded,
+    /// The subview has been cleared without being loaded.
+    Cleared,
+    /// The subview has been loaded.
+    Loaded(W),
+}
+
+/// A view wrapping a subview `W` that is only loaded from storage when it is first
+/// accessed, with [`LazyView::get_mut`].
+///
+/// The subview is stored under the same base key as it would be without the wrapper,
+/// so a field can be made lazy without migrating its data.
+///
+/// Until it is loaded, the subview has no pending changes and saving the view leaves it
+/// untouched in storage. Clearing an unloaded subview does not load it: the whole key
+/// prefix of the subview is deleted on the next flush, and the subview is cleared if it
+/// is loaded before that.
+#[derive(Debug)]
+pub struct LazyView<C, W> {
+    context: C,
+    state: LazyState<W>,
+}
+
+#[async_trait]
+impl<C, W> View<C> for LazyView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: View<C> + Send + Sync,
+{
+    fn context(&self) -> &C {
+        &self.context
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        Ok(Self {
+            context,
+            state: LazyState::Unloaded,
+        })
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        match &self.state {
+            LazyState::Unloaded => false,
+            LazyState::Cleared => true,
+            LazyState::Loaded(view) => view.has_pending_changes().await,
+        }
+    }
+
+    fn rollback(&mut self) {
+        match &mut self.state {
+            LazyState::Unloaded => {}
+            LazyState::Cleared => self.state = LazyState::Unloaded,
+            LazyState::Loaded(view) => view.rollback(),
+        }
+    }
+
+    fn clear(&mut self) {
+        match &mut self.state {
+            LazyState::Loaded(view) => view.clear(),
+            state => *state = LazyState::Cleared,
+        }
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        match &mut self.state {
+            LazyState::Unloaded => Ok(false),
+            LazyState::Cleared => {
+                batch.delete_key_prefix(self.context.base_key());
+                self.state = LazyState::Unloaded;
+                Ok(true)
+            }
+            LazyState::Loaded(view) => view.flush(batch),
+        }
+    }
+}
+
+impl<C, W> LazyView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: View<C> + Send + Sync,
+{
+    /// Returns `true` if the subview has been loaded from storage.
+    pub fn is_loaded(&self) -> bool {
+        matches!(self.state, LazyState::Loaded(_))
+    }
+
+    /// Loads a copy of the subview from storage, cleared if this view has been cleared.
+    async fn load_subview(&self) -> Result<W, ViewError> {
+        let mut view = W::load(self.context.clone()).await?;
+        if matches!(self.state, LazyState::Cleared) {
+            view.clear();
+        }
+        Ok(view)
+    }
+
+    /// Obtains a mutable reference to the subview, loading it on first access.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::lazy_view::LazyView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut view = LazyView::<_, RegisterView<_, u32>>::load(context).await.unwrap();
+    ///   assert!(!view.is_loaded());
+    ///   view.get_mut().await.unwrap().set(5);
+    ///   assert!(view.is_loaded());
+    ///   assert_eq!(*view.get_mut().await.unwrap().get(), 5);
+    /// # })
+    /// ```
+    pub async fn get_mut(&mut self) -> Result<&mut W, ViewError> {
+        if !self.is_loaded() {
+            let view = self.load_subview().await?;
+            self.state = LazyState::Loaded(view);
+        }
+        match &mut self.state {
+            LazyState::Loaded(view) => Ok(view),
+            LazyState::Unloaded | LazyState::Cleared => unreachable!("the subview was loaded"),
+        }
+    }
+}
+
+impl<C, W> ClonableView<C> for LazyView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: ClonableView<C> + Send + Sync,
+{
+    fn clone_unchecked(&mut self) -> Result<Self, ViewError> {
+        let state = match &mut self.state {
+            LazyState::Unloaded => LazyState::Unloaded,
+            LazyState::Cleared => LazyState::Cleared,
+            LazyState::Loaded(view) => LazyState::Loaded(view.clone_unchecked()?),
+        };
+        Ok(LazyView {
+            context: self.context.clone(),
+            state,
+        })
+    }
+}
+
+#[async_trait]
+impl<C, W> HashableView<C> for LazyView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: HashableView<C> + Send + Sync,
+{
+    type Hasher = W::Hasher;
+
+    async fn hash_mut(&mut self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        self.get_mut().await?.hash_mut().await
+    }
+
+    /// Hashes the subview, loading a temporary copy of it if it has not been loaded.
+    async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        match &self.state {
+            LazyState::Loaded(view) => view.hash().await,
+            LazyState::Unloaded | LazyState::Cleared => self.load_subview().await?.hash().await,
+        }
+    }
+}
+
+#[async_trait]
+impl<C, W> DiffableView<C> for LazyView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: DiffableView<C> + Send + Sync,
+{
+    /// Compares the subviews, loading temporary copies of those that have not been loaded.
+    async fn diff(&self, other: &Self) -> Result<Option<ViewDiff>, ViewError> {
+        let (view_copy, other_copy);
+        let view = match &self.state {
+            LazyState::Loaded(view) => view,
+            LazyState::Unloaded | LazyState::Cleared => {
+                view_copy = self.load_subview().await?;
+                &view_copy
+            }
+        };
+        let other = match &other.state {
+            LazyState::Loaded(other) => other,
+            LazyState::Unloaded | LazyState::Cleared => {
+                other_copy = other.load_subview().await?;
+                &other_copy
+            }
+        };
+        view.diff(other).await
+    }
+}
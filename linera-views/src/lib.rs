@@ -51,6 +51,7 @@ The following views implement the `View` trait:
 * `SetView` implements a set with keys.
 * `CollectionView` implements a map whose values are views themselves.
 * `ReentrantCollectionView` implements a map for which different keys can be accessed independently.
+* `LazyView` implements a subview that is only loaded from storage when it is first accessed.
 * `ViewContainer<C>` implements a `KeyValueStore` and is used internally.
 
 The `LogView` can be seen as an analog of `VecDeque` while `MapView` is an analog of `BTreeMap`.
@@ -68,6 +69,8 @@ use {
 /// The definition of the batches for writing in the database.
 pub mod batch;
 
+pub mod bench;
+
 /// The definitions used for the `KeyValueStore` and `Context`.
 pub mod common;
 
@@ -84,6 +87,15 @@ pub mod value_splitting;
 /// The definition of the `View` and related traits.
 pub mod views;
 
+/// The encodings of the values stored by views.
+pub mod codec;
+
+/// The comparison of two instances of a view.
+pub mod diff;
+
+/// The description of the storage layout of views, for runtime introspection.
+pub mod introspection;
+
 /// The `RegisterView` implements a register for a single value.
 pub mod register_view;
 
@@ -96,6 +108,15 @@ pub mod queue_view;
 /// The `MapView` implements a map with ordered keys.
 pub mod map_view;
 
+/// The `LazyView` implements a subview that is only loaded when it is first accessed.
+pub mod lazy_view;
+
+/// The `EnumView` implements a view storing one of the variants of an enum of subviews.
+pub mod enum_view;
+
+/// The `OptionalView` implements a subview that may be absent.
+pub mod optional_view;
+
 /// The `SetView` implements a set with ordered entries.
 pub mod set_view;
 
@@ -110,6 +131,24 @@ pub mod memory;
 /// The LRU (least recently used) caching.
 pub mod lru_caching;
 
+/// The encryption of the values of subviews marked with `#[view(encrypted)]`.
+#[cfg(with_encryption)]
+pub mod encryption;
+
+/// The code for retrying the failed operations of a key-value store.
+#[cfg(any(web, not(target_arch = "wasm32")))]
+pub mod retrying;
+
+/// A key-value store that shards its keys across several stores.
+pub mod sharded;
+
+/// A key-value store that prefixes its keys, so that several stores can share a backend.
+pub mod namespaced;
+
+/// A key-value store that writes to a primary store and reads from replicas.
+#[cfg(any(web, not(target_arch = "wasm32")))]
+pub mod replicated;
+
 /// The `ReentrantCollectionView` implements a map structure whose keys are ordered and the values are views with concurrent access.
 pub mod reentrant_collection_view;
 
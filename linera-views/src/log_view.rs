@@ -77,6 +77,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || !self.new_values.is_empty()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.new_values.clear();
@@ -10,6 +10,7 @@ use std::{
 };
 
 use async_lock::Mutex;
+use futures::Stream;
 use linked_hash_map::LinkedHashMap;
 #[cfg(with_testing)]
 use {
@@ -25,7 +26,10 @@ use {
 
 use crate::{
     batch::{Batch, WriteOperation},
-    common::{get_interval, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore},
+    common::{
+        get_interval, CompareAndSwapKeyValueStore, KeyValueStore, MaintenanceOp,
+        ReadableKeyValueStore, WritableKeyValueStore,
+    },
 };
 
 #[cfg(with_metrics)]
@@ -96,6 +100,13 @@ impl<'a> LruPrefixCache {
         }
     }
 
+    /// Removes the entry of the key, if any.
+    pub fn remove(&mut self, key: &[u8]) {
+        if self.map.remove(key).is_some() {
+            self.queue.remove(key);
+        }
+    }
+
     /// Gets the entry from the key.
     pub fn query(&'a self, key: &'a [u8]) -> Option<&'a Option<Vec<u8>>> {
         self.map.get(key)
@@ -205,6 +216,24 @@ where
     ) -> Result<Self::KeyValues, K::Error> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), K::Error>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn health_check(&self) -> Result<(), K::Error> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, K::Error> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl<K> WritableKeyValueStore<K::Error> for LruCachingStore<K>
@@ -240,6 +269,35 @@ where
     async fn clear_journal(&self, base_key: &[u8]) -> Result<(), K::Error> {
         self.store.clear_journal(base_key).await
     }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), K::Error> {
+        self.store.maintenance(op).await
+    }
+}
+
+impl<K> CompareAndSwapKeyValueStore<K::Error> for LruCachingStore<K>
+where
+    K: KeyValueStore + CompareAndSwapKeyValueStore<K::Error> + Send + Sync,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, K::Error> {
+        // The comparison cannot use the cache, since other clients may have changed the
+        // value in the meantime.
+        let swapped = self.store.compare_and_swap(key, expected, new).await?;
+        if let Some(lru_read_values) = &self.lru_read_values {
+            let mut lru_read_values = lru_read_values.lock().await;
+            if swapped {
+                lru_read_values.insert(key.to_vec(), Some(new.to_vec()));
+            } else {
+                lru_read_values.remove(key);
+            }
+        }
+        Ok(swapped)
+    }
 }
 
 impl<K> KeyValueStore for LruCachingStore<K>
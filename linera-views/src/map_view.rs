@@ -87,6 +87,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || !self.updates.is_empty() || !self.deleted_prefixes.is_empty()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.updates.clear();
@@ -810,6 +814,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.map.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.map.rollback()
     }
@@ -1224,6 +1232,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.map.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.map.rollback()
     }
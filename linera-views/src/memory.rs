@@ -10,9 +10,12 @@ use thiserror::Error;
 use crate::{
     batch::{Batch, DeletePrefixExpander, WriteOperation},
     common::{
-        get_interval, AdminKeyValueStore, CommonStoreConfig, Context, ContextFromStore,
-        KeyIterable, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+        check_disjoint_prefixes, get_interval, AdminKeyValueStore, CommonStoreConfig,
+        CompareAndSwapKeyValueStore, Context, ContextFromStore, KeyIterable, KeyValueStore,
+        ReadableKeyValueStore, WritableKeyValueStore,
     },
+    namespaced::NamespacedStoreError,
+    sharded::ShardedStoreError,
     value_splitting::DatabaseConsistencyError,
     views::ViewError,
 };
@@ -143,6 +146,22 @@ impl WritableKeyValueStore<MemoryContextError> for MemoryStore {
     }
 }
 
+impl CompareAndSwapKeyValueStore<MemoryContextError> for MemoryStore {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, MemoryContextError> {
+        let mut map = self.map.write().await;
+        if map.get(key).map(Vec::as_slice) != expected {
+            return Ok(false);
+        }
+        map.insert(key.to_vec(), new.to_vec());
+        Ok(true)
+    }
+}
+
 impl AdminKeyValueStore for MemoryStore {
     type Error = MemoryContextError;
     type Config = MemoryStoreConfig;
@@ -179,6 +198,41 @@ impl AdminKeyValueStore for MemoryStore {
 
 impl KeyValueStore for MemoryStore {
     type Error = MemoryContextError;
+
+    /// Swaps the keys under the lock of the map, so that no other operation sees them
+    /// half-swapped.
+    async fn swap_prefixes(
+        &self,
+        prefix1: &[u8],
+        prefix2: &[u8],
+    ) -> Result<(), MemoryContextError> {
+        if prefix1 == prefix2 {
+            return Ok(());
+        }
+        check_disjoint_prefixes(prefix1, prefix2)?;
+        let mut map = self.map.write().await;
+        let mut take_prefix = |prefix: &[u8]| {
+            let keys = map
+                .range(get_interval(prefix.to_vec()))
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>();
+            keys.into_iter()
+                .map(|key| {
+                    let value = map.remove(&key).expect("the key was just found");
+                    (key[prefix.len()..].to_vec(), value)
+                })
+                .collect::<Vec<_>>()
+        };
+        let key_values1 = take_prefix(prefix1);
+        let key_values2 = take_prefix(prefix2);
+        for (suffix, value) in key_values1 {
+            map.insert([prefix2, &suffix].concat(), value);
+        }
+        for (suffix, value) in key_values2 {
+            map.insert([prefix1, &suffix].concat(), value);
+        }
+        Ok(())
+    }
 }
 
 /// An implementation of [`crate::common::Context`] that stores all values in memory.
@@ -248,6 +302,14 @@ pub enum MemoryContextError {
     /// The database is not consistent
     #[error(transparent)]
     DatabaseConsistencyError(#[from] DatabaseConsistencyError),
+
+    /// The configuration of a sharded store is invalid
+    #[error(transparent)]
+    ShardedStoreError(#[from] ShardedStoreError),
+
+    /// The configuration of a namespaced store is invalid
+    #[error(transparent)]
+    NamespacedStoreError(#[from] NamespacedStoreError),
 }
 
 impl From<MemoryContextError> for ViewError {
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use convert_case::{Case, Casing};
+use futures::Stream;
 use linera_base::{
     prometheus_util::{register_histogram_vec, MeasureLatency},
     sync::Lazy,
@@ -10,7 +11,10 @@ use prometheus::HistogramVec;
 
 use crate::{
     batch::Batch,
-    common::{KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore},
+    common::{
+        CompareAndSwapKeyValueStore, KeyValueStore, MaintenanceOp, ReadableKeyValueStore,
+        WritableKeyValueStore,
+    },
 };
 
 #[derive(Clone)]
@@ -23,6 +27,7 @@ pub struct KeyValueStoreMetrics {
     find_key_values_by_prefix: HistogramVec,
     write_batch: HistogramVec,
     clear_journal: HistogramVec,
+    compare_and_swap: HistogramVec,
 }
 
 /// The metrics for the "rocks db"
@@ -94,6 +99,12 @@ impl KeyValueStoreMetrics {
         let clear_journal = register_histogram_vec(&clear_journal1, &clear_journal2, &[], None)
             .expect("Counter creation should not fail");
 
+        let compare_and_swap1 = format!("{}_compare_and_swap", var_name);
+        let compare_and_swap2 = format!("{} compare and swap", title_name);
+        let compare_and_swap =
+            register_histogram_vec(&compare_and_swap1, &compare_and_swap2, &[], None)
+                .expect("Counter creation should not fail");
+
         KeyValueStoreMetrics {
             read_value_bytes,
             contains_key,
@@ -102,6 +113,7 @@ impl KeyValueStoreMetrics {
             find_key_values_by_prefix,
             write_batch,
             clear_journal,
+            compare_and_swap,
         }
     }
 }
@@ -151,6 +163,24 @@ where
         let _metric = self.counter.find_key_values_by_prefix.measure_latency();
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), E>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn health_check(&self) -> Result<(), E> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, E> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl<K, E> WritableKeyValueStore<E> for MeteredStore<K>
@@ -168,6 +198,25 @@ where
         let _metric = self.counter.clear_journal.measure_latency();
         self.store.clear_journal(base_key).await
     }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), E> {
+        self.store.maintenance(op).await
+    }
+}
+
+impl<K, E> CompareAndSwapKeyValueStore<E> for MeteredStore<K>
+where
+    K: CompareAndSwapKeyValueStore<E> + Send + Sync,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, E> {
+        let _metric = self.counter.compare_and_swap.measure_latency();
+        self.store.compare_and_swap(key, expected, new).await
+    }
 }
 
 impl<K> KeyValueStore for MeteredStore<K>
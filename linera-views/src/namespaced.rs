@@ -0,0 +1,319 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use thiserror::Error;
+
+#[cfg(with_testing)]
+use crate::memory::{create_memory_store, MemoryStore};
+use crate::{
+    batch::{Batch, WriteOperation},
+    common::{
+        AdminKeyValueStore, CompareAndSwapKeyValueStore, KeyValueStore, MaintenanceOp,
+        ReadableKeyValueStore, WritableKeyValueStore,
+    },
+};
+
+/// The maximal length of the prefix of a [`NamespacedStore`].
+pub const MAX_PREFIX_SIZE: usize = 32;
+
+/// The configuration of a [`NamespacedStore`].
+#[derive(Clone, Debug)]
+pub struct NamespacedStoreConfig<C> {
+    /// The configuration of the inner store.
+    pub inner: C,
+    /// The prefix of all the keys of the store, of at most [`MAX_PREFIX_SIZE`] bytes.
+    pub prefix: Vec<u8>,
+}
+
+/// The errors specific to a [`NamespacedStore`].
+#[derive(Debug, Error)]
+pub enum NamespacedStoreError {
+    /// The prefix is longer than [`MAX_PREFIX_SIZE`].
+    #[error("The prefix of a namespaced store has {0} bytes, more than {MAX_PREFIX_SIZE}")]
+    PrefixTooLong(usize),
+}
+
+/// A key-value store that prepends a prefix to all of its keys, so that several logical
+/// stores can share a backend without their keys colliding.
+///
+/// Prefix searches are scoped to the prefix, and the prefix is not included in the keys
+/// they return. The prefixes of stores sharing a backend must not be prefixes of each
+/// other.
+///
+/// The inner store limits the size of the keys including the prefix. Since the prefix is
+/// only known at runtime, the keys of this store are limited to the size allowed by the
+/// inner store minus [`MAX_PREFIX_SIZE`], whatever the actual length of the prefix.
+#[derive(Clone)]
+pub struct NamespacedStore<S> {
+    /// The store holding the keys.
+    pub store: S,
+    prefix: Vec<u8>,
+}
+
+impl<S> NamespacedStore<S> {
+    /// Creates a new store prepending `prefix` to the keys of `store`.
+    ///
+    /// [`NamespacedStore::connect`] returns an error instead for a prefix that is too long.
+    ///
+    /// # Panics
+    ///
+    /// If `prefix` is longer than [`MAX_PREFIX_SIZE`].
+    pub fn new(store: S, prefix: Vec<u8>) -> Self {
+        assert!(
+            prefix.len() <= MAX_PREFIX_SIZE,
+            "The prefix of a namespaced store must have at most {MAX_PREFIX_SIZE} bytes"
+        );
+        Self { store, prefix }
+    }
+
+    /// Returns the prefix of all the keys of the store.
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Returns `key` with the prefix of the store prepended.
+    fn prefixed(&self, key: &[u8]) -> Vec<u8> {
+        let mut prefixed = Vec::with_capacity(self.prefix.len() + key.len());
+        prefixed.extend_from_slice(&self.prefix);
+        prefixed.extend_from_slice(key);
+        prefixed
+    }
+}
+
+impl<S> ReadableKeyValueStore<S::Error> for NamespacedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE.saturating_sub(MAX_PREFIX_SIZE);
+    type Keys = S::Keys;
+    type KeyValues = S::KeyValues;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, S::Error> {
+        self.store.read_value_bytes(&self.prefixed(key)).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, S::Error> {
+        self.store.contains_key(&self.prefixed(key)).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, S::Error> {
+        let keys = keys.iter().map(|key| self.prefixed(key)).collect();
+        self.store.read_multi_values_bytes(keys).await
+    }
+
+    // The inner store strips the whole searched prefix, including the prefix of this
+    // store, from the keys it returns.
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, S::Error> {
+        self.store
+            .find_keys_by_prefix(&self.prefixed(key_prefix))
+            .await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, S::Error> {
+        self.store
+            .find_key_values_by_prefix(&self.prefixed(key_prefix))
+            .await
+    }
+
+    async fn health_check(&self) -> Result<(), S::Error> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, S::Error> {
+        self.store
+            .approximate_key_count(&self.prefixed(key_prefix))
+            .await
+    }
+}
+
+impl<S> WritableKeyValueStore<S::Error> for NamespacedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), S::Error> {
+        let operations = batch
+            .operations
+            .into_iter()
+            .map(|operation| match operation {
+                WriteOperation::Delete { key } => WriteOperation::Delete {
+                    key: self.prefixed(&key),
+                },
+                WriteOperation::DeletePrefix { key_prefix } => WriteOperation::DeletePrefix {
+                    key_prefix: self.prefixed(&key_prefix),
+                },
+                WriteOperation::Put { key, value } => WriteOperation::Put {
+                    key: self.prefixed(&key),
+                    value,
+                },
+            })
+            .collect();
+        // The journal of the inner store is kept under the prefix too.
+        self.store
+            .write_batch(Batch { operations }, &self.prefixed(base_key))
+            .await
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), S::Error> {
+        self.store.clear_journal(&self.prefixed(base_key)).await
+    }
+
+    /// Runs the maintenance of the whole inner store, since the backends don't maintain
+    /// key ranges separately.
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), S::Error> {
+        self.store.maintenance(op).await
+    }
+}
+
+impl<S> CompareAndSwapKeyValueStore<S::Error> for NamespacedStore<S>
+where
+    S: KeyValueStore + CompareAndSwapKeyValueStore<<S as KeyValueStore>::Error> + Send + Sync,
+    S::Error: Send,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, S::Error> {
+        self.store
+            .compare_and_swap(&self.prefixed(key), expected, new)
+            .await
+    }
+}
+
+impl<S> KeyValueStore for NamespacedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    type Error = S::Error;
+}
+
+/// The namespaces of the inner store are shared with the stores using other prefixes:
+/// creating a namespace that exists succeeds, and deleting a namespace only deletes the
+/// keys with the prefix of this store.
+impl<S> AdminKeyValueStore for NamespacedStore<S>
+where
+    S: AdminKeyValueStore + KeyValueStore<Error = <S as AdminKeyValueStore>::Error> + Send + Sync,
+    <S as AdminKeyValueStore>::Error: Send + From<NamespacedStoreError>,
+{
+    type Error = <S as AdminKeyValueStore>::Error;
+    type Config = NamespacedStoreConfig<S::Config>;
+
+    async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, Self::Error> {
+        let length = config.prefix.len();
+        if length > MAX_PREFIX_SIZE {
+            return Err(NamespacedStoreError::PrefixTooLong(length).into());
+        }
+        let store = S::connect(&config.inner, namespace).await?;
+        Ok(Self::new(store, config.prefix.clone()))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        S::list_all(&config.inner).await
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        S::exists(&config.inner, namespace).await
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        if !S::exists(&config.inner, namespace).await? {
+            S::create(&config.inner, namespace).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        if !S::exists(&config.inner, namespace).await? {
+            return Ok(());
+        }
+        let store = Self::connect(config, namespace).await?;
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(Vec::new());
+        store.write_batch(batch, &[]).await
+    }
+}
+
+/// Creates a store prepending `prefix` to the keys of a memory store, for tests.
+#[cfg(with_testing)]
+pub fn create_namespaced_memory_store(prefix: Vec<u8>) -> NamespacedStore<MemoryStore> {
+    NamespacedStore::new(create_memory_store(), prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        batch::Batch,
+        common::{AdminKeyValueStore as _, ReadableKeyValueStore, WritableKeyValueStore as _},
+        memory::{create_memory_store, MemoryContextError, MemoryStore, MemoryStoreConfig},
+        namespaced::{
+            NamespacedStore, NamespacedStoreConfig, NamespacedStoreError, MAX_PREFIX_SIZE,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_namespaced_stores_sharing_a_backend_are_isolated() {
+        let backend = create_memory_store();
+        let store1 = NamespacedStore::new(backend.clone(), vec![1]);
+        let store2 = NamespacedStore::new(backend.clone(), vec![2]);
+        for (store, value) in [(&store1, 1), (&store2, 2)] {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0, 0], vec![value]);
+            batch.put_key_value_bytes(vec![0, 1], vec![value]);
+            store.write_batch(batch, &[]).await.unwrap();
+        }
+        assert_eq!(
+            store1.read_value_bytes(&[0, 0]).await.unwrap(),
+            Some(vec![1])
+        );
+        assert_eq!(
+            store2.read_value_bytes(&[0, 0]).await.unwrap(),
+            Some(vec![2])
+        );
+        assert_eq!(
+            store1.find_keys_by_prefix(&[0]).await.unwrap(),
+            vec![vec![0], vec![1]]
+        );
+        assert!(backend.contains_key(&[2, 0, 1]).await.unwrap());
+
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(vec![0]);
+        store1.write_batch(batch, &[]).await.unwrap();
+        assert!(store1.find_keys_by_prefix(&[]).await.unwrap().is_empty());
+        assert_eq!(store2.find_keys_by_prefix(&[]).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_store_bounds_the_prefix() {
+        assert_eq!(
+            <NamespacedStore<MemoryStore> as ReadableKeyValueStore<_>>::MAX_KEY_SIZE,
+            <MemoryStore as ReadableKeyValueStore<_>>::MAX_KEY_SIZE - MAX_PREFIX_SIZE
+        );
+        let config = NamespacedStoreConfig {
+            inner: MemoryStoreConfig::new(1),
+            prefix: vec![0; MAX_PREFIX_SIZE + 1],
+        };
+        let result = NamespacedStore::<MemoryStore>::connect(&config, "namespace").await;
+        assert!(matches!(
+            result,
+            Err(MemoryContextError::NamespacedStoreError(
+                NamespacedStoreError::PrefixTooLong(length)
+            )) if length == MAX_PREFIX_SIZE + 1
+        ));
+    }
+}
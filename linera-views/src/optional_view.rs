@@ -0,0 +1,242 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::Write;
+
+use async_trait::async_trait;
+
+use crate::{
+    batch::Batch,
+    common::{Context, MIN_VIEW_TAG},
+    diff::{DiffableView, ViewDiff},
+    views::{ClonableView, HashableView, Hasher, View, ViewError},
+};
+
+/// A view holding a subview `W` that may be absent, e.g. for an optional part of the
+/// state of an application. An absent subview is never loaded.
+///
+/// Relative to the base key of the view, an empty value under the tag `MIN_VIEW_TAG`
+/// marks that the subview is present, and the subview itself is stored under the tag
+/// `MIN_VIEW_TAG + 1`. Removing the subview removes all its data on the next flush.
+#[derive(Debug)]
+pub struct OptionalView<C, W> {
+    context: C,
+    /// Whether the presence marker is in storage.
+    stored: bool,
+    /// The subview, if it is present.
+    view: Option<W>,
+    /// The subview in storage, after it was removed, so that it can be restored.
+    stored_view: Option<W>,
+}
+
+impl<C: Context, W> OptionalView<C, W> {
+    /// Returns the context of the subview.
+    fn subview_context(&self) -> C {
+        self.context
+            .clone_with_base_key(self.context.base_tag(MIN_VIEW_TAG + 1))
+    }
+}
+
+#[async_trait]
+impl<C, W> View<C> for OptionalView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: View<C> + Send + Sync,
+{
+    fn context(&self) -> &C {
+        &self.context
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        let stored = context
+            .contains_key(&context.base_tag(MIN_VIEW_TAG))
+            .await?;
+        let mut view = Self {
+            context,
+            stored,
+            view: None,
+            stored_view: None,
+        };
+        if stored {
+            view.view = Some(W::load(view.subview_context()).await?);
+        }
+        Ok(view)
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        match &self.view {
+            Some(view) => !self.stored || view.has_pending_changes().await,
+            None => self.stored,
+        }
+    }
+
+    fn rollback(&mut self) {
+        if let Some(view) = self.stored_view.take() {
+            self.view = Some(view);
+        } else if !self.stored {
+            self.view = None;
+        }
+        if let Some(view) = &mut self.view {
+            view.rollback();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.remove();
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        self.stored_view = None;
+        match &mut self.view {
+            Some(view) => {
+                view.flush(batch)?;
+                if !self.stored {
+                    batch.put_key_value_bytes(self.context.base_tag(MIN_VIEW_TAG), Vec::new());
+                    self.stored = true;
+                }
+                Ok(false)
+            }
+            None => {
+                if self.stored {
+                    batch.delete_key_prefix(self.context.base_key());
+                    self.stored = false;
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl<C, W> OptionalView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: View<C> + Send + Sync,
+{
+    /// Obtains a reference to the subview, if it is present.
+    pub fn get(&self) -> Option<&W> {
+        self.view.as_ref()
+    }
+
+    /// Obtains a mutable reference to the subview, if it is present.
+    pub fn get_mut(&mut self) -> Option<&mut W> {
+        self.view.as_mut()
+    }
+
+    /// Removes the subview. Its data is removed from storage on the next flush.
+    pub fn remove(&mut self) {
+        let view = self.view.take();
+        if self.stored && view.is_some() {
+            self.stored_view = view;
+        }
+    }
+
+    /// Obtains a mutable reference to the subview, inserting an empty one if it is
+    /// absent.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::memory::create_memory_context;
+    /// # use linera_views::optional_view::OptionalView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use crate::linera_views::views::View;
+    /// # let context = create_memory_context();
+    ///   let mut view = OptionalView::<_, RegisterView<_, u32>>::load(context).await.unwrap();
+    ///   assert!(view.get().is_none());
+    ///   view.get_or_insert().await.unwrap().set(5);
+    ///   assert_eq!(view.get().map(|register| *register.get()), Some(5));
+    /// # })
+    /// ```
+    pub async fn get_or_insert(&mut self) -> Result<&mut W, ViewError> {
+        if self.view.is_none() {
+            let view = match self.stored_view.take() {
+                Some(mut view) => {
+                    view.clear();
+                    view
+                }
+                // A view can only be created by loading it, but this reads nothing:
+                // the subview has no data in storage when the marker is absent.
+                None => W::load(self.subview_context()).await?,
+            };
+            self.view = Some(view);
+        }
+        match &mut self.view {
+            Some(view) => Ok(view),
+            None => unreachable!("the subview was inserted"),
+        }
+    }
+}
+
+impl<C, W> ClonableView<C> for OptionalView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: ClonableView<C> + Send + Sync,
+{
+    fn clone_unchecked(&mut self) -> Result<Self, ViewError> {
+        Ok(OptionalView {
+            context: self.context.clone(),
+            stored: self.stored,
+            view: self.view.as_mut().map(W::clone_unchecked).transpose()?,
+            stored_view: self
+                .stored_view
+                .as_mut()
+                .map(W::clone_unchecked)
+                .transpose()?,
+        })
+    }
+}
+
+#[async_trait]
+impl<C, W> HashableView<C> for OptionalView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: HashableView<C> + Send + Sync,
+{
+    type Hasher = W::Hasher;
+
+    /// Hashes an absent subview as a single zero byte, and a present one as a byte one
+    /// followed by its hash.
+    async fn hash_mut(&mut self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        let mut hasher = Self::Hasher::default();
+        match &mut self.view {
+            Some(view) => {
+                hasher.write_all(&[1])?;
+                hasher.write_all(view.hash_mut().await?.as_ref())?;
+            }
+            None => hasher.write_all(&[0])?,
+        }
+        Ok(hasher.finalize())
+    }
+
+    async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        let mut hasher = Self::Hasher::default();
+        match &self.view {
+            Some(view) => {
+                hasher.write_all(&[1])?;
+                hasher.write_all(view.hash().await?.as_ref())?;
+            }
+            None => hasher.write_all(&[0])?,
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+#[async_trait]
+impl<C, W> DiffableView<C> for OptionalView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: DiffableView<C> + Send + Sync,
+{
+    /// Compares the subviews. A subview that is present in only one of the views is
+    /// reported as changed.
+    async fn diff(&self, other: &Self) -> Result<Option<ViewDiff>, ViewError> {
+        match (&self.view, &other.view) {
+            (Some(view), Some(other)) => view.diff(other).await,
+            (None, None) => Ok(None),
+            (Some(_), None) | (None, Some(_)) => Ok(Some(ViewDiff::Changed)),
+        }
+    }
+}
@@ -80,6 +80,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || self.front_delete_count > 0 || !self.new_back_values.is_empty()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.front_delete_count = 0;
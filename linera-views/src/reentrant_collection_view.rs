@@ -14,6 +14,7 @@ use std::{
 use async_lock::{Mutex, RwLock, RwLockReadGuardArc, RwLockWriteGuardArc};
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
+use tokio::task::JoinSet;
 #[cfg(with_metrics)]
 use {
     linera_base::prometheus_util::{self, MeasureLatency},
@@ -114,6 +115,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || !self.updates.lock().await.is_empty()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.updates.get_mut().clear();
@@ -458,6 +463,28 @@ where
     ViewError: From<C::Error>,
     W: View<C> + Send + Sync + 'static,
 {
+    /// Loads the subviews with the given short keys in parallel.
+    ///
+    /// The loads still running are aborted if the returned future is dropped.
+    async fn load_views(
+        context: &C,
+        short_keys: impl Iterator<Item = &Vec<u8>>,
+    ) -> Result<Vec<W>, ViewError> {
+        let mut tasks = JoinSet::new();
+        for (index, short_key) in short_keys.enumerate() {
+            let key = context.base_tag_index(KeyTag::Subview as u8, short_key);
+            let context = context.clone_with_base_key(key);
+            tasks.spawn(async move { (index, W::load(context).await) });
+        }
+        let mut views = Vec::with_capacity(tasks.len());
+        views.resize_with(tasks.len(), || None);
+        while let Some(result) = tasks.join_next().await {
+            let (index, view) = result?;
+            views[index] = Some(view?);
+        }
+        Ok(views.into_iter().flatten().collect())
+    }
+
     /// Load multiple entries for writing at once.
     /// The entries in short_keys have to be all distinct.
     /// ```rust
@@ -499,20 +526,12 @@ where
                 }
             }
         }
-        let mut handles = Vec::new();
-        for short_key in &selected_short_keys {
-            let short_key = short_key.0.clone();
-            let context = self.context.clone();
-            handles.push(tokio::spawn(async move {
-                let key = context.base_tag_index(KeyTag::Subview as u8, &short_key);
-                let context = context.clone_with_base_key(key);
-                W::load(context).await
-            }));
-        }
-        let response = futures::future::join_all(handles).await;
-        for (i, view) in response.into_iter().enumerate() {
-            let (short_key, to_be_cleared) = &selected_short_keys[i];
-            let mut view = view??;
+        let views = Self::load_views(
+            &self.context,
+            selected_short_keys.iter().map(|(short_key, _)| short_key),
+        )
+        .await?;
+        for (mut view, (short_key, to_be_cleared)) in views.into_iter().zip(&selected_short_keys) {
             if *to_be_cleared {
                 view.clear();
             }
@@ -577,20 +596,12 @@ where
                 }
             }
         }
-        let mut handles = Vec::new();
-        for short_key in &selected_short_keys {
-            let short_key = short_key.0.clone();
-            let context = self.context.clone();
-            handles.push(tokio::spawn(async move {
-                let key = context.base_tag_index(KeyTag::Subview as u8, &short_key);
-                let context = context.clone_with_base_key(key);
-                W::load(context).await
-            }));
-        }
-        let response = futures::future::join_all(handles).await;
-        for (i, view) in response.into_iter().enumerate() {
-            let (short_key, to_be_cleared) = &selected_short_keys[i];
-            let mut view = view??;
+        let views = Self::load_views(
+            &self.context,
+            selected_short_keys.iter().map(|(short_key, _)| short_key),
+        )
+        .await?;
+        for (mut view, (short_key, to_be_cleared)) in views.into_iter().zip(&selected_short_keys) {
             if *to_be_cleared {
                 view.clear();
             }
@@ -847,6 +858,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.collection.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.collection.rollback()
     }
@@ -1273,6 +1288,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.collection.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.collection.rollback()
     }
@@ -65,6 +65,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || self.update.is_some()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.update = None;
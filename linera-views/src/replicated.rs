@@ -0,0 +1,328 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures::future;
+use linera_base::time::{Duration, Instant};
+
+use crate::{
+    batch::Batch,
+    common::{
+        AdminKeyValueStore, CompareAndSwapKeyValueStore, KeyValueStore, MaintenanceOp,
+        ReadableKeyValueStore, WritableKeyValueStore,
+    },
+};
+
+/// How a [`ReplicatedStore`] chooses between its primary and its replicas for reads.
+#[derive(Clone, Copy, Debug)]
+pub struct ReplicationPolicy {
+    /// How long the replicas may take to catch up with a write to the primary. Reads are
+    /// sent to the primary until this long after the last write made through the store.
+    pub max_replica_lag: Duration,
+}
+
+impl Default for ReplicationPolicy {
+    fn default() -> Self {
+        ReplicationPolicy {
+            max_replica_lag: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The configuration of a [`ReplicatedStore`].
+#[derive(Clone, Debug)]
+pub struct ReplicatedStoreConfig<C> {
+    /// The configuration of the primary store.
+    pub primary: C,
+    /// The configurations of the read replicas.
+    pub replicas: Vec<C>,
+    /// How reads are distributed.
+    pub policy: ReplicationPolicy,
+}
+
+/// A key-value store that writes to a primary store and spreads reads across read
+/// replicas, in turn. A read that fails on a replica is retried on the primary.
+///
+/// The replicas are kept up to date by the backend, not by this store, and they may lag
+/// behind the primary. Writes made through this store can be read back (read-your-writes),
+/// provided the replicas catch up within [`ReplicationPolicy::max_replica_lag`]. Writes
+/// made by other clients of the primary may not be visible for a while.
+#[derive(Clone)]
+pub struct ReplicatedStore<S> {
+    /// The store receiving the writes.
+    pub primary: S,
+    /// The read replicas.
+    pub replicas: Vec<S>,
+    policy: ReplicationPolicy,
+    next_replica: Arc<AtomicUsize>,
+    last_write: Arc<Mutex<Option<Instant>>>,
+}
+
+impl<S> ReplicatedStore<S> {
+    /// Creates a new store writing to `primary` and reading from `replicas` according to
+    /// `policy`.
+    pub fn new(primary: S, replicas: Vec<S>, policy: ReplicationPolicy) -> Self {
+        Self {
+            primary,
+            replicas,
+            policy,
+            next_replica: Arc::default(),
+            last_write: Arc::default(),
+        }
+    }
+
+    /// Returns the replica to read from next, unless the replicas may not have caught up
+    /// with the last write yet.
+    fn replica(&self) -> Option<&S> {
+        if self.replicas.is_empty() {
+            return None;
+        }
+        let last_write = *self.last_write.lock().unwrap();
+        if last_write.is_some_and(|time| time.elapsed() < self.policy.max_replica_lag) {
+            return None;
+        }
+        let index = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        Some(&self.replicas[index % self.replicas.len()])
+    }
+
+    /// Records that the primary was written to.
+    fn record_write(&self) {
+        *self.last_write.lock().unwrap() = Some(Instant::now());
+    }
+}
+
+impl<S> ReplicatedStore<S>
+where
+    S: KeyValueStore,
+{
+    /// Runs the read `operation` on a replica, or on the primary if there is no suitable
+    /// replica or the replica failed.
+    async fn read<'a, F, Fut, T>(&'a self, operation: F) -> Result<T, S::Error>
+    where
+        F: Fn(&'a S) -> Fut,
+        Fut: Future<Output = Result<T, S::Error>>,
+    {
+        if let Some(replica) = self.replica() {
+            match operation(replica).await {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    tracing::warn!(?error, "Failed to read from a replica; using the primary")
+                }
+            }
+        }
+        operation(&self.primary).await
+    }
+}
+
+impl<S> ReadableKeyValueStore<S::Error> for ReplicatedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    type Keys = S::Keys;
+    type KeyValues = S::KeyValues;
+
+    fn max_stream_queries(&self) -> usize {
+        self.primary.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, S::Error> {
+        self.read(|store| store.read_value_bytes(key)).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, S::Error> {
+        self.read(|store| store.contains_key(key)).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, S::Error> {
+        self.read(|store| store.read_multi_values_bytes(keys.clone()))
+            .await
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, S::Error> {
+        self.read(|store| store.find_keys_by_prefix(key_prefix))
+            .await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, S::Error> {
+        self.read(|store| store.find_key_values_by_prefix(key_prefix))
+            .await
+    }
+
+    /// Checks the primary only, since reads bypass the replicas that fail.
+    async fn health_check(&self) -> Result<(), S::Error> {
+        self.primary.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, S::Error> {
+        self.read(|store| store.approximate_key_count(key_prefix))
+            .await
+    }
+}
+
+impl<S> WritableKeyValueStore<S::Error> for ReplicatedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), S::Error> {
+        let result = self.primary.write_batch(batch, base_key).await;
+        // Even a failed write may have partially reached the primary.
+        self.record_write();
+        result
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), S::Error> {
+        let result = self.primary.clear_journal(base_key).await;
+        self.record_write();
+        result
+    }
+
+    /// Runs the maintenance of the primary only, since the replicas are managed by the
+    /// backend.
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), S::Error> {
+        self.primary.maintenance(op).await
+    }
+}
+
+impl<S> CompareAndSwapKeyValueStore<S::Error> for ReplicatedStore<S>
+where
+    S: KeyValueStore + CompareAndSwapKeyValueStore<<S as KeyValueStore>::Error> + Send + Sync,
+    S::Error: Send,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, S::Error> {
+        let result = self.primary.compare_and_swap(key, expected, new).await;
+        self.record_write();
+        result
+    }
+}
+
+impl<S> KeyValueStore for ReplicatedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    type Error = S::Error;
+}
+
+/// The namespaces are only managed on the primary, and the backend is expected to replicate
+/// them.
+impl<S> AdminKeyValueStore for ReplicatedStore<S>
+where
+    S: AdminKeyValueStore + Send,
+    S::Error: Send,
+{
+    type Error = S::Error;
+    type Config = ReplicatedStoreConfig<S::Config>;
+
+    async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, Self::Error> {
+        let primary = S::connect(&config.primary, namespace).await?;
+        let replicas = future::try_join_all(
+            config
+                .replicas
+                .iter()
+                .map(|config| S::connect(config, namespace)),
+        )
+        .await?;
+        Ok(Self::new(primary, replicas, config.policy))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        S::list_all(&config.primary).await
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        S::exists(&config.primary, namespace).await
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        S::create(&config.primary, namespace).await
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        S::delete(&config.primary, namespace).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::time::Duration;
+
+    use crate::{
+        batch::Batch,
+        common::{ReadableKeyValueStore as _, WritableKeyValueStore as _},
+        memory::{create_memory_store, MemoryStore},
+        replicated::{ReplicatedStore, ReplicationPolicy},
+    };
+
+    /// Creates a replicated store whose primary and replicas each hold a different value
+    /// for the key `[0]`, so that reads show which store they went to.
+    async fn create_labeled_store(max_replica_lag: Duration) -> ReplicatedStore<MemoryStore> {
+        let stores = (0..3).map(|_| create_memory_store()).collect::<Vec<_>>();
+        for (label, store) in stores.iter().enumerate() {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0], vec![label as u8]);
+            store.write_batch(batch, &[]).await.unwrap();
+        }
+        let mut stores = stores.into_iter();
+        let primary = stores.next().unwrap();
+        let policy = ReplicationPolicy { max_replica_lag };
+        ReplicatedStore::new(primary, stores.collect(), policy)
+    }
+
+    async fn read_label(store: &ReplicatedStore<MemoryStore>) -> u8 {
+        store.read_value_bytes(&[0]).await.unwrap().unwrap()[0]
+    }
+
+    #[tokio::test]
+    async fn test_replicated_store_balances_reads_across_replicas() {
+        let store = create_labeled_store(Duration::from_secs(3600)).await;
+        let mut labels = Vec::new();
+        for _ in 0..4 {
+            labels.push(read_label(&store).await);
+        }
+        assert_eq!(labels, [1, 2, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_replicated_store_reads_its_writes_from_the_primary() {
+        let store = create_labeled_store(Duration::from_secs(3600)).await;
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1], vec![]);
+        store.write_batch(batch, &[]).await.unwrap();
+        assert_eq!(read_label(&store).await, 0);
+        assert!(store.contains_key(&[1]).await.unwrap());
+        assert!(!store.replicas[0].contains_key(&[1]).await.unwrap());
+
+        let store = ReplicatedStore::new(
+            store.primary,
+            store.replicas,
+            ReplicationPolicy {
+                max_replica_lag: Duration::ZERO,
+            },
+        );
+        store.write_batch(Batch::new(), &[]).await.unwrap();
+        assert_eq!(read_label(&store).await, 1);
+    }
+}
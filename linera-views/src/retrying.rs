@@ -0,0 +1,425 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::future::Future;
+
+use linera_base::time::{timer::sleep, Duration};
+
+use crate::{
+    batch::Batch,
+    common::{
+        CompareAndSwapKeyValueStore, KeyIterable, KeyValueIterable, KeyValueStore, MaintenanceOp,
+        ReadableKeyValueStore, WritableKeyValueStore,
+    },
+    value_splitting::DatabaseConsistencyError,
+    views::ViewError,
+};
+
+/// The policy deciding which failed operations a [`RetryingStore`] retries, and how often.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts for an operation, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry. It doubles after every retry.
+    pub initial_delay: Duration,
+    /// The maximum delay between two attempts.
+    pub max_delay: Duration,
+    /// Whether an error is transient, so that the failed operation should be retried.
+    pub is_retryable: fn(&ViewError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(5),
+            is_retryable: is_transient_error,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay to wait for after the given number of failed attempts.
+    fn delay(&self, failed_attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failed_attempts.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+
+    /// Returns the delay to wait for after the given number of failed attempts, the last
+    /// one with `error`. A delay requested by a throttling backend is always honored.
+    fn delay_after(&self, failed_attempts: u32, error: &ViewError) -> Duration {
+        let delay = self.delay(failed_attempts);
+        error
+            .retry_after()
+            .map_or(delay, |retry_after| retry_after.max(delay))
+    }
+}
+
+/// Returns `true` for the errors that the storage backends report as transient, i.e. that
+/// may go away when the operation is retried: throttling and I/O errors.
+///
+/// Other errors of the backends, e.g. keys that are too long, fail the same way every
+/// time. They are only retried by policies that recognize the transient ones among them.
+pub fn is_transient_error(error: &ViewError) -> bool {
+    matches!(error, ViewError::Io(_) | ViewError::Throttled { .. })
+}
+
+/// A key-value store that retries the operations of the inner store that failed with a
+/// retryable error, with exponential backoff.
+///
+/// An operation that isn't idempotent, i.e. `compare_and_swap` and `swap_prefixes`, may
+/// have been applied even though it failed, e.g. if the connection was lost before the
+/// response. Such operations are only retried when they were rejected by a throttling
+/// backend, and only if the policy retries throttling errors.
+///
+/// Errors of the inner store are converted into [`ViewError`]s so that the
+/// [`RetryPolicy`] can classify them.
+#[derive(Clone)]
+pub struct RetryingStore<S> {
+    /// The inner store.
+    pub store: S,
+    policy: RetryPolicy,
+}
+
+impl<S> RetryingStore<S> {
+    /// Creates a new store retrying the failed operations of `store` according to `policy`.
+    pub fn new(store: S, policy: RetryPolicy) -> Self {
+        Self { store, policy }
+    }
+}
+
+impl<S> RetryingStore<S>
+where
+    S: KeyValueStore,
+    ViewError: From<S::Error>,
+{
+    /// Runs `operation` until it succeeds, fails with an error that is not retryable, or
+    /// runs out of attempts.
+    async fn retry<F, Fut, T>(&self, operation: F) -> Result<T, ViewError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S::Error>>,
+    {
+        self.retry_if(self.policy.is_retryable, operation).await
+    }
+
+    /// Runs an operation that isn't idempotent like [`RetryingStore::retry`], but only
+    /// retries it if it was rejected by a throttling backend, so that it wasn't applied.
+    async fn retry_rejected<F, Fut, T>(&self, operation: F) -> Result<T, ViewError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S::Error>>,
+    {
+        let is_retryable = self.policy.is_retryable;
+        self.retry_if(
+            |error| error.is_throttled() && is_retryable(error),
+            operation,
+        )
+        .await
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error for which `is_retryable`
+    /// is false, or runs out of attempts.
+    async fn retry_if<F, Fut, T>(
+        &self,
+        is_retryable: impl Fn(&ViewError) -> bool,
+        operation: F,
+    ) -> Result<T, ViewError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, S::Error>>,
+    {
+        let mut failed_attempts = 0;
+        loop {
+            let error = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => ViewError::from(error),
+            };
+            failed_attempts += 1;
+            if failed_attempts >= self.policy.max_attempts || !is_retryable(&error) {
+                return Err(error);
+            }
+            let delay = self.policy.delay_after(failed_attempts, &error);
+            tracing::warn!(%error, failed_attempts, ?delay, "Retrying a failed store operation");
+            sleep(delay).await;
+        }
+    }
+}
+
+impl<S> ReadableKeyValueStore<ViewError> for RetryingStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+    ViewError: From<S::Error>,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ViewError> {
+        self.retry(|| self.store.read_value_bytes(key)).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, ViewError> {
+        self.retry(|| self.store.contains_key(key)).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, ViewError> {
+        self.retry(|| self.store.read_multi_values_bytes(keys.clone()))
+            .await
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, ViewError> {
+        self.retry(|| async {
+            let keys = self.store.find_keys_by_prefix(key_prefix).await?;
+            keys.iterator().map(|key| key.map(<[u8]>::to_vec)).collect()
+        })
+        .await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, ViewError> {
+        self.retry(|| async {
+            let key_values = self.store.find_key_values_by_prefix(key_prefix).await?;
+            key_values.into_iterator_owned().collect()
+        })
+        .await
+    }
+
+    async fn health_check(&self) -> Result<(), ViewError> {
+        Ok(self.store.health_check().await?)
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, ViewError> {
+        self.retry(|| self.store.approximate_key_count(key_prefix))
+            .await
+    }
+}
+
+impl<S> WritableKeyValueStore<ViewError> for RetryingStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+    ViewError: From<S::Error>,
+{
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), ViewError> {
+        self.retry(|| self.store.write_batch(batch.clone(), base_key))
+            .await
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), ViewError> {
+        self.retry(|| self.store.clear_journal(base_key)).await
+    }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), ViewError> {
+        Ok(self.store.maintenance(op).await?)
+    }
+}
+
+impl<S> CompareAndSwapKeyValueStore<ViewError> for RetryingStore<S>
+where
+    S: KeyValueStore + CompareAndSwapKeyValueStore<<S as KeyValueStore>::Error> + Send + Sync,
+    S::Error: Send,
+    ViewError: From<S::Error>,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, ViewError> {
+        self.retry_rejected(|| self.store.compare_and_swap(key, expected, new))
+            .await
+    }
+}
+
+impl<S> KeyValueStore for RetryingStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send + From<DatabaseConsistencyError>,
+    ViewError: From<S::Error>,
+{
+    type Error = ViewError;
+
+    /// Swaps the prefixes with the inner store, so that a store swapping them atomically
+    /// still does.
+    async fn swap_prefixes(&self, prefix1: &[u8], prefix2: &[u8]) -> Result<(), ViewError> {
+        self.retry_rejected(|| self.store.swap_prefixes(prefix1, prefix2))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use linera_base::time::Duration;
+
+    use crate::{
+        batch::Batch,
+        common::{
+            CompareAndSwapKeyValueStore, KeyValueStore, ReadableKeyValueStore,
+            WritableKeyValueStore,
+        },
+        memory::{create_memory_store, MemoryContextError, MemoryStore},
+        retrying::{RetryPolicy, RetryingStore},
+        views::ViewError,
+    };
+
+    fn test_policy(is_retryable: fn(&ViewError) -> bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            is_retryable,
+        }
+    }
+
+    /// Runs an operation that fails `failures` times before succeeding, and returns its
+    /// result and the number of attempts.
+    async fn run_flaky_operation(
+        store: &RetryingStore<MemoryStore>,
+        failures: u32,
+    ) -> (Result<(), ViewError>, u32) {
+        let attempts = AtomicU32::new(0);
+        let result = store
+            .retry(|| async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                    Err(MemoryContextError::TooLargeValue)
+                } else {
+                    Ok(())
+                }
+            })
+            .await;
+        (result, attempts.into_inner())
+    }
+
+    #[tokio::test]
+    async fn test_retrying_store_retries_retryable_errors() {
+        let store = RetryingStore::new(create_memory_store(), test_policy(|_| true));
+        let (result, attempts) = run_flaky_operation(&store, 2).await;
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+
+        let (result, attempts) = run_flaky_operation(&store, 3).await;
+        assert!(matches!(result, Err(ViewError::ContextError { .. })));
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_store_gives_up_on_other_errors() {
+        let store = RetryingStore::new(create_memory_store(), test_policy(|_| false));
+        let (result, attempts) = run_flaky_operation(&store, 1).await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_only_retries_transient_errors() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            ..RetryPolicy::default()
+        };
+        // A value that is too large fails the same way every time.
+        let store = RetryingStore::new(create_memory_store(), policy);
+        let (result, attempts) = run_flaky_operation(&store, 1).await;
+        assert!(matches!(result, Err(ViewError::ContextError { .. })));
+        assert_eq!(attempts, 1);
+        let io_error = ViewError::Io(io::Error::other("connection reset"));
+        assert!((policy.is_retryable)(&io_error));
+    }
+
+    #[tokio::test]
+    async fn test_retrying_store_forwards_swaps() -> Result<(), ViewError> {
+        let store = RetryingStore::new(create_memory_store(), RetryPolicy::default());
+        assert!(store.compare_and_swap(b"key", None, b"value").await?);
+        assert!(!store.compare_and_swap(b"key", None, b"other").await?);
+        assert_eq!(
+            store.read_value_bytes(b"key").await?,
+            Some(b"value".to_vec())
+        );
+
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(b"a1".to_vec(), b"first".to_vec());
+        batch.put_key_value_bytes(b"b2".to_vec(), b"second".to_vec());
+        store.write_batch(batch, &[]).await?;
+        store.swap_prefixes(b"a", b"b").await?;
+        assert_eq!(
+            store.read_value_bytes(b"b1").await?,
+            Some(b"first".to_vec())
+        );
+        assert_eq!(
+            store.read_value_bytes(b"a2").await?,
+            Some(b"second".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retrying_store_only_retries_rejected_swaps() {
+        let store = RetryingStore::new(create_memory_store(), test_policy(|_| true));
+        let attempts = AtomicU32::new(0);
+        let result = store
+            .retry_rejected(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(MemoryContextError::TooLargeValue)
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.into_inner(), 1);
+    }
+
+    #[test]
+    fn test_retry_delays_grow_exponentially() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            ..RetryPolicy::default()
+        };
+        let delays = (1..=4)
+            .map(|attempts| policy.delay(attempts))
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [10, 20, 40, 50].map(Duration::from_millis).to_vec());
+    }
+
+    #[test]
+    fn test_retry_delays_honor_throttling() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            ..RetryPolicy::default()
+        };
+        let throttled = |retry_after| ViewError::Throttled { retry_after };
+        assert!((policy.is_retryable)(&throttled(None)));
+        assert_eq!(
+            policy.delay_after(2, &throttled(None)),
+            Duration::from_millis(20)
+        );
+        assert_eq!(
+            policy.delay_after(2, &throttled(Some(Duration::from_secs(1)))),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            policy.delay_after(3, &throttled(Some(Duration::from_millis(5)))),
+            Duration::from_millis(40)
+        );
+    }
+}
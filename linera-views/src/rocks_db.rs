@@ -5,9 +5,10 @@ use std::{
     ffi::OsString,
     ops::{Bound, Bound::Excluded},
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
+use futures::{stream, Stream, TryStreamExt as _};
 use linera_base::ensure;
 use thiserror::Error;
 #[cfg(with_testing)]
@@ -23,10 +24,13 @@ use crate::metering::{
 use crate::{
     batch::{Batch, WriteOperation},
     common::{
-        get_upper_bound, AdminKeyValueStore, CommonStoreConfig, ContextFromStore, KeyValueStore,
-        ReadableKeyValueStore, WritableKeyValueStore,
+        get_upper_bound, AdminKeyValueStore, CommonStoreConfig, CompareAndSwapKeyValueStore,
+        ContextFromStore, KeyValueStore, MaintenanceOp, ReadableKeyValueStore,
+        WritableKeyValueStore,
     },
     lru_caching::LruCachingStore,
+    namespaced::NamespacedStoreError,
+    sharded::ShardedStoreError,
     value_splitting::{DatabaseConsistencyError, ValueSplittingStore},
 };
 
@@ -42,6 +46,9 @@ const MAX_VALUE_SIZE: usize = 3221225072;
 // 8388608 and so for offset reason we decrease by 400
 const MAX_KEY_SIZE: usize = 8388208;
 
+/// The number of key-value pairs read at a time when streaming a prefix.
+const SCAN_PAGE_SIZE: usize = 1000;
+
 /// The RocksDB client that we use.
 pub type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 
@@ -50,6 +57,8 @@ pub type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 pub struct RocksDbStoreInternal {
     db: Arc<DB>,
     max_stream_queries: usize,
+    /// Serializes the compare-and-swap operations, which RocksDB doesn't provide.
+    compare_and_swap_lock: Arc<Mutex<()>>,
 }
 
 /// The initial configuration of the system
@@ -176,6 +185,88 @@ impl ReadableKeyValueStore<RocksDbContextError> for RocksDbStoreInternal {
         .await?;
         Ok(key_values)
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), RocksDbContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        // The state is the key to start the next page from, or `None` after the last page.
+        stream::try_unfold(Some(key_prefix.to_vec()), move |start_key| async move {
+            let Some(start_key) = start_key else {
+                return Ok::<_, RocksDbContextError>(None);
+            };
+            ensure!(
+                key_prefix.len() <= MAX_KEY_SIZE,
+                RocksDbContextError::KeyTooLong
+            );
+            let client = self.clone();
+            let prefix = key_prefix.to_vec();
+            let (key_values, next_start_key) = tokio::task::spawn_blocking(move || {
+                let len = prefix.len();
+                let mut iter = client.db.raw_iterator();
+                let mut key_values = Vec::new();
+                iter.seek(&start_key);
+                while let Some(key) = iter.key() {
+                    if !key.starts_with(&prefix) {
+                        break;
+                    }
+                    if key_values.len() == SCAN_PAGE_SIZE {
+                        return (key_values, Some(key.to_vec()));
+                    }
+                    if let Some(value) = iter.value() {
+                        key_values.push(Ok((key[len..].to_vec(), value.to_vec())));
+                    }
+                    iter.next();
+                }
+                (key_values, None)
+            })
+            .await?;
+            Ok(Some((stream::iter(key_values), next_start_key)))
+        })
+        .try_flatten()
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, RocksDbContextError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            RocksDbContextError::KeyTooLong
+        );
+        let client = self.clone();
+        let prefix = key_prefix.to_vec();
+        let estimate = tokio::task::spawn_blocking(move || -> Result<_, RocksDbContextError> {
+            let total_keys = client.db.property_int_value("rocksdb.estimate-num-keys")?;
+            if prefix.is_empty() {
+                return Ok(total_keys);
+            }
+            let Excluded(upper_bound) = get_upper_bound(&prefix) else {
+                return Ok(None);
+            };
+            let total_size = client
+                .db
+                .property_int_value("rocksdb.total-sst-files-size")?;
+            let (Some(total_keys), Some(total_size)) = (total_keys, total_size) else {
+                return Ok(None);
+            };
+            if total_size == 0 {
+                return Ok(None);
+            }
+            // The estimate assumes that the keys of the range are as large as the others.
+            let range = rocksdb::Range::new(&prefix, &upper_bound);
+            let range_size = client.db.get_approximate_sizes(&[range])[0];
+            let count = u128::from(total_keys) * u128::from(range_size) / u128::from(total_size);
+            Ok(Some(count as u64))
+        })
+        .await??;
+        match estimate {
+            Some(count) => Ok(count),
+            // Either the keys are only in memory, where the sizes of ranges are not
+            // estimated, or the prefix has no upper bound.
+            None => Ok(self.find_keys_by_prefix(key_prefix).await?.len() as u64),
+        }
+    }
 }
 
 impl WritableKeyValueStore<RocksDbContextError> for RocksDbStoreInternal {
@@ -240,6 +331,45 @@ impl WritableKeyValueStore<RocksDbContextError> for RocksDbStoreInternal {
     async fn clear_journal(&self, _base_key: &[u8]) -> Result<(), RocksDbContextError> {
         Ok(())
     }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), RocksDbContextError> {
+        let client = self.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), RocksDbContextError> {
+            match op {
+                MaintenanceOp::Compact => client.db.compact_range(None::<&[u8]>, None::<&[u8]>),
+                MaintenanceOp::Flush => client.db.flush()?,
+            }
+            Ok(())
+        })
+        .await?
+    }
+}
+
+impl CompareAndSwapKeyValueStore<RocksDbContextError> for RocksDbStoreInternal {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, RocksDbContextError> {
+        ensure!(key.len() <= MAX_KEY_SIZE, RocksDbContextError::KeyTooLong);
+        let client = self.clone();
+        let key = key.to_vec();
+        let expected = expected.map(<[u8]>::to_vec);
+        let new = new.to_vec();
+        tokio::task::spawn_blocking(move || -> Result<bool, RocksDbContextError> {
+            let _guard = client
+                .compare_and_swap_lock
+                .lock()
+                .expect("the compare-and-swap lock should not be poisoned");
+            if client.db.get(&key)? != expected {
+                return Ok(false);
+            }
+            client.db.put(&key, new)?;
+            Ok(true)
+        })
+        .await?
+    }
 }
 
 impl AdminKeyValueStore for RocksDbStoreInternal {
@@ -256,6 +386,7 @@ impl AdminKeyValueStore for RocksDbStoreInternal {
         Ok(RocksDbStoreInternal {
             db: Arc::new(db),
             max_stream_queries,
+            compare_and_swap_lock: Arc::default(),
         })
     }
 
@@ -424,6 +555,20 @@ impl ReadableKeyValueStore<RocksDbContextError> for RocksDbStore {
     ) -> Result<Self::KeyValues, RocksDbContextError> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), RocksDbContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, RocksDbContextError> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl WritableKeyValueStore<RocksDbContextError> for RocksDbStore {
@@ -436,6 +581,21 @@ impl WritableKeyValueStore<RocksDbContextError> for RocksDbStore {
     async fn clear_journal(&self, base_key: &[u8]) -> Result<(), RocksDbContextError> {
         self.store.clear_journal(base_key).await
     }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), RocksDbContextError> {
+        self.store.maintenance(op).await
+    }
+}
+
+impl CompareAndSwapKeyValueStore<RocksDbContextError> for RocksDbStore {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, RocksDbContextError> {
+        self.store.compare_and_swap(key, expected, new).await
+    }
 }
 
 impl AdminKeyValueStore for RocksDbStore {
@@ -538,6 +698,14 @@ pub enum RocksDbContextError {
     /// The database is not coherent
     #[error(transparent)]
     DatabaseConsistencyError(#[from] DatabaseConsistencyError),
+
+    /// The configuration of a sharded store is invalid
+    #[error(transparent)]
+    ShardedStoreError(#[from] ShardedStoreError),
+
+    /// The configuration of a namespaced store is invalid
+    #[error(transparent)]
+    NamespacedStoreError(#[from] NamespacedStoreError),
 }
 
 impl From<RocksDbContextError> for crate::views::ViewError {
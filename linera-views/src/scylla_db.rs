@@ -19,12 +19,17 @@ use std::{ops::Deref, sync::Arc};
 
 use async_lock::{Semaphore, SemaphoreGuard};
 use async_trait::async_trait;
-use futures::{future::join_all, FutureExt as _, StreamExt};
+use futures::{future::join_all, stream, FutureExt as _, Stream, StreamExt, TryStreamExt as _};
 use linera_base::ensure;
 use scylla::{
-    frame::request::batch::BatchType,
+    frame::{request::batch::BatchType, response::result::CqlValue},
     query::Query,
-    transport::errors::{DbError, QueryError},
+    statement::Consistency,
+    transport::{
+        errors::{DbError, QueryError},
+        iterator::RowIterator,
+        session::PoolSize,
+    },
     IntoTypedRows, Session, SessionBuilder,
 };
 use thiserror::Error;
@@ -32,16 +37,18 @@ use thiserror::Error;
 #[cfg(with_metrics)]
 use crate::metering::{MeteredStore, LRU_CACHING_METRICS, SCYLLA_DB_METRICS};
 use crate::{
-    batch::{Batch, DeletePrefixExpander, UnorderedBatch},
+    batch::{Batch, DeletePrefixExpander, SimplifiedBatch, UnorderedBatch},
     common::{
-        get_upper_bound_option, AdminKeyValueStore, CommonStoreConfig, ContextFromStore,
-        KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+        get_upper_bound_option, AdminKeyValueStore, CommonStoreConfig, CompareAndSwapKeyValueStore,
+        ContextFromStore, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
     },
     journaling::{
         DirectKeyValueStore, DirectWritableKeyValueStore, JournalConsistencyError,
         JournalingKeyValueStore,
     },
     lru_caching::LruCachingStore,
+    namespaced::NamespacedStoreError,
+    sharded::ShardedStoreError,
     value_splitting::DatabaseConsistencyError,
 };
 #[cfg(with_testing)]
@@ -50,85 +57,133 @@ use crate::{lru_caching::TEST_CACHE_SIZE, test_utils::generate_test_namespace};
 /// The client for ScyllaDb.
 /// * The session allows to pass queries
 /// * The namespace that is being assigned to the database
+/// * The consistency level of the write batches
 /// * The prepared queries used for implementing the features of `KeyValueStore`.
 struct ScyllaDbClient {
     session: Session,
     namespace: String,
+    write_consistency: Consistency,
     read_value: Query,
     contains_key: Query,
     write_batch_delete_prefix_unbounded: Query,
     write_batch_delete_prefix_bounded: Query,
     write_batch_deletion: Query,
     write_batch_insertion: Query,
+    compare_and_swap_insertion: Query,
+    compare_and_swap_update: Query,
     find_keys_by_prefix_unbounded: Query,
     find_keys_by_prefix_bounded: Query,
     find_key_values_by_prefix_unbounded: Query,
     find_key_values_by_prefix_bounded: Query,
+    count_keys_by_prefix_unbounded: Query,
+    count_keys_by_prefix_bounded: Query,
 }
 
 impl ScyllaDbClient {
-    fn new(session: Session, namespace: String) -> Self {
+    fn new(
+        session: Session,
+        namespace: String,
+        read_consistency: Consistency,
+        write_consistency: Consistency,
+    ) -> Self {
+        let read_query = |query: String| {
+            let mut query = Query::new(query);
+            query.set_consistency(read_consistency);
+            query
+        };
+        let write_query = |query: String| {
+            let mut query = Query::new(query);
+            query.set_consistency(write_consistency);
+            query
+        };
+
         let query = format!(
             "SELECT v FROM kv.{} WHERE dummy = 0 AND k = ? ALLOW FILTERING",
             namespace
         );
-        let read_value = Query::new(query);
+        let read_value = read_query(query);
         let query = format!(
             "SELECT dummy FROM kv.{} WHERE dummy = 0 AND k = ? ALLOW FILTERING",
             namespace
         );
-        let contains_key = Query::new(query);
+        let contains_key = read_query(query);
 
         let query = format!("DELETE FROM kv.{} WHERE dummy = 0 AND k >= ?", namespace);
-        let write_batch_delete_prefix_unbounded = Query::new(query);
+        let write_batch_delete_prefix_unbounded = write_query(query);
         let query = format!(
             "DELETE FROM kv.{} WHERE dummy = 0 AND k >= ? AND k < ?",
             namespace
         );
-        let write_batch_delete_prefix_bounded = Query::new(query);
+        let write_batch_delete_prefix_bounded = write_query(query);
         let query = format!("DELETE FROM kv.{} WHERE dummy = 0 AND k = ?", namespace);
-        let write_batch_deletion = Query::new(query);
+        let write_batch_deletion = write_query(query);
         let query = format!(
             "INSERT INTO kv.{} (dummy, k, v) VALUES (0, ?, ?)",
             namespace
         );
-        let write_batch_insertion = Query::new(query);
+        let write_batch_insertion = write_query(query);
+        let query = format!(
+            "INSERT INTO kv.{} (dummy, k, v) VALUES (0, ?, ?) IF NOT EXISTS",
+            namespace
+        );
+        let compare_and_swap_insertion = write_query(query);
+        let query = format!(
+            "UPDATE kv.{} SET v = ? WHERE dummy = 0 AND k = ? IF v = ?",
+            namespace
+        );
+        let compare_and_swap_update = write_query(query);
 
         let query = format!(
             "SELECT k FROM kv.{} WHERE dummy = 0 AND k >= ? ALLOW FILTERING",
             namespace
         );
-        let find_keys_by_prefix_unbounded = Query::new(query);
+        let find_keys_by_prefix_unbounded = read_query(query);
         let query = format!(
             "SELECT k FROM kv.{} WHERE dummy = 0 AND k >= ? AND k < ? ALLOW FILTERING",
             namespace
         );
-        let find_keys_by_prefix_bounded = Query::new(query);
+        let find_keys_by_prefix_bounded = read_query(query);
 
         let query = format!(
             "SELECT k,v FROM kv.{} WHERE dummy = 0 AND k >= ? ALLOW FILTERING",
             namespace
         );
-        let find_key_values_by_prefix_unbounded = Query::new(query);
+        let find_key_values_by_prefix_unbounded = read_query(query);
         let query = format!(
             "SELECT k,v FROM kv.{} WHERE dummy = 0 AND k >= ? AND k < ? ALLOW FILTERING",
             namespace
         );
-        let find_key_values_by_prefix_bounded = Query::new(query);
+        let find_key_values_by_prefix_bounded = read_query(query);
+
+        let query = format!(
+            "SELECT COUNT(*) FROM kv.{} WHERE dummy = 0 AND k >= ? ALLOW FILTERING",
+            namespace
+        );
+        let count_keys_by_prefix_unbounded = read_query(query);
+        let query = format!(
+            "SELECT COUNT(*) FROM kv.{} WHERE dummy = 0 AND k >= ? AND k < ? ALLOW FILTERING",
+            namespace
+        );
+        let count_keys_by_prefix_bounded = read_query(query);
 
         Self {
             session,
             namespace,
+            write_consistency,
             read_value,
             contains_key,
             write_batch_delete_prefix_unbounded,
             write_batch_delete_prefix_bounded,
             write_batch_deletion,
             write_batch_insertion,
+            compare_and_swap_insertion,
+            compare_and_swap_update,
             find_keys_by_prefix_unbounded,
             find_keys_by_prefix_bounded,
             find_key_values_by_prefix_unbounded,
             find_key_values_by_prefix_bounded,
+            count_keys_by_prefix_unbounded,
+            count_keys_by_prefix_bounded,
         }
     }
 
@@ -164,6 +219,7 @@ impl ScyllaDbClient {
     ) -> Result<(), ScyllaDbContextError> {
         let session = &self.session;
         let mut batch_query = scylla::statement::batch::Batch::new(BatchType::Logged);
+        batch_query.set_consistency(self.write_consistency);
         let mut batch_values = Vec::new();
         let query1 = &self.write_batch_delete_prefix_unbounded;
         let query2 = &self.write_batch_delete_prefix_bounded;
@@ -202,6 +258,36 @@ impl ScyllaDbClient {
         Ok(())
     }
 
+    async fn compare_and_swap_internal(
+        &self,
+        key: Vec<u8>,
+        expected: Option<Vec<u8>>,
+        new: Vec<u8>,
+    ) -> Result<bool, ScyllaDbContextError> {
+        ensure!(key.len() <= MAX_KEY_SIZE, ScyllaDbContextError::KeyTooLong);
+        let session = &self.session;
+        // Lightweight transactions return whether they were applied in the first column.
+        let result = match expected {
+            None => {
+                let values = (key, new);
+                let query = &self.compare_and_swap_insertion;
+                session.query(query.clone(), values).await?
+            }
+            Some(expected) => {
+                let values = (new, key, expected);
+                let query = &self.compare_and_swap_update;
+                session.query(query.clone(), values).await?
+            }
+        };
+        let applied = result
+            .rows
+            .into_iter()
+            .flatten()
+            .next()
+            .and_then(|row| row.columns.into_iter().next().flatten());
+        Ok(matches!(applied, Some(CqlValue::Boolean(true))))
+    }
+
     async fn find_keys_by_prefix_internal(
         &self,
         key_prefix: Vec<u8>,
@@ -238,32 +324,77 @@ impl ScyllaDbClient {
         &self,
         key_prefix: Vec<u8>,
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ScyllaDbContextError> {
+        let len = key_prefix.len();
+        let mut rows = self.query_key_values_by_prefix(key_prefix).await?;
+        let mut key_values = Vec::new();
+        while let Some(row) = rows.next().await {
+            let key = row?.into_typed::<(Vec<u8>, Vec<u8>)>()?;
+            let short_key = key.0[len..].to_vec();
+            key_values.push((short_key, key.1));
+        }
+        Ok(key_values)
+    }
+
+    async fn count_keys_by_prefix_internal(
+        &self,
+        key_prefix: Vec<u8>,
+    ) -> Result<u64, ScyllaDbContextError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            ScyllaDbContextError::KeyTooLong
+        );
+        let session = &self.session;
+        // The keys are counted by the server, without being sent.
+        let query_unbounded = &self.count_keys_by_prefix_unbounded;
+        let query_bounded = &self.count_keys_by_prefix_bounded;
+        let mut rows = match get_upper_bound_option(&key_prefix) {
+            None => {
+                let values = (key_prefix,);
+                session.query_iter(query_unbounded.clone(), values).await?
+            }
+            Some(upper_bound) => {
+                let values = (key_prefix, upper_bound);
+                session.query_iter(query_bounded.clone(), values).await?
+            }
+        };
+        let count = match rows.next().await {
+            Some(row) => row?.into_typed::<(i64,)>()?.0,
+            None => 0,
+        };
+        Ok(count as u64)
+    }
+
+    async fn health_check_internal(&self) -> Result<(), ScyllaDbContextError> {
+        self.session
+            .query("SELECT now() FROM system.local", &[])
+            .await?;
+        Ok(())
+    }
+
+    /// Starts a query of the `(key, value)` pairs matching the prefix, whose rows are
+    /// fetched one page at a time as the returned iterator is consumed.
+    async fn query_key_values_by_prefix(
+        &self,
+        key_prefix: Vec<u8>,
+    ) -> Result<RowIterator, ScyllaDbContextError> {
         ensure!(
             key_prefix.len() <= MAX_KEY_SIZE,
             ScyllaDbContextError::KeyTooLong
         );
         let session = &self.session;
-        // Read the value of a key
-        let len = key_prefix.len();
         let query_unbounded = &self.find_key_values_by_prefix_unbounded;
         let query_bounded = &self.find_key_values_by_prefix_bounded;
-        let mut rows = match get_upper_bound_option(&key_prefix) {
+        let rows = match get_upper_bound_option(&key_prefix) {
             None => {
-                let values = (key_prefix.clone(),);
+                let values = (key_prefix,);
                 session.query_iter(query_unbounded.clone(), values).await?
             }
             Some(upper_bound) => {
-                let values = (key_prefix.clone(), upper_bound);
+                let values = (key_prefix, upper_bound);
                 session.query_iter(query_bounded.clone(), values).await?
             }
         };
-        let mut key_values = Vec::new();
-        while let Some(row) = rows.next().await {
-            let key = row?.into_typed::<(Vec<u8>, Vec<u8>)>()?;
-            let short_key = key.0[len..].to_vec();
-            key_values.push((short_key, key.1));
-        }
-        Ok(key_values)
+        Ok(rows)
     }
 }
 
@@ -330,6 +461,14 @@ pub enum ScyllaDbContextError {
     #[error(transparent)]
     DatabaseConsistencyError(#[from] DatabaseConsistencyError),
 
+    /// The configuration of a sharded store is invalid
+    #[error(transparent)]
+    ShardedStoreError(#[from] ShardedStoreError),
+
+    /// The configuration of a namespaced store is invalid
+    #[error(transparent)]
+    NamespacedStoreError(#[from] NamespacedStoreError),
+
     /// The journal is not coherent
     #[error(transparent)]
     JournalConsistencyError(#[from] JournalConsistencyError),
@@ -337,6 +476,14 @@ pub enum ScyllaDbContextError {
 
 impl From<ScyllaDbContextError> for crate::views::ViewError {
     fn from(error: ScyllaDbContextError) -> Self {
+        // ScyllaDB doesn't suggest how long to wait when it is overloaded.
+        if let ScyllaDbContextError::ScyllaDbQueryError(QueryError::DbError(
+            DbError::Overloaded | DbError::RateLimitReached { .. },
+            _,
+        )) = &error
+        {
+            return Self::Throttled { retry_after: None };
+        }
         Self::ContextError {
             backend: "scylla_db".to_string(),
             error: error.to_string(),
@@ -399,6 +546,43 @@ impl ReadableKeyValueStore<ScyllaDbContextError> for ScyllaDbStoreInternal {
             .find_key_values_by_prefix_internal(key_prefix.to_vec())
             .await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), ScyllaDbContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        let len = key_prefix.len();
+        stream::once(async move {
+            let store = self.store.deref();
+            let _guard = self.acquire().await;
+            let rows = store
+                .query_key_values_by_prefix(key_prefix.to_vec())
+                .await?;
+            Ok::<_, ScyllaDbContextError>(rows.map_err(ScyllaDbContextError::from))
+        })
+        .try_flatten()
+        .and_then(move |row| async move {
+            let (key, value) = row.into_typed::<(Vec<u8>, Vec<u8>)>()?;
+            Ok((key[len..].to_vec(), value))
+        })
+    }
+
+    async fn health_check(&self) -> Result<(), ScyllaDbContextError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        store.health_check_internal().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, ScyllaDbContextError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        store
+            .count_keys_by_prefix_internal(key_prefix.to_vec())
+            .await
+    }
 }
 
 #[async_trait]
@@ -422,8 +606,11 @@ impl DirectWritableKeyValueStore<ScyllaDbContextError> for ScyllaDbStoreInternal
 
     async fn write_batch(&self, batch: Self::Batch) -> Result<(), ScyllaDbContextError> {
         let store = self.store.deref();
-        let _guard = self.acquire().await;
-        store.write_batch_internal(batch).await
+        for batch in batch.into_chunks(Self::MAX_BATCH_SIZE, Self::MAX_BATCH_TOTAL_SIZE)? {
+            let _guard = self.acquire().await;
+            store.write_batch_internal(batch).await?;
+        }
+        Ok(())
     }
 }
 
@@ -433,12 +620,17 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
 
     async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, ScyllaDbContextError> {
         Self::check_namespace(namespace)?;
-        let session = SessionBuilder::new()
-            .known_node(config.uri.as_str())
-            .build()
-            .boxed()
-            .await?;
-        let store = ScyllaDbClient::new(session, namespace.to_string());
+        let mut session_builder = SessionBuilder::new().known_node(config.uri.as_str());
+        if let Some(pool_size) = config.pool_size {
+            session_builder = session_builder.pool_size(pool_size);
+        }
+        let session = session_builder.build().boxed().await?;
+        let store = ScyllaDbClient::new(
+            session,
+            namespace.to_string(),
+            config.read_consistency,
+            config.write_consistency,
+        );
         let store = Arc::new(store);
         let semaphore = config
             .common_config
@@ -595,6 +787,21 @@ impl DirectKeyValueStore for ScyllaDbStoreInternal {
     type Error = ScyllaDbContextError;
 }
 
+impl CompareAndSwapKeyValueStore<ScyllaDbContextError> for ScyllaDbStoreInternal {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, ScyllaDbContextError> {
+        let store = self.store.deref();
+        let _guard = self.acquire().await;
+        store
+            .compare_and_swap_internal(key.to_vec(), expected.map(<[u8]>::to_vec), new.to_vec())
+            .await
+    }
+}
+
 impl DeletePrefixExpander for ScyllaDbClient {
     type Error = ScyllaDbContextError;
 
@@ -641,6 +848,10 @@ pub struct ScyllaDbStore {
     store: LruCachingStore<JournalingKeyValueStore<ScyllaDbStoreInternal>>,
 }
 
+/// The consistency level used by default for both reads and writes, which is the default
+/// of the ScyllaDB driver.
+pub const DEFAULT_CONSISTENCY: Consistency = Consistency::LocalQuorum;
+
 /// The type for building a new ScyllaDB Key Value Store
 #[derive(Debug)]
 pub struct ScyllaDbStoreConfig {
@@ -648,6 +859,27 @@ pub struct ScyllaDbStoreConfig {
     pub uri: String,
     /// The common configuration of the key value store
     pub common_config: CommonStoreConfig,
+    /// The consistency level of the queries reading from the database
+    pub read_consistency: Consistency,
+    /// The consistency level of the queries and batches writing to the database
+    pub write_consistency: Consistency,
+    /// The number of connections kept open to each node or shard, if not the driver's
+    /// default of one connection per shard
+    pub pool_size: Option<PoolSize>,
+}
+
+impl ScyllaDbStoreConfig {
+    /// Creates a configuration using [`DEFAULT_CONSISTENCY`] for reads and writes, and the
+    /// default connection pool size.
+    pub fn new(uri: String, common_config: CommonStoreConfig) -> Self {
+        Self {
+            uri,
+            common_config,
+            read_consistency: DEFAULT_CONSISTENCY,
+            write_consistency: DEFAULT_CONSISTENCY,
+            pool_size: None,
+        }
+    }
 }
 
 impl ReadableKeyValueStore<ScyllaDbContextError> for ScyllaDbStore {
@@ -690,6 +922,24 @@ impl ReadableKeyValueStore<ScyllaDbContextError> for ScyllaDbStore {
     ) -> Result<Self::KeyValues, ScyllaDbContextError> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), ScyllaDbContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn health_check(&self) -> Result<(), ScyllaDbContextError> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, ScyllaDbContextError> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl WritableKeyValueStore<ScyllaDbContextError> for ScyllaDbStore {
@@ -704,6 +954,20 @@ impl WritableKeyValueStore<ScyllaDbContextError> for ScyllaDbStore {
     }
 }
 
+impl CompareAndSwapKeyValueStore<ScyllaDbContextError> for ScyllaDbStore {
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, ScyllaDbContextError> {
+        self.store
+            .compare_and_swap(key, expected, new)
+            .boxed()
+            .await
+    }
+}
+
 impl AdminKeyValueStore for ScyllaDbStore {
     type Error = ScyllaDbContextError;
     type Config = ScyllaDbStoreConfig;
@@ -780,7 +1044,7 @@ pub fn create_scylla_db_test_uri() -> String {
 pub async fn create_scylla_db_test_config() -> ScyllaDbStoreConfig {
     let uri = create_scylla_db_test_uri();
     let common_config = create_scylla_db_common_config();
-    ScyllaDbStoreConfig { uri, common_config }
+    ScyllaDbStoreConfig::new(uri, common_config)
 }
 
 /// Creates a ScyllaDB test store.
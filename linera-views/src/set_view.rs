@@ -59,6 +59,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.delete_storage_first || !self.updates.is_empty()
+    }
+
     fn rollback(&mut self) {
         self.delete_storage_first = false;
         self.updates.clear();
@@ -364,6 +368,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.set.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.set.rollback()
     }
@@ -613,6 +621,10 @@ where
         })
     }
 
+    async fn has_pending_changes(&self) -> bool {
+        self.set.has_pending_changes().await
+    }
+
     fn rollback(&mut self) {
         self.set.rollback()
     }
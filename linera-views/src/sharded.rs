@@ -0,0 +1,380 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::future;
+use sha3::{Digest as _, Sha3_256};
+use thiserror::Error;
+
+#[cfg(with_testing)]
+use crate::memory::{create_memory_store, MemoryStore};
+use crate::{
+    batch::{Batch, WriteOperation},
+    common::{
+        AdminKeyValueStore, CompareAndSwapKeyValueStore, KeyIterable, KeyValueIterable,
+        KeyValueStore, MaintenanceOp, ReadableKeyValueStore, WritableKeyValueStore,
+    },
+};
+
+/// The configuration of a [`ShardedStore`], with one configuration per shard.
+#[derive(Clone, Debug)]
+pub struct ShardedStoreConfig<C> {
+    /// The configurations of the inner stores, in the order of the shards.
+    pub shards: Vec<C>,
+}
+
+impl<C> ShardedStoreConfig<C> {
+    /// Returns the configurations of the shards, or an error if there are none.
+    fn checked_shards(&self) -> Result<&[C], ShardedStoreError> {
+        if self.shards.is_empty() {
+            return Err(ShardedStoreError::NoShards);
+        }
+        Ok(&self.shards)
+    }
+}
+
+/// The errors specific to a [`ShardedStore`].
+#[derive(Debug, Error)]
+pub enum ShardedStoreError {
+    /// The configuration has no shards.
+    #[error("A sharded store needs at least one shard")]
+    NoShards,
+}
+
+/// A key-value store that spreads its keys across several inner stores, e.g. RocksDB
+/// instances on separate disks.
+///
+/// Each key is assigned to a shard by hashing it, so the number and order of the shards
+/// must not change once data has been written. Prefix searches query all the shards and
+/// merge their results in key order.
+///
+/// A batch is split into one batch per shard, and the shards are written independently: a
+/// batch that touches several shards is not atomic, even if the inner stores are.
+#[derive(Clone)]
+pub struct ShardedStore<S> {
+    /// The inner stores, one per shard.
+    pub stores: Vec<S>,
+}
+
+impl<S> ShardedStore<S> {
+    /// Creates a new store spreading its keys across `stores`.
+    ///
+    /// [`ShardedStore::connect`] returns an error instead for a configuration without
+    /// shards.
+    ///
+    /// # Panics
+    ///
+    /// If `stores` is empty.
+    pub fn new(stores: Vec<S>) -> Self {
+        assert!(
+            !stores.is_empty(),
+            "A sharded store needs at least one shard"
+        );
+        Self { stores }
+    }
+
+    /// Returns the index of the shard that holds `key`.
+    fn shard_index(&self, key: &[u8]) -> usize {
+        let hash = Sha3_256::digest(key);
+        let prefix = u64::from_le_bytes(hash[..8].try_into().expect("hashes have 32 bytes"));
+        (prefix % self.stores.len() as u64) as usize
+    }
+
+    /// Returns the inner store that holds `key`.
+    fn shard(&self, key: &[u8]) -> &S {
+        &self.stores[self.shard_index(key)]
+    }
+}
+
+impl<S> ReadableKeyValueStore<S::Error> for ShardedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.stores[0].max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, S::Error> {
+        self.shard(key).read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, S::Error> {
+        self.shard(key).contains_key(key).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, S::Error> {
+        let mut values = vec![None; keys.len()];
+        let mut positions_by_shard = vec![Vec::new(); self.stores.len()];
+        let mut keys_by_shard = vec![Vec::new(); self.stores.len()];
+        for (position, key) in keys.into_iter().enumerate() {
+            let index = self.shard_index(&key);
+            positions_by_shard[index].push(position);
+            keys_by_shard[index].push(key);
+        }
+        let values_by_shard = future::try_join_all(self.stores.iter().zip(keys_by_shard).map(
+            |(store, keys)| async move {
+                if keys.is_empty() {
+                    Ok(Vec::new())
+                } else {
+                    store.read_multi_values_bytes(keys).await
+                }
+            },
+        ))
+        .await?;
+        for (positions, shard_values) in positions_by_shard.into_iter().zip(values_by_shard) {
+            for (position, value) in positions.into_iter().zip(shard_values) {
+                values[position] = value;
+            }
+        }
+        Ok(values)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, S::Error> {
+        let keys_by_shard = future::try_join_all(self.stores.iter().map(|store| async move {
+            let shard_keys = store.find_keys_by_prefix(key_prefix).await?;
+            shard_keys
+                .iterator()
+                .map(|key| key.map(<[u8]>::to_vec))
+                .collect::<Result<Vec<_>, _>>()
+        }))
+        .await?;
+        let mut keys = keys_by_shard.into_iter().flatten().collect::<Vec<_>>();
+        // Every key is in a single shard, so sorting merges the shards without duplicates.
+        keys.sort_unstable();
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, S::Error> {
+        let key_values_by_shard =
+            future::try_join_all(self.stores.iter().map(|store| async move {
+                let shard_key_values = store.find_key_values_by_prefix(key_prefix).await?;
+                shard_key_values
+                    .into_iterator_owned()
+                    .collect::<Result<Vec<_>, _>>()
+            }))
+            .await?;
+        let mut key_values = key_values_by_shard
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        key_values.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+        Ok(key_values)
+    }
+
+    async fn health_check(&self) -> Result<(), S::Error> {
+        future::try_join_all(self.stores.iter().map(|store| store.health_check())).await?;
+        Ok(())
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, S::Error> {
+        let counts = future::try_join_all(
+            self.stores
+                .iter()
+                .map(|store| store.approximate_key_count(key_prefix)),
+        )
+        .await?;
+        Ok(counts.into_iter().sum())
+    }
+}
+
+impl<S> WritableKeyValueStore<S::Error> for ShardedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), S::Error> {
+        let mut batches = vec![Batch::new(); self.stores.len()];
+        for operation in batch.operations {
+            match &operation {
+                WriteOperation::Delete { key } | WriteOperation::Put { key, .. } => {
+                    batches[self.shard_index(key)].operations.push(operation);
+                }
+                // Keys with the prefix may be in any shard.
+                WriteOperation::DeletePrefix { .. } => {
+                    for batch in &mut batches {
+                        batch.operations.push(operation.clone());
+                    }
+                }
+            }
+        }
+        future::try_join_all(
+            self.stores
+                .iter()
+                .zip(batches)
+                .filter(|(_, batch)| !batch.operations.is_empty())
+                .map(|(store, batch)| store.write_batch(batch, base_key)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), S::Error> {
+        future::try_join_all(
+            self.stores
+                .iter()
+                .map(|store| store.clear_journal(base_key)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), S::Error> {
+        future::try_join_all(self.stores.iter().map(|store| store.maintenance(op))).await?;
+        Ok(())
+    }
+}
+
+impl<S> CompareAndSwapKeyValueStore<S::Error> for ShardedStore<S>
+where
+    S: KeyValueStore + CompareAndSwapKeyValueStore<<S as KeyValueStore>::Error> + Send + Sync,
+    S::Error: Send,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, S::Error> {
+        self.shard(key).compare_and_swap(key, expected, new).await
+    }
+}
+
+impl<S> KeyValueStore for ShardedStore<S>
+where
+    S: KeyValueStore + Send + Sync,
+    S::Error: Send,
+{
+    type Error = S::Error;
+}
+
+impl<S> AdminKeyValueStore for ShardedStore<S>
+where
+    S: AdminKeyValueStore + Send,
+    S::Error: Send + From<ShardedStoreError>,
+{
+    type Error = S::Error;
+    type Config = ShardedStoreConfig<S::Config>;
+
+    async fn connect(config: &Self::Config, namespace: &str) -> Result<Self, Self::Error> {
+        let stores = future::try_join_all(
+            config
+                .checked_shards()?
+                .iter()
+                .map(|config| S::connect(config, namespace)),
+        )
+        .await?;
+        Ok(Self::new(stores))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        let namespaces_by_shard = future::try_join_all(
+            config
+                .checked_shards()?
+                .iter()
+                .map(|config| S::list_all(config)),
+        )
+        .await?;
+        let mut namespaces_by_shard = namespaces_by_shard.into_iter();
+        let mut namespaces = namespaces_by_shard.next().unwrap_or_default();
+        // Only list the namespaces that exist in all the shards.
+        for shard_namespaces in namespaces_by_shard {
+            namespaces.retain(|namespace| shard_namespaces.contains(namespace));
+        }
+        Ok(namespaces)
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        let exists_by_shard = future::try_join_all(
+            config
+                .checked_shards()?
+                .iter()
+                .map(|config| S::exists(config, namespace)),
+        )
+        .await?;
+        Ok(exists_by_shard.into_iter().all(|exists| exists))
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        future::try_join_all(
+            config
+                .checked_shards()?
+                .iter()
+                .map(|config| S::create(config, namespace)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        future::try_join_all(
+            config
+                .checked_shards()?
+                .iter()
+                .map(|config| S::delete(config, namespace)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Creates a store sharding its keys across `num_shards` memory stores, for tests.
+#[cfg(with_testing)]
+pub fn create_sharded_memory_store(num_shards: usize) -> ShardedStore<MemoryStore> {
+    ShardedStore::new((0..num_shards).map(|_| create_memory_store()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        batch::Batch,
+        common::{AdminKeyValueStore as _, ReadableKeyValueStore as _, WritableKeyValueStore as _},
+        memory::{create_memory_store, MemoryContextError, MemoryStore},
+        sharded::{ShardedStore, ShardedStoreConfig, ShardedStoreError},
+    };
+
+    #[tokio::test]
+    async fn test_sharded_store_spreads_keys_across_shards() {
+        let store = ShardedStore::new((0..4).map(|_| create_memory_store()).collect());
+        let mut batch = Batch::new();
+        for i in 0..64u8 {
+            batch.put_key_value_bytes(vec![0, i], vec![i]);
+        }
+        store.write_batch(batch, &[]).await.unwrap();
+
+        let mut total = 0;
+        for shard in &store.stores {
+            let count = shard.find_keys_by_prefix(&[0]).await.unwrap().len();
+            assert!(count > 0 && count < 64);
+            total += count;
+        }
+        assert_eq!(total, 64);
+        let keys = store.find_keys_by_prefix(&[0]).await.unwrap();
+        assert_eq!(keys, (0..64u8).map(|i| vec![i]).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_sharded_store_rejects_configurations_without_shards() {
+        let config = ShardedStoreConfig { shards: vec![] };
+        let result = ShardedStore::<MemoryStore>::connect(&config, "namespace").await;
+        assert!(matches!(
+            result,
+            Err(MemoryContextError::ShardedStoreError(
+                ShardedStoreError::NoShards
+            ))
+        ));
+        let result = ShardedStore::<MemoryStore>::create(&config, "namespace").await;
+        assert!(result.is_err());
+    }
+}
@@ -7,6 +7,7 @@ use std::{
     fmt::Debug,
 };
 
+use futures::TryStreamExt as _;
 use rand::{Rng, SeedableRng};
 use tracing::warn;
 
@@ -15,7 +16,11 @@ use crate::{
         Batch, WriteOperation,
         WriteOperation::{Delete, Put},
     },
-    common::{KeyIterable, KeyValueIterable, LocalAdminKeyValueStore, LocalKeyValueStore},
+    common::{
+        KeyIterable, KeyValueIterable, LocalAdminKeyValueStore, LocalCompareAndSwapKeyValueStore,
+        LocalKeyValueStore, MaintenanceOp,
+    },
+    value_splitting::DatabaseConsistencyError,
 };
 
 // The following seed is chosen to have equal numbers of 1s and 0s, as advised by
@@ -654,6 +659,199 @@ pub async fn run_writes_from_state<C: LocalKeyValueStore>(key_value_store: &C) {
     }
 }
 
+/// Clears a prefix of a populated store and checks that only the keys under that prefix
+/// were deleted.
+pub async fn run_clear_prefix<C: LocalKeyValueStore + Sync>(key_value_store: &C) {
+    let mut rng = make_deterministic_rng();
+    let key_prefix = get_random_key_prefix();
+    let mut batch = Batch::new();
+    for prefix_end in 0..4 {
+        let mut prefix = key_prefix.clone();
+        prefix.push(prefix_end);
+        for key_value in get_random_key_values_prefix(&mut rng, prefix, 8, 20, 10) {
+            batch.put_key_value_bytes(key_value.0, key_value.1);
+        }
+    }
+    let mut key_values = realize_batch(&batch);
+    key_value_store.write_batch(batch, &[]).await.unwrap();
+
+    let mut cleared_prefix = key_prefix.clone();
+    cleared_prefix.push(1);
+    key_value_store.clear_prefix(&cleared_prefix).await.unwrap();
+    key_values.retain(|key, _| !key.starts_with(&cleared_prefix));
+    assert_eq!(
+        read_key_values_prefix(key_value_store, &key_prefix).await,
+        key_values
+    );
+    assert!(read_key_values_prefix(key_value_store, &cleared_prefix)
+        .await
+        .is_empty());
+}
+
+/// Runs the maintenance operations on a populated store and checks that its keys are
+/// unchanged.
+pub async fn run_maintenance<C: LocalKeyValueStore + Sync>(key_value_store: &C) {
+    let mut rng = make_deterministic_rng();
+    let key_prefix = get_random_key_prefix();
+    let mut batch = Batch::new();
+    for key_value in get_random_key_values_prefix(&mut rng, key_prefix.clone(), 8, 20, 100) {
+        batch.put_key_value_bytes(key_value.0, key_value.1);
+    }
+    let key_values = realize_batch(&batch);
+    key_value_store.write_batch(batch, &[]).await.unwrap();
+
+    for op in [MaintenanceOp::Flush, MaintenanceOp::Compact] {
+        key_value_store.maintenance(op).await.unwrap();
+        assert_eq!(
+            read_key_values_prefix(key_value_store, &key_prefix).await,
+            key_values
+        );
+    }
+}
+
+/// Swaps two prefixes of a populated store and checks that their keys were exchanged,
+/// while the keys under another prefix were left untouched. Swapping a prefix with
+/// itself must do nothing, and swapping overlapping prefixes must fail.
+pub async fn run_swap_prefixes<C>(key_value_store: &C)
+where
+    C: LocalKeyValueStore + Sync,
+    C::Error: From<DatabaseConsistencyError>,
+{
+    let mut rng = make_deterministic_rng();
+    let key_prefix = get_random_key_prefix();
+    let mut batch = Batch::new();
+    for prefix_end in 0..3 {
+        let mut prefix = key_prefix.clone();
+        prefix.push(prefix_end);
+        for key_value in get_random_key_values_prefix(&mut rng, prefix, 8, 20, 10) {
+            batch.put_key_value_bytes(key_value.0, key_value.1);
+        }
+    }
+    let key_values = realize_batch(&batch);
+    key_value_store.write_batch(batch, &[]).await.unwrap();
+
+    let prefix1 = [key_prefix.clone(), vec![1]].concat();
+    let prefix2 = [key_prefix.clone(), vec![2]].concat();
+    key_value_store
+        .swap_prefixes(&prefix1, &prefix1)
+        .await
+        .unwrap();
+    assert!(key_value_store
+        .swap_prefixes(&key_prefix, &prefix1)
+        .await
+        .is_err());
+    assert_eq!(
+        read_key_values_prefix(key_value_store, &key_prefix).await,
+        key_values
+    );
+    key_value_store
+        .swap_prefixes(&prefix1, &prefix2)
+        .await
+        .unwrap();
+    let expected = key_values
+        .into_iter()
+        .map(|(key, value)| {
+            if let Some(suffix) = key.strip_prefix(prefix1.as_slice()) {
+                ([prefix2.as_slice(), suffix].concat(), value)
+            } else if let Some(suffix) = key.strip_prefix(prefix2.as_slice()) {
+                ([prefix1.as_slice(), suffix].concat(), value)
+            } else {
+                (key, value)
+            }
+        })
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(
+        read_key_values_prefix(key_value_store, &key_prefix).await,
+        expected
+    );
+}
+
+/// Checks that `scan_stream` yields the same key-value pairs, in the same order, as
+/// `find_key_values_by_prefix`, including for prefixes spanning several pages and for
+/// values that are split into several segments.
+pub async fn run_scan_stream<C: LocalKeyValueStore + Sync>(key_value_store: &C) {
+    let mut rng = make_deterministic_rng();
+    let key_prefix = get_random_key_prefix();
+    let mut batch = Batch::new();
+    for (len_value, n) in [(10, 1500), (1000, 10)] {
+        for key_value in get_random_key_values_prefix(&mut rng, key_prefix.clone(), 8, len_value, n)
+        {
+            batch.put_key_value_bytes(key_value.0, key_value.1);
+        }
+    }
+    key_value_store.write_batch(batch, &[]).await.unwrap();
+
+    for prefix in [key_prefix.clone(), [key_prefix.clone(), vec![0]].concat()] {
+        let expected = key_value_store
+            .find_key_values_by_prefix(&prefix)
+            .await
+            .unwrap()
+            .into_iterator_owned()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let streamed = key_value_store
+            .scan_stream(&prefix)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(streamed, expected);
+    }
+}
+
+/// Checks that `approximate_key_count` counts the keys matching a prefix. The stores
+/// count exactly when they hold as few keys as in this test.
+pub async fn run_approximate_key_count<C: LocalKeyValueStore + Sync>(key_value_store: &C) {
+    let mut rng = make_deterministic_rng();
+    let key_prefix = get_random_key_prefix();
+    let mut batch = Batch::new();
+    for key_value in get_random_key_values_prefix(&mut rng, key_prefix.clone(), 8, 10, 200) {
+        batch.put_key_value_bytes(key_value.0, key_value.1);
+    }
+    key_value_store.write_batch(batch, &[]).await.unwrap();
+
+    for prefix in [key_prefix.clone(), [key_prefix.clone(), vec![0]].concat()] {
+        let keys = key_value_store.find_keys_by_prefix(&prefix).await.unwrap();
+        let count = key_value_store
+            .approximate_key_count(&prefix)
+            .await
+            .unwrap();
+        assert_eq!(count, keys.iterator().count() as u64);
+    }
+    let count = key_value_store
+        .approximate_key_count(&get_random_key_prefix())
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+/// Checks that `compare_and_swap` only writes the new value when the current one is the
+/// expected one.
+pub async fn run_compare_and_swap<C>(key_value_store: &C)
+where
+    C: LocalKeyValueStore + LocalCompareAndSwapKeyValueStore<<C as LocalKeyValueStore>::Error>,
+{
+    let mut key = get_random_key_prefix();
+    key.push(0);
+    let swap = |expected: Option<&'static [u8]>, new: &'static [u8]| {
+        let key = key.clone();
+        async move {
+            key_value_store
+                .compare_and_swap(&key, expected, new)
+                .await
+                .unwrap()
+        }
+    };
+    assert!(!swap(Some(b"a"), b"b").await);
+    assert!(swap(None, b"a").await);
+    assert!(!swap(None, b"b").await);
+    assert!(!swap(Some(b"b"), b"c").await);
+    assert!(swap(Some(b"a"), b"c").await);
+    assert_eq!(
+        key_value_store.read_value_bytes(&key).await.unwrap(),
+        Some(b"c".to_vec())
+    );
+}
+
 async fn namespaces_with_prefix<S: LocalAdminKeyValueStore>(
     config: &S::Config,
     prefix: &str,
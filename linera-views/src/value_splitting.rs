@@ -3,15 +3,16 @@
 
 use std::fmt::Debug;
 
-use futures::FutureExt as _;
+use futures::{stream, FutureExt as _, Stream, StreamExt as _};
 use linera_base::ensure;
 use thiserror::Error;
 
 use crate::{
     batch::{Batch, WriteOperation},
     common::{
-        AdminKeyValueStore, CommonStoreConfig, ContextFromStore, KeyIterable, KeyValueIterable,
-        KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+        AdminKeyValueStore, CommonStoreConfig, CompareAndSwapKeyValueStore, ContextFromStore,
+        KeyIterable, KeyValueIterable, KeyValueStore, MaintenanceOp, ReadableKeyValueStore,
+        WritableKeyValueStore,
     },
     memory::{MemoryContextError, MemoryStore, MemoryStoreConfig, TEST_MEMORY_MAX_STREAM_QUERIES},
 };
@@ -30,6 +31,14 @@ pub enum DatabaseConsistencyError {
     /// no count of size u32 is available in the value
     #[error("no count of size u32 is available in the value")]
     NoCountAvailable,
+
+    /// The value does not fit in a single segment, so it cannot be compared and swapped atomically
+    #[error("the value does not fit in a single segment, so it cannot be compared and swapped atomically")]
+    TooLargeCompareAndSwapValue,
+
+    /// The keys of two prefixes cannot be swapped, since one is a prefix of the other
+    #[error("the keys of two prefixes cannot be swapped, since one is a prefix of the other")]
+    OverlappingPrefixes,
 }
 
 /// A key-value store with no size limit for values.
@@ -192,6 +201,53 @@ where
         }
         Ok(key_values)
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), K::Error>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        let small_key_values = self.store.scan_stream(key_prefix).boxed();
+        stream::try_unfold(small_key_values, |mut small_key_values| async move {
+            loop {
+                let Some(result) = small_key_values.next().await else {
+                    return Ok(None);
+                };
+                let (mut big_key, value) = result?;
+                if Self::read_index_from_key(&big_key)? != 0 {
+                    continue; // Leftover segment from an earlier value.
+                }
+                big_key.truncate(big_key.len() - 4);
+                let key = big_key;
+                let count = Self::read_count_from_value(&value)?;
+                let mut big_value = value[4..].to_vec();
+                for idx in 1..count {
+                    let (big_key, value) = small_key_values
+                        .next()
+                        .await
+                        .ok_or(DatabaseConsistencyError::MissingSegment)??;
+                    ensure!(
+                        Self::read_index_from_key(&big_key)? == idx
+                            && big_key.starts_with(&key)
+                            && big_key.len() == key.len() + 4,
+                        DatabaseConsistencyError::MissingSegment
+                    );
+                    big_value.extend(value);
+                }
+                return Ok(Some(((key, big_value), small_key_values)));
+            }
+        })
+    }
+
+    async fn health_check(&self) -> Result<(), K::Error> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, K::Error> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl<K> WritableKeyValueStore<K::Error> for ValueSplittingStore<K>
@@ -237,6 +293,39 @@ where
     async fn clear_journal(&self, base_key: &[u8]) -> Result<(), K::Error> {
         self.store.clear_journal(base_key).await
     }
+
+    async fn maintenance(&self, op: MaintenanceOp) -> Result<(), K::Error> {
+        self.store.maintenance(op).await
+    }
+}
+
+/// Only values fitting in a single segment can be compared and swapped, since the
+/// segments of larger values are not written atomically.
+impl<K> CompareAndSwapKeyValueStore<K::Error> for ValueSplittingStore<K>
+where
+    K: KeyValueStore + CompareAndSwapKeyValueStore<K::Error> + Send + Sync,
+    K::Error: From<bcs::Error> + From<DatabaseConsistencyError>,
+{
+    async fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, K::Error> {
+        let max_len = K::MAX_VALUE_SIZE - 4;
+        ensure!(
+            new.len() <= max_len && expected.map_or(true, |value| value.len() <= max_len),
+            DatabaseConsistencyError::TooLargeCompareAndSwapValue
+        );
+        let big_key = Self::get_segment_key(key, 0)?;
+        let expected = expected
+            .map(|value| Self::get_initial_count_first_chunk(1, value))
+            .transpose()?;
+        let new = Self::get_initial_count_first_chunk(1, new)?;
+        self.store
+            .compare_and_swap(&big_key, expected.as_deref(), &new)
+            .await
+    }
 }
 
 impl<K> AdminKeyValueStore for ValueSplittingStore<K>
@@ -461,6 +550,24 @@ impl ReadableKeyValueStore<MemoryContextError> for TestMemoryStore {
     ) -> Result<Self::KeyValues, MemoryContextError> {
         self.store.find_key_values_by_prefix(key_prefix).await
     }
+
+    fn scan_stream<'a>(
+        &'a self,
+        key_prefix: &'a [u8],
+    ) -> impl Stream<Item = Result<(Vec<u8>, Vec<u8>), MemoryContextError>> + Send + 'a
+    where
+        Self: Sync,
+    {
+        self.store.scan_stream(key_prefix)
+    }
+
+    async fn health_check(&self) -> Result<(), MemoryContextError> {
+        self.store.health_check().await
+    }
+
+    async fn approximate_key_count(&self, key_prefix: &[u8]) -> Result<u64, MemoryContextError> {
+        self.store.approximate_key_count(key_prefix).await
+    }
 }
 
 impl WritableKeyValueStore<MemoryContextError> for TestMemoryStore {
@@ -4,14 +4,14 @@
 use std::{fmt::Debug, io::Write};
 
 use async_trait::async_trait;
-use linera_base::{crypto::CryptoHash, data_types::ArithmeticError};
+use linera_base::{crypto::CryptoHash, data_types::ArithmeticError, time::Duration};
 pub use linera_views_derive::{
     ClonableView, CryptoHashRootView, CryptoHashView, HashableView, RootView, View,
 };
 use serde::Serialize;
 use thiserror::Error;
 
-use crate::{batch::Batch, common::HasherOutput};
+use crate::{batch::Batch, common::HasherOutput, value_splitting::DatabaseConsistencyError};
 
 #[cfg(test)]
 #[path = "unit_tests/views.rs"]
@@ -27,6 +27,9 @@ pub trait View<C>: Sized {
     /// Creates a view or a subview.
     async fn load(context: C) -> Result<Self, ViewError>;
 
+    /// Returns `true` if flushing this view would persist changes to storage.
+    async fn has_pending_changes(&self) -> bool;
+
     /// Discards all pending changes. After that `flush` should have no effect to storage.
     fn rollback(&mut self);
 
@@ -90,6 +93,13 @@ pub enum ViewError {
     #[error("Entry does not exist in memory: {0}")]
     NotFound(String),
 
+    /// An optimistic update failed because the entry did not have the expected value.
+    #[error("Conflicting update of {key}")]
+    Conflict {
+        /// A description of the entry that was concurrently modified.
+        key: String,
+    },
+
     /// The database is corrupt: Entries don't have the expected hash.
     #[error("Inconsistent database entries")]
     InconsistentEntries,
@@ -101,6 +111,21 @@ pub enum ViewError {
     /// The value is too large for the client
     #[error("The value is too large for the client")]
     TooLargeValue,
+
+    /// A value couldn't be decrypted: the encryption key is wrong or the entry is corrupt.
+    #[error("Failed to decrypt a value")]
+    DecryptionFailed,
+
+    /// The keys could not be read or written consistently.
+    #[error(transparent)]
+    DatabaseConsistency(#[from] DatabaseConsistencyError),
+
+    /// The storage backend is over its capacity or quota and rejected the operation.
+    #[error("The storage backend is throttling requests")]
+    Throttled {
+        /// How long the backend asked to wait before retrying, if it said so.
+        retry_after: Option<Duration>,
+    },
 }
 
 impl ViewError {
@@ -108,6 +133,32 @@ impl ViewError {
     pub fn not_found<T: Debug>(msg: &str, key: T) -> ViewError {
         ViewError::NotFound(format!("{} {:?}", msg, key))
     }
+
+    /// Returns whether this is a `NotFound` error, i.e. the entry doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ViewError::NotFound(_))
+    }
+
+    /// Returns whether this is a `Throttled` error, i.e. the operation should be retried
+    /// later.
+    pub fn is_throttled(&self) -> bool {
+        matches!(self, ViewError::Throttled { .. })
+    }
+
+    /// Returns how long the storage backend asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ViewError::Throttled { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Creates a `Conflict` error with the given message and key.
+    pub fn conflict<T: Debug>(msg: &str, key: T) -> ViewError {
+        ViewError::Conflict {
+            key: format!("{} {:?}", msg, key),
+        }
+    }
 }
 
 /// A view that supports hashing its values.
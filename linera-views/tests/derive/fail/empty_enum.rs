@@ -0,0 +1,9 @@
+#![allow(dead_code)]
+
+use linera_views::views::View;
+
+#[derive(View)]
+#[view(context = "linera_views::memory::MemoryContext<()>")]
+enum TestView {}
+
+fn main() {}
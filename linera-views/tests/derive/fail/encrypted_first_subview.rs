@@ -0,0 +1,12 @@
+#![allow(dead_code)]
+
+use linera_views::{encryption::EncryptedContext, register_view::RegisterView, views::View};
+
+#[derive(View)]
+struct TestView<C> {
+    #[view(encrypted)]
+    secret: RegisterView<EncryptedContext<C>, u32>,
+    register: RegisterView<C, u32>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#![allow(dead_code)]
+
+use linera_views::{register_view::RegisterView, views::RootView};
+
+#[derive(RootView)]
+enum TestView<C> {
+    Counter(RegisterView<C, u64>),
+}
+
+fn main() {}
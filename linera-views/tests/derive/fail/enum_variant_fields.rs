@@ -0,0 +1,10 @@
+#![allow(dead_code)]
+
+use linera_views::{register_view::RegisterView, views::View};
+
+#[derive(View)]
+enum TestView<C> {
+    Pair(RegisterView<C, u32>, RegisterView<C, u32>),
+}
+
+fn main() {}
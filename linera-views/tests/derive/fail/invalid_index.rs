@@ -0,0 +1,11 @@
+#![allow(dead_code)]
+
+use linera_views::{register_view::RegisterView, views::View};
+
+#[derive(View)]
+struct TestView<C> {
+    #[view(index = "-1")]
+    register: RegisterView<C, u32>,
+}
+
+fn main() {}
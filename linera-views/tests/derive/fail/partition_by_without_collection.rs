@@ -0,0 +1,15 @@
+#![allow(dead_code)]
+
+use linera_views::{map_view::MapView, views::View};
+
+fn shard(key: &u64) -> u8 {
+    (key % 4) as u8
+}
+
+#[derive(View)]
+struct TestView<C> {
+    #[view(partition_by = shard)]
+    balances: MapView<C, u64, u64>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#![allow(dead_code)]
+
+use linera_views::views::View;
+
+#[derive(View)]
+union TestView {
+    value: u32,
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+// A root view using the attributes of the struct and of its subviews together.
+
+use linera_views::{
+    introspection::IntrospectableView,
+    lazy_view::LazyView,
+    log_view::LogView,
+    memory::{create_memory_context, MemoryContext},
+    optional_view::OptionalView,
+    register_view::RegisterView,
+    views::{RootView, View},
+};
+
+#[derive(RootView)]
+#[view(sequential_load)]
+#[view(expose_context)]
+#[view(introspect)]
+#[view(diff)]
+struct ConfigView<C> {
+    #[view(index = "2")]
+    name: RegisterView<C, String>,
+    #[view(lazy)]
+    archive: LazyView<C, LogView<C, u64>>,
+    #[view(index = "0")]
+    counter: RegisterView<C, u64>,
+    extra: OptionalView<C, RegisterView<C, u64>>,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let context = create_memory_context();
+    let mut view = ConfigView::load(context.clone()).await.unwrap();
+    view.name.set("config".to_owned());
+    view.counter.set(1);
+    view.load_archive().await.unwrap().push(1);
+    view.extra.get_or_insert().await.unwrap().set(2);
+    view.save().await.unwrap();
+
+    let mut view = ConfigView::load(context).await.unwrap();
+    assert_eq!(view.name.get(), "config");
+    assert_eq!(*view.counter.get(), 1);
+    assert_eq!(view.load_archive().await.unwrap().count(), 1);
+    assert_eq!(view.extra.get().map(|extra| *extra.get()), Some(2));
+    assert_eq!(ConfigView::<MemoryContext<()>>::LAYOUT.fields.len(), 4);
+}
@@ -0,0 +1,29 @@
+// A root view with a concrete context, set with `#[view(context = "...")]`.
+
+use linera_views::{
+    collection_view::CollectionView,
+    memory::{create_memory_context, MemoryContext},
+    register_view::RegisterView,
+    views::{RootView, View},
+};
+
+#[derive(RootView)]
+#[view(context = "MemoryContext<()>")]
+struct CountersView {
+    total: RegisterView<MemoryContext<()>, u64>,
+    counters: CollectionView<MemoryContext<()>, String, RegisterView<MemoryContext<()>, u64>>,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let context = create_memory_context();
+    let mut view = CountersView::load(context.clone()).await.unwrap();
+    view.total.set(2);
+    view.counters.load_entry_mut("a").await.unwrap().set(2);
+    view.save().await.unwrap();
+
+    let view = CountersView::load(context).await.unwrap();
+    assert_eq!(*view.total.get(), 2);
+    let counter = view.counters.try_load_entry("a").await.unwrap().unwrap();
+    assert_eq!(*counter.get(), 2);
+}
@@ -0,0 +1,30 @@
+// A view enum, where each variant wraps one subview, saved through an `EnumView`.
+
+use linera_views::{
+    enum_view::EnumView,
+    log_view::LogView,
+    memory::create_memory_context,
+    register_view::RegisterView,
+    views::{RootView, View},
+};
+
+#[derive(View)]
+#[view(introspect)]
+enum ModeView<C> {
+    Counter(RegisterView<C, u64>),
+    Log(LogView<C, u32>),
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let context = create_memory_context();
+    let mut view = EnumView::<_, ModeView<_>>::load(context.clone()).await.unwrap();
+    let ModeView::Counter(counter) = view.get_mut() else {
+        panic!("the first variant should be the default one");
+    };
+    counter.set(5);
+    view.save().await.unwrap();
+
+    let view = EnumView::<_, ModeView<_>>::load(context).await.unwrap();
+    assert!(matches!(view.get(), ModeView::Counter(counter) if *counter.get() == 5));
+}
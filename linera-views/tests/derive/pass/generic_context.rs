@@ -0,0 +1,32 @@
+// A root view whose context is its first generic parameter, with another generic
+// parameter constrained by a where clause.
+
+use linera_views::{
+    log_view::LogView,
+    memory::create_memory_context,
+    register_view::RegisterView,
+    views::{RootView, View},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+#[derive(RootView)]
+struct StateView<C, T>
+where
+    T: Clone + Default + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    value: RegisterView<C, T>,
+    history: LogView<C, T>,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let context = create_memory_context();
+    let mut view = StateView::<_, String>::load(context.clone()).await.unwrap();
+    view.value.set("hello".to_owned());
+    view.history.push("hello".to_owned());
+    view.save().await.unwrap();
+
+    let view = StateView::<_, String>::load(context).await.unwrap();
+    assert_eq!(view.value.get(), "hello");
+    assert_eq!(view.history.count(), 1);
+}
@@ -0,0 +1,42 @@
+// A root view with a map partitioned by the remainder of its keys.
+
+use linera_views::{
+    collection_view::CollectionView,
+    map_view::MapView,
+    memory::create_memory_context,
+    views::{RootView, View},
+};
+
+mod partitions {
+    pub fn shard(key: &u64) -> u8 {
+        (key % 4) as u8
+    }
+}
+
+#[derive(RootView)]
+struct LedgerView<C> {
+    #[view(partition_by = partitions::shard)]
+    balances: CollectionView<C, u8, MapView<C, u64, u64>>,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let context = create_memory_context();
+    let mut view = LedgerView::load(context.clone()).await.unwrap();
+    for key in [1, 5, 6] {
+        view.load_balances_partition(&key)
+            .await
+            .unwrap()
+            .insert(&key, 10 * key)
+            .unwrap();
+    }
+    view.save().await.unwrap();
+
+    let view = LedgerView::load(context).await.unwrap();
+    assert_eq!(view.balances.indices().await.unwrap(), [1, 2]);
+    let partition = view.balances_partition(&5).await.unwrap().unwrap();
+    assert_eq!(partition.indices().await.unwrap(), [1, 5]);
+    assert_eq!(partition.get(&5).await.unwrap(), Some(50));
+    drop(partition);
+    assert!(view.balances_partition(&3).await.unwrap().is_none());
+}
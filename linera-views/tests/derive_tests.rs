@@ -0,0 +1,14 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks the inputs that the derive macros for views accept, and the errors they report
+//! for the others.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+#[test]
+fn test_derive_macros() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/derive/pass/*.rs");
+    cases.compile_fail("tests/derive/fail/*.rs");
+}
@@ -6,9 +6,12 @@ use linera_views::{
     common::{ReadableKeyValueStore, WritableKeyValueStore},
     key_value_store_view::ViewContainer,
     memory::{create_memory_context, create_memory_store},
+    namespaced::create_namespaced_memory_store,
+    sharded::create_sharded_memory_store,
     test_utils::{
-        self, get_random_test_scenarios, run_big_write_read, run_reads, run_writes_from_blank,
-        run_writes_from_state,
+        self, get_random_test_scenarios, run_approximate_key_count, run_big_write_read,
+        run_clear_prefix, run_compare_and_swap, run_maintenance, run_reads, run_scan_stream,
+        run_swap_prefixes, run_writes_from_blank, run_writes_from_state,
     },
     value_splitting::create_test_memory_store,
 };
@@ -247,3 +250,261 @@ async fn test_scylla_db_writes_from_state() {
     let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
     run_writes_from_state(&key_value_store).await;
 }
+
+#[tokio::test]
+async fn test_memory_clear_prefix() {
+    let key_value_store = create_memory_store();
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_key_value_store_view_memory_clear_prefix() {
+    let context = create_memory_context();
+    let key_value_store = ViewContainer::new(context).await.unwrap();
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_clear_prefix() {
+    let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_dynamo_db_clear_prefix() {
+    let key_value_store = linera_views::dynamo_db::create_dynamo_db_test_store().await;
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_scylla_db_clear_prefix() {
+    let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_memory_maintenance() {
+    let key_value_store = create_memory_store();
+    run_maintenance(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_maintenance() {
+    let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+    run_maintenance(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_memory_swap_prefixes() {
+    let key_value_store = create_memory_store();
+    run_swap_prefixes(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_key_value_store_view_memory_swap_prefixes() {
+    let context = create_memory_context();
+    let key_value_store = ViewContainer::new(context).await.unwrap();
+    run_swap_prefixes(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_swap_prefixes() {
+    let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+    run_swap_prefixes(&key_value_store).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_dynamo_db_swap_prefixes() {
+    let key_value_store = linera_views::dynamo_db::create_dynamo_db_test_store().await;
+    run_swap_prefixes(&key_value_store).await;
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_scylla_db_swap_prefixes() {
+    let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
+    run_swap_prefixes(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_memory_compare_and_swap() {
+    let key_value_store = create_memory_store();
+    run_compare_and_swap(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_compare_and_swap() {
+    let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+    run_compare_and_swap(&key_value_store).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_dynamo_db_compare_and_swap() {
+    let key_value_store = linera_views::dynamo_db::create_dynamo_db_test_store().await;
+    run_compare_and_swap(&key_value_store).await;
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_scylla_db_compare_and_swap() {
+    let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
+    run_compare_and_swap(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_memory_scan_stream() {
+    let key_value_store = create_memory_store();
+    run_scan_stream(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_test_memory_scan_stream() {
+    let key_value_store = create_test_memory_store();
+    run_scan_stream(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_scan_stream() {
+    let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+    run_scan_stream(&key_value_store).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_dynamo_db_scan_stream() {
+    let key_value_store = linera_views::dynamo_db::create_dynamo_db_test_store().await;
+    run_scan_stream(&key_value_store).await;
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_scylla_db_scan_stream() {
+    let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
+    run_scan_stream(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_memory_approximate_key_count() {
+    let key_value_store = create_memory_store();
+    run_approximate_key_count(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_sharded_memory_approximate_key_count() {
+    let key_value_store = create_sharded_memory_store(3);
+    run_approximate_key_count(&key_value_store).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_rocks_db_approximate_key_count() {
+    let (key_value_store, _dir) = linera_views::rocks_db::create_rocks_db_test_store().await;
+    run_approximate_key_count(&key_value_store).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_dynamo_db_approximate_key_count() {
+    let key_value_store = linera_views::dynamo_db::create_dynamo_db_test_store().await;
+    run_approximate_key_count(&key_value_store).await;
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_scylla_db_approximate_key_count() {
+    let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
+    run_approximate_key_count(&key_value_store).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_dynamo_db_health_check() {
+    let key_value_store = linera_views::dynamo_db::create_dynamo_db_test_store().await;
+    key_value_store.health_check().await.unwrap();
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_scylla_db_health_check() {
+    let key_value_store = linera_views::scylla_db::create_scylla_db_test_store().await;
+    key_value_store.health_check().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reads_sharded_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store = create_sharded_memory_store(3);
+        run_reads(key_value_store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_sharded_memory_writes_from_blank() {
+    let key_value_store = create_sharded_memory_store(3);
+    run_writes_from_blank(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_sharded_memory_writes_from_state() {
+    let key_value_store = create_sharded_memory_store(3);
+    run_writes_from_state(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_sharded_memory_clear_prefix() {
+    let key_value_store = create_sharded_memory_store(3);
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_sharded_memory_compare_and_swap() {
+    let key_value_store = create_sharded_memory_store(3);
+    run_compare_and_swap(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_sharded_memory_scan_stream() {
+    let key_value_store = create_sharded_memory_store(3);
+    run_scan_stream(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_reads_namespaced_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store = create_namespaced_memory_store(vec![7]);
+        run_reads(key_value_store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_namespaced_memory_writes_from_blank() {
+    let key_value_store = create_namespaced_memory_store(vec![7]);
+    run_writes_from_blank(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_namespaced_memory_writes_from_state() {
+    let key_value_store = create_namespaced_memory_store(vec![7]);
+    run_writes_from_state(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_namespaced_memory_clear_prefix() {
+    let key_value_store = create_namespaced_memory_store(vec![7]);
+    run_clear_prefix(&key_value_store).await;
+}
+
+#[tokio::test]
+async fn test_namespaced_memory_compare_and_swap() {
+    let key_value_store = create_namespaced_memory_store(vec![7]);
+    run_compare_and_swap(&key_value_store).await;
+}
@@ -18,24 +18,35 @@ use linera_views::{
         WriteOperation::{Delete, DeletePrefix, Put},
     },
     collection_view::HashedCollectionView,
-    common::Context,
+    common::{
+        Context, ContextFromStore, KeyValueStore, ReadableKeyValueStore, WritableKeyValueStore,
+        MIN_VIEW_TAG,
+    },
+    diff::{DiffableView, ViewDiff},
+    enum_view::EnumView,
+    introspection::ViewRegistry,
     key_value_store_view::{KeyValueStoreMemoryContext, KeyValueStoreView, ViewContainer},
-    log_view::HashedLogView,
+    lazy_view::LazyView,
+    log_view::{HashedLogView, LogView},
     lru_caching::{LruCachingMemoryContext, LruCachingStore},
     map_view::HashedMapView,
     memory::{
-        create_memory_context, MemoryContext, MemoryStore, MemoryStoreMap,
-        TEST_MEMORY_MAX_STREAM_QUERIES,
+        create_memory_context, create_memory_store, MemoryContext, MemoryContextError, MemoryStore,
+        MemoryStoreMap, TEST_MEMORY_MAX_STREAM_QUERIES,
     },
-    queue_view::HashedQueueView,
+    optional_view::OptionalView,
+    queue_view::{HashedQueueView, QueueView},
     reentrant_collection_view::HashedReentrantCollectionView,
-    register_view::HashedRegisterView,
+    register_view::{HashedRegisterView, RegisterView},
     set_view::HashedSetView,
     test_utils::{
         self, get_random_byte_vector, get_random_key_value_operations, get_random_key_values,
         random_shuffle, span_random_reordering_put_delete,
     },
-    views::{CryptoHashRootView, HashableView, Hasher, RootView, View, ViewError},
+    views::{
+        ClonableView, CryptoHashRootView, CryptoHashView, HashableView, Hasher, RootView, View,
+        ViewError,
+    },
 };
 #[cfg(with_dynamodb)]
 use linera_views::{
@@ -1262,3 +1273,457 @@ async fn check_large_write_memory() {
     let mut store = MemoryTestStore::new().await;
     check_large_write(&mut store, vector).await;
 }
+
+/// A memory store that records the batches written to it.
+#[derive(Clone)]
+pub struct RecordingStore {
+    store: MemoryStore,
+    batches: Arc<std::sync::Mutex<Vec<Batch>>>,
+}
+
+impl ReadableKeyValueStore<MemoryContextError> for RecordingStore {
+    const MAX_KEY_SIZE: usize = MemoryStore::MAX_KEY_SIZE;
+    type Keys = <MemoryStore as ReadableKeyValueStore<MemoryContextError>>::Keys;
+    type KeyValues = <MemoryStore as ReadableKeyValueStore<MemoryContextError>>::KeyValues;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, MemoryContextError> {
+        self.store.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, MemoryContextError> {
+        self.store.contains_key(key).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, MemoryContextError> {
+        self.store.read_multi_values_bytes(keys).await
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::Keys, MemoryContextError> {
+        self.store.find_keys_by_prefix(key_prefix).await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, MemoryContextError> {
+        self.store.find_key_values_by_prefix(key_prefix).await
+    }
+}
+
+impl WritableKeyValueStore<MemoryContextError> for RecordingStore {
+    const MAX_VALUE_SIZE: usize = MemoryStore::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), MemoryContextError> {
+        self.batches.lock().unwrap().push(batch.clone());
+        self.store.write_batch(batch, base_key).await
+    }
+
+    async fn clear_journal(&self, base_key: &[u8]) -> Result<(), MemoryContextError> {
+        self.store.clear_journal(base_key).await
+    }
+}
+
+impl KeyValueStore for RecordingStore {
+    type Error = MemoryContextError;
+}
+
+#[derive(RootView)]
+#[view(introspect)]
+pub struct TwoSubviewsView<C> {
+    pub register: RegisterView<C, u64>,
+    pub queue: QueueView<C, u64>,
+}
+
+#[tokio::test]
+async fn test_save_only_flushes_changed_subviews() -> anyhow::Result<()> {
+    let store = RecordingStore {
+        store: create_memory_store(),
+        batches: Arc::default(),
+    };
+    let context = ContextFromStore {
+        store: store.clone(),
+        base_key: Vec::new(),
+        extra: (),
+    };
+    let mut view = TwoSubviewsView::load(context.clone()).await?;
+    view.queue.push_back(1);
+    view.save().await?;
+
+    let mut view = TwoSubviewsView::load(context.clone()).await?;
+    assert!(!view.has_pending_changes().await);
+    *view.register.get_mut() = 42;
+    assert!(view.has_pending_changes().await);
+    store.batches.lock().unwrap().clear();
+    view.save().await?;
+    assert!(!view.has_pending_changes().await);
+
+    let register_prefix = view.register.context().base_key();
+    let batches = store.batches.lock().unwrap();
+    assert_eq!(batches.len(), 1);
+    assert!(!batches[0].operations.is_empty());
+    for operation in &batches[0].operations {
+        let key = match operation {
+            Delete { key } | Put { key, .. } => key,
+            DeletePrefix { key_prefix } => key_prefix,
+        };
+        assert!(key.starts_with(&register_prefix));
+    }
+    Ok(())
+}
+
+#[derive(View)]
+#[view(introspect)]
+pub enum ModeView<C> {
+    Counter(RegisterView<C, u64>),
+    Log(LogView<C, u32>),
+}
+
+#[tokio::test]
+async fn test_enum_view_switches_variants() -> anyhow::Result<()> {
+    let context = create_memory_context();
+    let counter_key = context.base_tag_index(MIN_VIEW_TAG + 1, &[0]);
+
+    let mut view = EnumView::<_, ModeView<_>>::load(context.clone()).await?;
+    let ModeView::Counter(counter) = view.get_mut() else {
+        panic!("the first variant should be the default one");
+    };
+    *counter.get_mut() = 5;
+    view.save().await?;
+
+    let mut view = EnumView::<_, ModeView<_>>::load(context.clone()).await?;
+    assert!(!view.has_pending_changes().await);
+    let ModeView::Counter(counter) = view.get_mut() else {
+        panic!("the counter variant should have been loaded");
+    };
+    assert_eq!(*counter.get(), 5);
+    *counter.get_mut() = 6;
+    let mut batch = Batch::new();
+    view.flush(&mut batch)?;
+    assert_eq!(batch.operations.len(), 1);
+    assert!(matches!(&batch.operations[0], Put { key, .. } if *key == counter_key));
+
+    let ModeView::Log(log) = view.set_variant(1).await? else {
+        panic!("the log variant should be active");
+    };
+    log.push(7);
+    assert!(view.has_pending_changes().await);
+    view.rollback();
+    assert!(matches!(view.get(), ModeView::Counter(counter) if *counter.get() == 6));
+    assert!(!view.has_pending_changes().await);
+
+    let ModeView::Log(log) = view.set_variant(1).await? else {
+        panic!("the log variant should be active");
+    };
+    log.push(7);
+    view.save().await?;
+    assert!(!view.has_pending_changes().await);
+
+    let view = EnumView::<_, ModeView<_>>::load(context.clone()).await?;
+    let ModeView::Log(log) = view.get() else {
+        panic!("the log variant should have been loaded");
+    };
+    assert_eq!(log.read(..).await?, vec![7]);
+    let counter = RegisterView::<_, u64>::load(context.clone_with_base_key(counter_key)).await?;
+    assert_eq!(*counter.get(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_enum_view_clear_resets_the_variant() -> anyhow::Result<()> {
+    let context = create_memory_context();
+    let mut view = EnumView::<_, ModeView<_>>::load(context.clone()).await?;
+    let ModeView::Log(log) = view.set_variant(1).await? else {
+        panic!("the log variant should be active");
+    };
+    log.push(7);
+    view.save().await?;
+
+    let mut view = EnumView::<_, ModeView<_>>::load(context.clone()).await?;
+    view.clear();
+    assert!(matches!(view.get(), ModeView::Counter(counter) if *counter.get() == 0));
+    view.rollback();
+    assert!(matches!(view.get(), ModeView::Log(_)));
+    view.clear();
+    view.save().await?;
+    assert!(context.find_keys_by_prefix(&[]).await?.is_empty());
+
+    let mut view = EnumView::<_, ModeView<_>>::load(context.clone()).await?;
+    assert!(matches!(view.get(), ModeView::Counter(counter) if *counter.get() == 0));
+    let ModeView::Counter(counter) = view.get_mut() else {
+        panic!("the counter variant should be active");
+    };
+    *counter.get_mut() = 5;
+    view.save().await?;
+    let view = EnumView::<_, ModeView<_>>::load(context).await?;
+    assert!(matches!(view.get(), ModeView::Counter(counter) if *counter.get() == 5));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_view_layouts_match_subview_keys() -> anyhow::Result<()> {
+    let context = create_memory_context();
+    let mut registry = ViewRegistry::new();
+    assert!(registry.register::<TwoSubviewsView<MemoryContext<()>>>());
+    assert!(registry.register::<ModeView<MemoryContext<()>>>());
+    assert!(registry.register::<TwoSubviewsView<MemoryContext<()>>>());
+    assert_eq!(
+        registry
+            .layouts()
+            .map(|layout| layout.name)
+            .collect::<Vec<_>>(),
+        ["ModeView", "TwoSubviewsView"]
+    );
+
+    let layout = registry.get("TwoSubviewsView").unwrap();
+    let names = layout.fields.iter().map(|field| field.name);
+    assert_eq!(names.collect::<Vec<_>>(), ["register", "queue"]);
+    assert_eq!(layout.fields[1].type_name, "QueueView<C, u64>");
+    let view = TwoSubviewsView::load(context.clone()).await?;
+    assert_eq!(
+        view.register.context().base_key(),
+        layout.fields[0].key_prefix
+    );
+    assert_eq!(view.queue.context().base_key(), layout.fields[1].key_prefix);
+
+    let layout = registry.get("ModeView").unwrap();
+    let view = EnumView::<_, ModeView<_>>::load(context).await?;
+    let ModeView::Counter(counter) = view.get() else {
+        panic!("the first variant should be the default one");
+    };
+    assert_eq!(layout.fields[0].name, "Counter");
+    assert_eq!(counter.context().base_key(), layout.fields[0].key_prefix);
+    Ok(())
+}
+
+#[derive(RootView)]
+pub struct ReorderedSubviewsView<C> {
+    #[view(index = "1")]
+    pub queue: QueueView<C, u64>,
+    #[view(index = "0")]
+    pub register: RegisterView<C, u64>,
+}
+
+#[tokio::test]
+async fn test_subview_indices_keep_keys_of_reordered_fields() -> anyhow::Result<()> {
+    let context = create_memory_context();
+    let mut view = TwoSubviewsView::load(context.clone()).await?;
+    *view.register.get_mut() = 3;
+    view.queue.push_back(8);
+    view.save().await?;
+
+    let view = ReorderedSubviewsView::load(context).await?;
+    assert_eq!(*view.register.get(), 3);
+    assert_eq!(view.queue.elements().await?, vec![8]);
+    Ok(())
+}
+
+#[derive(CryptoHashRootView, ClonableView)]
+pub struct OptionalSubviewView<C> {
+    pub register: RegisterView<C, u64>,
+    pub log: OptionalView<C, LogView<C, u32>>,
+}
+
+#[tokio::test]
+async fn test_optional_subview_toggles_across_saves() -> anyhow::Result<()> {
+    let context = create_memory_context();
+    let log_key = context.derive_tag_key(MIN_VIEW_TAG, &1)?;
+    let marker_key = context
+        .clone_with_base_key(log_key.clone())
+        .base_tag(MIN_VIEW_TAG);
+
+    let mut view = OptionalSubviewView::load(context.clone()).await?;
+    assert!(view.log.get().is_none());
+    assert!(!view.has_pending_changes().await);
+    view.log.get_or_insert().await?.push(4);
+    assert!(view.has_pending_changes().await);
+    view.save().await?;
+    assert!(context.contains_key(&marker_key).await?);
+
+    let mut view = OptionalSubviewView::load(context.clone()).await?;
+    assert!(!view.has_pending_changes().await);
+    let log = view.log.get().expect("the log should have been loaded");
+    assert_eq!(log.read(..).await?, vec![4]);
+    view.log.remove();
+    assert!(view.has_pending_changes().await);
+    view.rollback();
+    assert_eq!(view.log.get().map(LogView::count), Some(1));
+    assert!(!view.has_pending_changes().await);
+    view.log.get_mut().unwrap().push(5);
+    let mut batch = Batch::new();
+    view.flush(&mut batch)?;
+    assert!(!batch
+        .operations
+        .iter()
+        .any(|operation| matches!(operation, Put { key, .. } if *key == marker_key)));
+    view.log.remove();
+    assert!(view.has_pending_changes().await);
+    view.save().await?;
+    assert!(context.find_keys_by_prefix(&log_key).await?.is_empty());
+
+    let mut view = OptionalSubviewView::load(context.clone()).await?;
+    assert!(view.log.get().is_none());
+    assert!(!view.has_pending_changes().await);
+    let hash_without_log = view.crypto_hash().await?;
+    assert_eq!(view.log.get_or_insert().await?.count(), 0);
+    assert_ne!(view.crypto_hash().await?, hash_without_log);
+    view.save().await?;
+
+    let mut view = OptionalSubviewView::load(context).await?;
+    let log = view.log.get().expect("the log should have been loaded");
+    assert_eq!(log.count(), 0);
+    let clone = view.clone_unchecked()?;
+    assert!(clone.log.get().is_some());
+    Ok(())
+}
+
+#[derive(RootView)]
+pub struct LazySubviewView<C> {
+    pub register: RegisterView<C, u64>,
+    #[view(lazy)]
+    pub log: LazyView<C, LogView<C, u32>>,
+}
+
+#[tokio::test]
+async fn test_lazy_subview_is_loaded_on_first_access() -> anyhow::Result<()> {
+    let context = create_memory_context();
+    let mut view = LazySubviewView::load(context.clone()).await?;
+    assert!(!view.log.is_loaded());
+    view.load_log().await?.push(4);
+    assert!(view.log.is_loaded());
+    view.save().await?;
+
+    let mut view = LazySubviewView::load(context.clone()).await?;
+    *view.register.get_mut() = 3;
+    view.save().await?;
+    assert!(!view.log.is_loaded());
+    assert_eq!(view.load_log().await?.read(..).await?, vec![4]);
+
+    let mut view = LazySubviewView::load(context.clone()).await?;
+    view.clear();
+    assert!(view.has_pending_changes().await);
+    assert!(!view.log.is_loaded());
+    view.save().await?;
+    assert!(context
+        .find_keys_by_prefix(&context.base_key())
+        .await?
+        .is_empty());
+
+    let mut view = LazySubviewView::load(context).await?;
+    assert_eq!(*view.register.get(), 0);
+    assert_eq!(view.load_log().await?.count(), 0);
+    Ok(())
+}
+
+#[cfg(with_encryption)]
+mod encryption {
+    use linera_views::{
+        common::{Context as _, ContextFromStore},
+        encryption::{EncryptedContext, EncryptionKey},
+        map_view::MapView,
+        memory::{MemoryContext, TEST_MEMORY_MAX_STREAM_QUERIES},
+        register_view::RegisterView,
+        views::{RootView, View, ViewError},
+    };
+
+    #[derive(RootView)]
+    pub struct EncryptedSubviewView<C> {
+        pub register: RegisterView<C, u64>,
+        #[view(encrypted)]
+        pub secrets: MapView<EncryptedContext<C>, String, String>,
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_subview_values_are_ciphertext() -> anyhow::Result<()> {
+        let context = MemoryContext::new(TEST_MEMORY_MAX_STREAM_QUERIES, EncryptionKey::generate());
+        let mut view = EncryptedSubviewView::load(context.clone()).await?;
+        view.register.set(7);
+        view.secrets.insert("alice", "hunter2".to_string())?;
+        view.save().await?;
+
+        // The keys are stored in cleartext, but not the values of the encrypted subview.
+        let key_values = context.find_key_values_by_prefix(&[]).await?;
+        let contains = |bytes: &[u8], part: &[u8]| bytes.windows(part.len()).any(|w| w == part);
+        assert!(key_values.iter().any(|(key, _)| contains(key, b"alice")));
+        assert!(!key_values
+            .iter()
+            .any(|(_, value)| contains(value, b"hunter2")));
+
+        let view = EncryptedSubviewView::load(context.clone()).await?;
+        assert_eq!(*view.register.get(), 7);
+        assert_eq!(
+            view.secrets.get("alice").await?,
+            Some("hunter2".to_string())
+        );
+        assert_eq!(view.secrets.indices().await?, vec!["alice".to_string()]);
+
+        // The values can't be read with another key.
+        let context = ContextFromStore {
+            extra: EncryptionKey::generate(),
+            ..context
+        };
+        let view = EncryptedSubviewView::load(context).await?;
+        assert_eq!(*view.register.get(), 7);
+        assert!(matches!(
+            view.secrets.get("alice").await,
+            Err(ViewError::DecryptionFailed)
+        ));
+        Ok(())
+    }
+}
+
+#[derive(View)]
+#[view(diff)]
+pub struct DiffableSubview<C> {
+    pub queue: QueueView<C, u64>,
+}
+
+#[derive(RootView)]
+#[view(diff)]
+pub struct DiffableRootView<C> {
+    pub register: RegisterView<C, u64>,
+    pub subview: DiffableSubview<C>,
+    pub log: OptionalView<C, LogView<C, u32>>,
+}
+
+#[tokio::test]
+async fn test_diff_reports_changed_subviews() -> anyhow::Result<()> {
+    let mut view = DiffableRootView::load(create_memory_context()).await?;
+    let mut other = DiffableRootView::load(create_memory_context()).await?;
+    assert_eq!(view.diff(&other).await?, None);
+
+    other.register.set(1);
+    assert_eq!(
+        view.diff(&other).await?,
+        Some(ViewDiff::Subviews(vec![("register", ViewDiff::Changed)]))
+    );
+
+    view.register.set(1);
+    view.subview.queue.push_back(3);
+    other.log.get_or_insert().await?.push(4);
+    assert_eq!(
+        view.diff(&other).await?,
+        Some(ViewDiff::Subviews(vec![
+            (
+                "subview",
+                ViewDiff::Subviews(vec![("queue", ViewDiff::Changed)])
+            ),
+            ("log", ViewDiff::Changed),
+        ]))
+    );
+
+    view.save().await?;
+    other.subview.queue.push_back(3);
+    view.log.get_or_insert().await?.push(4);
+    assert_eq!(view.diff(&other).await?, None);
+    Ok(())
+}